@@ -0,0 +1,456 @@
+use std::rc::Rc;
+
+use anchor_client::solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType};
+use anchor_client::solana_sdk::commitment_config::CommitmentConfig;
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use anchor_client::{Client, Cluster, Program};
+use anyhow::{anyhow, bail, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+
+use got_a_min_sdk::instructions as ix;
+use got_a_min_sdk::state::{FuelCostType, Location, LocationType, MobilityType, Processor, Resource, Storage, Unit};
+use got_a_min_sdk::{pda, ID};
+
+mod map;
+
+#[derive(Parser)]
+#[command(name = "got-a-min-cli", version, about = "Play and administer a got_a_min game from the command line")]
+struct Cli {
+    /// RPC URL or moniker (localnet, devnet, testnet, mainnet).
+    #[arg(short, long, global = true, env = "GOT_A_MIN_URL", default_value = "localnet")]
+    url: String,
+    /// Keypair paying for and signing the transactions, defaults to the Solana CLI keypair.
+    #[arg(short, long, global = true, env = "GOT_A_MIN_KEYPAIR")]
+    keypair: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create and configure the game of this keypair.
+    #[command(subcommand)]
+    Game(GameCommand),
+    /// Create, explore and inspect locations.
+    #[command(subcommand)]
+    Location(LocationCommand),
+    /// Create and move units.
+    #[command(subcommand)]
+    Unit(UnitCommand),
+    /// Claim production and manage processors.
+    #[command(subcommand)]
+    Processor(ProcessorCommand),
+    /// Move resources between and inspect storages.
+    #[command(subcommand)]
+    Storage(StorageCommand),
+    /// Draw the map.
+    #[command(subcommand)]
+    Map(MapCommand),
+    /// Bring an account written by an older program to the current layout.
+    Migrate {
+        #[arg(value_enum)]
+        kind: AccountKind,
+        account: Pubkey,
+    },
+}
+
+#[derive(Subcommand)]
+enum GameCommand {
+    /// Create the game account of this keypair.
+    Init {
+        #[arg(long, default_value_t = 30)]
+        swap_fee_bps: u16,
+    },
+    /// Change the swap fee of the game.
+    Fees { swap_fee_bps: u16 },
+    /// Set the delay before a retooled processor produces again.
+    RetoolDelay { seconds: i64 },
+    /// Create the game clock used by test-clock builds.
+    ClockInit { timestamp: i64 },
+    /// Move the game clock forward.
+    ClockAdvance { seconds: i64 },
+}
+
+#[derive(Subcommand)]
+enum LocationCommand {
+    /// Create a location owned by this keypair.
+    Init {
+        #[arg(allow_hyphen_values = true)]
+        x: i64,
+        #[arg(allow_hyphen_values = true)]
+        y: i64,
+        #[arg(long, default_value_t = 100)]
+        capacity: i64,
+        #[arg(long, value_enum, default_value_t = Kind::Unexplored)]
+        kind: Kind,
+    },
+    /// Explore a game tile, creating it first when needed.
+    Explore { x: u8, y: u8 },
+    /// Print a location.
+    Show {
+        #[arg(allow_hyphen_values = true)]
+        x: i64,
+        #[arg(allow_hyphen_values = true)]
+        y: i64,
+        /// Owner of the location, defaults to this keypair.
+        #[arg(long)]
+        owner: Option<Pubkey>,
+    },
+}
+
+#[derive(Subcommand)]
+enum UnitCommand {
+    /// Create a unit at a location of [game].
+    Init {
+        name: String,
+        #[arg(long, value_parser = parse_xy, allow_hyphen_values = true)]
+        at: (i64, i64),
+        #[arg(long)]
+        game: Pubkey,
+    },
+    /// Start moving a unit between two locations.
+    Move {
+        name: String,
+        #[arg(long, value_parser = parse_xy, allow_hyphen_values = true)]
+        from: (i64, i64),
+        #[arg(long, value_parser = parse_xy, allow_hyphen_values = true)]
+        to: (i64, i64),
+    },
+    /// Complete the move of a unit that has arrived.
+    Arrive {
+        name: String,
+        #[arg(long, value_parser = parse_xy, allow_hyphen_values = true)]
+        to: (i64, i64),
+        #[arg(long)]
+        map: Pubkey,
+    },
+    /// Print a unit.
+    Show { name: String },
+}
+
+#[derive(Subcommand)]
+enum ProcessorCommand {
+    /// Claim the finished production of a processor into its linked storages.
+    Claim { processor: Pubkey },
+    /// Link a storage to a processor.
+    Link { processor: Pubkey, storage: Pubkey },
+    /// Print a processor.
+    Show { processor: Pubkey },
+}
+
+#[derive(Subcommand)]
+enum StorageCommand {
+    /// Move an amount between two storages of this keypair.
+    Transfer {
+        from: Pubkey,
+        to: Pubkey,
+        amount: i64,
+    },
+    /// Create a storage at a location of this keypair.
+    Init {
+        resource: Pubkey,
+        #[arg(long, value_parser = parse_xy, allow_hyphen_values = true)]
+        at: (i64, i64),
+        #[arg(long, default_value_t = 100)]
+        capacity: i64,
+        /// Speed of a movable storage, fixed when not given.
+        #[arg(long)]
+        speed: Option<i64>,
+    },
+    /// Set the amount of a storage, needs a debug build and the game authority.
+    SetAmount { storage: Pubkey, amount: i64 },
+    /// Print a storage.
+    Show { storage: Pubkey },
+}
+
+#[derive(Subcommand)]
+enum MapCommand {
+    /// Draw the locations of an owner.
+    Show {
+        /// Owner of the locations, defaults to this keypair.
+        #[arg(long)]
+        owner: Option<Pubkey>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Kind {
+    Unexplored,
+    Space,
+    Planet,
+    Moon,
+    Asteroid,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum AccountKind {
+    Location,
+    Storage,
+    Processor,
+    Unit,
+    Game,
+    Resource,
+    GameTile,
+    Map,
+    Offer,
+    OrderBook,
+    Pool,
+    LiquidityPosition,
+    Auction,
+    Shipment,
+    TradeRoute,
+    ProductionQueue,
+    TransferAllowList,
+}
+
+impl From<Kind> for LocationType {
+    fn from(kind: Kind) -> Self {
+        match kind {
+            Kind::Unexplored => LocationType::Unexplored,
+            Kind::Space => LocationType::Space,
+            Kind::Planet => LocationType::Planet,
+            Kind::Moon => LocationType::Moon,
+            Kind::Asteroid => LocationType::Asteroid,
+        }
+    }
+}
+
+fn parse_xy(s: &str) -> std::result::Result<(i64, i64), String> {
+    let (x, y) = s.split_once(',').ok_or_else(|| format!("expected x,y but got {}", s))?;
+    let x = x.trim().parse().map_err(|e| format!("bad x in {}: {}", s, e))?;
+    let y = y.trim().parse().map_err(|e| format!("bad y in {}: {}", s, e))?;
+    Ok((x, y))
+}
+
+fn keypair_path(keypair: Option<String>) -> Result<String> {
+    match keypair {
+        Some(path) => Ok(path),
+        None => {
+            let home = std::env::var("HOME").map_err(|_| anyhow!("no --keypair given and HOME is not set"))?;
+            Ok(format!("{}/.config/solana/id.json", home))
+        },
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let path = keypair_path(cli.keypair)?;
+    let payer = Rc::new(read_keypair_file(&path).map_err(|e| anyhow!("reading keypair {}: {}", path, e))?);
+    let cluster: Cluster = cli.url.parse()?;
+    let client = Client::new_with_options(cluster, payer.clone(), CommitmentConfig::confirmed());
+    let program = client.program(ID);
+
+    match cli.command {
+        Command::Game(command) => game(&program, command),
+        Command::Location(command) => location(&program, command),
+        Command::Unit(command) => unit(&program, command),
+        Command::Processor(command) => processor(&program, command),
+        Command::Storage(command) => storage(&program, &payer, command),
+        Command::Map(command) => match command {
+            MapCommand::Show { owner } => map::show(&program, owner.unwrap_or_else(|| program.payer())),
+        },
+        Command::Migrate { kind, account } => migrate(&program, kind, account),
+    }
+}
+
+fn send(program: &Program, instructions: Vec<anchor_client::solana_sdk::instruction::Instruction>, signers: &[&Keypair]) -> Result<()> {
+    let mut request = program.request();
+    for ix in instructions {
+        request = request.instruction(ix);
+    }
+    for signer in signers {
+        request = request.signer(*signer);
+    }
+    let signature = request.send()?;
+    println!("{}", signature);
+    Ok(())
+}
+
+fn game(program: &Program, command: GameCommand) -> Result<()> {
+    let authority = program.payer();
+    let instruction = match command {
+        GameCommand::Init { swap_fee_bps } => ix::init_game(authority, swap_fee_bps),
+        GameCommand::Fees { swap_fee_bps } => ix::update_game_fees(authority, swap_fee_bps),
+        GameCommand::RetoolDelay { seconds } => ix::update_game_retool_delay(authority, seconds),
+        GameCommand::ClockInit { timestamp } => ix::init_game_clock(authority, timestamp),
+        GameCommand::ClockAdvance { seconds } => ix::advance_game_clock(authority, seconds),
+    };
+    send(program, vec![instruction], &[])
+}
+
+fn location(program: &Program, command: LocationCommand) -> Result<()> {
+    let owner = program.payer();
+    match command {
+        LocationCommand::Init { x, y, capacity, kind } => {
+            send(program, vec![ix::init_location(owner, x, y, capacity, kind.into())], &[])
+        },
+        LocationCommand::Explore { x, y } => {
+            let xy = [x, y];
+            let mut instructions = vec![];
+            if program.rpc().get_account(&pda::game_tile(&owner, xy).0).is_err() {
+                instructions.push(ix::create_game_tile(owner, xy));
+            }
+            instructions.push(ix::explore_game_tile(owner, xy));
+            send(program, instructions, &[])
+        },
+        LocationCommand::Show { x, y, owner: location_owner } => {
+            let address = pda::map_location(&location_owner.unwrap_or(owner), x, y).0;
+            let location: Location = program.account(address)?;
+            println!("location  {}", address);
+            println!("owner     {}", location.owner);
+            println!("position  {},{}", location.pos_x, location.pos_y);
+            println!("type      {:?}", location.location_type);
+            println!("occupied  {}/{}", location.occupied_space, location.capacity);
+            for occupant in location.occupied_by.iter() {
+                println!("  {} of {}", occupant.item, occupant.player);
+            }
+            Ok(())
+        },
+    }
+}
+
+fn unit(program: &Program, command: UnitCommand) -> Result<()> {
+    let owner = program.payer();
+    match command {
+        UnitCommand::Init { name, at, game } => send(program, vec![ix::init_unit(owner, &name, at.0, at.1, game)], &[]),
+        UnitCommand::Move { name, from, to } => send(program, vec![ix::move_unit_start(owner, &name, from, to)], &[]),
+        UnitCommand::Arrive { name, to, map } => send(program, vec![ix::move_unit_complete(owner, &name, to, map)], &[]),
+        UnitCommand::Show { name } => {
+            let address = pda::unit(&owner, &name).0;
+            let unit: Unit = program.account(address)?;
+            println!("unit        {}", address);
+            println!("name        {}", unit.name);
+            println!("location    {}", unit.at_location_id);
+            println!("speed       {}", unit.movement_speed);
+            println!("arrives at  {}", unit.arrives_at);
+            Ok(())
+        },
+    }
+}
+
+// The linked storage holding [resource_id].
+fn linked_storage(linked: &[(Pubkey, Storage)], resource_id: &Pubkey) -> Result<Pubkey> {
+    linked.iter()
+        .find(|(_, storage)| storage.resource_id == *resource_id)
+        .map(|(address, _)| *address)
+        .ok_or_else(|| anyhow!("no linked storage holds resource {}", resource_id))
+}
+
+fn processor(program: &Program, command: ProcessorCommand) -> Result<()> {
+    match command {
+        ProcessorCommand::Claim { processor: address } => {
+            let processor: Processor = program.account(address)?;
+            let resource: Resource = program.account(processor.output_resource_id)?;
+            let linked = processor.linked_storages.iter()
+                .map(|storage| Ok((*storage, program.account::<Storage>(*storage)?)))
+                .collect::<Result<Vec<_>>>()?;
+
+            let storage = linked_storage(&linked, &processor.output_resource_id)?;
+            let storage_fuel = match processor.fuel_cost_type {
+                FuelCostType::Nothing => storage,
+                _ => linked_storage(&linked, &processor.fuel_resource_id)?,
+            };
+            let instruction = match resource.input.as_slice() {
+                [] => ix::produce_without_input(address, processor.output_resource_id, storage, storage_fuel),
+                [input] => {
+                    let storage_input = linked_storage(&linked, input)?;
+                    ix::produce_with_one_input(address, processor.output_resource_id, storage, storage_input, storage_fuel)
+                },
+                [input_1, input_2] => {
+                    let inputs = (linked_storage(&linked, input_1)?, linked_storage(&linked, input_2)?);
+                    ix::produce_with_two_inputs(address, processor.output_resource_id, storage, inputs, storage_fuel)
+                },
+                inputs => bail!("resources with {} inputs can't be produced", inputs.len()),
+            };
+            send(program, vec![instruction], &[])
+        },
+        ProcessorCommand::Link { processor, storage } => {
+            let output = program.account::<Processor>(processor)?.output_resource_id;
+            send(program, vec![ix::link_storage(program.payer(), processor, output, storage)], &[])
+        },
+        ProcessorCommand::Show { processor: address } => {
+            let processor: Processor = program.account(address)?;
+            println!("processor   {}", address);
+            println!("type        {:?}", processor.processor_type);
+            println!("location    {}", processor.location_id);
+            println!("output      {} x{} per {}s", processor.output_resource_id, processor.output_rate, processor.processing_duration);
+            println!("fuel        {:?} {}", processor.fuel_cost_type, processor.fuel_resource_id);
+            println!("level       {}", processor.level);
+            println!("awaiting    {}", processor.awaiting_units);
+            println!("claimed at  {}", processor.claimed_at);
+            println!("durability  {}/{}", processor.durability, processor.max_durability);
+            for storage in processor.linked_storages.iter() {
+                println!("  linked {}", storage);
+            }
+            Ok(())
+        },
+    }
+}
+
+fn storage(program: &Program, payer: &Keypair, command: StorageCommand) -> Result<()> {
+    let owner = payer.pubkey();
+    match command {
+        StorageCommand::Transfer { from, to, amount } => send(program, vec![ix::move_between_storage(owner, from, to, amount)], &[]),
+        StorageCommand::Init { resource, at, capacity, speed } => {
+            let storage = Keypair::new();
+            let mobility = match speed {
+                Some(speed) => (MobilityType::Movable, speed),
+                None => (MobilityType::Fixed, 0),
+            };
+            println!("storage {}", storage.pubkey());
+            send(
+                program,
+                vec![ix::init_storage(owner, storage.pubkey(), pda::map_location(&owner, at.0, at.1).0, at, resource, capacity, mobility)],
+                &[&storage],
+            )
+        },
+        StorageCommand::SetAmount { storage, amount } => {
+            send(program, vec![ix::debug_set_storage_amount(owner, storage, amount)], &[])
+        },
+        StorageCommand::Show { storage: address } => {
+            let storage: Storage = program.account(address)?;
+            println!("storage     {}", address);
+            println!("resource    {}", storage.resource_id);
+            println!("location    {}", storage.location_id);
+            println!("amount      {}/{}", storage.amount, storage.capacity);
+            println!("mobility    {:?} {}", storage.mobility_type, storage.movement_speed);
+            println!("arrives at  {}", storage.arrives_at);
+            Ok(())
+        },
+    }
+}
+
+fn migrate(program: &Program, kind: AccountKind, account: Pubkey) -> Result<()> {
+    let ix = match kind {
+        AccountKind::Location => ix::migrate_location(program.payer(), account),
+        AccountKind::Storage => ix::migrate_storage(program.payer(), account),
+        AccountKind::Processor => ix::migrate_processor(program.payer(), account),
+        AccountKind::Unit => ix::migrate_unit(program.payer(), account),
+        AccountKind::Game => ix::migrate_game(program.payer(), account),
+        AccountKind::Resource => ix::migrate_resource(program.payer(), account),
+        AccountKind::GameTile => ix::migrate_game_tile(program.payer(), account),
+        AccountKind::Map => ix::migrate_map(program.payer(), account),
+        AccountKind::Offer => ix::migrate_offer(program.payer(), account),
+        AccountKind::OrderBook => ix::migrate_order_book(program.payer(), account),
+        AccountKind::Pool => ix::migrate_pool(program.payer(), account),
+        AccountKind::LiquidityPosition => ix::migrate_liquidity_position(program.payer(), account),
+        AccountKind::Auction => ix::migrate_auction(program.payer(), account),
+        AccountKind::Shipment => ix::migrate_shipment(program.payer(), account),
+        AccountKind::TradeRoute => ix::migrate_trade_route(program.payer(), account),
+        AccountKind::ProductionQueue => ix::migrate_production_queue(program.payer(), account),
+        AccountKind::TransferAllowList => ix::migrate_transfer_allow_list(program.payer(), account),
+    };
+    send(program, vec![ix], &[])
+}
+
+// Locations of [owner], read with a filter on the owner field right after the discriminator and version.
+pub fn owned_locations(program: &Program, owner: &Pubkey) -> Result<Vec<(Pubkey, Location)>> {
+    let filter = RpcFilterType::Memcmp(Memcmp {
+        offset: 9,
+        bytes: MemcmpEncodedBytes::Bytes(owner.to_bytes().to_vec()),
+        encoding: None,
+    });
+    Ok(program.accounts::<Location>(vec![filter])?)
+}
@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_client::Program;
+use anyhow::{bail, Result};
+
+use got_a_min_sdk::state::{Location, LocationType};
+
+use crate::owned_locations;
+
+// Wider maps don't fit a terminal, show a part with `location show` instead.
+const MAX_WIDTH: i64 = 120;
+
+fn symbol(location: &Location) -> char {
+    if !location.occupied_by.is_empty() {
+        return '#';
+    }
+    match location.location_type {
+        LocationType::Unexplored => '?',
+        LocationType::Space => '.',
+        LocationType::Planet => 'O',
+        LocationType::Moon => 'o',
+        LocationType::Asteroid => '*',
+    }
+}
+
+// Draws the locations of [owner] with y growing upwards, occupied locations as #.
+pub fn show(program: &Program, owner: Pubkey) -> Result<()> {
+    let locations = owned_locations(program, &owner)?;
+    if locations.is_empty() {
+        println!("{} has no locations", owner);
+        return Ok(());
+    }
+
+    let min_x = locations.iter().map(|(_, l)| l.pos_x).min().unwrap_or(0);
+    let max_x = locations.iter().map(|(_, l)| l.pos_x).max().unwrap_or(0);
+    let min_y = locations.iter().map(|(_, l)| l.pos_y).min().unwrap_or(0);
+    let max_y = locations.iter().map(|(_, l)| l.pos_y).max().unwrap_or(0);
+    if max_x - min_x >= MAX_WIDTH {
+        bail!("locations span x {}..={}, more than {} columns", min_x, max_x, MAX_WIDTH);
+    }
+
+    let by_position: HashMap<(i64, i64), &Location> = locations.iter()
+        .map(|(_, location)| ((location.pos_x, location.pos_y), location))
+        .collect();
+
+    for y in (min_y..=max_y).rev() {
+        let row: String = (min_x..=max_x)
+            .map(|x| by_position.get(&(x, y)).map_or(' ', |location| symbol(location)))
+            .collect();
+        println!("{:>6} {}", y, row.trim_end());
+    }
+    println!("{:>6} x {}..={}", "", min_x, max_x);
+    println!("{} locations, ? unexplored . space O planet o moon * asteroid # occupied", locations.len());
+    Ok(())
+}
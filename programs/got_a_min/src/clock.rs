@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+use crate::state::GameClock;
+
+// Current time for every handler. Only test-clock builds let the game clock, passed among the
+// remaining accounts, replace the Clock sysvar.
+#[cfg(feature = "test-clock")]
+pub fn now(remaining_accounts: &[AccountInfo]) -> Result<i64> {
+    match remaining_accounts.iter().find(|info| is_game_clock(info)) {
+        Some(info) => Ok(Account::<GameClock>::try_from(info)?.timestamp),
+        None => Ok(Clock::get()?.unix_timestamp),
+    }
+}
+
+#[cfg(not(feature = "test-clock"))]
+pub fn now(_remaining_accounts: &[AccountInfo]) -> Result<i64> {
+    Ok(Clock::get()?.unix_timestamp)
+}
+
+// Lets handlers reading their own remaining accounts skip the clock. Only the program's one
+// clock counts, any other account of its type is just another account.
+pub fn is_game_clock(info: &AccountInfo) -> bool {
+    cfg!(feature = "test-clock") && *info.key == GameClock::address()
+}
@@ -24,5 +24,48 @@ pub enum ValidationError {
     #[msg("Location has not yet been explored.")]                               LocationUnexplored,
     #[msg("Unit attempting invalid move.")]                                     UnitMoveInvalid,
     #[msg("Invalid input parameter.")]                                          InvalidInput,
+    #[msg("Transfer not allowed by recipient.")]                                TransferNotAllowed,
+    #[msg("Allow-list has too many players.")]                                  AllowListFull,
+    #[msg("Order book is full.")]                                               OrderBookFull,
+    #[msg("Order not found.")]                                                  OrderNotFound,
+    #[msg("Orders from the same player cannot be matched.")]                    SelfTradeNotAllowed,
+    #[msg("Storage of the counterparty not supplied.")]                         CounterpartyStorageMissing,
+    #[msg("Arithmetic overflow.")]                                              MathOverflow,
+    #[msg("Result is worse than the accepted minimum.")]                        SlippageExceeded,
+    #[msg("Not enough liquidity in pool.")]                                     InsufficientLiquidity,
+    #[msg("Not enough liquidity shares.")]                                      InsufficientShares,
+    #[msg("Auction has ended.")]                                                AuctionEnded,
+    #[msg("Auction has not ended yet.")]                                        AuctionNotEnded,
+    #[msg("Bid is too low.")]                                                   BidTooLow,
+    #[msg("Shipment has not arrived yet.")]                                     ShipmentNotArrived,
+    #[msg("No trade route cycle is due yet.")]                                  TradeRouteNotDue,
+    #[msg("Production queue is full.")]                                         ProductionQueueFull,
+    #[msg("Job not found.")]                                                    JobNotFound,
+    #[msg("Processor is already at the highest level.")]                        MaxLevelReached,
+    #[msg("Processor is paused.")]                                              ProcessorPaused,
+    #[msg("Processor is not paused.")]                                          ProcessorNotPaused,
+    #[msg("Storage is not linked to the processor.")]                           StorageNotLinked,
+    #[msg("Processor has too many linked storages.")]                           LinkedStoragesFull,
+    #[msg("Only available in builds with the test-clock feature.")]             TestClockDisabled,
+    #[msg("Only available in builds with the debug feature.")]                  DebugDisabled,
     #[msg("Trying stuff out and failing quite deliberately.")]                  ExperimentalError,
+    #[msg("Position is outside of the map.")]                                   MapOutOfBounds,
+    #[msg("Map has no room for more tiles.")]                                   MapFull,
+    #[msg("Account has an old layout, migrate it first.")]                      AccountOutdated,
+    #[msg("Account is already at the current version.")]                        AccountUpToDate,
+    #[msg("Source and destination storage are the same.")]                      SameStorage,
+    #[msg("Processor has queued jobs, claim them from the queue.")]             ProductionQueueActive,
+    #[msg("Fuel cost type not supported by the processor.")]                    InvalidFuelCostType,
+    #[msg("Nothing can be shipped on the trade route.")]                        NothingToShip,
+    #[msg("Processor has production waiting, claim it first.")]                 ProductionNotClaimed,
+}
+
+impl From<got_a_min_rules::RuleError> for ValidationError {
+    fn from(error: got_a_min_rules::RuleError) -> Self {
+        match error {
+            got_a_min_rules::RuleError::MathOverflow => ValidationError::MathOverflow,
+            got_a_min_rules::RuleError::MapOutOfBounds => ValidationError::MapOutOfBounds,
+            got_a_min_rules::RuleError::MapFull => ValidationError::MapFull,
+        }
+    }
 }
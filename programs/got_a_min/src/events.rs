@@ -0,0 +1,347 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{AuctionKind, LocationType, OrderSide, ProcessorType};
+
+#[event]
+pub struct StorageTransferred {
+    pub from_storage: Pubkey,
+    pub to_storage: Pubkey,
+    pub from_player: Pubkey,
+    pub to_player: Pubkey,
+    pub resource_id: Pubkey,
+    pub location_id: Pubkey,
+    pub amount: i64,
+}
+
+#[event]
+pub struct OfferCreated {
+    pub offer: Pubkey,
+    pub maker: Pubkey,
+    pub location_id: Pubkey,
+    pub offered_resource_id: Pubkey,
+    pub offered_amount: i64,
+    pub requested_resource_id: Pubkey,
+    pub requested_amount: i64,
+}
+
+#[event]
+pub struct OfferAccepted {
+    pub offer: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub offered_amount: i64,
+    pub requested_amount: i64,
+}
+
+#[event]
+pub struct OfferCancelled {
+    pub offer: Pubkey,
+    pub maker: Pubkey,
+    pub returned_amount: i64,
+}
+
+#[event]
+pub struct OrderPlaced {
+    pub order_book: Pubkey,
+    pub order_id: u64,
+    pub owner: Pubkey,
+    pub side: OrderSide,
+    pub price: i64,
+    pub amount: i64,
+}
+
+#[event]
+pub struct OrderFilled {
+    pub order_book: Pubkey,
+    pub order_id: u64,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub side: OrderSide,
+    pub price: i64,
+    pub amount: i64,
+}
+
+#[event]
+pub struct OrderClaimed {
+    pub order_book: Pubkey,
+    pub order_id: u64,
+    pub owner: Pubkey,
+    pub side: OrderSide,
+    pub amount: i64,
+}
+
+#[event]
+pub struct OrderCancelled {
+    pub order_book: Pubkey,
+    pub order_id: u64,
+    pub owner: Pubkey,
+    pub amount: i64,
+}
+
+#[event]
+pub struct LiquidityAdded {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub amount_a: i64,
+    pub amount_b: i64,
+    pub shares: i64,
+}
+
+#[event]
+pub struct LiquidityRemoved {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub amount_a: i64,
+    pub amount_b: i64,
+    pub shares: i64,
+}
+
+#[event]
+pub struct PoolSwapped {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub resource_in_id: Pubkey,
+    pub amount_in: i64,
+    pub resource_out_id: Pubkey,
+    pub amount_out: i64,
+}
+
+#[event]
+pub struct AuctionCreated {
+    pub auction: Pubkey,
+    pub seller: Pubkey,
+    pub item: Pubkey,
+    pub kind: AuctionKind,
+    pub min_bid: i64,
+    pub end_at: i64,
+}
+
+#[event]
+pub struct BidPlaced {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: i64,
+}
+
+#[event]
+pub struct BidRefunded {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: i64,
+}
+
+#[event]
+pub struct AuctionSettled {
+    pub auction: Pubkey,
+    pub item: Pubkey,
+    pub kind: AuctionKind,
+    pub seller: Pubkey,
+    pub winner: Pubkey,     // Default when the auction ended without bids.
+    pub price: i64,
+}
+
+#[event]
+pub struct ShipmentSent {
+    pub shipment: Pubkey,
+    pub sender: Pubkey,
+    pub storage_to: Pubkey,
+    pub amount: i64,
+    pub arrives_at: i64,
+}
+
+#[event]
+pub struct ShipmentReceived {
+    pub shipment: Pubkey,
+    pub storage_to: Pubkey,
+    pub amount: i64,
+}
+
+#[event]
+pub struct TradeRouteRun {
+    pub trade_route: Pubkey,
+    pub shipment: Pubkey,
+    pub cycles: i64,
+    pub amount: i64,
+    pub fuel: i64,
+    pub arrives_at: i64,
+}
+
+#[event]
+pub struct JobQueued {
+    pub queue: Pubkey,
+    pub job_id: u64,
+    pub resource_id: Pubkey,
+    pub quantity: i64,
+    pub priority: u8,
+}
+
+#[event]
+pub struct JobCompleted {
+    pub queue: Pubkey,
+    pub job_id: u64,
+    pub resource_id: Pubkey,
+    pub quantity: i64,
+}
+
+#[event]
+pub struct ProcessorUpgraded {
+    pub processor: Pubkey,
+    pub level: u8,
+    pub output_rate: i64,
+    pub processing_duration: i64,
+}
+
+#[event]
+pub struct ProcessorPaused {
+    pub processor: Pubkey,
+    pub paused_at: i64,
+}
+
+#[event]
+pub struct ProcessorResumed {
+    pub processor: Pubkey,
+    pub paused_for: i64,
+}
+
+#[event]
+pub struct ProcessorRetooled {
+    pub processor: Pubkey,
+    pub output_resource_id: Pubkey,
+    pub producing_from: i64,
+}
+
+#[event]
+pub struct ProcessorRepaired {
+    pub processor: Pubkey,
+    pub durability: i64,
+}
+
+#[event]
+pub struct LocationInitialized {
+    pub location: Pubkey,
+    pub owner: Pubkey,
+    pub x: i64,
+    pub y: i64,
+    pub capacity: i64,
+    pub location_type: LocationType,
+}
+
+#[event]
+pub struct LocationExplored {
+    pub location: Pubkey,
+    pub location_type: LocationType,
+}
+
+#[event]
+pub struct GameTileExplored {
+    pub game_tile: Pubkey,
+    pub x: u8,
+    pub y: u8,
+    pub tile_type: u8,
+}
+
+#[event]
+pub struct ResourceInitialized {
+    pub resource: Pubkey,
+    pub owner: Pubkey,
+    pub name: String,
+    pub inputs: Vec<Pubkey>,
+    pub input_amounts: Vec<i64>,
+}
+
+#[event]
+pub struct ProcessorInitialized {
+    pub processor: Pubkey,
+    pub owner: Pubkey,
+    pub location_id: Pubkey,
+    pub output_resource_id: Pubkey,
+    pub processor_type: ProcessorType,
+    pub output_rate: i64,
+    pub processing_duration: i64,
+}
+
+#[event]
+pub struct ProductionClaimed {
+    pub processor: Pubkey,
+    pub storage: Pubkey,
+    pub resource_id: Pubkey,
+    pub produced: i64,
+    pub delivered: i64,
+    pub awaiting_units: i64,
+}
+
+#[event]
+pub struct ProcessorMoved {
+    pub processor: Pubkey,
+    pub from_location_id: Pubkey,
+    pub to_location_id: Pubkey,
+    pub arrives_at: i64,
+}
+
+#[event]
+pub struct StorageLinked {
+    pub processor: Pubkey,
+    pub storage: Pubkey,
+}
+
+#[event]
+pub struct StorageUnlinked {
+    pub processor: Pubkey,
+    pub storage: Pubkey,
+}
+
+#[event]
+pub struct StorageInitialized {
+    pub storage: Pubkey,
+    pub owner: Pubkey,
+    pub resource_id: Pubkey,
+    pub location_id: Pubkey,
+    pub capacity: i64,
+}
+
+#[event]
+pub struct StorageMoved {
+    pub storage: Pubkey,
+    pub from_location_id: Pubkey,
+    pub to_location_id: Pubkey,
+    pub arrives_at: i64,
+}
+
+#[event]
+pub struct StorageArrived {
+    pub storage: Pubkey,
+    pub location_id: Pubkey,
+}
+
+#[event]
+pub struct UnitInitialized {
+    pub unit: Pubkey,
+    pub owner: Pubkey,
+    pub name: String,
+    pub location_id: Pubkey,
+}
+
+#[event]
+pub struct UnitMoveStarted {
+    pub unit: Pubkey,
+    pub from_location_id: Pubkey,
+    pub to_location_id: Pubkey,
+    pub arrives_at: i64,
+}
+
+#[event]
+pub struct UnitArrived {
+    pub unit: Pubkey,
+    pub location_id: Pubkey,
+}
+
+#[event]
+pub struct GameUpdated {
+    pub game: Pubkey,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub version: u8,
+}
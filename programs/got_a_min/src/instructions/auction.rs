@@ -0,0 +1,616 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+
+use crate::state::{auction::*, storage::*, Location, Processor};
+use crate::instructions::{location, production_queue};
+use crate::events::{AuctionCreated, AuctionSettled, BidPlaced, BidRefunded};
+use crate::errors::ValidationError;
+use crate::math;
+
+// Create ---------------------------------
+// What's being sold, where and for what, the same for every kind of auction.
+struct AuctionTerms {
+    item: Pubkey,
+    kind: AuctionKind,
+    location_id: Pubkey,
+    bid_resource_id: Pubkey,
+    min_bid: i64,
+    duration: i64,
+}
+
+fn init_auction(auction: &mut Account<Auction>, seller: Pubkey, terms: AuctionTerms, current_timestamp: i64) -> Result<()> {
+    let AuctionTerms { item, kind, location_id, bid_resource_id, min_bid, duration } = terms;
+    require!(min_bid > 0, ValidationError::InvalidInput);
+    require!(duration > 0, ValidationError::InvalidInput);
+
+    auction.seller = seller;
+    auction.item = item;
+    auction.kind = kind;
+    auction.location_id = location_id;
+    auction.bid_resource_id = bid_resource_id;
+    auction.goods_amount = 0;
+    auction.min_bid = min_bid;
+    auction.highest_bid = 0;
+    auction.highest_bidder = Pubkey::default();
+    auction.highest_bidder_storage = Pubkey::default();
+    auction.end_at = math::add(current_timestamp, duration)?;
+
+    emit!(AuctionCreated { auction: auction.key(), seller, item, kind, min_bid, end_at: auction.end_at });
+
+    Ok(())
+}
+
+fn init_escrow(escrow: &mut Account<Storage>, auction: Pubkey, resource_id: Pubkey, location_id: Pubkey, capacity: i64) {
+    escrow.version = Storage::VERSION;
+    escrow.owner = auction;
+    escrow.resource_id = resource_id;
+    escrow.location_id = location_id;
+    escrow.amount = 0;
+    escrow.capacity = capacity;
+    escrow.mobility_type = MobilityType::Fixed;
+    escrow.movement_speed = 0;
+    escrow.arrives_at = 0;
+}
+
+pub fn create_location(ctx: Context<CreateLocationAuction>, bid_resource_id: Pubkey, min_bid: i64, duration: i64, current_timestamp: i64) -> Result<()> {
+    let auction: &mut Account<Auction> = &mut ctx.accounts.auction;
+    let bid_escrow: &mut Account<Storage> = &mut ctx.accounts.bid_escrow;
+    let location: &mut Account<Location> = &mut ctx.accounts.location;
+    let seller: &Signer = &ctx.accounts.seller;
+
+    let terms = AuctionTerms { item: location.key(), kind: AuctionKind::Location, location_id: location.key(), bid_resource_id, min_bid, duration };
+    init_auction(auction, seller.key(), terms, current_timestamp)?;
+    auction.version = Auction::VERSION;
+    auction.bump = *ctx.bumps.get("auction").unwrap();
+    auction.bid_escrow_bump = *ctx.bumps.get("bid_escrow").unwrap();
+    init_escrow(bid_escrow, auction.key(), bid_resource_id, location.key(), i64::MAX);
+
+    // Locked by the auction until settled.
+    location.owner = auction.key();
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CreateLocationAuction<'info> {
+    #[account(
+        init,
+        payer = seller,
+        space = Auction::LEN,
+        seeds = [
+            b"auction",
+            location.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        init,
+        payer = seller,
+        space = Storage::LEN,
+        seeds = [
+            b"auction-bid",
+            auction.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub bid_escrow: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = location.owner == seller.key() @ ValidationError::OwnerRequired,
+        constraint = location.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub location: Account<'info, Location>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_processor(ctx: Context<CreateProcessorAuction>, bid_resource_id: Pubkey, min_bid: i64, duration: i64, current_timestamp: i64) -> Result<()> {
+    let auction: &mut Account<Auction> = &mut ctx.accounts.auction;
+    let bid_escrow: &mut Account<Storage> = &mut ctx.accounts.bid_escrow;
+    let processor: &mut Account<Processor> = &mut ctx.accounts.processor;
+    let seller: &Signer = &ctx.accounts.seller;
+
+    require!(!processor.is_moving(current_timestamp), ValidationError::NotAllowedWhileMoving);
+    production_queue::require_no_jobs(&ctx.accounts.queue)?;
+
+    // What the processor made so far is the seller's, they claim it before it's put up.
+    processor.accrue(current_timestamp)?;
+    require!(processor.awaiting_units == 0 && processor.finished_units()? == 0, ValidationError::ProductionNotClaimed);
+
+    let terms = AuctionTerms { item: processor.key(), kind: AuctionKind::Processor, location_id: processor.location_id, bid_resource_id, min_bid, duration };
+    init_auction(auction, seller.key(), terms, current_timestamp)?;
+    auction.version = Auction::VERSION;
+    auction.bump = *ctx.bumps.get("auction").unwrap();
+    auction.bid_escrow_bump = *ctx.bumps.get("bid_escrow").unwrap();
+    init_escrow(bid_escrow, auction.key(), bid_resource_id, processor.location_id, i64::MAX);
+
+    // Locked by the auction until settled, the seller's storages no longer get its output and
+    // it doesn't produce for anyone in the meantime.
+    processor.owner = auction.key();
+    processor.linked_storages.clear();
+    if processor.paused_at.is_none() {
+        processor.pause(current_timestamp)?;
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CreateProcessorAuction<'info> {
+    #[account(
+        init,
+        payer = seller,
+        space = Auction::LEN,
+        seeds = [
+            b"auction",
+            processor.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        init,
+        payer = seller,
+        space = Storage::LEN,
+        seeds = [
+            b"auction-bid",
+            auction.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub bid_escrow: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = processor.owner == seller.key() @ ValidationError::OwnerRequired,
+        constraint = processor.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub processor: Account<'info, Processor>,
+    /// CHECK: The processor's production queue, which might not have been created. Only deserialized when it exists.
+    #[account(
+        seeds = [
+            b"production-queue",
+            processor.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub queue: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_goods(ctx: Context<CreateGoodsAuction>, amount: i64, bid_resource_id: Pubkey, min_bid: i64, duration: i64, current_timestamp: i64) -> Result<()> {
+    let auction: &mut Account<Auction> = &mut ctx.accounts.auction;
+    let bid_escrow: &mut Account<Storage> = &mut ctx.accounts.bid_escrow;
+    let goods_escrow: &mut Account<Storage> = &mut ctx.accounts.goods_escrow;
+    let seller_storage: &mut Account<Storage> = &mut ctx.accounts.seller_storage;
+    let seller: &Signer = &ctx.accounts.seller;
+
+    require!(amount > 0, ValidationError::InvalidInput);
+    require!(seller_storage.location_id(current_timestamp).is_some(), ValidationError::NotAllowedWhileMoving);
+
+    let location_id = seller_storage.location_id;
+    let terms = AuctionTerms { item: seller_storage.key(), kind: AuctionKind::Goods, location_id, bid_resource_id, min_bid, duration };
+    init_auction(auction, seller.key(), terms, current_timestamp)?;
+    auction.version = Auction::VERSION;
+    auction.goods_amount = amount;
+    auction.bump = *ctx.bumps.get("auction").unwrap();
+    auction.bid_escrow_bump = *ctx.bumps.get("bid_escrow").unwrap();
+    auction.goods_escrow_bump = *ctx.bumps.get("goods_escrow").unwrap();
+    init_escrow(bid_escrow, auction.key(), bid_resource_id, location_id, i64::MAX);
+    init_escrow(goods_escrow, auction.key(), seller_storage.resource_id, location_id, amount);
+
+    seller_storage.remove(amount)?;
+    goods_escrow.add(amount, location_id)?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CreateGoodsAuction<'info> {
+    #[account(
+        init,
+        payer = seller,
+        space = Auction::LEN,
+        seeds = [
+            b"auction",
+            seller_storage.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        init,
+        payer = seller,
+        space = Storage::LEN,
+        seeds = [
+            b"auction-bid",
+            auction.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub bid_escrow: Account<'info, Storage>,
+    #[account(
+        init,
+        payer = seller,
+        space = Storage::LEN,
+        seeds = [
+            b"auction-goods",
+            auction.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub goods_escrow: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = seller_storage.owner == seller.key() @ ValidationError::OwnerRequired,
+        constraint = seller_storage.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub seller_storage: Account<'info, Storage>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Bid ------------------------------------
+// An outbid bid moves to the refund escrow of its bidder, who claims it from there. The escrow is
+// made on the first refund, paid for by whoever outbids.
+pub fn place_bid(ctx: Context<PlaceBid>, amount: i64, current_timestamp: i64) -> Result<()> {
+    let auction: &mut Account<Auction> = &mut ctx.accounts.auction;
+    let bid_escrow: &mut Account<Storage> = &mut ctx.accounts.bid_escrow;
+    let bidder_storage: &mut Account<Storage> = &mut ctx.accounts.bidder_storage;
+    let bidder: &Signer = &ctx.accounts.bidder;
+
+    require!(!auction.has_ended(current_timestamp), ValidationError::AuctionEnded);
+    require!(bidder.key() != auction.seller, ValidationError::SelfTradeNotAllowed);
+    require!(amount >= auction.min_bid && amount > auction.highest_bid, ValidationError::BidTooLow);
+    require!(location::same_location_id(Some(auction.location_id), bidder_storage.location_id(current_timestamp)), ValidationError::DifferentLocations);
+
+    let location_id = auction.location_id;
+    let mut charge = amount;
+
+    if auction.has_bid() {
+        let refund = auction.highest_bid;
+
+        if auction.highest_bidder == bidder.key() {
+            // Raising your own bid only locks the difference.
+            charge = math::sub(charge, refund)?;
+        } else {
+            let info = ctx.accounts.refund_escrow.to_account_info();
+            if info.data_is_empty() {
+                let bump = *ctx.bumps.get("refund_escrow").unwrap();
+                create_refund_escrow(auction, &info, bidder, &ctx.accounts.system_program, bump)?;
+            }
+
+            let mut refund_escrow: Account<Storage> = Account::try_from(&info)?;
+            bid_escrow.remove(refund)?;
+            refund_escrow.add(refund, location_id)?;
+            refund_escrow.exit(ctx.program_id)?;
+        }
+    }
+
+    bidder_storage.remove(charge)?;
+    bid_escrow.add(charge, location_id)?;
+
+    auction.highest_bid = amount;
+    auction.highest_bidder = bidder.key();
+    auction.highest_bidder_storage = bidder_storage.key();
+
+    emit!(BidPlaced { auction: auction.key(), bidder: bidder.key(), amount });
+
+    Ok(())
+}
+
+fn create_refund_escrow<'info>(auction: &Account<'info, Auction>, escrow: &AccountInfo<'info>, payer: &Signer<'info>, system_program: &Program<'info, System>, bump: u8) -> Result<()> {
+    let auction_id = auction.key();
+    let seeds: &[&[u8]] = &[b"auction-refund", auction_id.as_ref(), auction.highest_bidder.as_ref(), &[bump]];
+    let create = system_program::CreateAccount { from: payer.to_account_info(), to: escrow.clone() };
+    let rent = Rent::get()?.minimum_balance(Storage::LEN);
+    system_program::create_account(
+        CpiContext::new_with_signer(system_program.to_account_info(), create, &[seeds]),
+        rent,
+        Storage::LEN as u64,
+        &crate::ID,
+    )?;
+
+    // Held for the outbid bidder, owned by the auction like its other escrows.
+    let refund_escrow = Storage {
+        version: Storage::VERSION,
+        owner: auction_id,
+        resource_id: auction.bid_resource_id,
+        location_id: auction.location_id,
+        amount: 0,
+        capacity: i64::MAX,
+        mobility_type: MobilityType::Fixed,
+        movement_speed: 0,
+        arrives_at: 0,
+    };
+    refund_escrow.try_serialize(&mut &mut escrow.try_borrow_mut_data()?[..])
+}
+
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"auction",
+            auction.item.as_ref(),
+        ],
+        bump = auction.bump,
+        constraint = auction.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        mut,
+        seeds = [
+            b"auction-bid",
+            auction.key().as_ref(),
+        ],
+        bump = auction.bid_escrow_bump,
+        constraint = bid_escrow.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub bid_escrow: Account<'info, Storage>,
+    /// CHECK: The refund escrow of the current highest bidder, only created and loaded when outbidding them.
+    #[account(
+        mut,
+        seeds = [
+            b"auction-refund",
+            auction.key().as_ref(),
+            auction.highest_bidder.as_ref(),
+        ],
+        bump,
+    )]
+    pub refund_escrow: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        constraint = bidder_storage.owner == bidder.key() @ ValidationError::OwnerRequired,
+        constraint = bidder_storage.resource_id == auction.bid_resource_id @ ValidationError::ResourceNotMatching,
+        constraint = bidder_storage.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub bidder_storage: Account<'info, Storage>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Also works after the auction was settled and closed, the escrow is closed once it's claimed.
+pub fn claim_bid_refund(ctx: Context<ClaimBidRefund>) -> Result<()> {
+    let refund_escrow: &mut Account<Storage> = &mut ctx.accounts.refund_escrow;
+    let bidder_storage: &mut Account<Storage> = &mut ctx.accounts.bidder_storage;
+    let bidder: &Signer = &ctx.accounts.bidder;
+
+    let amount = refund_escrow.amount;
+    refund_escrow.remove(amount)?;
+    bidder_storage.add(amount, refund_escrow.location_id)?;
+
+    emit!(BidRefunded { auction: refund_escrow.owner, bidder: bidder.key(), amount });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ClaimBidRefund<'info> {
+    #[account(
+        mut,
+        close = bidder,
+        seeds = [
+            b"auction-refund",
+            refund_escrow.owner.as_ref(),
+            bidder.key().as_ref(),
+        ],
+        bump,
+        constraint = refund_escrow.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub refund_escrow: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = bidder_storage.owner == bidder.key() @ ValidationError::OwnerRequired,
+        constraint = bidder_storage.resource_id == refund_escrow.resource_id @ ValidationError::ResourceNotMatching,
+        constraint = bidder_storage.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub bidder_storage: Account<'info, Storage>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+}
+
+// Settle ---------------------------------
+// Pays the seller and hands the item to the highest bidder, or back to the seller without bids.
+fn settle(auction: &Account<Auction>, bid_escrow: &mut Account<Storage>, seller_storage: &mut Account<Storage>, current_timestamp: i64) -> Result<Pubkey> {
+    require!(auction.has_ended(current_timestamp), ValidationError::AuctionNotEnded);
+
+    bid_escrow.remove(auction.highest_bid)?;
+    seller_storage.add(auction.highest_bid, auction.location_id)?;
+
+    let new_owner = auction.new_owner();
+
+    emit!(AuctionSettled {
+        auction: auction.key(),
+        item: auction.item,
+        kind: auction.kind,
+        seller: auction.seller,
+        winner: auction.highest_bidder,
+        price: auction.highest_bid,
+    });
+
+    Ok(new_owner)
+}
+
+pub fn settle_location(ctx: Context<SettleLocationAuction>, current_timestamp: i64) -> Result<()> {
+    let auction: &Account<Auction> = &ctx.accounts.auction;
+    let location: &mut Account<Location> = &mut ctx.accounts.location;
+
+    // Whatever occupies the location stays with its players, only the location changes hands.
+    location.owner = settle(auction, &mut ctx.accounts.bid_escrow, &mut ctx.accounts.seller_storage, current_timestamp)?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SettleLocationAuction<'info> {
+    #[account(
+        mut,
+        close = seller,
+        has_one = seller,
+        constraint = auction.kind == AuctionKind::Location @ ValidationError::InvalidInput,
+        constraint = settler.key() == auction.seller || settler.key() == auction.highest_bidder @ ValidationError::OwnerRequired,
+        constraint = auction.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        mut,
+        close = seller,
+        seeds = [
+            b"auction-bid",
+            auction.key().as_ref(),
+        ],
+        bump = auction.bid_escrow_bump,
+        constraint = bid_escrow.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub bid_escrow: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = seller_storage.owner == auction.seller @ ValidationError::OwnerRequired,
+        constraint = seller_storage.resource_id == auction.bid_resource_id @ ValidationError::ResourceNotMatching,
+        constraint = seller_storage.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub seller_storage: Account<'info, Storage>,
+    #[account(
+        mut,
+        address = auction.item,
+        constraint = location.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub location: Account<'info, Location>,
+    /// CHECK: Only receives the rent of the closed auction and escrow, verified by has_one on the auction.
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+    pub settler: Signer<'info>,
+}
+
+pub fn settle_processor(ctx: Context<SettleProcessorAuction>, current_timestamp: i64) -> Result<()> {
+    let auction: &Account<Auction> = &ctx.accounts.auction;
+    let processor: &mut Account<Processor> = &mut ctx.accounts.processor;
+    let location: &mut Account<Location> = &mut ctx.accounts.location;
+
+    let new_owner = settle(auction, &mut ctx.accounts.bid_escrow, &mut ctx.accounts.seller_storage, current_timestamp)?;
+
+    // Production picks up from the settlement, nothing accrues for the time it was auctioned.
+    processor.owner = new_owner;
+    processor.linked_storages.clear();
+    processor.resume(current_timestamp)?;
+    location.transfer(&processor.key(), new_owner)
+}
+
+#[derive(Accounts)]
+pub struct SettleProcessorAuction<'info> {
+    #[account(
+        mut,
+        close = seller,
+        has_one = seller,
+        constraint = auction.kind == AuctionKind::Processor @ ValidationError::InvalidInput,
+        constraint = settler.key() == auction.seller || settler.key() == auction.highest_bidder @ ValidationError::OwnerRequired,
+        constraint = auction.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        mut,
+        close = seller,
+        seeds = [
+            b"auction-bid",
+            auction.key().as_ref(),
+        ],
+        bump = auction.bid_escrow_bump,
+        constraint = bid_escrow.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub bid_escrow: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = seller_storage.owner == auction.seller @ ValidationError::OwnerRequired,
+        constraint = seller_storage.resource_id == auction.bid_resource_id @ ValidationError::ResourceNotMatching,
+        constraint = seller_storage.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub seller_storage: Account<'info, Storage>,
+    #[account(
+        mut,
+        address = auction.item,
+        constraint = processor.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub processor: Account<'info, Processor>,
+    #[account(
+        mut,
+        address = processor.location_id,
+        constraint = location.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub location: Account<'info, Location>,
+    /// CHECK: Only receives the rent of the closed auction and escrow, verified by has_one on the auction.
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+    pub settler: Signer<'info>,
+}
+
+pub fn settle_goods(ctx: Context<SettleGoodsAuction>, current_timestamp: i64) -> Result<()> {
+    let auction: &Account<Auction> = &ctx.accounts.auction;
+    let goods_escrow: &mut Account<Storage> = &mut ctx.accounts.goods_escrow;
+    let goods_to: &mut Account<Storage> = &mut ctx.accounts.goods_to;
+
+    let new_owner = settle(auction, &mut ctx.accounts.bid_escrow, &mut ctx.accounts.seller_storage, current_timestamp)?;
+
+    require!(goods_to.owner == new_owner, ValidationError::OwnerRequired);
+    require!(location::same_location_id(Some(auction.location_id), goods_to.location_id(current_timestamp)), ValidationError::DifferentLocations);
+
+    goods_escrow.remove(auction.goods_amount)?;
+    goods_to.add(auction.goods_amount, auction.location_id)
+}
+
+#[derive(Accounts)]
+pub struct SettleGoodsAuction<'info> {
+    #[account(
+        mut,
+        close = seller,
+        has_one = seller,
+        constraint = auction.kind == AuctionKind::Goods @ ValidationError::InvalidInput,
+        constraint = settler.key() == auction.seller || settler.key() == auction.highest_bidder @ ValidationError::OwnerRequired,
+        constraint = auction.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        mut,
+        close = seller,
+        seeds = [
+            b"auction-bid",
+            auction.key().as_ref(),
+        ],
+        bump = auction.bid_escrow_bump,
+        constraint = bid_escrow.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub bid_escrow: Account<'info, Storage>,
+    #[account(
+        mut,
+        close = seller,
+        seeds = [
+            b"auction-goods",
+            auction.key().as_ref(),
+        ],
+        bump = auction.goods_escrow_bump,
+        constraint = goods_escrow.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub goods_escrow: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = seller_storage.owner == auction.seller @ ValidationError::OwnerRequired,
+        constraint = seller_storage.resource_id == auction.bid_resource_id @ ValidationError::ResourceNotMatching,
+        constraint = seller_storage.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub seller_storage: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = goods_to.resource_id == goods_escrow.resource_id @ ValidationError::ResourceNotMatching,
+        constraint = goods_to.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub goods_to: Account<'info, Storage>,
+    /// CHECK: Only receives the rent of the closed auction and escrows, verified by has_one on the auction.
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+    pub settler: Signer<'info>,
+}
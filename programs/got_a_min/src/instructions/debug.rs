@@ -1,7 +1,26 @@
 use anchor_lang::prelude::*;
 
-use crate::state::{storage::*};
+use crate::state::{storage::*, Game};
+use crate::errors::ValidationError;
 
+// Debug and experimental instructions only exist in builds with the debug feature, and even then
+// the authority of the game has to sign, so a debug build can't be used as a free-money button.
+#[derive(Accounts)]
+pub struct DebugAuthority<'info> {
+    #[account(
+        seeds = [
+            b"game",
+            game.authority.as_ref(),
+        ],
+        bump = game.bump,
+        has_one = authority @ ValidationError::OwnerRequired,
+        constraint = game.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub game: Account<'info, Game>,
+    pub authority: Signer<'info>,
+}
+
+#[cfg(feature = "debug")]
 pub fn set_storage_amount(
     ctx: Context<DebugSetStorageAmount>,
     amount: i64,
@@ -13,6 +32,7 @@ pub fn set_storage_amount(
 
 #[derive(Accounts)]
 pub struct DebugSetStorageAmount<'info> {
-    #[account(mut)]
+    #[account(mut, constraint = storage.is_current() @ ValidationError::AccountOutdated)]
     pub storage: Account<'info, Storage>,
+    pub admin: DebugAuthority<'info>,
 }
@@ -4,6 +4,7 @@ use anchor_lang::prelude::*;
 
 pub fn create_game_tile(ctx: Context<CreateGameTile>, xy: [u8; 2]) -> Result<()> {
     let game_tile = &mut ctx.accounts.game_tile;
+    game_tile.version = GameTile::VERSION;
     game_tile.x = xy[0];
     game_tile.y = xy[1];
     game_tile.name = "unknown".to_string();
@@ -24,12 +25,15 @@ fn fake_rng(key: Pubkey) -> u8 {
 use std::hash::{Hasher};
 use std::collections::hash_map::DefaultHasher;
 
+use crate::events::{GameTileExplored, GameUpdated};
 use crate::errors::ValidationError;
-use crate::state::{Location, Storage};
+use crate::program::GotAMin;
+use crate::state::{Location, Game, BPS_DENOMINATOR, MAX_LEVEL};
 
 
 #[account]
 pub struct GameTile {
+    pub version: u8,
     pub x: u8,
     pub y: u8,
     pub tile_type: u8,
@@ -37,6 +41,16 @@ pub struct GameTile {
     pub bump: u8,
 }
 
+impl GameTile {
+    pub const VERSION: u8 = 1;
+
+    pub const LEN: usize = 8 + 1 + 1 + 1 + 1 + (4 + 32) + 1;
+
+    pub fn is_current(&self) -> bool {
+        self.version == Self::VERSION
+    }
+}
+
 /*fn pos_seed<'info>(param1: u8, param2: u8) -> &'info [u8] {
     let bytes = [param1, param2];
     &bytes
@@ -50,7 +64,7 @@ pub struct CreateGameTile<'info> {
     #[account(
         init, 
         payer = owner, 
-        space = 8 + 1 + 1 + 1 + (4 + 32) + 1,
+        space = GameTile::LEN,
         seeds = [
             b"game-tile", 
             owner.key().as_ref(),
@@ -81,16 +95,18 @@ pub fn explore_game_tile(ctx: Context<ExploreGameTile>, xy: [u8; 2]) -> Result<(
     require!(game_tile.tile_type == 0, ValidationError::ExperimentalError);
 
     let tile_type = fake_rng(game_tile.key());
-    if tile_type % 28 == 0 {
+    if tile_type.is_multiple_of(28) {
         game_tile.name = "planet".to_string();
         game_tile.tile_type = 3;
-    } else if tile_type % 5 == 0 {
+    } else if tile_type.is_multiple_of(5) {
         game_tile.name = "asteroid".to_string();
         game_tile.tile_type = 2;
     } else {
         game_tile.name = "space".to_string();
         game_tile.tile_type = 1;
     }
+
+    emit!(GameTileExplored { game_tile: game_tile.key(), x: xy[0], y: xy[1], tile_type: game_tile.tile_type });
     
     Ok(())
 }
@@ -108,6 +124,7 @@ pub struct ExploreGameTile<'info> {
             &xy,
         ],
         bump = game_tile.bump,
+        constraint = game_tile.is_current() @ ValidationError::AccountOutdated,
     )]
     pub game_tile: Account<'info, GameTile>,
     #[account(
@@ -118,6 +135,123 @@ pub struct ExploreGameTile<'info> {
             &xy,
         ],
         bump = location.bump,
+        constraint = location.is_current() @ ValidationError::AccountOutdated,
     )]
     pub location: Account<'info, Location>,
 }
+
+// Game ----------------------------------
+pub fn init_game(ctx: Context<InitGame>, swap_fee_bps: u16) -> Result<()> {
+    let game: &mut Account<Game> = &mut ctx.accounts.game;
+    let authority: &Signer = &ctx.accounts.authority;
+
+    require!(swap_fee_bps as i64 <= BPS_DENOMINATOR, ValidationError::InvalidInput);
+
+    game.version = Game::VERSION;
+    game.authority = authority.key();
+    game.swap_fee_bps = swap_fee_bps;
+    game.level_throughput_bps = vec!(BPS_DENOMINATOR as u32);
+    game.upgrade_resource_id = Pubkey::default();
+    game.upgrade_cost_base = 0;
+    game.retool_delay = 0;
+    game.max_durability = 0;
+    game.repair_resource_id = Pubkey::default();
+    game.repair_cost_per_point = 0;
+    game.bump = *ctx.bumps.get("game").unwrap();
+
+    Ok(())
+}
+
+// Only the upgrade authority of the program can start a game, so processors and pools can't pick their own rules.
+#[derive(Accounts)]
+pub struct InitGame<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = Game::LEN,
+        seeds = [
+            b"game",
+            authority.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub game: Account<'info, Game>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()) @ ValidationError::OwnerRequired)]
+    pub program: Program<'info, GotAMin>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(authority.key()) @ ValidationError::OwnerRequired)]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn update_game_fees(ctx: Context<UpdateGame>, swap_fee_bps: u16) -> Result<()> {
+    let game: &mut Account<Game> = &mut ctx.accounts.game;
+
+    require!(swap_fee_bps as i64 <= BPS_DENOMINATOR, ValidationError::InvalidInput);
+
+    game.swap_fee_bps = swap_fee_bps;
+
+    emit!(GameUpdated { game: game.key() });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateGame<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"game",
+            authority.key().as_ref(),
+        ],
+        bump = game.bump,
+        has_one = authority @ ValidationError::OwnerRequired,
+        constraint = game.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub game: Account<'info, Game>,
+    pub authority: Signer<'info>,
+}
+
+pub fn update_game_levels(ctx: Context<UpdateGame>, level_throughput_bps: Vec<u32>, upgrade_resource_id: Pubkey, upgrade_cost_base: i64) -> Result<()> {
+    let game: &mut Account<Game> = &mut ctx.accounts.game;
+
+    require!(!level_throughput_bps.is_empty() && level_throughput_bps.len() <= MAX_LEVEL, ValidationError::InvalidInput);
+    require!(level_throughput_bps.iter().all(|bps| *bps > 0), ValidationError::InvalidInput);
+    require!(upgrade_cost_base >= 0, ValidationError::InvalidInput);
+
+    game.level_throughput_bps = level_throughput_bps;
+    game.upgrade_resource_id = upgrade_resource_id;
+    game.upgrade_cost_base = upgrade_cost_base;
+
+    emit!(GameUpdated { game: game.key() });
+
+    Ok(())
+}
+
+pub fn update_game_retool_delay(ctx: Context<UpdateGame>, retool_delay: i64) -> Result<()> {
+    let game: &mut Account<Game> = &mut ctx.accounts.game;
+
+    require!(retool_delay >= 0, ValidationError::InvalidInput);
+
+    game.retool_delay = retool_delay;
+
+    emit!(GameUpdated { game: game.key() });
+
+    Ok(())
+}
+
+pub fn update_game_wear(ctx: Context<UpdateGame>, max_durability: i64, repair_resource_id: Pubkey, repair_cost_per_point: i64) -> Result<()> {
+    let game: &mut Account<Game> = &mut ctx.accounts.game;
+
+    require!(max_durability >= 0, ValidationError::InvalidInput);
+    require!(repair_cost_per_point >= 0, ValidationError::InvalidInput);
+
+    game.max_durability = max_durability;
+    game.repair_resource_id = repair_resource_id;
+    game.repair_cost_per_point = repair_cost_per_point;
+
+    emit!(GameUpdated { game: game.key() });
+
+    Ok(())
+}
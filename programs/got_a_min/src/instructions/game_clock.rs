@@ -0,0 +1,150 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash;
+use anchor_lang::{Discriminator, InstructionData};
+
+use crate::state::GameClock;
+use crate::errors::ValidationError;
+use crate::program::GotAMin;
+#[cfg(feature = "test-clock")]
+use crate::math;
+
+// Anchor 0.25 dispatches every instruction fn of the program module whatever its cfg, so the clock's
+// instructions aren't among them. Test-clock builds route them from the program's fallback instead,
+// other builds don't know them at all.
+#[cfg(feature = "test-clock")]
+pub fn dispatch(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> Result<()> {
+    let (sighash, mut args) = data.split_at(8);
+
+    if sighash == InitGameClockArgs::discriminator() {
+        let InitGameClockArgs { timestamp } = deserialize(&mut args)?;
+        run(program_id, accounts, args, |ctx| init(ctx, timestamp))
+    } else if sighash == AdvanceGameClockArgs::discriminator() {
+        let AdvanceGameClockArgs { seconds } = deserialize(&mut args)?;
+        run(program_id, accounts, args, |ctx| advance(ctx, seconds))
+    } else {
+        Err(ErrorCode::InstructionFallbackNotFound.into())
+    }
+}
+
+#[cfg(not(feature = "test-clock"))]
+pub fn dispatch(_program_id: &Pubkey, _accounts: &[AccountInfo], _data: &[u8]) -> Result<()> {
+    Err(ErrorCode::InstructionFallbackNotFound.into())
+}
+
+#[cfg(feature = "test-clock")]
+fn deserialize<T: AnchorDeserialize>(args: &mut &[u8]) -> Result<T> {
+    T::deserialize(args).map_err(|_| ErrorCode::InstructionDidNotDeserialize.into())
+}
+
+// What Anchor generates for its own instructions: accounts in, handler, accounts written back.
+#[cfg(feature = "test-clock")]
+fn run<'info, T: Accounts<'info> + AccountsExit<'info>>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'info>],
+    args: &[u8],
+    handler: impl FnOnce(Context<'_, '_, '_, 'info, T>) -> Result<()>,
+) -> Result<()> {
+    let mut bumps = std::collections::BTreeMap::new();
+    let mut reallocs = std::collections::BTreeSet::new();
+    let mut remaining_accounts: &[AccountInfo<'info>] = accounts;
+    let mut accounts = T::try_accounts(program_id, &mut remaining_accounts, args, &mut bumps, &mut reallocs)?;
+
+    handler(Context::new(program_id, &mut accounts, remaining_accounts, bumps))?;
+
+    accounts.exit(program_id)
+}
+
+// The arguments of the clock's instructions, tagged like Anchor tags its own.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct InitGameClockArgs {
+    pub timestamp: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct AdvanceGameClockArgs {
+    pub seconds: i64,
+}
+
+impl Discriminator for InitGameClockArgs {
+    fn discriminator() -> [u8; 8] {
+        sighash("init_game_clock")
+    }
+}
+
+impl Discriminator for AdvanceGameClockArgs {
+    fn discriminator() -> [u8; 8] {
+        sighash("advance_game_clock")
+    }
+}
+
+impl InstructionData for InitGameClockArgs {
+    fn data(&self) -> Vec<u8> {
+        [&Self::discriminator()[..], &self.try_to_vec().unwrap()].concat()
+    }
+}
+
+impl InstructionData for AdvanceGameClockArgs {
+    fn data(&self) -> Vec<u8> {
+        [&Self::discriminator()[..], &self.try_to_vec().unwrap()].concat()
+    }
+}
+
+fn sighash(name: &str) -> [u8; 8] {
+    let mut sighash = [0; 8];
+    sighash.copy_from_slice(&hash::hash(format!("global:{}", name).as_bytes()).to_bytes()[..8]);
+    sighash
+}
+
+// There's one clock for the program, set up by its upgrade authority like the game.
+#[cfg(feature = "test-clock")]
+pub fn init(ctx: Context<InitGameClock>, timestamp: i64) -> Result<()> {
+    let game_clock: &mut Account<GameClock> = &mut ctx.accounts.game_clock;
+
+    game_clock.authority = ctx.accounts.authority.key();
+    game_clock.timestamp = timestamp;
+    game_clock.bump = *ctx.bumps.get("game_clock").unwrap();
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitGameClock<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = GameClock::LEN,
+        seeds = [b"game-clock"],
+        bump,
+    )]
+    pub game_clock: Account<'info, GameClock>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()) @ ValidationError::OwnerRequired)]
+    pub program: Program<'info, GotAMin>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(authority.key()) @ ValidationError::OwnerRequired)]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+// Time only moves forward, like the real clock.
+#[cfg(feature = "test-clock")]
+pub fn advance(ctx: Context<AdvanceGameClock>, seconds: i64) -> Result<()> {
+    let game_clock: &mut Account<GameClock> = &mut ctx.accounts.game_clock;
+
+    require!(seconds >= 0, ValidationError::InvalidInput);
+    game_clock.timestamp = math::add(game_clock.timestamp, seconds)?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AdvanceGameClock<'info> {
+    #[account(
+        mut,
+        seeds = [b"game-clock"],
+        bump = game_clock.bump,
+        has_one = authority @ ValidationError::OwnerRequired,
+    )]
+    pub game_clock: Account<'info, GameClock>,
+    pub authority: Signer<'info>,
+}
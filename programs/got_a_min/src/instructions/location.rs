@@ -2,12 +2,15 @@ use anchor_lang::prelude::*;
 use std::hash::{Hasher, Hash};
 use std::collections::hash_map::DefaultHasher;
 use crate::state::location::*;
+use crate::events::LocationInitialized;
+use got_a_min_rules::movement;
 
 
 pub fn init(ctx: Context<InitLocation>, x: i64, y: i64, capacity: i64, location_type: LocationType) -> Result<()> {
     let location: &mut Account<Location> = &mut ctx.accounts.location;
     let owner: &Signer = &ctx.accounts.owner;
 
+    location.version = Location::VERSION;
     location.owner = *owner.key;
     location.pos_x = x;
     location.pos_y = y;
@@ -17,7 +20,7 @@ pub fn init(ctx: Context<InitLocation>, x: i64, y: i64, capacity: i64, location_
     location.location_type = location_type;
     location.bump = *ctx.bumps.get("location").unwrap();
 
-    msg!("Location {}x{} init", x, y);
+    emit!(LocationInitialized { location: location.key(), owner: location.owner, x, y, capacity, location_type: location.location_type.clone() });
 
     Ok(())
 }
@@ -28,10 +31,7 @@ pub fn register_move(owner: &Signer, from_location: &mut Account<Location>, to_l
 }
 
 pub fn same_location_id(location_id_1: Option<Pubkey>, location_id_2: Option<Pubkey>) -> bool {
-    match (location_id_1, location_id_2) {
-        (Some(l1), Some(l2)) => l1 == l2,
-        _ => false,
-    }
+    movement::same_location(location_id_1, location_id_2)
 }
 
 pub fn fake_rng(key: Pubkey) -> u8 {
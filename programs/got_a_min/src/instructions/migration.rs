@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+
+use crate::events::AccountMigrated;
+use crate::errors::ValidationError;
+
+const DISCRIMINATOR_LENGTH: usize = 8;
+const VERSION_LENGTH: usize = 1;
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    /// CHECK: Checked against the discriminator of the migrated type, it doesn't deserialize before.
+    #[account(mut, owner = crate::ID)]
+    pub account: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Brings an account of type T to [version] in place. The version byte follows the discriminator,
+// accounts from before it existed start with their first field there, so migrating them inserts
+// the byte and grows the account by one, with [payer] covering the extra rent. The first field
+// can hold any byte, so the old layout is told apart by its length, [fits] says which lengths the
+// current layout allocates.
+pub fn migrate<T: AccountDeserialize + Discriminator>(ctx: Context<MigrateAccount>, version: u8, fits: fn(usize) -> bool) -> Result<()> {
+    let account = ctx.accounts.account.to_account_info();
+    let data = account.try_borrow_data()?.to_vec();
+    require!(data.len() > DISCRIMINATOR_LENGTH, ErrorCode::AccountDiscriminatorNotFound);
+    require!(data[..DISCRIMINATOR_LENGTH] == T::discriminator(), ErrorCode::AccountDiscriminatorMismatch);
+    require!(!fits(data.len()), ValidationError::AccountUpToDate);
+    require!(fits(data.len() + VERSION_LENGTH), ErrorCode::AccountDidNotDeserialize);
+
+    let mut migrated = data[..DISCRIMINATOR_LENGTH].to_vec();
+    migrated.push(version);
+    migrated.extend_from_slice(&data[DISCRIMINATOR_LENGTH..]);
+    T::try_deserialize(&mut &migrated[..])?;
+
+    let rent = Rent::get()?.minimum_balance(migrated.len()).saturating_sub(account.lamports());
+    if rent > 0 {
+        let transfer = system_program::Transfer { from: ctx.accounts.payer.to_account_info(), to: account.clone() };
+        system_program::transfer(CpiContext::new(ctx.accounts.system_program.to_account_info(), transfer), rent)?;
+    }
+    account.realloc(migrated.len(), false)?;
+    account.try_borrow_mut_data()?.copy_from_slice(&migrated);
+
+    emit!(AccountMigrated { account: account.key(), version });
+
+    Ok(())
+}
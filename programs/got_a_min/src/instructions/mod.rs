@@ -7,13 +7,62 @@ pub use storage::*;
 pub use stuff::*;
 pub use unit::*;
 
+// Named rather than globbed, their handlers share names like init or claim. The #[program] macro
+// looks for the client accounts of every instruction at the crate root, so those come along.
+pub use auction::{ClaimBidRefund, CreateGoodsAuction, CreateLocationAuction, CreateProcessorAuction, PlaceBid, SettleGoodsAuction, SettleLocationAuction, SettleProcessorAuction};
+pub(crate) use auction::{__client_accounts_claim_bid_refund, __client_accounts_create_goods_auction, __client_accounts_create_location_auction, __client_accounts_create_processor_auction, __client_accounts_place_bid, __client_accounts_settle_goods_auction, __client_accounts_settle_location_auction, __client_accounts_settle_processor_auction};
+#[cfg(feature = "cpi")]
+pub(crate) use auction::{__cpi_client_accounts_claim_bid_refund, __cpi_client_accounts_create_goods_auction, __cpi_client_accounts_create_location_auction, __cpi_client_accounts_create_processor_auction, __cpi_client_accounts_place_bid, __cpi_client_accounts_settle_goods_auction, __cpi_client_accounts_settle_location_auction, __cpi_client_accounts_settle_processor_auction};
+pub use migration::MigrateAccount;
+pub(crate) use migration::__client_accounts_migrate_account;
+#[cfg(feature = "cpi")]
+pub(crate) use migration::__cpi_client_accounts_migrate_account;
+pub use offer::{AcceptOffer, CancelOffer, CreateOffer};
+pub(crate) use offer::{__client_accounts_accept_offer, __client_accounts_cancel_offer, __client_accounts_create_offer};
+#[cfg(feature = "cpi")]
+pub(crate) use offer::{__cpi_client_accounts_accept_offer, __cpi_client_accounts_cancel_offer, __cpi_client_accounts_create_offer};
+pub use order_book::{CancelOrder, ClaimOrder, InitOrderBook, PlaceOrder};
+pub(crate) use order_book::{__client_accounts_cancel_order, __client_accounts_claim_order, __client_accounts_init_order_book, __client_accounts_place_order};
+#[cfg(feature = "cpi")]
+pub(crate) use order_book::{__cpi_client_accounts_cancel_order, __cpi_client_accounts_claim_order, __cpi_client_accounts_init_order_book, __cpi_client_accounts_place_order};
+pub use pool::{AddLiquidity, CreatePool, RemoveLiquidity, Swap};
+pub(crate) use pool::{__client_accounts_add_liquidity, __client_accounts_create_pool, __client_accounts_remove_liquidity, __client_accounts_swap};
+#[cfg(feature = "cpi")]
+pub(crate) use pool::{__cpi_client_accounts_add_liquidity, __cpi_client_accounts_create_pool, __cpi_client_accounts_remove_liquidity, __cpi_client_accounts_swap};
+pub use production_queue::{CancelJob, ClaimJobs, EnqueueJob, InitProductionQueue};
+pub(crate) use production_queue::{__client_accounts_cancel_job, __client_accounts_claim_jobs, __client_accounts_enqueue_job, __client_accounts_init_production_queue};
+#[cfg(feature = "cpi")]
+pub(crate) use production_queue::{__cpi_client_accounts_cancel_job, __cpi_client_accounts_claim_jobs, __cpi_client_accounts_enqueue_job, __cpi_client_accounts_init_production_queue};
+pub use shipment::ReceiveShipment;
+pub(crate) use shipment::__client_accounts_receive_shipment;
+#[cfg(feature = "cpi")]
+pub(crate) use shipment::__cpi_client_accounts_receive_shipment;
+pub use trade_route::{CloseTradeRoute, CreateTradeRoute, RunTradeRoute};
+pub(crate) use trade_route::{__client_accounts_close_trade_route, __client_accounts_create_trade_route, __client_accounts_run_trade_route};
+#[cfg(feature = "cpi")]
+pub(crate) use trade_route::{__cpi_client_accounts_close_trade_route, __cpi_client_accounts_create_trade_route, __cpi_client_accounts_run_trade_route};
+pub use transfer::{InitTransferAllowList, TransferToPlayer, UpdateTransferAllowList};
+pub(crate) use transfer::{__client_accounts_init_transfer_allow_list, __client_accounts_transfer_to_player, __client_accounts_update_transfer_allow_list};
+#[cfg(feature = "cpi")]
+pub(crate) use transfer::{__cpi_client_accounts_init_transfer_allow_list, __cpi_client_accounts_transfer_to_player, __cpi_client_accounts_update_transfer_allow_list};
+
+pub mod auction;
 pub mod game;
+pub mod game_clock;
 pub mod location;
 pub mod map;
+pub mod migration;
+pub mod offer;
+pub mod order_book;
+pub mod pool;
+pub mod production_queue;
 pub mod resource;
 pub mod processor;
+pub mod shipment;
 pub mod storage;
 pub mod stuff;
+pub mod trade_route;
+pub mod transfer;
 pub mod unit;
 
 // ----
@@ -0,0 +1,212 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{offer::*, storage::*};
+use crate::instructions::location;
+use crate::events::{OfferAccepted, OfferCancelled, OfferCreated};
+use crate::errors::ValidationError;
+
+pub fn create(ctx: Context<CreateOffer>, offered_amount: i64, requested_resource_id: Pubkey, requested_amount: i64, current_timestamp: i64) -> Result<()> {
+    let offer: &mut Account<Offer> = &mut ctx.accounts.offer;
+    let escrow: &mut Account<Storage> = &mut ctx.accounts.escrow;
+    let maker_storage: &mut Account<Storage> = &mut ctx.accounts.maker_storage;
+
+    require!(offered_amount > 0, ValidationError::InvalidInput);
+    require!(requested_amount > 0, ValidationError::InvalidInput);
+    // Both sides of a same-resource trade could be one storage of the taker, see [AcceptOffer].
+    require!(requested_resource_id != maker_storage.resource_id, ValidationError::InvalidInput);
+
+    let location_id = maker_storage.location_id(current_timestamp);
+    require!(location_id.is_some(), ValidationError::NotAllowedWhileMoving);
+
+    offer.version = Offer::VERSION;
+    offer.maker = maker_storage.owner;
+    offer.location_id = maker_storage.location_id;
+    offer.offered_resource_id = maker_storage.resource_id;
+    offer.offered_amount = offered_amount;
+    offer.requested_resource_id = requested_resource_id;
+    offer.requested_amount = requested_amount;
+    offer.escrow_bump = *ctx.bumps.get("escrow").unwrap();
+
+    escrow.version = Storage::VERSION;
+    escrow.owner = offer.key();
+    escrow.resource_id = offer.offered_resource_id;
+    escrow.location_id = offer.location_id;
+    escrow.amount = 0;
+    escrow.capacity = offered_amount;
+    escrow.mobility_type = MobilityType::Fixed;
+    escrow.movement_speed = 0;
+    escrow.arrives_at = 0;
+
+    maker_storage.remove(offered_amount)?;
+    escrow.add(offered_amount, offer.location_id)?;
+
+    emit!(OfferCreated {
+        offer: offer.key(),
+        maker: offer.maker,
+        location_id: offer.location_id,
+        offered_resource_id: offer.offered_resource_id,
+        offered_amount,
+        requested_resource_id,
+        requested_amount,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CreateOffer<'info> {
+    #[account(init, payer = maker, space = Offer::LEN)]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        init,
+        payer = maker,
+        space = Storage::LEN,
+        seeds = [
+            b"offer-escrow",
+            offer.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub escrow: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = maker_storage.owner == maker.key() @ ValidationError::OwnerRequired,
+        constraint = maker_storage.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub maker_storage: Account<'info, Storage>,
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn accept(ctx: Context<AcceptOffer>, current_timestamp: i64) -> Result<()> {
+    let offer: &Account<Offer> = &ctx.accounts.offer;
+    let escrow: &mut Account<Storage> = &mut ctx.accounts.escrow;
+    let maker_storage: &mut Account<Storage> = &mut ctx.accounts.maker_storage;
+    let taker_storage_from: &mut Account<Storage> = &mut ctx.accounts.taker_storage_from;
+    let taker_storage_to: &mut Account<Storage> = &mut ctx.accounts.taker_storage_to;
+    let taker: &Signer = &ctx.accounts.taker;
+
+    require!(maker_storage.resource_id == offer.requested_resource_id, ValidationError::ResourceNotMatching);
+    require!(taker_storage_from.resource_id == offer.requested_resource_id, ValidationError::ResourceNotMatching);
+    require!(taker_storage_to.resource_id == offer.offered_resource_id, ValidationError::ResourceNotMatching);
+
+    let offer_location_id = Some(offer.location_id);
+    require!(location::same_location_id(offer_location_id, maker_storage.location_id(current_timestamp)), ValidationError::DifferentLocations);
+    require!(location::same_location_id(offer_location_id, taker_storage_from.location_id(current_timestamp)), ValidationError::DifferentLocations);
+    require!(location::same_location_id(offer_location_id, taker_storage_to.location_id(current_timestamp)), ValidationError::DifferentLocations);
+
+    taker_storage_from.remove(offer.requested_amount)?;
+    maker_storage.add(offer.requested_amount, offer.location_id)?;
+
+    escrow.remove(offer.offered_amount)?;
+    taker_storage_to.add(offer.offered_amount, offer.location_id)?;
+
+    emit!(OfferAccepted {
+        offer: offer.key(),
+        maker: offer.maker,
+        taker: taker.key(),
+        offered_amount: offer.offered_amount,
+        requested_amount: offer.requested_amount,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AcceptOffer<'info> {
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        constraint = offer.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        mut,
+        close = maker,
+        seeds = [
+            b"offer-escrow",
+            offer.key().as_ref(),
+        ],
+        bump = offer.escrow_bump,
+        constraint = escrow.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub escrow: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = maker_storage.owner == offer.maker @ ValidationError::OwnerRequired,
+        constraint = maker_storage.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub maker_storage: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = taker_storage_from.key() != maker_storage.key() @ ValidationError::SameStorage,
+        constraint = taker_storage_from.owner == taker.key() @ ValidationError::OwnerRequired,
+        constraint = taker_storage_from.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub taker_storage_from: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = taker_storage_to.key() != taker_storage_from.key() @ ValidationError::SameStorage,
+        constraint = taker_storage_to.owner == taker.key() @ ValidationError::OwnerRequired,
+        constraint = taker_storage_to.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub taker_storage_to: Account<'info, Storage>,
+    /// CHECK: Only receives the rent of the closed offer and escrow, verified by has_one on the offer.
+    #[account(mut)]
+    pub maker: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub taker: Signer<'info>,
+}
+
+pub fn cancel(ctx: Context<CancelOffer>, current_timestamp: i64) -> Result<()> {
+    let offer: &Account<Offer> = &ctx.accounts.offer;
+    let escrow: &mut Account<Storage> = &mut ctx.accounts.escrow;
+    let maker_storage: &mut Account<Storage> = &mut ctx.accounts.maker_storage;
+
+    require!(maker_storage.resource_id == offer.offered_resource_id, ValidationError::ResourceNotMatching);
+    require!(location::same_location_id(Some(offer.location_id), maker_storage.location_id(current_timestamp)), ValidationError::DifferentLocations);
+
+    let amount = escrow.amount;
+    escrow.remove(amount)?;
+    maker_storage.add(amount, offer.location_id)?;
+
+    emit!(OfferCancelled {
+        offer: offer.key(),
+        maker: offer.maker,
+        returned_amount: amount,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CancelOffer<'info> {
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker @ ValidationError::OwnerRequired,
+        constraint = offer.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub offer: Account<'info, Offer>,
+    #[account(
+        mut,
+        close = maker,
+        seeds = [
+            b"offer-escrow",
+            offer.key().as_ref(),
+        ],
+        bump = offer.escrow_bump,
+        constraint = escrow.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub escrow: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = maker_storage.owner == maker.key() @ ValidationError::OwnerRequired,
+        constraint = maker_storage.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub maker_storage: Account<'info, Storage>,
+    #[account(mut)]
+    pub maker: Signer<'info>,
+}
@@ -0,0 +1,456 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+
+use crate::state::{order_book::*, storage::*, Location};
+use crate::instructions::location;
+use crate::events::{OrderCancelled, OrderClaimed, OrderFilled, OrderPlaced};
+use crate::errors::ValidationError;
+use crate::math;
+
+pub fn init(ctx: Context<InitOrderBook>, base_resource_id: Pubkey, quote_resource_id: Pubkey) -> Result<()> {
+    let order_book: &mut Account<OrderBook> = &mut ctx.accounts.order_book;
+    let base_vault: &mut Account<Storage> = &mut ctx.accounts.base_vault;
+    let quote_vault: &mut Account<Storage> = &mut ctx.accounts.quote_vault;
+    let location: &Account<Location> = &ctx.accounts.location;
+
+    require!(base_resource_id != quote_resource_id, ValidationError::InvalidInput);
+
+    order_book.version = OrderBook::VERSION;
+    order_book.location_id = location.key();
+    order_book.base_resource_id = base_resource_id;
+    order_book.quote_resource_id = quote_resource_id;
+    order_book.next_order_id = 0;
+    order_book.bids = vec!();
+    order_book.asks = vec!();
+    order_book.bump = *ctx.bumps.get("order_book").unwrap();
+    order_book.base_vault_bump = *ctx.bumps.get("base_vault").unwrap();
+    order_book.quote_vault_bump = *ctx.bumps.get("quote_vault").unwrap();
+
+    init_vault(base_vault, order_book.key(), base_resource_id, location.key());
+    init_vault(quote_vault, order_book.key(), quote_resource_id, location.key());
+
+    Ok(())
+}
+
+fn init_vault(vault: &mut Account<Storage>, owner: Pubkey, resource_id: Pubkey, location_id: Pubkey) {
+    vault.version = Storage::VERSION;
+    vault.owner = owner;
+    vault.resource_id = resource_id;
+    vault.location_id = location_id;
+    vault.amount = 0;
+    vault.capacity = i64::MAX;
+    vault.mobility_type = MobilityType::Fixed;
+    vault.movement_speed = 0;
+    vault.arrives_at = 0;
+}
+
+#[derive(Accounts)]
+#[instruction(base_resource_id: Pubkey, quote_resource_id: Pubkey)]
+pub struct InitOrderBook<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = OrderBook::space(0),
+        seeds = [
+            b"order-book",
+            location.key().as_ref(),
+            base_resource_id.as_ref(),
+            quote_resource_id.as_ref(),
+        ],
+        bump,
+    )]
+    pub order_book: Account<'info, OrderBook>,
+    #[account(
+        init,
+        payer = owner,
+        space = Storage::LEN,
+        seeds = [
+            b"order-book-vault",
+            order_book.key().as_ref(),
+            base_resource_id.as_ref(),
+        ],
+        bump,
+    )]
+    pub base_vault: Account<'info, Storage>,
+    #[account(
+        init,
+        payer = owner,
+        space = Storage::LEN,
+        seeds = [
+            b"order-book-vault",
+            order_book.key().as_ref(),
+            quote_resource_id.as_ref(),
+        ],
+        bump,
+    )]
+    pub quote_vault: Account<'info, Storage>,
+    #[account(constraint = location.is_current() @ ValidationError::AccountOutdated)]
+    pub location: Account<'info, Location>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Matches against resting orders first and rests whatever is left. What a maker gets from a fill
+// stays in the vaults until they claim it, so a maker storage that moved or filled up since the
+// order was placed doesn't block takers. The owner's own orders are passed over, not matched.
+pub fn place(ctx: Context<PlaceOrder>, side: OrderSide, price: i64, amount: i64, current_timestamp: i64) -> Result<()> {
+    let order_book: &mut Account<OrderBook> = &mut ctx.accounts.order_book;
+    let base_vault: &mut Account<Storage> = &mut ctx.accounts.base_vault;
+    let quote_vault: &mut Account<Storage> = &mut ctx.accounts.quote_vault;
+    let base_storage: &mut Account<Storage> = &mut ctx.accounts.base_storage;
+    let quote_storage: &mut Account<Storage> = &mut ctx.accounts.quote_storage;
+    let owner: &Signer = &ctx.accounts.owner;
+
+    require!(price > 0, ValidationError::InvalidInput);
+    require!(amount > 0, ValidationError::InvalidInput);
+
+    let book_id = order_book.key();
+    let location_id = order_book.location_id;
+    require!(location::same_location_id(Some(location_id), base_storage.location_id(current_timestamp)), ValidationError::DifferentLocations);
+    require!(location::same_location_id(Some(location_id), quote_storage.location_id(current_timestamp)), ValidationError::DifferentLocations);
+
+    let mut remaining = amount;
+
+    match side {
+        OrderSide::Bid => {
+            while remaining > 0 {
+                let maker = match order_book.asks.iter_mut().find(|o| o.amount > 0 && o.owner != owner.key()) {
+                    Some(maker) if maker.price <= price => maker,
+                    _ => break,
+                };
+
+                let fill = remaining.min(maker.amount);
+                let cost = math::mul(fill, maker.price)?;
+
+                quote_storage.remove(cost)?;
+                quote_vault.add(cost, location_id)?;
+                base_vault.remove(fill)?;
+                base_storage.add(fill, location_id)?;
+
+                emit!(OrderFilled { order_book: book_id, order_id: maker.id, maker: maker.owner, taker: owner.key(), side: OrderSide::Ask, price: maker.price, amount: fill });
+
+                maker.amount = math::sub(maker.amount, fill)?;
+                maker.unclaimed = math::add(maker.unclaimed, cost)?;
+                remaining = math::sub(remaining, fill)?;
+            }
+
+            if remaining > 0 {
+                let locked = math::mul(remaining, price)?;
+                quote_storage.remove(locked)?;
+                quote_vault.add(locked, location_id)?;
+            }
+        },
+        OrderSide::Ask => {
+            while remaining > 0 {
+                let maker = match order_book.bids.iter_mut().find(|o| o.amount > 0 && o.owner != owner.key()) {
+                    Some(maker) if maker.price >= price => maker,
+                    _ => break,
+                };
+
+                let fill = remaining.min(maker.amount);
+                let proceeds = math::mul(fill, maker.price)?;
+
+                base_storage.remove(fill)?;
+                base_vault.add(fill, location_id)?;
+                quote_vault.remove(proceeds)?;
+                quote_storage.add(proceeds, location_id)?;
+
+                emit!(OrderFilled { order_book: book_id, order_id: maker.id, maker: maker.owner, taker: owner.key(), side: OrderSide::Bid, price: maker.price, amount: fill });
+
+                maker.amount = math::sub(maker.amount, fill)?;
+                maker.unclaimed = math::add(maker.unclaimed, fill)?;
+                remaining = math::sub(remaining, fill)?;
+            }
+
+            if remaining > 0 {
+                base_storage.remove(remaining)?;
+                base_vault.add(remaining, location_id)?;
+            }
+        },
+    }
+
+    if remaining > 0 {
+        require!(order_book.open_order_count() < ORDER_BOOK_MAX_ORDERS, ValidationError::OrderBookFull);
+
+        let order_id = order_book.next_order_id;
+        order_book.next_order_id = math::next_id(order_id)?;
+        order_book.insert(side, Order {
+            id: order_id,
+            owner: owner.key(),
+            base_storage: base_storage.key(),
+            quote_storage: quote_storage.key(),
+            price,
+            amount: remaining,
+            unclaimed: 0,
+        });
+        resize(order_book, owner, &ctx.accounts.system_program)?;
+
+        emit!(OrderPlaced { order_book: book_id, order_id, owner: owner.key(), side, price, amount: remaining });
+    }
+
+    Ok(())
+}
+
+// Fits the account to its orders, the payer covers the rent of a new order and gets it back
+// when one leaves.
+fn resize<'info>(order_book: &Account<'info, OrderBook>, payer: &Signer<'info>, system_program: &Program<'info, System>) -> Result<()> {
+    let account = order_book.to_account_info();
+    let space = OrderBook::space(order_book.order_count());
+    let rent = Rent::get()?.minimum_balance(space);
+    let lamports = account.lamports();
+
+    if rent > lamports {
+        let transfer = system_program::Transfer { from: payer.to_account_info(), to: account.clone() };
+        system_program::transfer(CpiContext::new(system_program.to_account_info(), transfer), rent - lamports)?;
+    } else if lamports > rent {
+        **account.try_borrow_mut_lamports()? -= lamports - rent;
+        **payer.to_account_info().try_borrow_mut_lamports()? += lamports - rent;
+    }
+    account.realloc(space, false)?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct PlaceOrder<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"order-book",
+            order_book.location_id.as_ref(),
+            order_book.base_resource_id.as_ref(),
+            order_book.quote_resource_id.as_ref(),
+        ],
+        bump = order_book.bump,
+        constraint = order_book.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub order_book: Account<'info, OrderBook>,
+    #[account(
+        mut,
+        seeds = [
+            b"order-book-vault",
+            order_book.key().as_ref(),
+            order_book.base_resource_id.as_ref(),
+        ],
+        bump = order_book.base_vault_bump,
+        constraint = base_vault.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub base_vault: Account<'info, Storage>,
+    #[account(
+        mut,
+        seeds = [
+            b"order-book-vault",
+            order_book.key().as_ref(),
+            order_book.quote_resource_id.as_ref(),
+        ],
+        bump = order_book.quote_vault_bump,
+        constraint = quote_vault.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub quote_vault: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = base_storage.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = base_storage.resource_id == order_book.base_resource_id @ ValidationError::ResourceNotMatching,
+        constraint = base_storage.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub base_storage: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = quote_storage.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = quote_storage.resource_id == order_book.quote_resource_id @ ValidationError::ResourceNotMatching,
+        constraint = quote_storage.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub quote_storage: Account<'info, Storage>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Returns what's still locked in the order along with anything not yet claimed.
+pub fn cancel(ctx: Context<CancelOrder>, order_id: u64) -> Result<()> {
+    let order_book: &mut Account<OrderBook> = &mut ctx.accounts.order_book;
+    let base_vault: &mut Account<Storage> = &mut ctx.accounts.base_vault;
+    let quote_vault: &mut Account<Storage> = &mut ctx.accounts.quote_vault;
+    let base_storage: &mut Account<Storage> = &mut ctx.accounts.base_storage;
+    let quote_storage: &mut Account<Storage> = &mut ctx.accounts.quote_storage;
+    let owner: &Signer = &ctx.accounts.owner;
+
+    let (side, order) = match order_book.take(&owner.key(), order_id) {
+        Some(taken) => taken,
+        None => return err!(ValidationError::OrderNotFound),
+    };
+
+    let location_id = order_book.location_id;
+    match side {
+        OrderSide::Bid => {
+            let locked = math::mul(order.amount, order.price)?;
+            quote_vault.remove(locked)?;
+            quote_storage.add(locked, location_id)?;
+            base_vault.remove(order.unclaimed)?;
+            base_storage.add(order.unclaimed, location_id)?;
+        },
+        OrderSide::Ask => {
+            base_vault.remove(order.amount)?;
+            base_storage.add(order.amount, location_id)?;
+            quote_vault.remove(order.unclaimed)?;
+            quote_storage.add(order.unclaimed, location_id)?;
+        },
+    }
+
+    resize(order_book, owner, &ctx.accounts.system_program)?;
+
+    emit!(OrderCancelled { order_book: order_book.key(), order_id, owner: owner.key(), amount: order.amount });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CancelOrder<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"order-book",
+            order_book.location_id.as_ref(),
+            order_book.base_resource_id.as_ref(),
+            order_book.quote_resource_id.as_ref(),
+        ],
+        bump = order_book.bump,
+        constraint = order_book.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub order_book: Account<'info, OrderBook>,
+    #[account(
+        mut,
+        seeds = [
+            b"order-book-vault",
+            order_book.key().as_ref(),
+            order_book.base_resource_id.as_ref(),
+        ],
+        bump = order_book.base_vault_bump,
+        constraint = base_vault.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub base_vault: Account<'info, Storage>,
+    #[account(
+        mut,
+        seeds = [
+            b"order-book-vault",
+            order_book.key().as_ref(),
+            order_book.quote_resource_id.as_ref(),
+        ],
+        bump = order_book.quote_vault_bump,
+        constraint = quote_vault.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub quote_vault: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = base_storage.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = base_storage.resource_id == order_book.base_resource_id @ ValidationError::ResourceNotMatching,
+        constraint = base_storage.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub base_storage: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = quote_storage.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = quote_storage.resource_id == order_book.quote_resource_id @ ValidationError::ResourceNotMatching,
+        constraint = quote_storage.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub quote_storage: Account<'info, Storage>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Moves what fills brought the order to the owner's storage, an order that's filled completely
+// leaves the book.
+pub fn claim(ctx: Context<ClaimOrder>, order_id: u64) -> Result<()> {
+    let order_book: &mut Account<OrderBook> = &mut ctx.accounts.order_book;
+    let base_vault: &mut Account<Storage> = &mut ctx.accounts.base_vault;
+    let quote_vault: &mut Account<Storage> = &mut ctx.accounts.quote_vault;
+    let base_storage: &mut Account<Storage> = &mut ctx.accounts.base_storage;
+    let quote_storage: &mut Account<Storage> = &mut ctx.accounts.quote_storage;
+    let owner: &Signer = &ctx.accounts.owner;
+
+    let location_id = order_book.location_id;
+    let (side, order) = match order_book.find_mut(&owner.key(), order_id) {
+        Some(found) => found,
+        None => return err!(ValidationError::OrderNotFound),
+    };
+    let amount = order.unclaimed;
+    let filled = order.amount == 0;
+    order.unclaimed = 0;
+
+    match side {
+        OrderSide::Bid => {
+            base_vault.remove(amount)?;
+            base_storage.add(amount, location_id)?;
+        },
+        OrderSide::Ask => {
+            quote_vault.remove(amount)?;
+            quote_storage.add(amount, location_id)?;
+        },
+    }
+
+    if filled {
+        order_book.take(&owner.key(), order_id);
+        resize(order_book, owner, &ctx.accounts.system_program)?;
+    }
+
+    emit!(OrderClaimed { order_book: order_book.key(), order_id, owner: owner.key(), side, amount });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ClaimOrder<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"order-book",
+            order_book.location_id.as_ref(),
+            order_book.base_resource_id.as_ref(),
+            order_book.quote_resource_id.as_ref(),
+        ],
+        bump = order_book.bump,
+        constraint = order_book.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub order_book: Account<'info, OrderBook>,
+    #[account(
+        mut,
+        seeds = [
+            b"order-book-vault",
+            order_book.key().as_ref(),
+            order_book.base_resource_id.as_ref(),
+        ],
+        bump = order_book.base_vault_bump,
+        constraint = base_vault.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub base_vault: Account<'info, Storage>,
+    #[account(
+        mut,
+        seeds = [
+            b"order-book-vault",
+            order_book.key().as_ref(),
+            order_book.quote_resource_id.as_ref(),
+        ],
+        bump = order_book.quote_vault_bump,
+        constraint = quote_vault.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub quote_vault: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = base_storage.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = base_storage.resource_id == order_book.base_resource_id @ ValidationError::ResourceNotMatching,
+        constraint = base_storage.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub base_storage: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = quote_storage.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = quote_storage.resource_id == order_book.quote_resource_id @ ValidationError::ResourceNotMatching,
+        constraint = quote_storage.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub quote_storage: Account<'info, Storage>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,415 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{pool::*, storage::*, Game, Location};
+use crate::instructions::location;
+use crate::events::{LiquidityAdded, LiquidityRemoved, PoolSwapped};
+use crate::errors::ValidationError;
+use crate::math;
+
+pub fn create(ctx: Context<CreatePool>, resource_a_id: Pubkey, resource_b_id: Pubkey) -> Result<()> {
+    let pool: &mut Account<Pool> = &mut ctx.accounts.pool;
+    let reserve_a: &mut Account<Storage> = &mut ctx.accounts.reserve_a;
+    let reserve_b: &mut Account<Storage> = &mut ctx.accounts.reserve_b;
+    let location: &Account<Location> = &ctx.accounts.location;
+
+    // One pool per pair, regardless of the order the resources are given in.
+    require!(resource_a_id < resource_b_id, ValidationError::InvalidInput);
+
+    pool.version = Pool::VERSION;
+    pool.game = ctx.accounts.game.key();
+    pool.location_id = location.key();
+    pool.resource_a_id = resource_a_id;
+    pool.resource_b_id = resource_b_id;
+    pool.lp_supply = 0;
+    pool.bump = *ctx.bumps.get("pool").unwrap();
+    pool.reserve_a_bump = *ctx.bumps.get("reserve_a").unwrap();
+    pool.reserve_b_bump = *ctx.bumps.get("reserve_b").unwrap();
+
+    init_reserve(reserve_a, pool.key(), resource_a_id, location.key());
+    init_reserve(reserve_b, pool.key(), resource_b_id, location.key());
+
+    Ok(())
+}
+
+fn init_reserve(reserve: &mut Account<Storage>, owner: Pubkey, resource_id: Pubkey, location_id: Pubkey) {
+    reserve.version = Storage::VERSION;
+    reserve.owner = owner;
+    reserve.resource_id = resource_id;
+    reserve.location_id = location_id;
+    reserve.amount = 0;
+    reserve.capacity = i64::MAX;
+    reserve.mobility_type = MobilityType::Fixed;
+    reserve.movement_speed = 0;
+    reserve.arrives_at = 0;
+}
+
+#[derive(Accounts)]
+#[instruction(resource_a_id: Pubkey, resource_b_id: Pubkey)]
+pub struct CreatePool<'info> {
+    #[account(
+        seeds = [
+            b"game",
+            game.authority.as_ref(),
+        ],
+        bump = game.bump,
+        constraint = game.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub game: Account<'info, Game>,
+    #[account(
+        init,
+        payer = owner,
+        space = Pool::LEN,
+        seeds = [
+            b"pool",
+            game.key().as_ref(),
+            location.key().as_ref(),
+            resource_a_id.as_ref(),
+            resource_b_id.as_ref(),
+        ],
+        bump,
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = owner,
+        space = Storage::LEN,
+        seeds = [
+            b"pool-reserve",
+            pool.key().as_ref(),
+            resource_a_id.as_ref(),
+        ],
+        bump,
+    )]
+    pub reserve_a: Account<'info, Storage>,
+    #[account(
+        init,
+        payer = owner,
+        space = Storage::LEN,
+        seeds = [
+            b"pool-reserve",
+            pool.key().as_ref(),
+            resource_b_id.as_ref(),
+        ],
+        bump,
+    )]
+    pub reserve_b: Account<'info, Storage>,
+    #[account(constraint = location.is_current() @ ValidationError::AccountOutdated)]
+    pub location: Account<'info, Location>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn add_liquidity(ctx: Context<AddLiquidity>, amount_a: i64, amount_b: i64, min_shares: i64, current_timestamp: i64) -> Result<()> {
+    let pool: &mut Account<Pool> = &mut ctx.accounts.pool;
+    let reserve_a: &mut Account<Storage> = &mut ctx.accounts.reserve_a;
+    let reserve_b: &mut Account<Storage> = &mut ctx.accounts.reserve_b;
+    let storage_a: &mut Account<Storage> = &mut ctx.accounts.storage_a;
+    let storage_b: &mut Account<Storage> = &mut ctx.accounts.storage_b;
+    let position: &mut Account<LiquidityPosition> = &mut ctx.accounts.position;
+    let owner: &Signer = &ctx.accounts.owner;
+
+    require!(amount_a > 0, ValidationError::InvalidInput);
+    require!(amount_b > 0, ValidationError::InvalidInput);
+    // A new position comes in zeroed, one the owner already had has to be current.
+    require!(position.pool == Pubkey::default() || position.is_current(), ValidationError::AccountOutdated);
+    require!(location::same_location_id(Some(pool.location_id), storage_a.location_id(current_timestamp)), ValidationError::DifferentLocations);
+    require!(location::same_location_id(Some(pool.location_id), storage_b.location_id(current_timestamp)), ValidationError::DifferentLocations);
+
+    let (shares, deposit_a, deposit_b) = match pool.lp_supply {
+        0 => (Pool::initial_shares(amount_a, amount_b)?, amount_a, amount_b),
+        lp_supply => {
+            let shares = Pool::shares_for_deposit(amount_a, amount_b, reserve_a.amount, reserve_b.amount, lp_supply)?;
+            let deposit_a = Pool::deposit_for_shares(shares, reserve_a.amount, lp_supply)?;
+            let deposit_b = Pool::deposit_for_shares(shares, reserve_b.amount, lp_supply)?;
+            (shares, deposit_a, deposit_b)
+        },
+    };
+
+    require!(shares > 0, ValidationError::InvalidInput);
+    require!(shares >= min_shares, ValidationError::SlippageExceeded);
+
+    storage_a.remove(deposit_a)?;
+    reserve_a.add(deposit_a, pool.location_id)?;
+    storage_b.remove(deposit_b)?;
+    reserve_b.add(deposit_b, pool.location_id)?;
+
+    pool.lp_supply = math::add(pool.lp_supply, shares)?;
+
+    position.version = LiquidityPosition::VERSION;
+    position.pool = pool.key();
+    position.owner = owner.key();
+    position.shares = math::add(position.shares, shares)?;
+    position.bump = *ctx.bumps.get("position").unwrap();
+
+    emit!(LiquidityAdded { pool: pool.key(), owner: owner.key(), amount_a: deposit_a, amount_b: deposit_b, shares });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct AddLiquidity<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            pool.game.as_ref(),
+            pool.location_id.as_ref(),
+            pool.resource_a_id.as_ref(),
+            pool.resource_b_id.as_ref(),
+        ],
+        bump = pool.bump,
+        constraint = pool.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        seeds = [
+            b"pool-reserve",
+            pool.key().as_ref(),
+            pool.resource_a_id.as_ref(),
+        ],
+        bump = pool.reserve_a_bump,
+        constraint = reserve_a.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub reserve_a: Account<'info, Storage>,
+    #[account(
+        mut,
+        seeds = [
+            b"pool-reserve",
+            pool.key().as_ref(),
+            pool.resource_b_id.as_ref(),
+        ],
+        bump = pool.reserve_b_bump,
+        constraint = reserve_b.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub reserve_b: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = storage_a.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = storage_a.resource_id == pool.resource_a_id @ ValidationError::ResourceNotMatching,
+        constraint = storage_a.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub storage_a: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = storage_b.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = storage_b.resource_id == pool.resource_b_id @ ValidationError::ResourceNotMatching,
+        constraint = storage_b.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub storage_b: Account<'info, Storage>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = LiquidityPosition::LEN,
+        seeds = [
+            b"liquidity",
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub position: Account<'info, LiquidityPosition>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn remove_liquidity(ctx: Context<RemoveLiquidity>, shares: i64, min_amount_a: i64, min_amount_b: i64, current_timestamp: i64) -> Result<()> {
+    let pool: &mut Account<Pool> = &mut ctx.accounts.pool;
+    let reserve_a: &mut Account<Storage> = &mut ctx.accounts.reserve_a;
+    let reserve_b: &mut Account<Storage> = &mut ctx.accounts.reserve_b;
+    let storage_a: &mut Account<Storage> = &mut ctx.accounts.storage_a;
+    let storage_b: &mut Account<Storage> = &mut ctx.accounts.storage_b;
+    let position: &mut Account<LiquidityPosition> = &mut ctx.accounts.position;
+    let owner: &Signer = &ctx.accounts.owner;
+
+    require!(shares > 0, ValidationError::InvalidInput);
+    require!(position.shares >= shares, ValidationError::InsufficientShares);
+    require!(location::same_location_id(Some(pool.location_id), storage_a.location_id(current_timestamp)), ValidationError::DifferentLocations);
+    require!(location::same_location_id(Some(pool.location_id), storage_b.location_id(current_timestamp)), ValidationError::DifferentLocations);
+
+    let amount_a = Pool::withdrawal_for_shares(shares, reserve_a.amount, pool.lp_supply)?;
+    let amount_b = Pool::withdrawal_for_shares(shares, reserve_b.amount, pool.lp_supply)?;
+
+    require!(amount_a >= min_amount_a, ValidationError::SlippageExceeded);
+    require!(amount_b >= min_amount_b, ValidationError::SlippageExceeded);
+
+    reserve_a.remove(amount_a)?;
+    storage_a.add(amount_a, pool.location_id)?;
+    reserve_b.remove(amount_b)?;
+    storage_b.add(amount_b, pool.location_id)?;
+
+    pool.lp_supply = math::sub(pool.lp_supply, shares)?;
+    position.shares = math::sub(position.shares, shares)?;
+
+    emit!(LiquidityRemoved { pool: pool.key(), owner: owner.key(), amount_a, amount_b, shares });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RemoveLiquidity<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"pool",
+            pool.game.as_ref(),
+            pool.location_id.as_ref(),
+            pool.resource_a_id.as_ref(),
+            pool.resource_b_id.as_ref(),
+        ],
+        bump = pool.bump,
+        constraint = pool.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        seeds = [
+            b"pool-reserve",
+            pool.key().as_ref(),
+            pool.resource_a_id.as_ref(),
+        ],
+        bump = pool.reserve_a_bump,
+        constraint = reserve_a.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub reserve_a: Account<'info, Storage>,
+    #[account(
+        mut,
+        seeds = [
+            b"pool-reserve",
+            pool.key().as_ref(),
+            pool.resource_b_id.as_ref(),
+        ],
+        bump = pool.reserve_b_bump,
+        constraint = reserve_b.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub reserve_b: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = storage_a.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = storage_a.resource_id == pool.resource_a_id @ ValidationError::ResourceNotMatching,
+        constraint = storage_a.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub storage_a: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = storage_b.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = storage_b.resource_id == pool.resource_b_id @ ValidationError::ResourceNotMatching,
+        constraint = storage_b.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub storage_b: Account<'info, Storage>,
+    #[account(
+        mut,
+        seeds = [
+            b"liquidity",
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+        ],
+        bump = position.bump,
+        has_one = owner @ ValidationError::OwnerRequired,
+        constraint = position.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub position: Account<'info, LiquidityPosition>,
+    pub owner: Signer<'info>,
+}
+
+pub fn swap(ctx: Context<Swap>, amount_in: i64, min_amount_out: i64, current_timestamp: i64) -> Result<()> {
+    let game: &Account<Game> = &ctx.accounts.game;
+    let pool: &Account<Pool> = &ctx.accounts.pool;
+    let storage_in: &mut Account<Storage> = &mut ctx.accounts.storage_in;
+    let storage_out: &mut Account<Storage> = &mut ctx.accounts.storage_out;
+    let owner: &Signer = &ctx.accounts.owner;
+
+    require!(amount_in > 0, ValidationError::InvalidInput);
+    require!(location::same_location_id(Some(pool.location_id), storage_in.location_id(current_timestamp)), ValidationError::DifferentLocations);
+    require!(location::same_location_id(Some(pool.location_id), storage_out.location_id(current_timestamp)), ValidationError::DifferentLocations);
+
+    let a_to_b = storage_in.resource_id == pool.resource_a_id && storage_out.resource_id == pool.resource_b_id;
+    let b_to_a = storage_in.resource_id == pool.resource_b_id && storage_out.resource_id == pool.resource_a_id;
+    require!(a_to_b || b_to_a, ValidationError::ResourceNotMatching);
+
+    let (reserve_in, reserve_out) = match a_to_b {
+        true => (&mut ctx.accounts.reserve_a, &mut ctx.accounts.reserve_b),
+        false => (&mut ctx.accounts.reserve_b, &mut ctx.accounts.reserve_a),
+    };
+
+    require!(reserve_in.amount > 0 && reserve_out.amount > 0, ValidationError::InsufficientLiquidity);
+
+    let amount_out = Pool::swap_output(amount_in, reserve_in.amount, reserve_out.amount, game.swap_fee_bps)?;
+
+    require!(amount_out > 0, ValidationError::InsufficientLiquidity);
+    require!(amount_out >= min_amount_out, ValidationError::SlippageExceeded);
+
+    storage_in.remove(amount_in)?;
+    reserve_in.add(amount_in, pool.location_id)?;
+    reserve_out.remove(amount_out)?;
+    storage_out.add(amount_out, pool.location_id)?;
+
+    emit!(PoolSwapped {
+        pool: pool.key(),
+        owner: owner.key(),
+        resource_in_id: storage_in.resource_id,
+        amount_in,
+        resource_out_id: storage_out.resource_id,
+        amount_out,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    #[account(
+        address = pool.game,
+        constraint = game.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub game: Account<'info, Game>,
+    #[account(
+        seeds = [
+            b"pool",
+            pool.game.as_ref(),
+            pool.location_id.as_ref(),
+            pool.resource_a_id.as_ref(),
+            pool.resource_b_id.as_ref(),
+        ],
+        bump = pool.bump,
+        constraint = pool.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        seeds = [
+            b"pool-reserve",
+            pool.key().as_ref(),
+            pool.resource_a_id.as_ref(),
+        ],
+        bump = pool.reserve_a_bump,
+        constraint = reserve_a.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub reserve_a: Account<'info, Storage>,
+    #[account(
+        mut,
+        seeds = [
+            b"pool-reserve",
+            pool.key().as_ref(),
+            pool.resource_b_id.as_ref(),
+        ],
+        bump = pool.reserve_b_bump,
+        constraint = reserve_b.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub reserve_b: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = storage_in.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = storage_in.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub storage_in: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = storage_out.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = storage_out.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub storage_out: Account<'info, Storage>,
+    pub owner: Signer<'info>,
+}
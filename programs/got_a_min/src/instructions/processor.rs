@@ -1,17 +1,39 @@
 use anchor_lang::prelude::*;
 use crate::instructions::location;
+use crate::instructions::production_queue;
+use crate::state::storage::MobilityType;
+use crate::state::Game;
 use crate::state::Location;
 use crate::state::OwnershipRef;
 use crate::state::processor::*;
 use crate::state::resource::*;
+use crate::state::shipment::*;
 use crate::state::storage::*;
+use crate::events::{ProcessorInitialized, ProcessorMoved, ProcessorPaused, ProcessorRepaired, ProcessorResumed, ProcessorRetooled, ProcessorUpgraded, ProductionClaimed, ShipmentSent, StorageLinked, StorageUnlinked};
 use crate::errors::ValidationError;
+use crate::math;
+use got_a_min_rules::production;
+
+// What a new processor makes, what that burns and how it gets around.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ProcessorSpec {
+    pub processor_type: ProcessorType,
+    pub fuel_resource_id: Pubkey,
+    pub output_resource_id: Pubkey,
+    pub output_rate: i64,
+    pub processing_duration: i64,
+    pub fuel_cost_type: FuelCostType,
+    pub mobility_type: MobilityType,
+    pub movement_speed: i64,
+}
 
-pub fn init(ctx: Context<InitProcessor>, processor_type: ProcessorType, fuel_resource_id: Pubkey, output_resource_id: Pubkey, output_rate: i64, processing_duration: i64, fuel_cost_type: FuelCostType, current_timestamp: i64) -> Result<()> {
+pub fn init(ctx: Context<InitProcessor>, spec: ProcessorSpec, current_timestamp: i64) -> Result<()> {
+    let ProcessorSpec { processor_type, fuel_resource_id, output_resource_id, output_rate, processing_duration, fuel_cost_type, mobility_type, movement_speed } = spec;
     let processor: &mut Account<Processor> = &mut ctx.accounts.processor;
     let location: &mut Account<Location> = &mut ctx.accounts.location;
     let owner: &Signer = &ctx.accounts.owner;
 
+    processor.version = Processor::VERSION;
     processor.owner = *owner.key;
     processor.location_id = location.key();
     processor.fuel_resource_id = fuel_resource_id;
@@ -20,43 +42,70 @@ pub fn init(ctx: Context<InitProcessor>, processor_type: ProcessorType, fuel_res
     processor.processing_duration = processing_duration;
     processor.awaiting_units = 0;
     processor.claimed_at = current_timestamp;
+    processor.progress = 0;
     processor.processor_type = processor_type;
     processor.fuel_cost_type = fuel_cost_type;
+    processor.game_id = ctx.accounts.game.key();
+    processor.level = 1;
+    processor.base_output_rate = output_rate;
+    processor.base_processing_duration = processing_duration;
+    processor.paused_at = None;
+    processor.durability = ctx.accounts.game.max_durability;
+    processor.max_durability = ctx.accounts.game.max_durability;
+    processor.mobility_type = mobility_type;
+    processor.movement_speed = movement_speed;
+    processor.arrives_at = 0;
+    processor.linked_storages = vec!();
 
     require!(processor.output_rate > 0, ValidationError::InvalidInput);
     require!(processor.processing_duration > 0, ValidationError::InvalidInput);
+    // Only a sender covers a distance, anything else would burn nothing.
+    require!(processor.fuel_cost_type != FuelCostType::Distance || processor.processor_type == ProcessorType::Sender, ValidationError::InvalidFuelCostType);
+
+    emit!(ProcessorInitialized {
+        processor: processor.key(),
+        owner: processor.owner,
+        location_id: processor.location_id,
+        output_resource_id,
+        processor_type: processor.processor_type.clone(),
+        output_rate,
+        processing_duration,
+    });
 
     location.add(owner, OwnershipRef { item: processor.key(), player: owner.key() })
 }
 
 // claim any units "done" waiting
-fn move_awaiting(processor: &mut Account<Processor>, storage_out: &mut Account<Storage>, current_timestamp: i64, max_output: i64) -> Result<()> {
-    require!(processor.processing_duration > 0, ValidationError::ExperimentalError);
-    
-    let prod_during_diff_time = calc_awaiting("move_awt", current_timestamp, processor, max_output);
-
+fn move_awaiting(processor: &mut Account<Processor>, storage_out: &mut Account<Storage>) -> Result<i64> {
     require!(processor.awaiting_units >= 0, ValidationError::ExperimentalError);
-    require!(prod_during_diff_time >= 0, ValidationError::ExperimentalError);
 
-    let withdraw_awaiting = processor.awaiting_units.min(prod_during_diff_time);
+    let withdraw_awaiting_within_capacity = math::checked(production::deliverable(processor.awaiting_units, storage_out.capacity, storage_out.amount))?;
 
-    let available_capacity = match storage_out.capacity - storage_out.amount {
-        diff if diff > 0 => diff,
-        _ => 0,
+    match processor.processor_type {
+        ProcessorType::Producer => storage_out.add(withdraw_awaiting_within_capacity, processor.location_id)?,
+        ProcessorType::Sender => storage_out.add_impl(withdraw_awaiting_within_capacity, processor.location_id, false)?,
     };
 
-    require!(available_capacity >= 0, ValidationError::ExperimentalError);
-    require!(withdraw_awaiting >= 0, ValidationError::ExperimentalError);
+    processor.awaiting_units = math::sub(processor.awaiting_units, withdraw_awaiting_within_capacity)?;
 
-    let withdraw_awaiting_within_capacity = available_capacity.min(withdraw_awaiting);
+    Ok(withdraw_awaiting_within_capacity)
+}
 
-    match processor.processor_type {
-        ProcessorType::Producer => storage_out.add(withdraw_awaiting_within_capacity, processor.location_id)?,
-        ProcessorType::Sender => storage_out.add_impl(withdraw_awaiting_within_capacity, processor.location_id, false)?,
+// Moves what's awaiting into [storage_out] after [produced] units were added to it.
+fn deliver_awaiting(processor: &mut Account<Processor>, storage_out: &mut Account<Storage>, produced: i64) -> Result<()> {
+    let delivered = match processor.awaiting_units > 0 {
+        true => move_awaiting(processor, storage_out)?,
+        false => 0,
     };
 
-    processor.awaiting_units -= withdraw_awaiting_within_capacity;
-    processor.claimed_at += (processor.processing_duration * withdraw_awaiting_within_capacity) / processor.output_rate;
+    emit!(ProductionClaimed {
+        processor: processor.key(),
+        storage: storage_out.key(),
+        resource_id: storage_out.resource_id,
+        produced,
+        delivered,
+        awaiting_units: processor.awaiting_units,
+    });
 
     Ok(())
 }
@@ -65,17 +114,24 @@ pub fn claim_production(ctx: Context<ProcessesResource>, current_timestamp: i64)
     let producer = &mut ctx.accounts.processor;
     let resource = &ctx.accounts.resource;
     let storage: &mut Account<Storage> = &mut ctx.accounts.storage;
+    let storage_fuel: &mut Account<Storage> = &mut ctx.accounts.storage_fuel;
 
     // Verify owner gets the resources, currently fun for anyone reading the source code
     // let owner: &Signer = &ctx.accounts.owner;
 
     msg!("claim_production/");
 
-    producer.awaiting_units = calc_awaiting("claim_prod", current_timestamp, producer, i64::MAX);
+    require!(!producer.is_moving(current_timestamp), ValidationError::NotAllowedWhileMoving);
+    production_queue::require_no_jobs(&ctx.accounts.queue)?;
 
-    if producer.awaiting_units > 0 {
-        move_awaiting(producer, storage, current_timestamp, i64::MAX)?;
-    }
+    validate_fuel(producer, storage_fuel, current_timestamp)?;
+    let limit_output_based_on_fuel_available = producer.max_units_for_fuel(storage_fuel.amount, 0);
+
+    let calculated_awaiting = calc_awaiting("claim_prod", current_timestamp, producer, limit_output_based_on_fuel_available)?;
+    burn_fuel(producer, storage_fuel, calculated_awaiting, 0)?;
+    producer.awaiting_units = math::add(producer.awaiting_units, calculated_awaiting)?;
+
+    deliver_awaiting(producer, storage, calculated_awaiting)?;
 
     msg!("/claim_production");
 
@@ -90,22 +146,39 @@ pub fn claim_production(ctx: Context<ProcessesResource>, current_timestamp: i64)
     let prod_during_diff_time = prod_slots_during_diff_time * processor.output_rate;
 */
 
-fn calc_awaiting(label: &str, current_timestamp: i64, processor: &Account<Processor>, max_output: i64) -> i64 {
-    let diff_time = current_timestamp - processor.claimed_at;
-    let prod_slots_during_diff_time = diff_time / processor.processing_duration;
-    let prod_during_diff_time = prod_slots_during_diff_time * processor.output_rate;
-    msg!("{} [{} / {}] slots: {}, prod: {}", label, current_timestamp, diff_time, prod_slots_during_diff_time, prod_during_diff_time);
-    prod_during_diff_time.min(max_output)
+// Units finished since the last claim, at most [max_output], taken out of the processor's progress.
+fn calc_awaiting(label: &str, current_timestamp: i64, processor: &mut Account<Processor>, max_output: i64) -> Result<i64> {
+    let prod_during_diff_time = processor.calc_awaiting(current_timestamp, max_output)?;
+    msg!("{} [{}] efficiency: {}, progress: {}, prod: {}", label, current_timestamp, processor.efficiency_bps()?, processor.progress, prod_during_diff_time);
+    Ok(prod_during_diff_time)
 }
 
-fn validate_by_type(processor: &Account<Processor>, storage_out: &Account<Storage>, storage_in: &Account<Storage>, storage_fuel: Option<&Account<Storage>>, current_timestamp: i64) -> Result<()> {
-    require!(location::same_location_id(Some(processor.location_id), storage_in.location_id(current_timestamp)), ValidationError::DifferentLocations);
-
+pub fn validate_fuel(processor: &Account<Processor>, storage_fuel: &Account<Storage>, current_timestamp: i64) -> Result<()> {
     if processor.fuel_cost_type != FuelCostType::Nothing {
-        let fuel_location = storage_fuel.map(|s| s.location_id(current_timestamp)).flatten();
-        require!(location::same_location_id(Some(processor.location_id), fuel_location), ValidationError::DifferentLocations);
+        require!(storage_fuel.resource_id == processor.fuel_resource_id, ValidationError::FuelNotSupplied);
+        require!(location::same_location_id(processor.location_id(current_timestamp), storage_fuel.location_id(current_timestamp)), ValidationError::DifferentLocations);
     }
 
+    Ok(())
+}
+
+// Fuel for [units] already limited by max_units_for_fuel, so only a sender can come up short.
+pub fn burn_fuel(processor: &Account<Processor>, storage_fuel: &mut Account<Storage>, units: i64, distance: i64) -> Result<()> {
+    let fuel_cost = processor.fuel_cost(units, distance)?;
+
+    if fuel_cost > 0 {
+        require!(storage_fuel.amount >= fuel_cost, ValidationError::FuelNotEnough);
+        storage_fuel.remove(fuel_cost)?;
+    }
+
+    Ok(())
+}
+
+fn validate_by_type(processor: &Account<Processor>, storage_out: &Account<Storage>, storage_in: &Account<Storage>, storage_fuel: &Account<Storage>, current_timestamp: i64) -> Result<()> {
+    require!(location::same_location_id(processor.location_id(current_timestamp), storage_in.location_id(current_timestamp)), ValidationError::DifferentLocations);
+
+    validate_fuel(processor, storage_fuel, current_timestamp)?;
+
     match processor.processor_type { 
         ProcessorType::Producer => {
             require!(location::same_location_id(storage_out.location_id(current_timestamp), storage_in.location_id(current_timestamp)), ValidationError::DifferentLocations);
@@ -127,8 +200,9 @@ pub fn produce_with_one_input(ctx: Context<ProcessesResourceWith1Input>, current
     
     require!(processor.processor_type == ProcessorType::Producer, ValidationError::InvalidProcessorType);
     require!(resource_to_produce.key().eq(&storage.resource_id), ValidationError::InputStorageNotSupplied);
+    production_queue::require_no_jobs(&ctx.accounts.queue)?;
 
-    validate_by_type(&processor, storage, storage_in, Some(&storage_fuel), current_timestamp)?;
+    validate_by_type(processor, storage, storage_in, storage_fuel, current_timestamp)?;
 
     let input_exists = resource_to_produce.input.iter()
         .position(|input| input.key().eq(&storage_in.resource_id));
@@ -137,19 +211,19 @@ pub fn produce_with_one_input(ctx: Context<ProcessesResourceWith1Input>, current
 
     let index = input_exists.unwrap();
     let input_per_output_unit = resource_to_produce.input_amount[index];
-    let limit_output_based_on_input_available = storage_in.amount / input_per_output_unit;
+    let limit_output_based_on_input_available = math::div(storage_in.amount, input_per_output_unit)?;
+    let limit_output_based_on_fuel_available = processor.max_units_for_fuel(storage_fuel.amount, 0);
 
-    let calculated_awaiting = calc_awaiting("prod_1", current_timestamp, &processor, limit_output_based_on_input_available);
-    let total_input = input_per_output_unit * calculated_awaiting;
+    let calculated_awaiting = calc_awaiting("prod_1", current_timestamp, processor, limit_output_based_on_input_available.min(limit_output_based_on_fuel_available))?;
+    let total_input = math::mul(input_per_output_unit, calculated_awaiting)?;
 
     require!(storage_in.amount >= total_input, ValidationError::InputStorageAmountTooLow);
 
-    storage_in.amount -= total_input;
-    processor.awaiting_units += calculated_awaiting;
+    storage_in.remove(total_input)?;
+    burn_fuel(processor, storage_fuel, calculated_awaiting, 0)?;
+    processor.awaiting_units = math::add(processor.awaiting_units, calculated_awaiting)?;
 
-    if processor.awaiting_units > 0 {
-        move_awaiting(processor, storage, current_timestamp, total_input)?;
-    }
+    deliver_awaiting(processor, storage, calculated_awaiting)?;
 
     msg!("/produce_with_one_input");
 
@@ -162,38 +236,42 @@ pub fn produce_with_two_inputs(ctx: Context<ProcessesResourceWith2Inputs>, curre
     let storage: &mut Account<Storage> = &mut ctx.accounts.storage;
     let storage_in_1: &mut Account<Storage> = &mut ctx.accounts.storage_input_1;
     let storage_in_2: &mut Account<Storage> = &mut ctx.accounts.storage_input_2;
+    let storage_fuel: &mut Account<Storage> = &mut ctx.accounts.storage_fuel;
+
+    production_queue::require_no_jobs(&ctx.accounts.queue)?;
 
     let input_pos_1 = resource_to_produce.input.iter().position(|input| input.key().eq(&storage_in_1.resource_id));
     require!(input_pos_1.is_some(), ValidationError::InputStorage1NotSupplied);
     let input_pos_2 = resource_to_produce.input.iter().position(|input| input.key().eq(&storage_in_2.resource_id));
     require!(input_pos_2.is_some(), ValidationError::InputStorage2NotSupplied);
 
-    validate_by_type(&processor, storage, storage_in_1, None, current_timestamp)?;
-    validate_by_type(&processor, storage, storage_in_2, None, current_timestamp)?;
+    validate_by_type(processor, storage, storage_in_1, storage_fuel, current_timestamp)?;
+    validate_by_type(processor, storage, storage_in_2, storage_fuel, current_timestamp)?;
 
     let index_1 = input_pos_1.unwrap();
     let input_1_amount_per_unit = resource_to_produce.input_amount[index_1];
     require!(storage_in_1.amount >= input_1_amount_per_unit, ValidationError::InputStorageAmountTooLow);
-    let input_1_max_units = storage_in_1.amount / input_1_amount_per_unit;
+    let input_1_max_units = math::div(storage_in_1.amount, input_1_amount_per_unit)?;
 
     let index_2 = input_pos_2.unwrap();
     let input_2_amount_per_unit = resource_to_produce.input_amount[index_2];
     require!(storage_in_2.amount >= input_2_amount_per_unit, ValidationError::InputStorageAmountTooLow);
-    let input_2_max_units = storage_in_2.amount / input_2_amount_per_unit;
+    let input_2_max_units = math::div(storage_in_2.amount, input_2_amount_per_unit)?;
 
     let input_max_units = input_1_max_units.min(input_2_max_units);
+    let fuel_max_units = processor.max_units_for_fuel(storage_fuel.amount, 0);
 
-    let calculated_awaiting = calc_awaiting("prod_2", current_timestamp, &processor, input_max_units);
-    let input_1_amount_total = input_1_amount_per_unit * calculated_awaiting;
-    let input_2_amount_total = input_2_amount_per_unit * calculated_awaiting;
+    let calculated_awaiting = calc_awaiting("prod_2", current_timestamp, processor, input_max_units.min(fuel_max_units))?;
 
-    storage_in_1.amount -= input_1_amount_total;
-    storage_in_2.amount -= input_2_amount_total;    
-    processor.awaiting_units += calculated_awaiting;
+    let input_1_amount_total = math::mul(input_1_amount_per_unit, calculated_awaiting)?;
+    let input_2_amount_total = math::mul(input_2_amount_per_unit, calculated_awaiting)?;
 
-    if processor.awaiting_units > 0 {
-        move_awaiting(processor, storage, current_timestamp, input_max_units)?;
-    }
+    storage_in_1.remove(input_1_amount_total)?;
+    storage_in_2.remove(input_2_amount_total)?;
+    burn_fuel(processor, storage_fuel, calculated_awaiting, 0)?;
+    processor.awaiting_units = math::add(processor.awaiting_units, calculated_awaiting)?;
+
+    deliver_awaiting(processor, storage, calculated_awaiting)?;
 
     Ok(())
 }
@@ -202,7 +280,16 @@ pub fn produce_with_two_inputs(ctx: Context<ProcessesResourceWith2Inputs>, curre
 pub struct InitProcessor<'info> {
     #[account(init, payer = owner, space = Processor::LEN)]
     pub processor: Account<'info, Processor>,
-    #[account(mut)]
+    #[account(
+        seeds = [
+            b"game",
+            game.authority.as_ref(),
+        ],
+        bump = game.bump,
+        constraint = game.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub game: Account<'info, Game>,
+    #[account(mut, constraint = location.is_current() @ ValidationError::AccountOutdated)]
     pub location: Account<'info, Location>,
     #[account(mut)]
     pub owner: Signer<'info>,
@@ -211,90 +298,172 @@ pub struct InitProcessor<'info> {
 
 #[derive(Accounts)]
 pub struct ProcessesResource<'info> {
-    #[account(mut)]
+    #[account(mut, constraint = processor.is_current() @ ValidationError::AccountOutdated)]
     pub processor: Account<'info, Processor>,
-    #[account(mut)]
+    /// CHECK: The processor's production queue, which might not have been created. Only deserialized when it exists.
+    #[account(
+        seeds = [
+            b"production-queue",
+            processor.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub queue: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        address = processor.output_resource_id @ ValidationError::ResourceNotMatching,
+        constraint = resource.is_current() @ ValidationError::AccountOutdated,
+    )]
     pub resource: Account<'info, Resource>,
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = processor.is_linked(&storage.key()) @ ValidationError::StorageNotLinked,
+        constraint = storage.resource_id == processor.output_resource_id @ ValidationError::ResourceNotMatching,
+        constraint = storage.is_current() @ ValidationError::AccountOutdated,
+    )]
     pub storage: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = storage_fuel.key() != storage.key() @ ValidationError::SameStorage,
+        constraint = processor.fuel_cost_type == FuelCostType::Nothing || processor.is_linked(&storage_fuel.key()) @ ValidationError::StorageNotLinked,
+        constraint = storage_fuel.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub storage_fuel: Account<'info, Storage>,
 }
 
 #[derive(Accounts)]
 pub struct ProcessesResourceWith1Input<'info> {
-    #[account(mut)]
+    #[account(mut, constraint = processor.is_current() @ ValidationError::AccountOutdated)]
     pub processor: Account<'info, Processor>,
-    #[account(mut)]
+    /// CHECK: The processor's production queue, which might not have been created. Only deserialized when it exists.
+    #[account(
+        seeds = [
+            b"production-queue",
+            processor.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub queue: UncheckedAccount<'info>,
+    #[account(mut, constraint = resource_to_produce.is_current() @ ValidationError::AccountOutdated)]
     pub resource_to_produce: Account<'info, Resource>,
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = processor.is_linked(&storage.key()) @ ValidationError::StorageNotLinked,
+        constraint = storage.resource_id == processor.output_resource_id @ ValidationError::ResourceNotMatching,
+        constraint = storage.is_current() @ ValidationError::AccountOutdated,
+    )]
     pub storage: Account<'info, Storage>,
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = storage_input.key() != storage.key() @ ValidationError::SameStorage,
+        constraint = processor.is_linked(&storage_input.key()) @ ValidationError::StorageNotLinked,
+        constraint = storage_input.is_current() @ ValidationError::AccountOutdated,
+    )]
     pub storage_input: Account<'info, Storage>,
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = storage_fuel.key() != storage.key() @ ValidationError::SameStorage,
+        constraint = storage_fuel.key() != storage_input.key() @ ValidationError::SameStorage,
+        constraint = processor.fuel_cost_type == FuelCostType::Nothing || processor.is_linked(&storage_fuel.key()) @ ValidationError::StorageNotLinked,
+        constraint = storage_fuel.is_current() @ ValidationError::AccountOutdated,
+    )]
     pub storage_fuel: Account<'info, Storage>,
 }
 
 #[derive(Accounts)]
 pub struct ProcessesResourceWith2Inputs<'info> {
-    #[account(mut)]
+    #[account(mut, constraint = processor.is_current() @ ValidationError::AccountOutdated)]
     pub processor: Account<'info, Processor>,
-    #[account(mut)]
+    /// CHECK: The processor's production queue, which might not have been created. Only deserialized when it exists.
+    #[account(
+        seeds = [
+            b"production-queue",
+            processor.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub queue: UncheckedAccount<'info>,
+    #[account(mut, constraint = resource_to_produce.is_current() @ ValidationError::AccountOutdated)]
     pub resource_to_produce: Account<'info, Resource>,
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = processor.is_linked(&storage.key()) @ ValidationError::StorageNotLinked,
+        constraint = storage.resource_id == processor.output_resource_id @ ValidationError::ResourceNotMatching,
+        constraint = storage.is_current() @ ValidationError::AccountOutdated,
+    )]
     pub storage: Account<'info, Storage>,
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = storage_input_1.key() != storage.key() @ ValidationError::SameStorage,
+        constraint = processor.is_linked(&storage_input_1.key()) @ ValidationError::StorageNotLinked,
+        constraint = storage_input_1.is_current() @ ValidationError::AccountOutdated,
+    )]
     pub storage_input_1: Account<'info, Storage>,
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = storage_input_2.key() != storage.key() @ ValidationError::SameStorage,
+        constraint = storage_input_2.key() != storage_input_1.key() @ ValidationError::SameStorage,
+        constraint = processor.is_linked(&storage_input_2.key()) @ ValidationError::StorageNotLinked,
+        constraint = storage_input_2.is_current() @ ValidationError::AccountOutdated,
+    )]
     pub storage_input_2: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = storage_fuel.key() != storage.key() @ ValidationError::SameStorage,
+        constraint = storage_fuel.key() != storage_input_1.key() @ ValidationError::SameStorage,
+        constraint = storage_fuel.key() != storage_input_2.key() @ ValidationError::SameStorage,
+        constraint = processor.fuel_cost_type == FuelCostType::Nothing || processor.is_linked(&storage_fuel.key()) @ ValidationError::StorageNotLinked,
+        constraint = storage_fuel.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub storage_fuel: Account<'info, Storage>,
 }
 
-pub fn send(ctx: Context<SendResource>, send_amount: i64, current_timestamp: i64, from_x: i64, from_y: i64, to_x: i64, to_y: i64) -> Result<()> {
+pub fn send(ctx: Context<SendResource>, send_amount: i64, current_timestamp: i64, _from_x: i64, _from_y: i64, _to_x: i64, _to_y: i64) -> Result<()> {
     let processor = &mut ctx.accounts.processor;
     let resource_to_produce: &mut Account<Resource> = &mut ctx.accounts.resource_to_produce;
-    let storage_to: &mut Account<Storage> = &mut ctx.accounts.storage;
+    let storage_to: &Account<Storage> = &ctx.accounts.storage;
     let storage_from: &mut Account<Storage> = &mut ctx.accounts.storage_input;
     let storage_fuel: &mut Account<Storage> = &mut ctx.accounts.storage_fuel;
+    let shipment: &mut Account<Shipment> = &mut ctx.accounts.shipment;
     let from_location: &Account<Location> = &ctx.accounts.from_location;
     let to_location: &Account<Location> = &ctx.accounts.to_location;
+    let owner: &Signer = &ctx.accounts.owner;
 
     msg!("send/");
     
     require!(processor.processor_type == ProcessorType::Sender, ValidationError::InvalidProcessorType);
     require!(resource_to_produce.key().eq(&storage_to.resource_id), ValidationError::InputStorageNotSupplied);
-
-    require!(location::same_location_id(Some(processor.location_id), storage_from.location_id(current_timestamp)), ValidationError::DifferentLocations);
-
-    let calculated_awaiting = match send_amount {
-        //Some(amount) if amount <= storage_from.amount => amount,
-        _ => storage_from.amount,
-    };
-    
-    storage_from.amount -= calculated_awaiting;
-    require!(storage_from.amount >= 0, ValidationError::InputStorageAmountTooLow);
-
-    //storage_to.amount += calculated_awaiting;
-    processor.awaiting_units += calculated_awaiting;
-    move_awaiting(processor, storage_to, current_timestamp, i64::MAX)?;
-
-    if calculated_awaiting > 0 {
-        let fuel_cost = match processor.fuel_cost_type {
-            FuelCostType::Nothing => 0,
-            FuelCostType::Distance => {
-                let distance = from_location.distance(&to_location);
-                let fuel_cost_per_unit = distance.pow(2);
-                fuel_cost_per_unit * calculated_awaiting
-            },
-            FuelCostType::Output => 0,
-        };
-
-        if fuel_cost > 0 {
-            let fuel_location = storage_fuel.location_id(current_timestamp);
-            require!(location::same_location_id(Some(processor.location_id), fuel_location), ValidationError::DifferentLocations);
-
-            require!(storage_fuel.amount >= fuel_cost, ValidationError::FuelNotEnough);
-
-            storage_fuel.amount -= fuel_cost
-        }
-    }
+    require!(storage_from.resource_id == storage_to.resource_id, ValidationError::ResourceNotMatching);
+    require!(send_amount > 0, ValidationError::InvalidInput);
+
+    require!(processor.location_id(current_timestamp) == Some(from_location.key()), ValidationError::DifferentLocations);
+    require!(location::same_location_id(processor.location_id(current_timestamp), storage_from.location_id(current_timestamp)), ValidationError::DifferentLocations);
+    require!(storage_to.location_id == to_location.key(), ValidationError::DifferentLocations);
+
+    require!(storage_from.amount >= send_amount, ValidationError::InputStorageAmountTooLow);
+    storage_from.remove(send_amount)?;
+
+    validate_fuel(processor, storage_fuel, current_timestamp)?;
+    burn_fuel(processor, storage_fuel, send_amount, from_location.distance(to_location)?)?;
+
+    shipment.version = Shipment::VERSION;
+    shipment.owner = owner.key();
+    shipment.sender_id = processor.key();
+    shipment.resource_id = storage_from.resource_id;
+    shipment.amount = send_amount;
+    shipment.from_location_id = from_location.key();
+    shipment.to_location_id = to_location.key();
+    shipment.trade_route = Pubkey::default();
+    shipment.storage_to = storage_to.key();
+    shipment.arrives_at = math::add(current_timestamp, from_location.distance_time(to_location)?)?;
+
+    emit!(ShipmentSent {
+        shipment: shipment.key(),
+        sender: processor.key(),
+        storage_to: storage_to.key(),
+        amount: send_amount,
+        arrives_at: shipment.arrives_at,
+    });
 
     msg!("/send");
 
@@ -304,46 +473,375 @@ pub fn send(ctx: Context<SendResource>, send_amount: i64, current_timestamp: i64
 #[derive(Accounts)]
 #[instruction(
     send_amount: i64, 
-    current_timestamp: i64,
     from_x: i64,
     from_y: i64,
     to_x: i64,
     to_y: i64,
 )]
 pub struct SendResource<'info> {
-    #[account(mut)]
+    #[account(mut, constraint = processor.is_current() @ ValidationError::AccountOutdated)]
     pub processor: Account<'info, Processor>,
-    #[account(mut)]
+    #[account(mut, constraint = resource_to_produce.is_current() @ ValidationError::AccountOutdated)]
     pub resource_to_produce: Account<'info, Resource>,
-    #[account(mut)]
+    #[account(
+        constraint = processor.is_linked(&storage.key()) @ ValidationError::StorageNotLinked,
+        constraint = storage.is_current() @ ValidationError::AccountOutdated,
+    )]
     pub storage: Account<'info, Storage>,
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = storage_input.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = processor.is_linked(&storage_input.key()) @ ValidationError::StorageNotLinked,
+        constraint = storage_input.is_current() @ ValidationError::AccountOutdated,
+    )]
     pub storage_input: Account<'info, Storage>,
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = storage_fuel.key() != storage_input.key() @ ValidationError::SameStorage,
+        constraint = processor.fuel_cost_type == FuelCostType::Nothing || processor.is_linked(&storage_fuel.key()) @ ValidationError::StorageNotLinked,
+        constraint = storage_fuel.is_current() @ ValidationError::AccountOutdated,
+    )]
     pub storage_fuel: Account<'info, Storage>,
+    #[account(init, payer = owner, space = Shipment::LEN)]
+    pub shipment: Account<'info, Shipment>,
     #[account(
         mut,
-        seeds = [
-            b"map-location", 
-            owner.key().as_ref(),
-            &from_x.to_le_bytes(),
-            &from_y.to_le_bytes(),
-        ],
-        bump = from_location.bump,
+        constraint = from_location.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = from_location.is_at(from_x, from_y) @ ValidationError::DifferentLocations,
+        constraint = from_location.is_current() @ ValidationError::AccountOutdated,
     )]
     pub from_location: Account<'info, Location>,
     #[account(
         mut,
+        constraint = to_location.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = to_location.is_at(to_x, to_y) @ ValidationError::DifferentLocations,
+        constraint = to_location.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub to_location: Account<'info, Location>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+
+pub fn upgrade(ctx: Context<UpgradeProcessor>, current_timestamp: i64) -> Result<()> {
+    let game: &Account<Game> = &ctx.accounts.game;
+    let processor: &mut Account<Processor> = &mut ctx.accounts.processor;
+    let storage_payment: &mut Account<Storage> = &mut ctx.accounts.storage_payment;
+
+    require!(processor.level < game.max_level(), ValidationError::MaxLevelReached);
+    require!(location::same_location_id(processor.location_id(current_timestamp), storage_payment.location_id(current_timestamp)), ValidationError::DifferentLocations);
+
+    let cost = game.upgrade_cost(processor.level)?;
+    storage_payment.remove(cost)?;
+
+    let level = processor.level + 1;
+    let (output_rate, processing_duration) = game.level_rate(processor.base_output_rate, processor.base_processing_duration, level)?;
+
+    processor.rebase(output_rate, processing_duration, current_timestamp)?;
+    processor.level = level;
+
+    emit!(ProcessorUpgraded { processor: processor.key(), level, output_rate, processing_duration });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpgradeProcessor<'info> {
+    #[account(
+        address = processor.game_id,
+        constraint = game.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub game: Account<'info, Game>,
+    #[account(
+        mut,
+        constraint = processor.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = processor.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub processor: Account<'info, Processor>,
+    #[account(
+        mut,
+        constraint = storage_payment.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = storage_payment.resource_id == game.upgrade_resource_id @ ValidationError::ResourceNotMatching,
+        constraint = storage_payment.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub storage_payment: Account<'info, Storage>,
+    pub owner: Signer<'info>,
+}
+
+pub fn pause(ctx: Context<PauseProcessor>, current_timestamp: i64) -> Result<()> {
+    let processor: &mut Account<Processor> = &mut ctx.accounts.processor;
+
+    require!(processor.paused_at.is_none(), ValidationError::ProcessorPaused);
+
+    processor.pause(current_timestamp)?;
+
+    emit!(ProcessorPaused { processor: processor.key(), paused_at: current_timestamp });
+
+    Ok(())
+}
+
+pub fn resume(ctx: Context<PauseProcessor>, current_timestamp: i64) -> Result<()> {
+    let processor: &mut Account<Processor> = &mut ctx.accounts.processor;
+
+    require!(processor.paused_at.is_some(), ValidationError::ProcessorNotPaused);
+    let paused_for = processor.resume(current_timestamp)?;
+
+    emit!(ProcessorResumed { processor: processor.key(), paused_for });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct PauseProcessor<'info> {
+    #[account(
+        mut,
+        constraint = processor.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = processor.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub processor: Account<'info, Processor>,
+    pub owner: Signer<'info>,
+}
+
+// Delivers what's pending to [storage_out] before switching output, production the fuel can't cover is dropped.
+pub fn retool(ctx: Context<RetoolProcessor>, output_resource_id: Pubkey, current_timestamp: i64) -> Result<()> {
+    let game: &Account<Game> = &ctx.accounts.game;
+    let processor: &mut Account<Processor> = &mut ctx.accounts.processor;
+    let resource: &Account<Resource> = &ctx.accounts.resource;
+    let storage_out: &mut Account<Storage> = &mut ctx.accounts.storage_out;
+    let storage_fuel: &mut Account<Storage> = &mut ctx.accounts.storage_fuel;
+
+    require!(processor.processor_type == ProcessorType::Producer, ValidationError::InvalidProcessorType);
+    require!(output_resource_id != processor.output_resource_id, ValidationError::InvalidInput);
+    production_queue::require_no_jobs(&ctx.accounts.queue)?;
+    require!(location::same_location_id(processor.location_id(current_timestamp), storage_out.location_id(current_timestamp)), ValidationError::DifferentLocations);
+    validate_fuel(processor, storage_fuel, current_timestamp)?;
+
+    // Units made from inputs were paid for when produced, the rest only costs fuel.
+    if resource.input.is_empty() {
+        let fuel_max_units = processor.max_units_for_fuel(storage_fuel.amount, 0);
+        let calculated_awaiting = calc_awaiting("retool", current_timestamp, processor, fuel_max_units)?;
+        burn_fuel(processor, storage_fuel, calculated_awaiting, 0)?;
+        processor.awaiting_units = math::add(processor.awaiting_units, calculated_awaiting)?;
+    }
+
+    if processor.awaiting_units > 0 {
+        require!(math::sub(storage_out.capacity, storage_out.amount)? >= processor.awaiting_units, ValidationError::StorageFull);
+        storage_out.add(processor.awaiting_units, processor.location_id)?;
+    }
+
+    processor.output_resource_id = output_resource_id;
+    processor.awaiting_units = 0;
+    processor.progress = 0;
+    processor.claimed_at = math::add(processor.accrual_timestamp(current_timestamp), game.retool_delay)?;
+
+    emit!(ProcessorRetooled { processor: processor.key(), output_resource_id, producing_from: processor.claimed_at });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RetoolProcessor<'info> {
+    #[account(
+        address = processor.game_id,
+        constraint = game.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub game: Account<'info, Game>,
+    #[account(
+        mut,
+        constraint = processor.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = processor.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub processor: Account<'info, Processor>,
+    /// CHECK: The processor's production queue, which might not have been created. Only deserialized when it exists.
+    #[account(
         seeds = [
-            b"map-location", 
-            owner.key().as_ref(),
-            &to_x.to_le_bytes(),
-            &to_y.to_le_bytes(),
+            b"production-queue",
+            processor.key().as_ref(),
         ],
-        bump = to_location.bump,
+        bump,
+    )]
+    pub queue: UncheckedAccount<'info>,
+    #[account(
+        address = processor.output_resource_id,
+        constraint = resource.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub resource: Account<'info, Resource>,
+    #[account(
+        mut,
+        constraint = storage_out.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = storage_out.resource_id == processor.output_resource_id @ ValidationError::ResourceNotMatching,
+        constraint = storage_out.is_current() @ ValidationError::AccountOutdated,
     )]
+    pub storage_out: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = storage_fuel.owner == owner.key() || processor.is_linked(&storage_fuel.key()) @ ValidationError::OwnerRequired,
+        constraint = storage_fuel.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub storage_fuel: Account<'info, Storage>,
+    pub owner: Signer<'info>,
+}
+
+// Restores up to [points] of durability, paid for per point in the game's repair resource.
+pub fn repair(ctx: Context<RepairProcessor>, points: i64, current_timestamp: i64) -> Result<()> {
+    let game: &Account<Game> = &ctx.accounts.game;
+    let processor: &mut Account<Processor> = &mut ctx.accounts.processor;
+    let storage_payment: &mut Account<Storage> = &mut ctx.accounts.storage_payment;
+
+    let points = points.min(math::sub(processor.max_durability, processor.durability)?);
+    require!(points > 0, ValidationError::InvalidInput);
+    require!(location::same_location_id(processor.location_id(current_timestamp), storage_payment.location_id(current_timestamp)), ValidationError::DifferentLocations);
+
+    let cost = math::mul(points, game.repair_cost_per_point)?;
+    storage_payment.remove(cost)?;
+
+    // Time since the last claim keeps the efficiency it was worn down to.
+    processor.accrue(current_timestamp)?;
+    processor.durability = math::add(processor.durability, points)?;
+
+    emit!(ProcessorRepaired { processor: processor.key(), durability: processor.durability });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RepairProcessor<'info> {
+    #[account(
+        address = processor.game_id,
+        constraint = game.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub game: Account<'info, Game>,
+    #[account(
+        mut,
+        constraint = processor.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = processor.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub processor: Account<'info, Processor>,
+    #[account(
+        mut,
+        constraint = storage_payment.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = storage_payment.resource_id == game.repair_resource_id @ ValidationError::ResourceNotMatching,
+        constraint = storage_payment.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub storage_payment: Account<'info, Storage>,
+    pub owner: Signer<'info>,
+}
+
+// Like a movable storage, production is put on hold for the travel time. What was made before
+// leaving stays in the progress, a pause overlapping the trip isn't counted twice.
+pub fn move_to(ctx: Context<MoveProcessor>, current_timestamp: i64) -> Result<()> {
+    let processor: &mut Account<Processor> = &mut ctx.accounts.processor;
+    let from_location: &mut Account<Location> = &mut ctx.accounts.from_location;
+    let to_location: &mut Account<Location> = &mut ctx.accounts.to_location;
+    let owner: &Signer = &ctx.accounts.owner;
+
+    require!(processor.mobility_type == MobilityType::Movable, ValidationError::StorageTypeNotMovable);
+    require!(processor.movement_speed > 0, ValidationError::StorageTypeNotMovable);
+    require!(!processor.is_moving(current_timestamp), ValidationError::NotAllowedWhileMoving);
+
+    processor.location_id = to_location.key();
+    let distance_time = from_location.distance_time(to_location)?;
+    let travel_time = distance_time / processor.movement_speed;
+    let arrives_at = match travel_time {
+        0 => 0,
+        _ => math::add(current_timestamp, travel_time)?,
+    };
+    processor.arrives_at = arrives_at;
+    processor.hold(current_timestamp, arrives_at)?;
+
+    emit!(ProcessorMoved {
+        processor: processor.key(),
+        from_location_id: from_location.key(),
+        to_location_id: to_location.key(),
+        arrives_at: processor.arrives_at,
+    });
+
+    location::register_move(owner, from_location, to_location, OwnershipRef { item: processor.key(), player: processor.owner })
+}
+
+#[derive(Accounts)]
+pub struct MoveProcessor<'info> {
+    #[account(
+        mut,
+        constraint = processor.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = processor.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub processor: Account<'info, Processor>,
+    #[account(
+        mut,
+        address = processor.location_id @ ValidationError::DifferentLocations,
+        constraint = from_location.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub from_location: Account<'info, Location>,
+    #[account(mut, constraint = to_location.is_current() @ ValidationError::AccountOutdated)]
     pub to_location: Account<'info, Location>,
     #[account(mut)]
     pub owner: Signer<'info>,
 }
 
+pub fn link_storage(ctx: Context<LinkStorage>) -> Result<()> {
+    let processor: &mut Account<Processor> = &mut ctx.accounts.processor;
+    let output: &Account<Resource> = &ctx.accounts.output;
+    let storage: &Account<Storage> = &ctx.accounts.storage;
+
+    require!(!processor.is_linked(&storage.key()), ValidationError::InvalidInput);
+    // Only storages the processor can burn, produce or take inputs from.
+    let resource_id = storage.resource_id;
+    require!(resource_id == processor.fuel_resource_id || resource_id == output.key() || output.input.contains(&resource_id), ValidationError::ResourceNotMatching);
+    require!(processor.linked_storages.len() < PROCESSOR_MAX_LINKED_STORAGES, ValidationError::LinkedStoragesFull);
+
+    processor.linked_storages.push(storage.key());
+
+    emit!(StorageLinked { processor: processor.key(), storage: storage.key() });
+
+    Ok(())
+}
+
+pub fn unlink_storage(ctx: Context<UnlinkStorage>) -> Result<()> {
+    let processor: &mut Account<Processor> = &mut ctx.accounts.processor;
+    let storage: &Account<Storage> = &ctx.accounts.storage;
+
+    match processor.linked_storages.iter().position(|s| *s == storage.key()) {
+        Some(index) => processor.linked_storages.remove(index),
+        None => return err!(ValidationError::StorageNotLinked),
+    };
+
+    emit!(StorageUnlinked { processor: processor.key(), storage: storage.key() });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct LinkStorage<'info> {
+    #[account(
+        mut,
+        constraint = processor.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = processor.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub processor: Account<'info, Processor>,
+    #[account(
+        address = processor.output_resource_id @ ValidationError::ResourceNotMatching,
+        constraint = output.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub output: Account<'info, Resource>,
+    #[account(
+        constraint = storage.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = storage.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub storage: Account<'info, Storage>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UnlinkStorage<'info> {
+    #[account(
+        mut,
+        constraint = processor.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = processor.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub processor: Account<'info, Processor>,
+    #[account(constraint = storage.is_current() @ ValidationError::AccountOutdated)]
+    pub storage: Account<'info, Storage>,
+    pub owner: Signer<'info>,
+}
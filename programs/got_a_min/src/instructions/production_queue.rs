@@ -0,0 +1,280 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{production_queue::*, storage::*, FuelCostType, Processor, ProcessorType, Resource};
+use crate::instructions::{location, processor as processors};
+use crate::events::{JobCompleted, JobQueued};
+use crate::errors::ValidationError;
+use crate::clock;
+use crate::math;
+
+pub fn init(ctx: Context<InitProductionQueue>) -> Result<()> {
+    let queue: &mut Account<ProductionQueue> = &mut ctx.accounts.queue;
+    let processor: &Account<Processor> = &ctx.accounts.processor;
+
+    require!(processor.processor_type == ProcessorType::Producer, ValidationError::InvalidProcessorType);
+
+    queue.version = ProductionQueue::VERSION;
+    queue.processor = processor.key();
+    queue.next_job_id = 0;
+    queue.jobs = vec!();
+    queue.bump = *ctx.bumps.get("queue").unwrap();
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitProductionQueue<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = ProductionQueue::LEN,
+        seeds = [
+            b"production-queue",
+            processor.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub queue: Account<'info, ProductionQueue>,
+    #[account(
+        constraint = processor.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = processor.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub processor: Account<'info, Processor>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Input storages for the recipe are passed as remaining accounts, one per input resource.
+pub fn enqueue<'info>(ctx: Context<'_, '_, '_, 'info, EnqueueJob<'info>>, quantity: i64, priority: u8, current_timestamp: i64) -> Result<()> {
+    let queue: &mut Account<ProductionQueue> = &mut ctx.accounts.queue;
+    let processor: &mut Account<Processor> = &mut ctx.accounts.processor;
+    let recipe: &Account<Resource> = &ctx.accounts.recipe;
+
+    require!(quantity > 0, ValidationError::InvalidInput);
+    require!(queue.jobs.len() < PRODUCTION_QUEUE_MAX_JOBS, ValidationError::ProductionQueueFull);
+
+    let mut input_storages = load_input_storages(ctx.remaining_accounts, processor)?;
+
+    for (input_id, input_amount) in recipe.input.iter().zip(recipe.input_amount.iter()) {
+        let reserved = math::mul(*input_amount, quantity)?;
+        let storage = input_storage(&mut input_storages, input_id)?;
+
+        require!(location::same_location_id(processor.location_id(current_timestamp), storage.location_id(current_timestamp)), ValidationError::DifferentLocations);
+        require!(storage.amount >= reserved, ValidationError::InputStorageAmountTooLow);
+
+        storage.remove(reserved)?;
+    }
+
+    for storage in input_storages.iter() {
+        storage.exit(ctx.program_id)?;
+    }
+
+    // An idle processor starts on the new job right away.
+    if queue.jobs.is_empty() {
+        processor.claimed_at = processor.accrual_timestamp(current_timestamp);
+        processor.progress = 0;
+    }
+
+    let job_id = queue.next_job_id;
+    queue.next_job_id = math::next_id(job_id)?;
+    queue.insert(Job {
+        id: job_id,
+        resource_id: recipe.key(),
+        quantity,
+        produced: 0,
+        priority,
+    });
+
+    emit!(JobQueued { queue: queue.key(), job_id, resource_id: recipe.key(), quantity, priority });
+
+    Ok(())
+}
+
+// The queue owns the processor's progress while it has jobs, so the regular claims have to wait.
+pub fn require_no_jobs(queue: &UncheckedAccount) -> Result<()> {
+    if !queue.data_is_empty() {
+        let queue: Account<ProductionQueue> = Account::try_from(&queue.to_account_info())?;
+        require!(queue.is_current(), ValidationError::AccountOutdated);
+        require!(queue.jobs.is_empty(), ValidationError::ProductionQueueActive);
+    }
+    Ok(())
+}
+
+// The inputs have to be storages of the processor's owner linked to it, like those of the regular claims.
+fn load_input_storages<'info>(accounts: &[AccountInfo<'info>], processor: &Account<Processor>) -> Result<Vec<Account<'info, Storage>>> {
+    let mut storages: Vec<Account<'info, Storage>> = vec!();
+    for info in accounts.iter().filter(|info| !clock::is_game_clock(info)) {
+        require!(info.is_writable, ValidationError::InvalidInput);
+        // A storage loaded twice would have one copy overwrite the other on exit.
+        require!(storages.iter().all(|s| s.key() != info.key()), ValidationError::InvalidInput);
+        let storage: Account<'info, Storage> = Account::try_from(info)?;
+        require!(storage.is_current(), ValidationError::AccountOutdated);
+        require!(storage.owner == processor.owner, ValidationError::OwnerRequired);
+        require!(processor.is_linked(&storage.key()), ValidationError::StorageNotLinked);
+        storages.push(storage);
+    }
+    Ok(storages)
+}
+
+fn input_storage<'a, 'info>(storages: &'a mut [Account<'info, Storage>], resource_id: &Pubkey) -> Result<&'a mut Account<'info, Storage>> {
+    match storages.iter_mut().find(|s| s.resource_id == *resource_id) {
+        Some(storage) => Ok(storage),
+        None => err!(ValidationError::InputStorageNotSupplied),
+    }
+}
+
+#[derive(Accounts)]
+pub struct EnqueueJob<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"production-queue",
+            processor.key().as_ref(),
+        ],
+        bump = queue.bump,
+        constraint = queue.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub queue: Account<'info, ProductionQueue>,
+    #[account(
+        mut,
+        constraint = processor.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = processor.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub processor: Account<'info, Processor>,
+    #[account(
+        address = processor.output_resource_id @ ValidationError::ResourceNotMatching,
+        constraint = recipe.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub recipe: Account<'info, Resource>,
+    pub owner: Signer<'info>,
+}
+
+// Delivers finished units of the jobs at the front of the queue, as long as they produce into [storage_out]
+// and there's fuel to pay for them.
+pub fn claim(ctx: Context<ClaimJobs>, current_timestamp: i64) -> Result<()> {
+    let queue: &mut Account<ProductionQueue> = &mut ctx.accounts.queue;
+    let processor: &mut Account<Processor> = &mut ctx.accounts.processor;
+    let storage_out: &mut Account<Storage> = &mut ctx.accounts.storage_out;
+    let storage_fuel: &mut Account<Storage> = &mut ctx.accounts.storage_fuel;
+
+    require!(location::same_location_id(processor.location_id(current_timestamp), storage_out.location_id(current_timestamp)), ValidationError::DifferentLocations);
+    processors::validate_fuel(processor, storage_fuel, current_timestamp)?;
+
+    processor.accrue(current_timestamp)?;
+
+    let queue_id = queue.key();
+    while let Some(job) = queue.jobs.first_mut() {
+        if job.resource_id != storage_out.resource_id {
+            break;
+        }
+
+        let finished = processor.finished_units()?;
+        let available_capacity = math::sub(storage_out.capacity, storage_out.amount)?.max(0);
+        let units = finished.min(job.remaining()?)
+            .min(available_capacity)
+            .min(processor.max_units_for_fuel(storage_fuel.amount, 0));
+
+        if units > 0 {
+            processors::burn_fuel(processor, storage_fuel, units, 0)?;
+            storage_out.add(units, processor.location_id)?;
+            job.produced = math::add(job.produced, units)?;
+            processor.complete(units)?;
+        }
+
+        if job.remaining()? > 0 {
+            break;
+        }
+
+        emit!(JobCompleted { queue: queue_id, job_id: job.id, resource_id: job.resource_id, quantity: job.quantity });
+        queue.jobs.remove(0);
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ClaimJobs<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"production-queue",
+            processor.key().as_ref(),
+        ],
+        bump = queue.bump,
+        constraint = queue.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub queue: Account<'info, ProductionQueue>,
+    #[account(
+        mut,
+        address = queue.processor,
+        constraint = processor.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub processor: Account<'info, Processor>,
+    #[account(
+        mut,
+        constraint = storage_out.owner == processor.owner @ ValidationError::OwnerRequired,
+        constraint = processor.is_linked(&storage_out.key()) @ ValidationError::StorageNotLinked,
+        constraint = storage_out.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub storage_out: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = storage_fuel.key() != storage_out.key() @ ValidationError::SameStorage,
+        constraint = processor.fuel_cost_type == FuelCostType::Nothing || processor.is_linked(&storage_fuel.key()) @ ValidationError::StorageNotLinked,
+        constraint = storage_fuel.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub storage_fuel: Account<'info, Storage>,
+}
+
+// Returns the inputs reserved for what's left of the job to the storages passed as remaining accounts.
+pub fn cancel<'info>(ctx: Context<'_, '_, '_, 'info, CancelJob<'info>>, job_id: u64, current_timestamp: i64) -> Result<()> {
+    let queue: &mut Account<ProductionQueue> = &mut ctx.accounts.queue;
+    let processor: &mut Account<Processor> = &mut ctx.accounts.processor;
+    let recipe: &Account<Resource> = &ctx.accounts.recipe;
+
+    let (index, job) = queue.take(job_id).ok_or(ValidationError::JobNotFound)?;
+    require!(job.resource_id == recipe.key(), ValidationError::ResourceNotMatching);
+
+    let mut input_storages = load_input_storages(ctx.remaining_accounts, processor)?;
+
+    for (input_id, input_amount) in recipe.input.iter().zip(recipe.input_amount.iter()) {
+        let refund = math::mul(*input_amount, job.remaining()?)?;
+        input_storage(&mut input_storages, input_id)?.add(refund, processor.location_id)?;
+    }
+
+    for storage in input_storages.iter() {
+        storage.exit(ctx.program_id)?;
+    }
+
+    // Progress towards the next unit of a cancelled job in progress is lost.
+    if index == 0 {
+        processor.claimed_at = processor.accrual_timestamp(current_timestamp);
+        processor.progress = 0;
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CancelJob<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"production-queue",
+            processor.key().as_ref(),
+        ],
+        bump = queue.bump,
+        constraint = queue.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub queue: Account<'info, ProductionQueue>,
+    #[account(
+        mut,
+        constraint = processor.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = processor.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub processor: Account<'info, Processor>,
+    #[account(constraint = recipe.is_current() @ ValidationError::AccountOutdated)]
+    pub recipe: Account<'info, Resource>,
+    pub owner: Signer<'info>,
+}
@@ -1,11 +1,13 @@
 use anchor_lang::prelude::*;
 use crate::state::resource::*;
+use crate::events::ResourceInitialized;
 use crate::errors::ValidationError;
 
 pub fn init(ctx: Context<InitResource>, name: String, inputs: Vec<Pubkey>, input_amounts: Vec<i64>) -> Result<()> {
     let resource: &mut Account<Resource> = &mut ctx.accounts.resource;
     let owner: &Signer = &ctx.accounts.owner;
 
+    resource.version = Resource::VERSION;
     resource.owner = *owner.key;
     resource.name = name;
     resource.input = inputs;
@@ -15,6 +17,14 @@ pub fn init(ctx: Context<InitResource>, name: String, inputs: Vec<Pubkey>, input
     require!(resource.input.len() <= INPUT_MAX_SIZE, ValidationError::ResourceInputMax);
     require!(resource.input.len() == resource.input_amount.len(), ValidationError::MissingResourceInputAmount);
 
+    emit!(ResourceInitialized {
+        resource: resource.key(),
+        owner: resource.owner,
+        name: resource.name.clone(),
+        inputs: resource.input.clone(),
+        input_amounts: resource.input_amount.clone(),
+    });
+
     Ok(())
 }
 
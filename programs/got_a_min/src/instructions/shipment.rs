@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{shipment::*, storage::*, TradeRoute};
+use crate::instructions::location;
+use crate::events::ShipmentReceived;
+use crate::errors::ValidationError;
+use crate::math;
+
+// A shipment of a trade route takes the route as a remaining account, to release its room in
+// storage_to. Once the route is closed there's nothing left to release.
+pub fn receive<'info>(ctx: Context<'_, '_, '_, 'info, ReceiveShipment<'info>>, current_timestamp: i64) -> Result<()> {
+    let shipment: &Account<Shipment> = &ctx.accounts.shipment;
+    let storage_to: &mut Account<Storage> = &mut ctx.accounts.storage_to;
+
+    require!(shipment.has_arrived(current_timestamp), ValidationError::ShipmentNotArrived);
+    require!(location::same_location_id(Some(shipment.to_location_id), storage_to.location_id(current_timestamp)), ValidationError::DifferentLocations);
+
+    storage_to.add(shipment.amount, shipment.to_location_id)?;
+
+    if shipment.trade_route != Pubkey::default() {
+        let info = ctx.remaining_accounts.iter()
+            .find(|info| info.key() == shipment.trade_route)
+            .ok_or(ValidationError::InvalidInput)?;
+        if !info.data_is_empty() {
+            require!(info.is_writable, ValidationError::InvalidInput);
+            let mut trade_route: Account<'info, TradeRoute> = Account::try_from(info)?;
+            require!(trade_route.is_current(), ValidationError::AccountOutdated);
+            trade_route.in_flight = math::sub(trade_route.in_flight, shipment.amount)?;
+            trade_route.exit(ctx.program_id)?;
+        }
+    }
+
+    emit!(ShipmentReceived {
+        shipment: shipment.key(),
+        storage_to: storage_to.key(),
+        amount: shipment.amount,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ReceiveShipment<'info> {
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        has_one = storage_to,
+        constraint = shipment.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub shipment: Account<'info, Shipment>,
+    #[account(mut, constraint = storage_to.is_current() @ ValidationError::AccountOutdated)]
+    pub storage_to: Account<'info, Storage>,
+    /// CHECK: Only receives the rent of the closed shipment, verified by has_one on the shipment.
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+}
@@ -2,7 +2,10 @@ use anchor_lang::prelude::*;
 
 use crate::state::{storage::*, Location, OwnershipRef};
 use crate::instructions::location;
+use crate::instructions::debug::*;
+use crate::events::{StorageArrived, StorageInitialized, StorageMoved, StorageTransferred};
 use crate::errors::ValidationError;
+use crate::math;
 
 pub fn init(
     ctx: Context<InitStorage>,
@@ -17,6 +20,7 @@ pub fn init(
     let location: &mut Account<Location> = &mut ctx.accounts.location;
     let owner: &Signer = &ctx.accounts.owner;
 
+    storage.version = Storage::VERSION;
     storage.owner = owner.key();
     storage.resource_id = resource_id;
     storage.location_id = location.key();
@@ -26,6 +30,14 @@ pub fn init(
     storage.movement_speed = movement_speed;
     storage.arrives_at = 0;
 
+    emit!(StorageInitialized {
+        storage: storage.key(),
+        owner: storage.owner,
+        resource_id,
+        location_id: storage.location_id,
+        capacity,
+    });
+
     location.add(owner, OwnershipRef { item: storage.key(), player: owner.key() })
 }
 
@@ -44,13 +56,9 @@ pub struct InitStorage<'info> {
 
     #[account(
         mut,
-        seeds = [
-            b"map-location", 
-            owner.key().as_ref(),
-            &x.to_le_bytes(),
-            &y.to_le_bytes(),
-        ],
-        bump = location.bump,
+        constraint = location.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = location.is_at(x, y) @ ValidationError::DifferentLocations,
+        constraint = location.is_current() @ ValidationError::AccountOutdated,
     )]
     pub location: Account<'info, Location>,
 
@@ -62,22 +70,42 @@ pub struct InitStorage<'info> {
 pub fn move_between(ctx: Context<MoveBetweenStorage>, amount: i64) -> Result<()> {
     let from_storage: &mut Account<Storage> = &mut ctx.accounts.storage_from;
     let to_storage: &mut Account<Storage> = &mut ctx.accounts.storage_to;
-    let _owner: &Signer = &ctx.accounts.owner;
+    let owner: &Signer = &ctx.accounts.owner;
 
-    from_storage.remove(amount)?;
-    to_storage.add(amount, from_storage.location_id)?;
-    
+    require!(amount > 0, ValidationError::InvalidInput);
     require!(from_storage.resource_id == to_storage.resource_id, ValidationError::ResourceNotMatching);
     require!(from_storage.location_id == to_storage.location_id, ValidationError::DifferentLocations);
 
+    from_storage.remove(amount)?;
+    to_storage.add(amount, from_storage.location_id)?;
+
+    emit!(StorageTransferred {
+        from_storage: from_storage.key(),
+        to_storage: to_storage.key(),
+        from_player: owner.key(),
+        to_player: to_storage.owner,
+        resource_id: from_storage.resource_id,
+        location_id: from_storage.location_id,
+        amount,
+    });
+
     Ok(())
 }
 
 #[derive(Accounts)]
 pub struct MoveBetweenStorage<'info> {
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = storage_from.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = storage_from.is_current() @ ValidationError::AccountOutdated,
+    )]
     pub storage_from: Account<'info, Storage>,
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = storage_to.key() != storage_from.key() @ ValidationError::SameStorage,
+        constraint = storage_to.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = storage_to.is_current() @ ValidationError::AccountOutdated,
+    )]
     pub storage_to: Account<'info, Storage>,
     #[account(mut)]
     pub owner: Signer<'info>,
@@ -94,24 +122,30 @@ pub fn move_to_location(ctx: Context<MoveStorage>, current_timestamp: i64) -> Re
     require!(!storage.is_moving(current_timestamp), ValidationError::NotAllowedWhileMoving);
 
     storage.location_id = to_location.key();
-    let distance_time = from_location.distance_time(to_location);
+    let distance_time = from_location.distance_time(to_location)?;
     let travel_time = distance_time / storage.movement_speed;
     storage.arrives_at = match travel_time {
         0 => 0,
-        _ => current_timestamp + travel_time,
+        _ => math::add(current_timestamp, travel_time)?,
     };
 
+    emit!(StorageMoved {
+        storage: storage.key(),
+        from_location_id: from_location.key(),
+        to_location_id: to_location.key(),
+        arrives_at: storage.arrives_at,
+    });
 
     location::register_move(owner, from_location, to_location, OwnershipRef { item: storage.key(), player: storage.owner })
 }
 
 #[derive(Accounts)]
 pub struct MoveStorage<'info> {
-    #[account(mut)]
+    #[account(mut, constraint = storage.is_current() @ ValidationError::AccountOutdated)]
     pub storage: Account<'info, Storage>,
-    #[account(mut)]
+    #[account(mut, constraint = from_location.is_current() @ ValidationError::AccountOutdated)]
     pub from_location: Account<'info, Location>,
-    #[account(mut)]
+    #[account(mut, constraint = to_location.is_current() @ ValidationError::AccountOutdated)]
     pub to_location: Account<'info, Location>,
     #[account(mut)]
     pub owner: Signer<'info>,
@@ -125,6 +159,8 @@ pub fn update_move_status(ctx: Context<UpdateStorageMoveStatus>, current_timesta
     
     if storage.has_arrived(current_timestamp) {
         storage.arrives_at = 0;
+
+        emit!(StorageArrived { storage: storage.key(), location_id: storage.location_id });
     }
 
     Ok(())
@@ -132,7 +168,7 @@ pub fn update_move_status(ctx: Context<UpdateStorageMoveStatus>, current_timesta
 
 #[derive(Accounts)]
 pub struct UpdateStorageMoveStatus<'info> {
-    #[account(mut)]
+    #[account(mut, constraint = storage.is_current() @ ValidationError::AccountOutdated)]
     pub storage: Account<'info, Storage>,
     #[account(mut)]
     pub owner: Signer<'info>,
@@ -155,18 +191,21 @@ pub struct SimpleInitStorage<'info> {
             &xy,
         ],
         bump = location.bump,
+        constraint = location.is_current() @ ValidationError::AccountOutdated,
     )]
     pub location: Account<'info, Location>,
     pub system_program: Program<'info, System>,
 }
+#[cfg(feature = "debug")]
 pub fn simple_init(
-    ctx: Context<SimpleInitStorage>, xy: [u8; 2]) -> Result<()> {
+    ctx: Context<SimpleInitStorage>, _xy: [u8; 2]) -> Result<()> {
     let storage: &mut Account<Storage> = &mut ctx.accounts.storage;
     //let location: &mut Account<Location> = &mut ctx.accounts.location;
     let owner: &Signer = &ctx.accounts.owner;
 
     //require!(position[0] > position[1], ValidationError::ExperimentalError);
 
+    storage.version = Storage::VERSION;
     storage.owner = owner.key();
     storage.resource_id = owner.key();
     storage.location_id = owner.key();
@@ -183,7 +222,7 @@ pub fn simple_init(
 pub struct SimpleTestStorage<'info> {
     #[account(mut)]
     pub owner: Signer<'info>,
-    #[account(mut)]
+    #[account(mut, constraint = storage.is_current() @ ValidationError::AccountOutdated)]
     pub storage: Account<'info, Storage>,
     #[account(
         mut,
@@ -193,15 +232,15 @@ pub struct SimpleTestStorage<'info> {
             &xy,
         ],
         bump = location.bump,
+        constraint = location.is_current() @ ValidationError::AccountOutdated,
     )]
     pub location: Account<'info, Location>,
+    pub admin: DebugAuthority<'info>,
     pub system_program: Program<'info, System>,
 }
+#[cfg(feature = "debug")]
 pub fn simple_test(
-    ctx: Context<SimpleTestStorage>, position: [u8; 2]) -> Result<()> {
-    let storage: &mut Account<Storage> = &mut ctx.accounts.storage;
-    let location: &mut Account<Location> = &mut ctx.accounts.location;
-
+    _ctx: Context<SimpleTestStorage>, position: [u8; 2]) -> Result<()> {
     require!(position[0] < position[1], ValidationError::ExperimentalError);
 
     Ok(())
@@ -1,7 +1,9 @@
 use anchor_lang::prelude::*;
 use crate::state::stuff::*;
+use crate::instructions::debug::*;
 
 
+#[cfg(feature = "debug")]
 pub fn init(ctx: Context<InitStuff>, x: i64) -> Result<()> {
     let stuff: &mut Account<Stuff> = &mut ctx.accounts.stuff;
     
@@ -33,6 +35,7 @@ pub struct InitStuff<'info> {
     pub stuff: Account<'info, Stuff>,
     #[account(mut)]
     pub owner: Signer<'info>,
+    pub admin: DebugAuthority<'info>,
     pub system_program: Program<'info, System>,
 }
 
@@ -0,0 +1,179 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{trade_route::*, shipment::*, storage::*, FuelCostType, Location, Processor, ProcessorType, DISTANCE_TIME_FACTOR};
+use crate::instructions::{location, processor};
+use crate::events::TradeRouteRun;
+use crate::errors::ValidationError;
+use crate::math;
+
+pub fn create(ctx: Context<CreateTradeRoute>, amount_per_cycle: i64, cadence: i64, current_timestamp: i64) -> Result<()> {
+    let trade_route: &mut Account<TradeRoute> = &mut ctx.accounts.trade_route;
+    let sender: &Account<Processor> = &ctx.accounts.sender;
+    let storage_from: &Account<Storage> = &ctx.accounts.storage_from;
+    let storage_to: &Account<Storage> = &ctx.accounts.storage_to;
+    let from_location: &Account<Location> = &ctx.accounts.from_location;
+    let to_location: &Account<Location> = &ctx.accounts.to_location;
+    let owner: &Signer = &ctx.accounts.owner;
+
+    require!(sender.processor_type == ProcessorType::Sender, ValidationError::InvalidProcessorType);
+    require!(sender.fuel_cost_type == FuelCostType::Distance, ValidationError::InvalidFuelCostType);
+    require!(storage_from.resource_id == storage_to.resource_id, ValidationError::ResourceNotMatching);
+    require!(amount_per_cycle > 0, ValidationError::InvalidInput);
+    require!(cadence > 0 && cadence >= from_location.distance_time(to_location)?, ValidationError::InvalidInput);
+
+    trade_route.version = TradeRoute::VERSION;
+    trade_route.owner = owner.key();
+    trade_route.sender_id = sender.key();
+    trade_route.storage_from = storage_from.key();
+    trade_route.storage_to = storage_to.key();
+    trade_route.storage_fuel = ctx.accounts.storage_fuel.key();
+    trade_route.to_location_id = to_location.key();
+    trade_route.distance = from_location.distance(to_location)?;
+    trade_route.amount_per_cycle = amount_per_cycle;
+    trade_route.cadence = cadence;
+    trade_route.next_cycle_at = math::add(current_timestamp, cadence)?;
+    trade_route.in_flight = 0;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CreateTradeRoute<'info> {
+    #[account(init, payer = owner, space = TradeRoute::LEN)]
+    pub trade_route: Account<'info, TradeRoute>,
+    #[account(
+        constraint = sender.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = sender.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub sender: Account<'info, Processor>,
+    #[account(
+        constraint = storage_from.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = storage_from.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub storage_from: Account<'info, Storage>,
+    #[account(constraint = storage_to.is_current() @ ValidationError::AccountOutdated)]
+    pub storage_to: Account<'info, Storage>,
+    #[account(
+        constraint = storage_fuel.key() != storage_from.key() @ ValidationError::SameStorage,
+        constraint = storage_fuel.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = storage_fuel.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub storage_fuel: Account<'info, Storage>,
+    #[account(
+        address = sender.location_id @ ValidationError::DifferentLocations,
+        constraint = from_location.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub from_location: Account<'info, Location>,
+    #[account(
+        address = storage_to.location_id @ ValidationError::DifferentLocations,
+        constraint = to_location.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub to_location: Account<'info, Location>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Permissionless, anyone can crank a route once a cycle is due. The cargo of the due cycles leaves
+// as one shipment, which whoever cranks pays for and gets the rent of back once it's received.
+pub fn run(ctx: Context<RunTradeRoute>, current_timestamp: i64) -> Result<()> {
+    let trade_route: &mut Account<TradeRoute> = &mut ctx.accounts.trade_route;
+    let sender: &Account<Processor> = &ctx.accounts.sender;
+    let storage_from: &mut Account<Storage> = &mut ctx.accounts.storage_from;
+    let storage_to: &Account<Storage> = &ctx.accounts.storage_to;
+    let storage_fuel: &mut Account<Storage> = &mut ctx.accounts.storage_fuel;
+    let shipment: &mut Account<Shipment> = &mut ctx.accounts.shipment;
+    let payer: &Signer = &ctx.accounts.payer;
+
+    let cycles = trade_route.due_cycles(current_timestamp)?;
+    require!(cycles > 0, ValidationError::TradeRouteNotDue);
+
+    let from_location_id = sender.location_id(current_timestamp);
+    require!(location::same_location_id(from_location_id, storage_from.location_id(current_timestamp)), ValidationError::DifferentLocations);
+    require!(location::same_location_id(Some(trade_route.to_location_id), storage_to.location_id(current_timestamp)), ValidationError::DifferentLocations);
+    processor::validate_fuel(sender, storage_fuel, current_timestamp)?;
+
+    // Whatever doesn't fit or can't be paid for is skipped rather than carried over to the next cycle,
+    // but a run that ships nothing fails and leaves the cycles due.
+    let available_capacity = math::sub(math::sub(storage_to.capacity, storage_to.amount)?, trade_route.in_flight)?.max(0);
+    let amount = cycles.saturating_mul(trade_route.amount_per_cycle)
+        .min(storage_from.amount)
+        .min(available_capacity)
+        .min(sender.max_units_for_fuel(storage_fuel.amount, trade_route.distance));
+
+    require!(amount > 0, ValidationError::NothingToShip);
+
+    let fuel_before = storage_fuel.amount;
+    processor::burn_fuel(sender, storage_fuel, amount, trade_route.distance)?;
+    storage_from.remove(amount)?;
+
+    shipment.version = Shipment::VERSION;
+    shipment.owner = payer.key();
+    shipment.sender_id = sender.key();
+    shipment.resource_id = storage_from.resource_id;
+    shipment.amount = amount;
+    shipment.from_location_id = sender.location_id;
+    shipment.to_location_id = trade_route.to_location_id;
+    shipment.storage_to = storage_to.key();
+    shipment.arrives_at = math::add(current_timestamp, math::mul(trade_route.distance, DISTANCE_TIME_FACTOR)?)?;
+    shipment.trade_route = trade_route.key();
+
+    trade_route.in_flight = math::add(trade_route.in_flight, amount)?;
+    trade_route.next_cycle_at = math::add(trade_route.next_cycle_at, math::mul(cycles, trade_route.cadence)?)?;
+
+    emit!(TradeRouteRun {
+        trade_route: trade_route.key(),
+        shipment: shipment.key(),
+        cycles,
+        amount,
+        fuel: math::sub(fuel_before, storage_fuel.amount)?,
+        arrives_at: shipment.arrives_at,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RunTradeRoute<'info> {
+    #[account(
+        mut,
+        has_one = storage_from,
+        has_one = storage_to,
+        has_one = storage_fuel,
+        constraint = trade_route.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub trade_route: Account<'info, TradeRoute>,
+    #[account(
+        address = trade_route.sender_id,
+        constraint = sender.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub sender: Account<'info, Processor>,
+    #[account(mut, constraint = storage_from.is_current() @ ValidationError::AccountOutdated)]
+    pub storage_from: Account<'info, Storage>,
+    #[account(constraint = storage_to.is_current() @ ValidationError::AccountOutdated)]
+    pub storage_to: Account<'info, Storage>,
+    #[account(mut, constraint = storage_fuel.is_current() @ ValidationError::AccountOutdated)]
+    pub storage_fuel: Account<'info, Storage>,
+    #[account(init, payer = payer, space = Shipment::LEN)]
+    pub shipment: Account<'info, Shipment>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn close(_ctx: Context<CloseTradeRoute>) -> Result<()> {
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CloseTradeRoute<'info> {
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ ValidationError::OwnerRequired,
+        constraint = trade_route.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub trade_route: Account<'info, TradeRoute>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
@@ -0,0 +1,126 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{storage::*, transfer::*};
+use crate::instructions::location;
+use crate::events::StorageTransferred;
+use crate::errors::ValidationError;
+
+pub fn transfer_to_player(ctx: Context<TransferToPlayer>, amount: i64, current_timestamp: i64) -> Result<()> {
+    let from_storage: &mut Account<Storage> = &mut ctx.accounts.storage_from;
+    let to_storage: &mut Account<Storage> = &mut ctx.accounts.storage_to;
+    let allow_list: &UncheckedAccount = &ctx.accounts.allow_list;
+    let sender: &Signer = &ctx.accounts.sender;
+
+    require!(amount > 0, ValidationError::InvalidInput);
+    require!(from_storage.resource_id == to_storage.resource_id, ValidationError::ResourceNotMatching);
+
+    let location_id = from_storage.location_id(current_timestamp);
+    require!(location::same_location_id(location_id, to_storage.location_id(current_timestamp)), ValidationError::DifferentLocations);
+
+    // The recipient only restricts senders once an allow-list has been created.
+    if !allow_list.data_is_empty() {
+        let allow_list: Account<TransferAllowList> = Account::try_from(&allow_list.to_account_info())?;
+        require!(allow_list.is_current(), ValidationError::AccountOutdated);
+        require!(allow_list.allows(&sender.key()), ValidationError::TransferNotAllowed);
+    }
+
+    from_storage.remove(amount)?;
+    to_storage.add(amount, from_storage.location_id)?;
+
+    emit!(StorageTransferred {
+        from_storage: from_storage.key(),
+        to_storage: to_storage.key(),
+        from_player: from_storage.owner,
+        to_player: to_storage.owner,
+        resource_id: from_storage.resource_id,
+        location_id: from_storage.location_id,
+        amount,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct TransferToPlayer<'info> {
+    #[account(
+        mut,
+        constraint = storage_from.owner == sender.key() @ ValidationError::OwnerRequired,
+        constraint = storage_from.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub storage_from: Account<'info, Storage>,
+    #[account(
+        mut,
+        constraint = storage_to.key() != storage_from.key() @ ValidationError::SameStorage,
+        constraint = storage_to.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub storage_to: Account<'info, Storage>,
+    /// CHECK: The recipient's allow-list, which might not have been created. Only deserialized when it exists.
+    #[account(
+        seeds = [
+            b"transfer-allow-list",
+            storage_to.owner.as_ref(),
+        ],
+        bump,
+    )]
+    pub allow_list: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub sender: Signer<'info>,
+}
+
+pub fn init_allow_list(ctx: Context<InitTransferAllowList>, allowed: Vec<Pubkey>) -> Result<()> {
+    let allow_list: &mut Account<TransferAllowList> = &mut ctx.accounts.allow_list;
+    let owner: &Signer = &ctx.accounts.owner;
+
+    require!(allowed.len() <= ALLOW_LIST_MAX_SIZE, ValidationError::AllowListFull);
+
+    allow_list.version = TransferAllowList::VERSION;
+    allow_list.owner = owner.key();
+    allow_list.allowed = allowed;
+    allow_list.bump = *ctx.bumps.get("allow_list").unwrap();
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitTransferAllowList<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = TransferAllowList::LEN,
+        seeds = [
+            b"transfer-allow-list",
+            owner.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub allow_list: Account<'info, TransferAllowList>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn update_allow_list(ctx: Context<UpdateTransferAllowList>, allowed: Vec<Pubkey>) -> Result<()> {
+    let allow_list: &mut Account<TransferAllowList> = &mut ctx.accounts.allow_list;
+
+    require!(allowed.len() <= ALLOW_LIST_MAX_SIZE, ValidationError::AllowListFull);
+
+    allow_list.allowed = allowed;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateTransferAllowList<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"transfer-allow-list",
+            owner.key().as_ref(),
+        ],
+        bump = allow_list.bump,
+        has_one = owner @ ValidationError::OwnerRequired,
+        constraint = allow_list.is_current() @ ValidationError::AccountOutdated,
+    )]
+    pub allow_list: Account<'info, TransferAllowList>,
+    pub owner: Signer<'info>,
+}
@@ -1,6 +1,8 @@
 use anchor_lang::prelude::*;
 use crate::state::{unit::*, Location, LocationType, Map};
+use crate::events::{LocationExplored, UnitArrived, UnitInitialized, UnitMoveStarted};
 use crate::errors::ValidationError;
+use crate::math;
 
 pub fn init(ctx: Context<InitUnit>, name: String, _x: i64, _y: i64, _game: Pubkey) -> Result<()> {
     let unit: &mut Account<Unit> = &mut ctx.accounts.unit;
@@ -9,6 +11,7 @@ pub fn init(ctx: Context<InitUnit>, name: String, _x: i64, _y: i64, _game: Pubke
 
     require!(location.location_type != LocationType::Unexplored, ValidationError::LocationUnexplored);
 
+    unit.version = Unit::VERSION;
     unit.owner = *owner.key;
     unit.name = name;
     unit.at_location_id = location.key();
@@ -18,6 +21,8 @@ pub fn init(ctx: Context<InitUnit>, name: String, _x: i64, _y: i64, _game: Pubke
 
     require!(unit.name.len() <= NAME_LENGTH, ValidationError::NameTooLong);
 
+    emit!(UnitInitialized { unit: unit.key(), owner: unit.owner, name: unit.name.clone(), location_id: unit.at_location_id });
+
     Ok(())
 }
 
@@ -50,6 +55,7 @@ pub struct InitUnit<'info> {
             &y.to_le_bytes(),
         ],
         bump = location.bump,
+        constraint = location.is_current() @ ValidationError::AccountOutdated,
     )]
     pub location: Account<'info, Location>,
     #[account(mut)]
@@ -63,18 +69,25 @@ pub fn move_unit_start(ctx: Context<MoveUnitStart>, _from_x: i64, _from_y: i64,
     let to_location: &mut Account<Location> = &mut ctx.accounts.to_location;
 
     require!(unit.at_location_id == from_location.key(), ValidationError::ExperimentalError);
-    require!(from_location.distance(to_location) == 1, ValidationError::UnitMoveInvalid);
+    require!(from_location.distance(to_location)? == 1, ValidationError::UnitMoveInvalid);
     require!(unit.movement_speed > 0, ValidationError::ExperimentalError);
     require!(!unit.is_moving(current_timestamp), ValidationError::NotAllowedWhileMoving);
 
     unit.at_location_id = to_location.key();
-    let distance_time = from_location.distance_time(to_location);
+    let distance_time = from_location.distance_time(to_location)?;
     let travel_time = distance_time / unit.movement_speed;
     unit.arrives_at = match travel_time {
         0 => 0,
-        _ => current_timestamp + travel_time,
+        _ => math::add(current_timestamp, travel_time)?,
     };
 
+    emit!(UnitMoveStarted {
+        unit: unit.key(),
+        from_location_id: from_location.key(),
+        to_location_id: to_location.key(),
+        arrives_at: unit.arrives_at,
+    });
+
     Ok(())
 }
 
@@ -85,8 +98,11 @@ pub fn move_unit_complete(ctx: Context<MoveUnitComplete>, _to_x: i64, _to_y: i64
 
     if unit.arrives_at > 0 && unit.location_id(current_timestamp) == Some(to_location.key()) {
         unit.arrives_at = 0;
-    if to_location.location_type == LocationType::Unexplored {
-        to_location.explore(map);
+        emit!(UnitArrived { unit: unit.key(), location_id: to_location.key() });
+
+        if to_location.location_type == LocationType::Unexplored {
+            to_location.explore(map)?;
+            emit!(LocationExplored { location: to_location.key(), location_type: to_location.location_type.clone() });
         }
     }
 
@@ -104,28 +120,21 @@ pub struct MoveUnitStart<'info> {
             &string_to_seed(&name),
         ],
         bump = unit.bump,
+        constraint = unit.is_current() @ ValidationError::AccountOutdated,
     )]
     pub unit: Account<'info, Unit>,
     #[account(
         mut,
-        seeds = [
-            b"map-location", 
-            owner.key().as_ref(),
-            &from_x.to_le_bytes(),
-            &from_y.to_le_bytes(),
-        ],
-        bump = from_location.bump,
+        constraint = from_location.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = from_location.is_at(from_x, from_y) @ ValidationError::DifferentLocations,
+        constraint = from_location.is_current() @ ValidationError::AccountOutdated,
     )]
     pub from_location: Account<'info, Location>,
     #[account(
         mut,
-        seeds = [
-            b"map-location", 
-            owner.key().as_ref(),
-            &to_x.to_le_bytes(),
-            &to_y.to_le_bytes(),
-        ],
-        bump = to_location.bump,
+        constraint = to_location.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = to_location.is_at(to_x, to_y) @ ValidationError::DifferentLocations,
+        constraint = to_location.is_current() @ ValidationError::AccountOutdated,
     )]
     pub to_location: Account<'info, Location>,
     #[account(mut)]
@@ -144,20 +153,17 @@ pub struct MoveUnitComplete<'info> {
             &string_to_seed(&name),
         ],
         bump = unit.bump,
+        constraint = unit.is_current() @ ValidationError::AccountOutdated,
     )]
     pub unit: Account<'info, Unit>,
     #[account(
         mut,
-        seeds = [
-            b"map-location", 
-            owner.key().as_ref(),
-            &to_x.to_le_bytes(),
-            &to_y.to_le_bytes(),
-        ],
-        bump = to_location.bump,
+        constraint = to_location.owner == owner.key() @ ValidationError::OwnerRequired,
+        constraint = to_location.is_at(to_x, to_y) @ ValidationError::DifferentLocations,
+        constraint = to_location.is_current() @ ValidationError::AccountOutdated,
     )]
     pub to_location: Account<'info, Location>,
-    #[account(mut)]
+    #[account(mut, constraint = map.is_current() @ ValidationError::AccountOutdated)]
     pub map: Account<'info, Map>,
     #[account(mut)]
     pub owner: Signer<'info>,
@@ -1,11 +1,13 @@
 use anchor_lang::prelude::*;
 use instructions::*;
 use crate::state::MobilityType;
-use crate::state::ProcessorType;
-use crate::state::FuelCostType;
 use crate::state::LocationType;
+use crate::state::OrderSide;
 
+pub mod clock;
 pub mod errors;
+pub mod events;
+pub mod math;
 pub mod state;
 pub mod instructions;
 
@@ -13,10 +15,19 @@ pub mod instructions;
 // local: 
 declare_id!("CbU9TfAS58V2JprRyMZ54hM48nMseTxth6FW6sCW79nM");
 
+// Anchor 0.25 dispatches every instruction fn of the program module whatever its cfg, so builds
+// without a feature get stand-ins, out of Anchor's sight, that only fail with the feature's error.
+#[allow(unused_macros)]
+macro_rules! disabled_instructions {
+    ($error:expr; $($name:ident($accounts:ident $(, $arg:ty)*);)*) => {
+        $(pub fn $name(_ctx: Context<$accounts> $(, _: $arg)*) -> Result<()> {
+            Err($error.into())
+        })*
+    };
+}
+
 #[program]
 pub mod got_a_min {
-    use crate::state::FuelCostType;
-
     use super::*;
 
     pub fn create_game_tile(ctx: Context<CreateGameTile>, xy: [u8; 2]) -> Result<()> {
@@ -27,13 +38,33 @@ pub mod got_a_min {
         game::explore_game_tile(ctx, xy)
     }
 
+    pub fn init_game(ctx: Context<InitGame>, swap_fee_bps: u16) -> Result<()> {
+        game::init_game(ctx, swap_fee_bps)
+    }
+
+    pub fn update_game_fees(ctx: Context<UpdateGame>, swap_fee_bps: u16) -> Result<()> {
+        game::update_game_fees(ctx, swap_fee_bps)
+    }
+
+    pub fn update_game_levels(ctx: Context<UpdateGame>, level_throughput_bps: Vec<u32>, upgrade_resource_id: Pubkey, upgrade_cost_base: i64) -> Result<()> {
+        game::update_game_levels(ctx, level_throughput_bps, upgrade_resource_id, upgrade_cost_base)
+    }
+
+    pub fn update_game_retool_delay(ctx: Context<UpdateGame>, retool_delay: i64) -> Result<()> {
+        game::update_game_retool_delay(ctx, retool_delay)
+    }
+
+    pub fn update_game_wear(ctx: Context<UpdateGame>, max_durability: i64, repair_resource_id: Pubkey, repair_cost_per_point: i64) -> Result<()> {
+        game::update_game_wear(ctx, max_durability, repair_resource_id, repair_cost_per_point)
+    }
+
     pub fn init_location(ctx: Context<InitLocation>, x: i64, y: i64, capacity: i64, location_type: LocationType) -> Result<()> {
         location::init(ctx, x, y, capacity, location_type)
     }
 
-    pub fn init_processor(ctx: Context<InitProcessor>, processor_type: ProcessorType, fuel_resource_id: Pubkey, output_resource_id: Pubkey, output_rate: i64, processing_duration: i64, fuel_cost_type: FuelCostType) -> Result<()> {
-        let current_timestamp = Clock::get()?.unix_timestamp;
-        processor::init(ctx, processor_type, fuel_resource_id, output_resource_id, output_rate, processing_duration, fuel_cost_type, current_timestamp)
+    pub fn init_processor(ctx: Context<InitProcessor>, spec: ProcessorSpec) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        processor::init(ctx, spec, current_timestamp)
     }
 
     pub fn init_resource(ctx: Context<InitResource>, name: String, inputs: Vec<Pubkey>, input_amounts: Vec<i64>) -> Result<()> {
@@ -52,11 +83,13 @@ pub mod got_a_min {
         storage::init(ctx, resource_id, capacity, mobility_type, movement_speed, x, y)
     }
 
+    #[cfg(feature = "debug")]
     pub fn simple_init_storage(
         ctx: Context<SimpleInitStorage>, xy: [u8; 2]) -> Result<()> {
         storage::simple_init(ctx, xy)
     }
 
+    #[cfg(feature = "debug")]
     pub fn simple_test_storage(
         ctx: Context<SimpleTestStorage>, position: [u8; 2]) -> Result<()> {
         storage::simple_test(ctx, position)
@@ -66,47 +99,226 @@ pub mod got_a_min {
         storage::move_between(ctx, amount)
     }
 
+    pub fn transfer_to_player(ctx: Context<TransferToPlayer>, amount: i64) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        transfer::transfer_to_player(ctx, amount, current_timestamp)
+    }
+
+    pub fn init_transfer_allow_list(ctx: Context<InitTransferAllowList>, allowed: Vec<Pubkey>) -> Result<()> {
+        transfer::init_allow_list(ctx, allowed)
+    }
+
+    pub fn update_transfer_allow_list(ctx: Context<UpdateTransferAllowList>, allowed: Vec<Pubkey>) -> Result<()> {
+        transfer::update_allow_list(ctx, allowed)
+    }
+
+    pub fn create_offer(ctx: Context<CreateOffer>, offered_amount: i64, requested_resource_id: Pubkey, requested_amount: i64) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        offer::create(ctx, offered_amount, requested_resource_id, requested_amount, current_timestamp)
+    }
+
+    pub fn accept_offer(ctx: Context<AcceptOffer>) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        offer::accept(ctx, current_timestamp)
+    }
+
+    pub fn cancel_offer(ctx: Context<CancelOffer>) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        offer::cancel(ctx, current_timestamp)
+    }
+
+    pub fn init_order_book(ctx: Context<InitOrderBook>, base_resource_id: Pubkey, quote_resource_id: Pubkey) -> Result<()> {
+        order_book::init(ctx, base_resource_id, quote_resource_id)
+    }
+
+    pub fn place_order(ctx: Context<PlaceOrder>, side: OrderSide, price: i64, amount: i64) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        order_book::place(ctx, side, price, amount, current_timestamp)
+    }
+
+    pub fn cancel_order(ctx: Context<CancelOrder>, order_id: u64) -> Result<()> {
+        order_book::cancel(ctx, order_id)
+    }
+
+    pub fn claim_order(ctx: Context<ClaimOrder>, order_id: u64) -> Result<()> {
+        order_book::claim(ctx, order_id)
+    }
+
+    pub fn create_pool(ctx: Context<CreatePool>, resource_a_id: Pubkey, resource_b_id: Pubkey) -> Result<()> {
+        pool::create(ctx, resource_a_id, resource_b_id)
+    }
+
+    pub fn add_liquidity(ctx: Context<AddLiquidity>, amount_a: i64, amount_b: i64, min_shares: i64) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        pool::add_liquidity(ctx, amount_a, amount_b, min_shares, current_timestamp)
+    }
+
+    pub fn remove_liquidity(ctx: Context<RemoveLiquidity>, shares: i64, min_amount_a: i64, min_amount_b: i64) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        pool::remove_liquidity(ctx, shares, min_amount_a, min_amount_b, current_timestamp)
+    }
+
+    pub fn swap(ctx: Context<Swap>, amount_in: i64, min_amount_out: i64) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        pool::swap(ctx, amount_in, min_amount_out, current_timestamp)
+    }
+
+    pub fn create_location_auction(ctx: Context<CreateLocationAuction>, bid_resource_id: Pubkey, min_bid: i64, duration: i64) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        auction::create_location(ctx, bid_resource_id, min_bid, duration, current_timestamp)
+    }
+
+    pub fn create_processor_auction(ctx: Context<CreateProcessorAuction>, bid_resource_id: Pubkey, min_bid: i64, duration: i64) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        auction::create_processor(ctx, bid_resource_id, min_bid, duration, current_timestamp)
+    }
+
+    pub fn create_goods_auction(ctx: Context<CreateGoodsAuction>, amount: i64, bid_resource_id: Pubkey, min_bid: i64, duration: i64) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        auction::create_goods(ctx, amount, bid_resource_id, min_bid, duration, current_timestamp)
+    }
+
+    pub fn place_bid(ctx: Context<PlaceBid>, amount: i64) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        auction::place_bid(ctx, amount, current_timestamp)
+    }
+
+    pub fn claim_bid_refund(ctx: Context<ClaimBidRefund>) -> Result<()> {
+        auction::claim_bid_refund(ctx)
+    }
+
+    pub fn settle_location_auction(ctx: Context<SettleLocationAuction>) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        auction::settle_location(ctx, current_timestamp)
+    }
+
+    pub fn settle_processor_auction(ctx: Context<SettleProcessorAuction>) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        auction::settle_processor(ctx, current_timestamp)
+    }
+
+    pub fn settle_goods_auction(ctx: Context<SettleGoodsAuction>) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        auction::settle_goods(ctx, current_timestamp)
+    }
+
     pub fn update_storage_move_status(ctx: Context<UpdateStorageMoveStatus>) -> Result<()> {
-        let current_timestamp = Clock::get()?.unix_timestamp;
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
         storage::update_move_status(ctx, current_timestamp)
     }
 
     pub fn move_storage(ctx: Context<MoveStorage>) -> Result<()> {
-        let current_timestamp = Clock::get()?.unix_timestamp;
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
         storage::move_to_location(ctx, current_timestamp)
     }
 
     pub fn produce_without_input(ctx: Context<ProcessesResource>) -> Result<()> {
-        let current_timestamp = Clock::get()?.unix_timestamp;
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
         processor::claim_production(ctx, current_timestamp)
     }
 
     pub fn produce_with_one_input(ctx: Context<ProcessesResourceWith1Input>) -> Result<()> {
-        let current_timestamp = Clock::get()?.unix_timestamp;
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
         processor::produce_with_one_input(ctx, current_timestamp)
     }
 
     pub fn produce_with_two_inputs(ctx: Context<ProcessesResourceWith2Inputs>) -> Result<()> {
-        let current_timestamp = Clock::get()?.unix_timestamp;
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
         processor::produce_with_two_inputs(ctx, current_timestamp)
     }
 
+    pub fn upgrade_processor(ctx: Context<UpgradeProcessor>) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        processor::upgrade(ctx, current_timestamp)
+    }
+
+    pub fn pause_processor(ctx: Context<PauseProcessor>) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        processor::pause(ctx, current_timestamp)
+    }
+
+    pub fn resume_processor(ctx: Context<PauseProcessor>) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        processor::resume(ctx, current_timestamp)
+    }
+
+    pub fn retool_processor(ctx: Context<RetoolProcessor>, output_resource_id: Pubkey) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        processor::retool(ctx, output_resource_id, current_timestamp)
+    }
+
+    pub fn repair_processor(ctx: Context<RepairProcessor>, points: i64) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        processor::repair(ctx, points, current_timestamp)
+    }
+
+    pub fn move_processor(ctx: Context<MoveProcessor>) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        processor::move_to(ctx, current_timestamp)
+    }
+
+    pub fn link_storage(ctx: Context<LinkStorage>) -> Result<()> {
+        processor::link_storage(ctx)
+    }
+
+    pub fn unlink_storage(ctx: Context<UnlinkStorage>) -> Result<()> {
+        processor::unlink_storage(ctx)
+    }
+
+    pub fn init_production_queue(ctx: Context<InitProductionQueue>) -> Result<()> {
+        production_queue::init(ctx)
+    }
+
+    pub fn enqueue_job<'info>(ctx: Context<'_, '_, '_, 'info, EnqueueJob<'info>>, quantity: i64, priority: u8) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        production_queue::enqueue(ctx, quantity, priority, current_timestamp)
+    }
+
+    pub fn claim_jobs(ctx: Context<ClaimJobs>) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        production_queue::claim(ctx, current_timestamp)
+    }
+
+    pub fn cancel_job<'info>(ctx: Context<'_, '_, '_, 'info, CancelJob<'info>>, job_id: u64) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        production_queue::cancel(ctx, job_id, current_timestamp)
+    }
+
     pub fn send(ctx: Context<SendResource>, send_amount: i64, from_x: i64, from_y: i64, to_x: i64, to_y: i64) -> Result<()> {
-        let current_timestamp = Clock::get()?.unix_timestamp;
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
         processor::send(ctx, send_amount, current_timestamp, from_x, from_y, to_x, to_y)
     }
 
+    pub fn receive_shipment<'info>(ctx: Context<'_, '_, '_, 'info, ReceiveShipment<'info>>) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        shipment::receive(ctx, current_timestamp)
+    }
+
+    pub fn create_trade_route(ctx: Context<CreateTradeRoute>, amount_per_cycle: i64, cadence: i64) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        trade_route::create(ctx, amount_per_cycle, cadence, current_timestamp)
+    }
+
+    pub fn run_trade_route(ctx: Context<RunTradeRoute>) -> Result<()> {
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
+        trade_route::run(ctx, current_timestamp)
+    }
+
+    pub fn close_trade_route(ctx: Context<CloseTradeRoute>) -> Result<()> {
+        trade_route::close(ctx)
+    }
+
     pub fn init_unit(ctx: Context<InitUnit>, name: String, x: i64, y: i64, game: Pubkey) -> Result<()> {
         unit::init(ctx, name, x, y, game)
     }
 
     pub fn move_unit_start(ctx: Context<MoveUnitStart>, from_x: i64, from_y: i64, to_x: i64, to_y: i64, name: String) -> Result<()> {
-        let current_timestamp = Clock::get()?.unix_timestamp;
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
         unit::move_unit_start(ctx, from_x, from_y, to_x, to_y, name, current_timestamp)
     }
 
     pub fn move_unit_complete(ctx: Context<MoveUnitComplete>, to_x: i64, to_y: i64, name: String) -> Result<()> {
-        let current_timestamp = Clock::get()?.unix_timestamp;
+        let current_timestamp = clock::now(ctx.remaining_accounts)?;
         unit::move_unit_complete(ctx, to_x, to_y, name, current_timestamp)
     }
 
@@ -118,51 +330,98 @@ pub mod got_a_min {
         map::put(ctx, x, y, num)
     }
 
-    // -- debug --
-    pub fn debug_set_storage_amount(ctx: Context<DebugSetStorageAmount>, amount: i64) -> Result<()> {
-        debug::set_storage_amount(ctx, amount)
+    pub fn migrate_location(ctx: Context<MigrateAccount>) -> Result<()> {
+        migration::migrate::<state::Location>(ctx, state::Location::VERSION, |len| len == state::Location::LEN)
     }
 
-    pub fn debug_send(ctx: Context<SendResource>, send_amount: i64, current_timestamp: i64, from_x: i64, from_y: i64, to_x: i64, to_y: i64) -> Result<()> {
-        processor::send(ctx, send_amount, current_timestamp, from_x, from_y, to_x, to_y)
+    pub fn migrate_storage(ctx: Context<MigrateAccount>) -> Result<()> {
+        migration::migrate::<state::Storage>(ctx, state::Storage::VERSION, |len| len == state::Storage::LEN)
     }
 
-    pub fn debug_produce_without_input(ctx: Context<ProcessesResource>, current_timestamp: i64) -> Result<()> {
-        processor::claim_production(ctx, current_timestamp)
+    pub fn migrate_processor(ctx: Context<MigrateAccount>) -> Result<()> {
+        migration::migrate::<state::Processor>(ctx, state::Processor::VERSION, |len| len == state::Processor::LEN)
     }
 
-    pub fn debug_produce_with_one_input(ctx: Context<ProcessesResourceWith1Input>, current_timestamp: i64) -> Result<()> {
-        processor::produce_with_one_input(ctx, current_timestamp)
+    pub fn migrate_unit(ctx: Context<MigrateAccount>) -> Result<()> {
+        migration::migrate::<state::Unit>(ctx, state::Unit::VERSION, |len| len == state::Unit::LEN)
     }
 
-    pub fn debug_produce_with_two_inputs(ctx: Context<ProcessesResourceWith2Inputs>, current_timestamp: i64) -> Result<()> {
-        processor::produce_with_two_inputs(ctx, current_timestamp)
+    pub fn migrate_game(ctx: Context<MigrateAccount>) -> Result<()> {
+        migration::migrate::<state::Game>(ctx, state::Game::VERSION, |len| len == state::Game::LEN)
     }
 
-    pub fn debug_init_processor(ctx: Context<InitProcessor>, processor_type: ProcessorType, fuel_resource_id: Pubkey, output_resource_id: Pubkey, output_rate: i64, processing_duration: i64, fuel_cost_type: FuelCostType, current_timestamp: i64) -> Result<()> {
-        processor::init(ctx, processor_type, fuel_resource_id, output_resource_id, output_rate, processing_duration, fuel_cost_type, current_timestamp)
+    pub fn migrate_resource(ctx: Context<MigrateAccount>) -> Result<()> {
+        migration::migrate::<state::Resource>(ctx, state::Resource::VERSION, |len| len == state::Resource::LEN)
     }
 
-    pub fn debug_move_unit_start(ctx: Context<MoveUnitStart>, from_x: i64, from_y: i64, to_x: i64, to_y: i64, name: String, current_timestamp: i64) -> Result<()> {
-        unit::move_unit_start(ctx, from_x, from_y, to_x, to_y, name, current_timestamp)
+    pub fn migrate_game_tile(ctx: Context<MigrateAccount>) -> Result<()> {
+        migration::migrate::<GameTile>(ctx, GameTile::VERSION, |len| len == GameTile::LEN)
     }
 
-    pub fn debug_move_unit_complete(ctx: Context<MoveUnitComplete>, to_x: i64, to_y: i64, name: String, current_timestamp: i64) -> Result<()> {
-        unit::move_unit_complete(ctx, to_x, to_y, name, current_timestamp)
+    pub fn migrate_map(ctx: Context<MigrateAccount>) -> Result<()> {
+        migration::migrate::<state::Map>(ctx, state::Map::VERSION, |len| len == state::Map::LEN)
     }
 
-    pub fn debug_update_storage_move_status(ctx: Context<UpdateStorageMoveStatus>, current_timestamp: i64) -> Result<()> {
-        storage::update_move_status(ctx, current_timestamp)
+    pub fn migrate_offer(ctx: Context<MigrateAccount>) -> Result<()> {
+        migration::migrate::<state::Offer>(ctx, state::Offer::VERSION, |len| len == state::Offer::LEN)
     }
 
-    pub fn debug_move_storage(ctx: Context<MoveStorage>, current_timestamp: i64) -> Result<()> {
-        storage::move_to_location(ctx, current_timestamp)
+    pub fn migrate_order_book(ctx: Context<MigrateAccount>) -> Result<()> {
+        migration::migrate::<state::OrderBook>(ctx, state::OrderBook::VERSION, state::OrderBook::fits)
+    }
+
+    pub fn migrate_pool(ctx: Context<MigrateAccount>) -> Result<()> {
+        migration::migrate::<state::Pool>(ctx, state::Pool::VERSION, |len| len == state::Pool::LEN)
+    }
+
+    pub fn migrate_liquidity_position(ctx: Context<MigrateAccount>) -> Result<()> {
+        migration::migrate::<state::LiquidityPosition>(ctx, state::LiquidityPosition::VERSION, |len| len == state::LiquidityPosition::LEN)
+    }
+
+    pub fn migrate_auction(ctx: Context<MigrateAccount>) -> Result<()> {
+        migration::migrate::<state::Auction>(ctx, state::Auction::VERSION, |len| len == state::Auction::LEN)
+    }
+
+    pub fn migrate_shipment(ctx: Context<MigrateAccount>) -> Result<()> {
+        migration::migrate::<state::Shipment>(ctx, state::Shipment::VERSION, |len| len == state::Shipment::LEN)
+    }
+
+    pub fn migrate_trade_route(ctx: Context<MigrateAccount>) -> Result<()> {
+        migration::migrate::<state::TradeRoute>(ctx, state::TradeRoute::VERSION, |len| len == state::TradeRoute::LEN)
+    }
+
+    pub fn migrate_production_queue(ctx: Context<MigrateAccount>) -> Result<()> {
+        migration::migrate::<state::ProductionQueue>(ctx, state::ProductionQueue::VERSION, |len| len == state::ProductionQueue::LEN)
+    }
+
+    pub fn migrate_transfer_allow_list(ctx: Context<MigrateAccount>) -> Result<()> {
+        migration::migrate::<state::TransferAllowList>(ctx, state::TransferAllowList::VERSION, |len| len == state::TransferAllowList::LEN)
     }
 
+    // The test clock's instructions in test-clock builds, see [game_clock::dispatch].
+    pub fn fallback(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> Result<()> {
+        game_clock::dispatch(program_id, accounts, data)
+    }
+
+    // -- debug --
+    #[cfg(feature = "debug")]
+    pub fn debug_set_storage_amount(ctx: Context<DebugSetStorageAmount>, amount: i64) -> Result<()> {
+        debug::set_storage_amount(ctx, amount)
+    }
+
+    #[cfg(feature = "debug")]
     pub fn debug_init_stuff(ctx: Context<InitStuff>, x: i64) -> Result<()> {
         stuff::init(ctx, x)
     }
 
+    #[cfg(not(feature = "debug"))]
+    disabled_instructions!(errors::ValidationError::DebugDisabled;
+        simple_init_storage(SimpleInitStorage, [u8; 2]);
+        simple_test_storage(SimpleTestStorage, [u8; 2]);
+        debug_set_storage_amount(DebugSetStorageAmount, i64);
+        debug_init_stuff(InitStuff, i64);
+    );
+
 }
 
 
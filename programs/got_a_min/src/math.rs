@@ -0,0 +1,93 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ValidationError;
+
+use got_a_min_rules::math as rules;
+
+// The checked math of got_a_min_rules, with its errors turned into program errors.
+
+pub fn checked<T>(result: got_a_min_rules::Result<T>) -> Result<T> {
+    result.map_err(|e| error!(ValidationError::from(e)))
+}
+
+pub fn add(a: i64, b: i64) -> Result<i64> {
+    checked(rules::add(a, b))
+}
+
+pub fn sub(a: i64, b: i64) -> Result<i64> {
+    checked(rules::sub(a, b))
+}
+
+pub fn mul(a: i64, b: i64) -> Result<i64> {
+    checked(rules::mul(a, b))
+}
+
+pub fn div(a: i64, b: i64) -> Result<i64> {
+    checked(rules::div(a, b))
+}
+
+pub fn abs(a: i64) -> Result<i64> {
+    checked(rules::abs(a))
+}
+
+pub fn mul_div_floor(value: i64, numerator: i64, denominator: i64) -> Result<i64> {
+    checked(rules::mul_div_floor(value, numerator, denominator))
+}
+
+pub fn mul_div_ceil(value: i64, numerator: i64, denominator: i64) -> Result<i64> {
+    checked(rules::mul_div_ceil(value, numerator, denominator))
+}
+
+pub fn to_i64(value: i128) -> Result<i64> {
+    checked(rules::to_i64(value))
+}
+
+// The id after [id] for orders and jobs.
+pub fn next_id(id: u64) -> Result<u64> {
+    checked(rules::next_id(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overflows<T: std::fmt::Debug>(result: Result<T>) -> bool {
+        result.unwrap_err() == ValidationError::MathOverflow.into()
+    }
+
+    #[test]
+    fn add_sub_at_the_limits() {
+        assert_eq!(add(i64::MAX - 1, 1).unwrap(), i64::MAX);
+        assert!(overflows(add(i64::MAX, 1)));
+        assert!(overflows(add(i64::MIN, -1)));
+        assert_eq!(sub(i64::MIN + 1, 1).unwrap(), i64::MIN);
+        assert!(overflows(sub(i64::MIN, 1)));
+        assert!(overflows(sub(0, i64::MIN)));
+    }
+
+    #[test]
+    fn mul_div_abs_at_the_limits() {
+        assert_eq!(mul(i64::MAX, 1).unwrap(), i64::MAX);
+        assert!(overflows(mul(i64::MAX, 2)));
+        assert!(overflows(mul(i64::MIN, -1)));
+        assert!(overflows(div(1, 0)));
+        assert!(overflows(div(i64::MIN, -1)));
+        assert_eq!(abs(i64::MIN + 1).unwrap(), i64::MAX);
+        assert!(overflows(abs(i64::MIN)));
+    }
+
+    #[test]
+    fn mul_div_keeps_wide_intermediates() {
+        assert_eq!(mul_div_floor(i64::MAX, i64::MAX, i64::MAX).unwrap(), i64::MAX);
+        assert_eq!(mul_div_ceil(i64::MAX, 2, 3).unwrap(), (i64::MAX as i128 * 2 / 3 + 1) as i64);
+        assert!(overflows(mul_div_floor(i64::MAX, 2, 1)));
+        assert!(overflows(mul_div_floor(1, 1, 0)));
+        assert!(overflows(mul_div_ceil(i64::MAX, i64::MAX, 1)));
+        assert_eq!((mul_div_floor(-7, 1, 2).unwrap(), mul_div_ceil(-7, 1, 2).unwrap()), (-4, -3));
+        assert_eq!((mul_div_floor(7, -1, 2).unwrap(), mul_div_ceil(7, -1, 2).unwrap()), (-4, -3));
+        assert_eq!((mul_div_floor(-6, 1, 2).unwrap(), mul_div_ceil(-6, 1, 2).unwrap()), (-3, -3));
+        assert!(overflows(to_i64(i128::MAX)));
+        assert_eq!(next_id(0).unwrap(), 1);
+        assert!(overflows(next_id(u64::MAX)));
+    }
+}
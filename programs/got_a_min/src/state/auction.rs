@@ -0,0 +1,75 @@
+use anchor_lang::prelude::*;
+
+#[account]
+pub struct Auction {
+    pub version: u8,
+    pub seller: Pubkey,
+    pub item: Pubkey,               // The location, processor or storage being sold.
+    pub kind: AuctionKind,
+    pub location_id: Pubkey,        // Bids and goods change hands here.
+    pub bid_resource_id: Pubkey,
+    pub goods_amount: i64,          // Only used by goods auctions, locked in the goods escrow.
+    pub min_bid: i64,
+    pub highest_bid: i64,
+    pub highest_bidder: Pubkey,     // Default until the first bid.
+    pub highest_bidder_storage: Pubkey, // Where the highest bid came from.
+    pub end_at: i64,
+    pub bump: u8,
+    pub bid_escrow_bump: u8,
+    pub goods_escrow_bump: u8,  // Only used by goods auctions.
+}
+
+impl Auction {
+    pub const VERSION: u8 = 1;
+
+    pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + VERSION_LENGTH
+        + PUBLIC_KEY_LENGTH  // seller
+        + PUBLIC_KEY_LENGTH  // item
+        + AUCTION_KIND_LENGTH
+        + PUBLIC_KEY_LENGTH  // location_id
+        + PUBLIC_KEY_LENGTH  // bid_resource_id
+        + AMOUNT_LENGTH      // goods_amount
+        + AMOUNT_LENGTH      // min_bid
+        + AMOUNT_LENGTH      // highest_bid
+        + PUBLIC_KEY_LENGTH  // highest_bidder
+        + PUBLIC_KEY_LENGTH  // highest_bidder_storage
+        + TIMESTAMP_LENGTH   // end_at
+        + BUMP_LENGTH * 3
+    ;
+
+    pub fn is_current(&self) -> bool {
+        self.version == Self::VERSION
+    }
+
+    pub fn has_bid(&self) -> bool {
+        self.highest_bidder != Pubkey::default()
+    }
+
+    pub fn has_ended(&self, current_timestamp: i64) -> bool {
+        current_timestamp >= self.end_at
+    }
+
+    // Whoever ends up with the item once the auction is settled.
+    pub fn new_owner(&self) -> Pubkey {
+        match self.has_bid() {
+            true => self.highest_bidder,
+            false => self.seller,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AuctionKind {
+    Location,
+    Processor,
+    Goods,
+}
+
+const AMOUNT_LENGTH: usize = 8;
+const AUCTION_KIND_LENGTH: usize = 1;
+const BUMP_LENGTH: usize = 1;
+const DISCRIMINATOR_LENGTH: usize = 8;
+const PUBLIC_KEY_LENGTH: usize = 32;
+const TIMESTAMP_LENGTH: usize = 8;
+const VERSION_LENGTH: usize = 1;
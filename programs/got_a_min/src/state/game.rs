@@ -1,3 +1,84 @@
+use anchor_lang::prelude::*;
 
-pub const DISTANCE_TIME_FACTOR: i64 = 1000;
+use crate::errors::ValidationError;
+use crate::math;
 
+pub use got_a_min_rules::movement::DISTANCE_TIME_FACTOR;
+
+#[account]
+pub struct Game {
+    pub version: u8,
+    pub authority: Pubkey,
+    pub swap_fee_bps: u16,  // Taken from the input of every pool swap, in basis points.
+    pub level_throughput_bps: Vec<u32>, // Processor throughput at each level, level 1 first.
+    pub upgrade_resource_id: Pubkey,
+    pub upgrade_cost_base: i64, // Upgrading from level N costs N times this.
+    pub retool_delay: i64,  // Time a retooled processor waits before producing again.
+    pub max_durability: i64,    // Given to new processors, 0 turns off wear.
+    pub repair_resource_id: Pubkey,
+    pub repair_cost_per_point: i64,
+    pub bump: u8,
+}
+
+impl Game {
+    pub const VERSION: u8 = 1;
+
+    pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + VERSION_LENGTH
+        + PUBLIC_KEY_LENGTH  // authority
+        + SWAP_FEE_BPS_LENGTH
+        + LEVEL_THROUGHPUT_BPS_LENGTH
+        + PUBLIC_KEY_LENGTH  // upgrade_resource_id
+        + UPGRADE_COST_BASE_LENGTH
+        + RETOOL_DELAY_LENGTH
+        + DURABILITY_LENGTH  // max_durability
+        + PUBLIC_KEY_LENGTH  // repair_resource_id
+        + REPAIR_COST_PER_POINT_LENGTH
+        + BUMP_LENGTH
+    ;
+
+    pub fn is_current(&self) -> bool {
+        self.version == Self::VERSION
+    }
+
+    pub fn max_level(&self) -> u8 {
+        self.level_throughput_bps.len() as u8
+    }
+
+    pub fn upgrade_cost(&self, level: u8) -> Result<i64> {
+        math::mul(self.upgrade_cost_base, level as i64)
+    }
+
+    // Output rate and processing duration at [level], scaled from the level 1 values and kept as small as possible.
+    pub fn level_rate(&self, base_output_rate: i64, base_processing_duration: i64, level: u8) -> Result<(i64, i64)> {
+        let throughput_bps = *(level as usize).checked_sub(1)
+            .and_then(|index| self.level_throughput_bps.get(index))
+            .ok_or(ValidationError::MaxLevelReached)? as i64;
+        let output_rate = math::mul(base_output_rate, throughput_bps)?;
+        let processing_duration = math::mul(base_processing_duration, BPS_DENOMINATOR)?;
+        let divisor = gcd(output_rate, processing_duration);
+        Ok((math::div(output_rate, divisor)?, math::div(processing_duration, divisor)?))
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    match b {
+        0 => a,
+        _ => gcd(b, a % b),
+    }
+}
+
+pub use got_a_min_rules::production::BPS_DENOMINATOR;
+pub const MAX_LEVEL: usize = 10;
+const VEC_BASE_LEN: usize = 4;
+
+const BUMP_LENGTH: usize = 1;
+const DISCRIMINATOR_LENGTH: usize = 8;
+const DURABILITY_LENGTH: usize = 8;
+const LEVEL_THROUGHPUT_BPS_LENGTH: usize = VEC_BASE_LEN + (MAX_LEVEL * 4);
+const PUBLIC_KEY_LENGTH: usize = 32;
+const REPAIR_COST_PER_POINT_LENGTH: usize = 8;
+const RETOOL_DELAY_LENGTH: usize = 8;
+const SWAP_FEE_BPS_LENGTH: usize = 2;
+const UPGRADE_COST_BASE_LENGTH: usize = 8;
+const VERSION_LENGTH: usize = 1;
@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+
+// Stands in for the Clock sysvar in test-clock builds, see [clock::now].
+#[account]
+pub struct GameClock {
+    pub authority: Pubkey,
+    pub timestamp: i64,
+    pub bump: u8,
+}
+
+impl GameClock {
+    pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + PUBLIC_KEY_LENGTH  // authority
+        + TIMESTAMP_LENGTH
+        + BUMP_LENGTH
+    ;
+
+    pub fn address() -> Pubkey {
+        Pubkey::find_program_address(&[b"game-clock"], &crate::ID).0
+    }
+}
+
+const BUMP_LENGTH: usize = 1;
+const DISCRIMINATOR_LENGTH: usize = 8;
+const PUBLIC_KEY_LENGTH: usize = 32;
+const TIMESTAMP_LENGTH: usize = 8;
@@ -1,8 +1,10 @@
 use anchor_lang::prelude::*;
 
-use crate::{errors::ValidationError, instructions::{location::fake_rng, map}};
+use got_a_min_rules::movement::Position;
 
-use super::{Map, game};
+use crate::{errors::ValidationError, instructions::location::fake_rng, math};
+
+use super::Map;
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct OwnershipRef {
@@ -12,6 +14,7 @@ pub struct OwnershipRef {
 
 #[account]
 pub struct Location {
+    pub version: u8,
     pub owner: Pubkey,
     pub occupied_space: i64,
     pub capacity: i64,
@@ -23,7 +26,10 @@ pub struct Location {
 }
 
 impl Location {
+    pub const VERSION: u8 = 1;
+
     pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + VERSION_LENGTH
         + PUBLIC_KEY_LENGTH  // owner
         + OCCUPIED_SPACE_LENGTH
         + CAPACITY_LENGTH
@@ -34,10 +40,20 @@ impl Location {
         + BUMP_LENGTH
     ;
 
+    pub fn is_current(&self) -> bool {
+        self.version == Self::VERSION
+    }
+
+    // Locations are found by their position rather than their address, which stays derived from
+    // the player who created them after the location is sold.
+    pub fn is_at(&self, x: i64, y: i64) -> bool {
+        self.pos_x == x && self.pos_y == y
+    }
+
     pub fn add(&mut self, owner: &Signer, ownership_ref: OwnershipRef) -> Result<()> {
         require!(ownership_ref.player == owner.key(), ValidationError::OwnerRequired);
 
-        self.occupied_space += 1;
+        self.occupied_space = math::add(self.occupied_space, 1)?;
         self.occupied_by.push(ownership_ref);
         // verify that it only exists once in the list
         require!(self.occupied_space() <= self.capacity, ValidationError::LocationFull);
@@ -47,7 +63,7 @@ impl Location {
     pub fn remove(&mut self, owner: &Signer, ownership_ref: &OwnershipRef) -> Result<()> {
         require!(ownership_ref.player == owner.key(), ValidationError::OwnerRequired);
 
-        self.occupied_space -= 1;
+        self.occupied_space = math::sub(self.occupied_space, 1)?;
         match self.occupied_by.iter().position(|i| i.item == ownership_ref.item) {
             Some(index) => {
                 self.occupied_by.remove(index);
@@ -59,6 +75,16 @@ impl Location {
         Ok(())    
     }
 
+    // Hands an item occupying this location over to another player without moving it.
+    pub fn transfer(&mut self, item: &Pubkey, player: Pubkey) -> Result<()> {
+        match self.occupied_by.iter_mut().find(|i| i.item == *item) {
+            Some(ownership_ref) => ownership_ref.player = player,
+            None => require!(false, ValidationError::ExperimentalError), // Custom error for not finding item
+        }
+
+        Ok(())
+    }
+
     pub fn occupied_space(&self) -> i64 {
         match i64::try_from(self.occupied_by.len()) {
             Ok(value) => value,
@@ -66,17 +92,19 @@ impl Location {
         }
     }
 
-    pub fn distance(&self, other_location: &Location) -> i64 {
-        let diff_x = (self.pos_x - other_location.pos_x).abs();
-        let diff_y = (self.pos_y - other_location.pos_y).abs();
-        diff_x + diff_y
+    pub fn position(&self) -> Position {
+        Position::new(self.pos_x, self.pos_y)
+    }
+
+    pub fn distance(&self, other_location: &Location) -> Result<i64> {
+        math::checked(self.position().distance(&other_location.position()))
     }
     
-    pub fn distance_time(&self, other_location: &Location) -> i64 {
-        return self.distance(other_location) * game::DISTANCE_TIME_FACTOR;
+    pub fn distance_time(&self, other_location: &Location) -> Result<i64> {
+        math::checked(self.position().distance_time(&other_location.position()))
     }
 
-    pub fn explore(&mut self, map: &mut Account<Map>) {
+    pub fn explore(&mut self, map: &mut Account<Map>) -> Result<()> {
         self.location_type = match fake_rng(self.owner) {
             0 => LocationType::Planet,
             1 => LocationType::Moon,
@@ -87,11 +115,11 @@ impl Location {
             _ => LocationType::Space,
         };
 
-        map::put_2(map, self.pos_x as u8, self.pos_y as u8, 1);
+        map.put(self.pos_x as u8, self.pos_y as u8, 1)
     }
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
 pub enum LocationType {
     Unexplored,
     Space,
@@ -118,4 +146,5 @@ const OCCUPIED_SPACE_LENGTH: usize = 8;
 const POS_X_LENGTH: usize = 8;
 const POS_Y_LENGTH: usize = 8;
 const PUBLIC_KEY_LENGTH: usize = 32;
+const VERSION_LENGTH: usize = 1;
 const BUMP_LENGTH: usize = 1;
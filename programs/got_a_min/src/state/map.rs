@@ -1,7 +1,13 @@
 use anchor_lang::prelude::*;
+use got_a_min_rules::map::CsrMap;
+
+use crate::math;
+
+pub use got_a_min_rules::map::{MAP_MAX_HEIGHT, MAP_MAX_WIDTH};
 
 #[account]
 pub struct Map {
+    pub version: u8,
     pub owner: Pubkey,
     pub row_ptrs: [u8; MAP_MAX_HEIGHT],
     pub columns: [u8; MAP_MAX_WIDTH],
@@ -12,7 +18,10 @@ pub struct Map {
 }
 
 impl Map {
+    pub const VERSION: u8 = 1;
+
     pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + VERSION_LENGTH
         + PUBLIC_KEY_LENGTH  // owner
         + ROW_PTR_LENGTH
         + COL_LENGTH
@@ -21,16 +30,43 @@ impl Map {
         + HEIGHT_LENGTH
     ;
 
+    pub fn is_current(&self) -> bool {
+        self.version == Self::VERSION
+    }
 
-}
+    pub fn csr(&self) -> CsrMap {
+        CsrMap {
+            row_ptrs: self.row_ptrs,
+            columns: self.columns,
+            values: self.values,
+            width: self.width,
+            height: self.height,
+            compressed_value: self.compressed_value,
+        }
+    }
+
+    pub fn set_csr(&mut self, csr: &CsrMap) {
+        self.row_ptrs = csr.row_ptrs;
+        self.columns = csr.columns;
+        self.values = csr.values;
+        self.width = csr.width;
+        self.height = csr.height;
+        self.compressed_value = csr.compressed_value;
+    }
 
-pub const MAP_MAX_HEIGHT: usize = 10;
-pub const MAP_MAX_WIDTH: usize = 20;
+    pub fn put(&mut self, x: u8, y: u8, new_value: u8) -> Result<()> {
+        let mut csr = self.csr();
+        math::checked(csr.put(x, y, new_value))?;
+        self.set_csr(&csr);
+        Ok(())
+    }
+}
 
 const DISCRIMINATOR_LENGTH: usize = 8;
-const ROW_PTR_LENGTH: usize = 1 * MAP_MAX_HEIGHT;
-const COL_LENGTH: usize = 1 * (MAP_MAX_WIDTH * MAP_MAX_HEIGHT);
-const VAL_LENGTH: usize = 1 * COL_LENGTH;
+const ROW_PTR_LENGTH: usize = MAP_MAX_HEIGHT;
+const COL_LENGTH: usize = MAP_MAX_WIDTH * MAP_MAX_HEIGHT;
+const VAL_LENGTH: usize = COL_LENGTH;
 const PUBLIC_KEY_LENGTH: usize = 32;
 const WIDTH_LENGTH: usize = 1;
 const HEIGHT_LENGTH: usize = 1;
+const VERSION_LENGTH: usize = 1;
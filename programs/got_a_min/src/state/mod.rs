@@ -1,17 +1,35 @@
+pub use auction::*;
 pub use game::*;
+pub use game_clock::*;
 pub use location::*;
 pub use map::*;
+pub use offer::*;
+pub use order_book::*;
+pub use pool::*;
 pub use processor::*;
+pub use production_queue::*;
 pub use resource::*;
+pub use shipment::*;
 pub use storage::*;
 pub use stuff::*;
+pub use trade_route::*;
+pub use transfer::*;
 pub use unit::*;
 
+pub mod auction;
 pub mod game;
+pub mod game_clock;
 pub mod location;
 pub mod map;
+pub mod offer;
+pub mod order_book;
+pub mod pool;
 pub mod processor;
+pub mod production_queue;
 pub mod resource;
+pub mod shipment;
 pub mod storage;
 pub mod stuff;
+pub mod trade_route;
+pub mod transfer;
 pub mod unit;
\ No newline at end of file
@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+
+#[account]
+pub struct Offer {
+    pub version: u8,
+    pub maker: Pubkey,
+    pub location_id: Pubkey,
+    pub offered_resource_id: Pubkey,
+    pub offered_amount: i64,    // Locked in the offer's escrow Storage until accepted or cancelled.
+    pub requested_resource_id: Pubkey,
+    pub requested_amount: i64,
+    pub escrow_bump: u8,
+}
+
+impl Offer {
+    pub const VERSION: u8 = 1;
+
+    pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + VERSION_LENGTH
+        + PUBLIC_KEY_LENGTH  // maker
+        + PUBLIC_KEY_LENGTH  // location_id
+        + PUBLIC_KEY_LENGTH  // offered_resource_id
+        + AMOUNT_LENGTH      // offered_amount
+        + PUBLIC_KEY_LENGTH  // requested_resource_id
+        + AMOUNT_LENGTH      // requested_amount
+        + BUMP_LENGTH
+    ;
+
+    pub fn is_current(&self) -> bool {
+        self.version == Self::VERSION
+    }
+}
+
+const AMOUNT_LENGTH: usize = 8;
+const BUMP_LENGTH: usize = 1;
+const DISCRIMINATOR_LENGTH: usize = 8;
+const PUBLIC_KEY_LENGTH: usize = 32;
+const VERSION_LENGTH: usize = 1;
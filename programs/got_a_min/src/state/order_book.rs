@@ -0,0 +1,121 @@
+use anchor_lang::prelude::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Order {
+    pub id: u64,
+    pub owner: Pubkey,
+    pub base_storage: Pubkey,   // Delivers base for asks, receives base when a bid is filled.
+    pub quote_storage: Pubkey,  // Pays quote for bids, receives quote when an ask is filled.
+    pub price: i64,             // Quote units per base unit.
+    pub amount: i64,            // Base units left to fill.
+    pub unclaimed: i64,         // Filled and waiting in the vault, base for bids and quote for asks.
+}
+
+#[account]
+pub struct OrderBook {
+    pub version: u8,
+    pub location_id: Pubkey,
+    pub base_resource_id: Pubkey,
+    pub quote_resource_id: Pubkey,
+    pub next_order_id: u64,
+    pub bids: Vec<Order>,   // Highest price first, oldest first within a price. Filled orders stay until claimed.
+    pub asks: Vec<Order>,   // Lowest price first, oldest first within a price. Filled orders stay until claimed.
+    pub bump: u8,
+    pub base_vault_bump: u8,
+    pub quote_vault_bump: u8,
+}
+
+impl OrderBook {
+    pub const VERSION: u8 = 1;
+
+    pub const BASE_LEN: usize = DISCRIMINATOR_LENGTH
+        + VERSION_LENGTH
+        + PUBLIC_KEY_LENGTH  // location_id
+        + PUBLIC_KEY_LENGTH  // base_resource_id
+        + PUBLIC_KEY_LENGTH  // quote_resource_id
+        + NEXT_ORDER_ID_LENGTH
+        + VEC_BASE_LEN       // bids
+        + VEC_BASE_LEN       // asks
+        + BUMP_LENGTH * 3
+    ;
+
+    pub fn is_current(&self) -> bool {
+        self.version == Self::VERSION
+    }
+
+    // Resting orders share one allocation which grows and shrinks with the number of orders.
+    pub fn space(order_count: usize) -> usize {
+        Self::BASE_LEN + (order_count * ORDER_LENGTH)
+    }
+
+    // Whether [len] is a space the book takes for some number of orders.
+    pub fn fits(len: usize) -> bool {
+        len.checked_sub(Self::BASE_LEN).is_some_and(|orders_len| orders_len % ORDER_LENGTH == 0)
+    }
+
+    pub fn order_count(&self) -> usize {
+        self.bids.len() + self.asks.len()
+    }
+
+    // Orders still waiting for a fill, the ones only waiting for their owner's claim don't take
+    // a place in the book.
+    pub fn open_order_count(&self) -> usize {
+        self.bids.iter().chain(self.asks.iter()).filter(|o| o.amount > 0).count()
+    }
+
+    pub fn insert(&mut self, side: OrderSide, order: Order) {
+        match side {
+            OrderSide::Bid => {
+                let index = self.bids.iter().position(|o| o.price < order.price).unwrap_or(self.bids.len());
+                self.bids.insert(index, order);
+            },
+            OrderSide::Ask => {
+                let index = self.asks.iter().position(|o| o.price > order.price).unwrap_or(self.asks.len());
+                self.asks.insert(index, order);
+            },
+        }
+    }
+
+    pub fn find_mut(&mut self, owner: &Pubkey, order_id: u64) -> Option<(OrderSide, &mut Order)> {
+        if let Some(order) = self.bids.iter_mut().find(|o| o.id == order_id && o.owner == *owner) {
+            return Some((OrderSide::Bid, order));
+        }
+        self.asks.iter_mut().find(|o| o.id == order_id && o.owner == *owner).map(|order| (OrderSide::Ask, order))
+    }
+
+    pub fn take(&mut self, owner: &Pubkey, order_id: u64) -> Option<(OrderSide, Order)> {
+        if let Some(index) = self.bids.iter().position(|o| o.id == order_id && o.owner == *owner) {
+            return Some((OrderSide::Bid, self.bids.remove(index)));
+        }
+        if let Some(index) = self.asks.iter().position(|o| o.id == order_id && o.owner == *owner) {
+            return Some((OrderSide::Ask, self.asks.remove(index)));
+        }
+        None
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum OrderSide {
+    Bid,
+    Ask,
+}
+
+const VEC_BASE_LEN: usize = 4;
+pub const ORDER_BOOK_MAX_ORDERS: usize = 64;  // Open orders, see [OrderBook::open_order_count].
+
+const AMOUNT_LENGTH: usize = 8;
+const BUMP_LENGTH: usize = 1;
+const DISCRIMINATOR_LENGTH: usize = 8;
+const NEXT_ORDER_ID_LENGTH: usize = 8;
+const ORDER_ID_LENGTH: usize = 8;
+const ORDER_LENGTH: usize = ORDER_ID_LENGTH
+    + PUBLIC_KEY_LENGTH  // owner
+    + PUBLIC_KEY_LENGTH  // base_storage
+    + PUBLIC_KEY_LENGTH  // quote_storage
+    + PRICE_LENGTH
+    + AMOUNT_LENGTH
+    + AMOUNT_LENGTH      // unclaimed
+;
+const PRICE_LENGTH: usize = 8;
+const PUBLIC_KEY_LENGTH: usize = 32;
+const VERSION_LENGTH: usize = 1;
@@ -0,0 +1,113 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ValidationError;
+use crate::math::{self, mul_div_ceil, mul_div_floor, to_i64};
+use super::game::BPS_DENOMINATOR;
+
+#[account]
+pub struct Pool {
+    pub version: u8,
+    pub game: Pubkey,
+    pub location_id: Pubkey,
+    pub resource_a_id: Pubkey,  // Always the lower of the two resource keys.
+    pub resource_b_id: Pubkey,
+    pub lp_supply: i64,
+    pub bump: u8,
+    pub reserve_a_bump: u8,
+    pub reserve_b_bump: u8,
+}
+
+impl Pool {
+    pub const VERSION: u8 = 1;
+
+    pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + VERSION_LENGTH
+        + PUBLIC_KEY_LENGTH  // game
+        + PUBLIC_KEY_LENGTH  // location_id
+        + PUBLIC_KEY_LENGTH  // resource_a_id
+        + PUBLIC_KEY_LENGTH  // resource_b_id
+        + LP_SUPPLY_LENGTH
+        + BUMP_LENGTH * 3
+    ;
+
+    pub fn is_current(&self) -> bool {
+        self.version == Self::VERSION
+    }
+
+    // First deposit: shares = floor(sqrt(amount_a * amount_b))
+    pub fn initial_shares(amount_a: i64, amount_b: i64) -> Result<i64> {
+        let product = (amount_a as i128).checked_mul(amount_b as i128).ok_or(ValidationError::MathOverflow)?;
+        to_i64(isqrt(product))
+    }
+
+    // Later deposits: shares = min(floor(amount_a * supply / reserve_a), floor(amount_b * supply / reserve_b))
+    pub fn shares_for_deposit(amount_a: i64, amount_b: i64, reserve_a: i64, reserve_b: i64, lp_supply: i64) -> Result<i64> {
+        let shares_a = mul_div_floor(amount_a, lp_supply, reserve_a)?;
+        let shares_b = mul_div_floor(amount_b, lp_supply, reserve_b)?;
+        Ok(shares_a.min(shares_b))
+    }
+
+    // What a deposit of [shares] costs of one reserve, rounded up in favour of the pool.
+    pub fn deposit_for_shares(shares: i64, reserve: i64, lp_supply: i64) -> Result<i64> {
+        mul_div_ceil(shares, reserve, lp_supply)
+    }
+
+    // What burning [shares] returns of one reserve, rounded down in favour of the pool.
+    pub fn withdrawal_for_shares(shares: i64, reserve: i64, lp_supply: i64) -> Result<i64> {
+        mul_div_floor(shares, reserve, lp_supply)
+    }
+
+    // Constant product: out = floor(reserve_out * in_after_fee / (reserve_in + in_after_fee)),
+    // where fee = floor(amount_in * fee_bps / 10000) stays in the pool.
+    pub fn swap_output(amount_in: i64, reserve_in: i64, reserve_out: i64, fee_bps: u16) -> Result<i64> {
+        let fee = mul_div_floor(amount_in, fee_bps as i64, BPS_DENOMINATOR)?;
+        let amount_in_after_fee = math::sub(amount_in, fee)?;
+        let new_reserve_in = math::add(reserve_in, amount_in_after_fee)?;
+        mul_div_floor(reserve_out, amount_in_after_fee, new_reserve_in)
+    }
+}
+
+#[account]
+pub struct LiquidityPosition {
+    pub version: u8,
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub shares: i64,
+    pub bump: u8,
+}
+
+impl LiquidityPosition {
+    pub const VERSION: u8 = 1;
+
+    pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + VERSION_LENGTH
+        + PUBLIC_KEY_LENGTH  // pool
+        + PUBLIC_KEY_LENGTH  // owner
+        + SHARES_LENGTH
+        + BUMP_LENGTH
+    ;
+
+    pub fn is_current(&self) -> bool {
+        self.version == Self::VERSION
+    }
+}
+
+fn isqrt(value: i128) -> i128 {
+    if value < 2 {
+        return value.max(0);
+    }
+    let mut x = value;
+    let mut y = (x + 1) / 2;
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
+}
+
+const BUMP_LENGTH: usize = 1;
+const DISCRIMINATOR_LENGTH: usize = 8;
+const LP_SUPPLY_LENGTH: usize = 8;
+const PUBLIC_KEY_LENGTH: usize = 32;
+const SHARES_LENGTH: usize = 8;
+const VERSION_LENGTH: usize = 1;
@@ -1,7 +1,14 @@
 use anchor_lang::prelude::*;
 
+use got_a_min_rules::movement;
+use got_a_min_rules::production::Production;
+
+use crate::math;
+use super::storage::MobilityType;
+
 #[account]
 pub struct Processor {
+    pub version: u8,
     pub owner: Pubkey,
     pub location_id: Pubkey,
     pub fuel_resource_id: Pubkey, // Optional, should be type Optional<> when implemented in Anchor
@@ -9,13 +16,28 @@ pub struct Processor {
     pub output_rate: i64,   // Produce this many units per [processing_duration]. 
     pub processing_duration: i64,   // Solana time unit (usually 400-415ms)
     pub awaiting_units: i64,    // This amount can be claimed after waiting [processing_duration] * [awaiting_units] seconds.
-    pub claimed_at: i64,
+    pub claimed_at: i64,    // Time up to which [progress] has been counted.
+    pub progress: i64,  // Work towards the next units, see [unit_work].
     pub processor_type: ProcessorType,
     pub fuel_cost_type: FuelCostType,
+    pub game_id: Pubkey,    // Levels and upgrade costs follow this game.
+    pub level: u8,
+    pub base_output_rate: i64,  // [output_rate] and [processing_duration] at level 1.
+    pub base_processing_duration: i64,
+    pub paused_at: Option<i64>,     // Production stops accruing from this time until resumed.
+    pub durability: i64,    // Worn down by one per unit produced, production stops at 0.
+    pub max_durability: i64,    // 0 when the processor doesn't wear.
+    pub mobility_type: MobilityType,
+    pub movement_speed: i64,
+    pub arrives_at: i64,    // In transit to [location_id] until then, 0 when not moving.
+    pub linked_storages: Vec<Pubkey>,   // The only storages it takes from or delivers to.
 }
 
 impl Processor {
+    pub const VERSION: u8 = 1;
+
     pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + VERSION_LENGTH
         + PUBLIC_KEY_LENGTH  // owner
         + PUBLIC_KEY_LENGTH  // location_id
         + PUBLIC_KEY_LENGTH  // fuel_resource_id
@@ -24,33 +46,289 @@ impl Processor {
         + PROCESSING_DURATION_LENGTH
         + AWAITING_UNITS_LENGTH
         + CLAIMED_AT_LENGTH
+        + PROGRESS_LENGTH
         + PROCESSOR_TYPE_LENGTH
         + FUEL_COST_TYPE_LENGTH
+        + PUBLIC_KEY_LENGTH  // game_id
+        + LEVEL_LENGTH
+        + OUTPUT_RATE_LENGTH          // base_output_rate
+        + PROCESSING_DURATION_LENGTH  // base_processing_duration
+        + PAUSED_AT_LENGTH
+        + DURABILITY_LENGTH
+        + DURABILITY_LENGTH  // max_durability
+        + MOBILITY_TYPE_LENGTH
+        + MOVEMENT_SPEED_LENGTH
+        + ARRIVES_AT_LENGTH
+        + LINKED_STORAGES_LENGTH
         ;
+
+    pub fn is_current(&self) -> bool {
+        self.version == Self::VERSION
+    }
         
     pub fn size(&self) -> i64 {
         1
     }
+
+    pub fn location_id(&self, current_time: i64) -> Option<Pubkey> {
+        movement::location_at(self.location_id, self.arrives_at, current_time)
+    }
+
+    pub fn is_moving(&self, current_time: i64) -> bool {
+        self.location_id(current_time).is_none()
+    }
+
+    pub fn is_linked(&self, storage: &Pubkey) -> bool {
+        self.linked_storages.contains(storage)
+    }
+
+    // The production rules run on a copy of these fields, see set_production.
+    pub fn production(&self) -> Production {
+        Production {
+            output_rate: self.output_rate,
+            processing_duration: self.processing_duration,
+            claimed_at: self.claimed_at,
+            progress: self.progress,
+            paused_at: self.paused_at,
+            durability: self.durability,
+            max_durability: self.max_durability,
+        }
+    }
+
+    pub fn set_production(&mut self, production: Production) {
+        self.output_rate = production.output_rate;
+        self.processing_duration = production.processing_duration;
+        self.claimed_at = production.claimed_at;
+        self.progress = production.progress;
+        self.paused_at = production.paused_at;
+        self.durability = production.durability;
+        self.max_durability = production.max_durability;
+    }
+
+    fn update_production<T>(&mut self, update: impl FnOnce(&mut Production) -> got_a_min_rules::Result<T>) -> Result<T> {
+        let mut production = self.production();
+        let result = math::checked(update(&mut production))?;
+        self.set_production(production);
+        Ok(result)
+    }
+
+    pub fn accrual_timestamp(&self, current_timestamp: i64) -> i64 {
+        self.production().accrual_timestamp(current_timestamp)
+    }
+
+    pub fn pause(&mut self, current_timestamp: i64) -> Result<()> {
+        self.update_production(|production| {
+            production.pause(current_timestamp);
+            Ok(())
+        })
+    }
+
+    pub fn resume(&mut self, current_timestamp: i64) -> Result<i64> {
+        self.update_production(|production| production.resume(current_timestamp))
+    }
+
+    pub fn hold(&mut self, current_timestamp: i64, until: i64) -> Result<()> {
+        self.update_production(|production| production.hold(current_timestamp, until))
+    }
+
+    pub fn efficiency_bps(&self) -> Result<i64> {
+        math::checked(self.production().efficiency_bps())
+    }
+
+    pub fn unit_work(&self) -> Result<i64> {
+        math::checked(self.production().unit_work())
+    }
+
+    pub fn accrue(&mut self, current_timestamp: i64) -> Result<()> {
+        self.update_production(|production| production.accrue(current_timestamp))
+    }
+
+    pub fn finished_units(&self) -> Result<i64> {
+        math::checked(self.production().finished_units())
+    }
+
+    pub fn complete(&mut self, units: i64) -> Result<()> {
+        self.update_production(|production| production.complete(units))
+    }
+
+    pub fn wear(&mut self, units: i64) -> Result<()> {
+        self.update_production(|production| production.wear(units))
+    }
+
+    pub fn rebase(&mut self, output_rate: i64, processing_duration: i64, current_timestamp: i64) -> Result<()> {
+        self.update_production(|production| production.rebase(output_rate, processing_duration, current_timestamp))
+    }
+
+    // Units finished since the last claim, at most [max_output], taken out of the progress.
+    pub fn calc_awaiting(&mut self, current_timestamp: i64, max_output: i64) -> Result<i64> {
+        self.update_production(|production| production.calc_awaiting(current_timestamp, max_output))
+    }
+
+    // Fuel burnt for [units] of output, [distance] only matters to FuelCostType::Distance.
+    pub fn fuel_cost(&self, units: i64, distance: i64) -> Result<i64> {
+        match self.fuel_cost_type {
+            FuelCostType::Nothing => Ok(0),
+            FuelCostType::Output => math::mul(units, FUEL_PER_OUTPUT_UNIT),
+            FuelCostType::Distance => math::mul(math::mul(distance, distance)?, units),
+        }
+    }
+
+    // Units of output [fuel_available] is enough for.
+    pub fn max_units_for_fuel(&self, fuel_available: i64, distance: i64) -> i64 {
+        match self.fuel_cost(1, distance) {
+            Ok(0) => i64::MAX,
+            Ok(fuel_per_unit) => fuel_available / fuel_per_unit,
+            Err(_) => 0,
+        }
+    }
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
 pub enum ProcessorType {
     Producer,
     Sender,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
 pub enum FuelCostType {
     Nothing,
     Output,
     Distance,
 }
 
+pub const FUEL_PER_OUTPUT_UNIT: i64 = 1;
+pub const PROCESSOR_MAX_LINKED_STORAGES: usize = 8;
+pub use got_a_min_rules::production::{WEAR_PER_UNIT, WEAR_THRESHOLDS_BPS};
+
+const ARRIVES_AT_LENGTH: usize = 8;
 const AWAITING_UNITS_LENGTH: usize = 8;
 const CLAIMED_AT_LENGTH: usize = 8;
 const DISCRIMINATOR_LENGTH: usize = 8;
+const DURABILITY_LENGTH: usize = 8;
 const FUEL_COST_TYPE_LENGTH: usize = 1;
+const LEVEL_LENGTH: usize = 1;
+const LINKED_STORAGES_LENGTH: usize = VEC_BASE_LEN + (PROCESSOR_MAX_LINKED_STORAGES * PUBLIC_KEY_LENGTH);
+const MOBILITY_TYPE_LENGTH: usize = 1;
+const MOVEMENT_SPEED_LENGTH: usize = 8;
 const OUTPUT_RATE_LENGTH: usize = 8;
+const PAUSED_AT_LENGTH: usize = 1 + 8;
 const PROCESSING_DURATION_LENGTH: usize = 8;
 const PROCESSOR_TYPE_LENGTH: usize = 1;
+const PROGRESS_LENGTH: usize = 8;
 const PUBLIC_KEY_LENGTH: usize = 32;
+const VEC_BASE_LEN: usize = 4;
+const VERSION_LENGTH: usize = 1;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    // Starts out at [max_durability], which is 0 for a processor that doesn't wear.
+    fn processor(output_rate: i64, processing_duration: i64, max_durability: i64) -> Processor {
+        Processor {
+            version: Processor::VERSION,
+            owner: Pubkey::default(),
+            location_id: Pubkey::default(),
+            fuel_resource_id: Pubkey::default(),
+            output_resource_id: Pubkey::default(),
+            output_rate,
+            processing_duration,
+            awaiting_units: 0,
+            claimed_at: 0,
+            progress: 0,
+            processor_type: ProcessorType::Producer,
+            fuel_cost_type: FuelCostType::Nothing,
+            game_id: Pubkey::default(),
+            level: 1,
+            base_output_rate: output_rate,
+            base_processing_duration: processing_duration,
+            paused_at: None,
+            durability: max_durability,
+            max_durability,
+            mobility_type: MobilityType::Fixed,
+            movement_speed: 0,
+            arrives_at: 0,
+            linked_storages: vec!(),
+        }
+    }
+
+    fn claim(processor: &mut Processor, current_timestamp: i64, max_units: i64) -> i64 {
+        processor.accrue(current_timestamp).unwrap();
+        let units = processor.finished_units().unwrap().min(max_units);
+        processor.complete(units).unwrap();
+        units
+    }
+
+    fn durability() -> impl Strategy<Value = i64> {
+        prop_oneof![Just(0i64), 1..1_000i64]
+    }
+
+    proptest! {
+        #[test]
+        fn frequent_claims_produce_the_same_as_one(
+            output_rate in 1..50i64,
+            processing_duration in 1..500i64,
+            max_durability in durability(),
+            mut claims in prop::collection::vec(0..10_000i64, 0..30),
+            end in 0..10_000i64,
+        ) {
+            claims.sort();
+            let mut once = processor(output_rate, processing_duration, max_durability);
+            let mut often = processor(output_rate, processing_duration, max_durability);
+
+            let mut produced = 0;
+            for timestamp in claims.iter().filter(|timestamp| **timestamp <= end) {
+                produced += claim(&mut often, *timestamp, i64::MAX);
+            }
+            produced += claim(&mut often, end, i64::MAX);
+
+            prop_assert_eq!(produced, claim(&mut once, end, i64::MAX));
+            prop_assert_eq!(often.durability, once.durability);
+            if max_durability == 0 {
+                prop_assert_eq!(produced, end * output_rate / processing_duration);
+            }
+        }
+
+        #[test]
+        fn partial_claims_keep_what_is_left(
+            output_rate in 1..50i64,
+            processing_duration in 1..500i64,
+            max_durability in durability(),
+            claims in prop::collection::vec((0..1_000i64, 0..20i64), 0..30),
+        ) {
+            let mut once = processor(output_rate, processing_duration, max_durability);
+            let mut often = processor(output_rate, processing_duration, max_durability);
+
+            let mut timestamp = 0;
+            let mut produced = 0;
+            for (wait, max_units) in claims.iter() {
+                timestamp += wait;
+                produced += claim(&mut often, timestamp, *max_units);
+            }
+            produced += claim(&mut often, timestamp, i64::MAX);
+
+            prop_assert_eq!(produced, claim(&mut once, timestamp, i64::MAX));
+        }
+
+        #[test]
+        fn pausing_only_skips_the_paused_time(
+            output_rate in 1..50i64,
+            processing_duration in 1..500i64,
+            max_durability in durability(),
+            paused_at in 0..5_000i64,
+            paused_for in 0..5_000i64,
+            after in 0..5_000i64,
+        ) {
+            let mut paused = processor(output_rate, processing_duration, max_durability);
+            let mut running = processor(output_rate, processing_duration, max_durability);
+
+            paused.pause(paused_at).unwrap();
+            let during = claim(&mut paused, paused_at + paused_for, i64::MAX);
+            prop_assert_eq!(paused.resume(paused_at + paused_for).unwrap(), paused_for);
+            let produced = during + claim(&mut paused, paused_at + paused_for + after, i64::MAX);
+
+            prop_assert_eq!(produced, claim(&mut running, paused_at + after, i64::MAX));
+        }
+    }
+}
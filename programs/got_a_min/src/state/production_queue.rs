@@ -0,0 +1,76 @@
+use anchor_lang::prelude::*;
+
+use crate::math;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Job {
+    pub id: u64,
+    pub resource_id: Pubkey,    // The recipe, its inputs were reserved when the job was queued.
+    pub quantity: i64,
+    pub produced: i64,
+    pub priority: u8,
+}
+
+impl Job {
+    pub fn remaining(&self) -> Result<i64> {
+        math::sub(self.quantity, self.produced)
+    }
+}
+
+#[account]
+pub struct ProductionQueue {
+    pub version: u8,
+    pub processor: Pubkey,
+    pub next_job_id: u64,
+    pub jobs: Vec<Job>,     // Highest priority first, oldest first within a priority. The first job is in progress.
+    pub bump: u8,
+}
+
+impl ProductionQueue {
+    pub const VERSION: u8 = 1;
+
+    pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + VERSION_LENGTH
+        + PUBLIC_KEY_LENGTH  // processor
+        + NEXT_JOB_ID_LENGTH
+        + JOBS_LENGTH
+        + BUMP_LENGTH
+    ;
+
+    pub fn is_current(&self) -> bool {
+        self.version == Self::VERSION
+    }
+
+    // The job in progress is never pushed back by a job with a higher priority.
+    pub fn insert(&mut self, job: Job) {
+        let index = match self.jobs.iter().skip(1).position(|j| j.priority < job.priority) {
+            Some(index) => index + 1,
+            None => self.jobs.len(),
+        };
+        self.jobs.insert(index, job);
+    }
+
+    pub fn take(&mut self, job_id: u64) -> Option<(usize, Job)> {
+        let index = self.jobs.iter().position(|j| j.id == job_id)?;
+        Some((index, self.jobs.remove(index)))
+    }
+}
+
+const VEC_BASE_LEN: usize = 4;
+pub const PRODUCTION_QUEUE_MAX_JOBS: usize = 8;
+
+const AMOUNT_LENGTH: usize = 8;
+const BUMP_LENGTH: usize = 1;
+const DISCRIMINATOR_LENGTH: usize = 8;
+const JOB_ID_LENGTH: usize = 8;
+const JOB_LENGTH: usize = JOB_ID_LENGTH
+    + PUBLIC_KEY_LENGTH  // resource_id
+    + AMOUNT_LENGTH      // quantity
+    + AMOUNT_LENGTH      // produced
+    + PRIORITY_LENGTH
+;
+const JOBS_LENGTH: usize = VEC_BASE_LEN + (PRODUCTION_QUEUE_MAX_JOBS * JOB_LENGTH);
+const NEXT_JOB_ID_LENGTH: usize = 8;
+const PRIORITY_LENGTH: usize = 1;
+const PUBLIC_KEY_LENGTH: usize = 32;
+const VERSION_LENGTH: usize = 1;
@@ -2,6 +2,7 @@ use anchor_lang::prelude::*;
 
 #[account]
 pub struct Resource {
+    pub version: u8,
     pub owner: Pubkey,
     pub name: String,
     pub input: Vec<Pubkey>,
@@ -9,11 +10,18 @@ pub struct Resource {
 }
 
 impl Resource {
+    pub const VERSION: u8 = 1;
+
     pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + VERSION_LENGTH
         + PUBLIC_KEY_LENGTH
         + NAME_LENGTH 
         + INPUT_LENGTH
         + INPUT_AMOUNT_LENGTH;          
+
+    pub fn is_current(&self) -> bool {
+        self.version == Self::VERSION
+    }
 }
 
 const DISCRIMINATOR_LENGTH: usize = 8;
@@ -22,3 +30,4 @@ const INPUT_LENGTH: usize = PUBLIC_KEY_LENGTH * INPUT_MAX_SIZE;
 pub const INPUT_MAX_SIZE: usize = 2;
 pub const NAME_LENGTH: usize = 16 * 4;
 const PUBLIC_KEY_LENGTH: usize = 32;
+const VERSION_LENGTH: usize = 1;
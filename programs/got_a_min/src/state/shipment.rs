@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+#[account]
+pub struct Shipment {
+    pub version: u8,
+    pub owner: Pubkey,          // Paid for the account and gets its rent back once received.
+    pub sender_id: Pubkey,
+    pub resource_id: Pubkey,
+    pub amount: i64,
+    pub from_location_id: Pubkey,
+    pub to_location_id: Pubkey,
+    pub storage_to: Pubkey,     // Credited by receive_shipment once arrived.
+    pub arrives_at: i64,
+    pub trade_route: Pubkey,    // The route that shipped it, default for a one-off send.
+}
+
+impl Shipment {
+    pub const VERSION: u8 = 1;
+
+    pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + VERSION_LENGTH
+        + PUBLIC_KEY_LENGTH  // owner
+        + PUBLIC_KEY_LENGTH  // sender_id
+        + PUBLIC_KEY_LENGTH  // resource_id
+        + AMOUNT_LENGTH
+        + PUBLIC_KEY_LENGTH  // from_location_id
+        + PUBLIC_KEY_LENGTH  // to_location_id
+        + PUBLIC_KEY_LENGTH  // storage_to
+        + ARRIVES_AT_LENGTH
+        + PUBLIC_KEY_LENGTH  // trade_route
+    ;
+
+    pub fn is_current(&self) -> bool {
+        self.version == Self::VERSION
+    }
+
+    pub fn has_arrived(&self, current_timestamp: i64) -> bool {
+        current_timestamp >= self.arrives_at
+    }
+}
+
+const AMOUNT_LENGTH: usize = 8;
+const ARRIVES_AT_LENGTH: usize = 8;
+const DISCRIMINATOR_LENGTH: usize = 8;
+const PUBLIC_KEY_LENGTH: usize = 32;
+const VERSION_LENGTH: usize = 1;
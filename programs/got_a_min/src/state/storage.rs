@@ -1,9 +1,12 @@
 use anchor_lang::prelude::*;
+use got_a_min_rules::movement;
 
 use crate::errors::ValidationError;
+use crate::math;
 
 #[account]
 pub struct Storage {
+    pub version: u8,
     pub owner: Pubkey,
     pub resource_id: Pubkey,
     pub location_id: Pubkey,
@@ -15,7 +18,10 @@ pub struct Storage {
 }
 
 impl Storage {
+    pub const VERSION: u8 = 1;
+
     pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + VERSION_LENGTH
         + PUBLIC_KEY_LENGTH  // owner
         + PUBLIC_KEY_LENGTH  // resource_id
         + PUBLIC_KEY_LENGTH  // location_id
@@ -26,12 +32,16 @@ impl Storage {
         + ARRIVES_AT_LENGTH
     ;
 
+    pub fn is_current(&self) -> bool {
+        self.version == Self::VERSION
+    }
+
     pub fn add(&mut self, amount: i64, from_location_id: Pubkey) -> Result<()> {
         self.add_impl(amount, from_location_id, true)
     }
 
     pub fn add_impl(&mut self, amount: i64, from_location_id: Pubkey, verify_location: bool) -> Result<()> {
-        self.amount += amount;
+        self.amount = math::add(self.amount, amount)?;
         
         require!(self.amount <= self.capacity, ValidationError::StorageFull);
 
@@ -44,7 +54,7 @@ impl Storage {
     }
 
     pub fn remove(&mut self, amount: i64) -> Result<()> {
-        self.amount -= amount;
+        self.amount = math::sub(self.amount, amount)?;
         
         require!(self.amount >= 0, ValidationError::StorageAmountTooLow);
 
@@ -56,27 +66,19 @@ impl Storage {
     }
 
     pub fn location_id(&self, current_time: i64) -> Option<Pubkey> {
-        match self.arrives_at {
-            0 => Some(self.location_id),
-            timestamp => {
-                match current_time >= timestamp {
-                    true => Some(self.location_id),
-                    false => None,
-                }
-            },
-        }
+        movement::location_at(self.location_id, self.arrives_at, current_time)
     }
 
     pub fn is_moving(&self, current_time: i64) -> bool {
-        self.location_id(current_time).is_none()
+        movement::is_moving(self.arrives_at, current_time)
     }
 
     pub fn has_arrived(&self, current_time: i64) -> bool {
-        self.location_id(current_time).is_some() && self.arrives_at > 0
+        movement::has_arrived(self.arrives_at, current_time)
     }
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
 pub enum MobilityType {
     Fixed,
     Movable,
@@ -89,3 +91,4 @@ const DISCRIMINATOR_LENGTH: usize = 8;
 const MOBILITY_TYPE_LENGTH: usize = 1;
 const MOVEMENT_SPEED_LENGTH: usize = 8;
 const PUBLIC_KEY_LENGTH: usize = 32;
+const VERSION_LENGTH: usize = 1;
@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use crate::math;
+
+#[account]
+pub struct TradeRoute {
+    pub version: u8,
+    pub owner: Pubkey,
+    pub sender_id: Pubkey,
+    pub storage_from: Pubkey,
+    pub storage_to: Pubkey,
+    pub storage_fuel: Pubkey,
+    pub to_location_id: Pubkey,
+    pub distance: i64,
+    pub amount_per_cycle: i64,
+    pub cadence: i64,           // Never shorter than the travel time between the locations.
+    pub next_cycle_at: i64,     // A cycle's cargo is shipped when the cycle is due.
+    pub in_flight: i64,         // Shipped but not yet received, it counts against the room in storage_to.
+}
+
+impl TradeRoute {
+    pub const VERSION: u8 = 1;
+
+    pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + VERSION_LENGTH
+        + PUBLIC_KEY_LENGTH  // owner
+        + PUBLIC_KEY_LENGTH  // sender_id
+        + PUBLIC_KEY_LENGTH  // storage_from
+        + PUBLIC_KEY_LENGTH  // storage_to
+        + PUBLIC_KEY_LENGTH  // storage_fuel
+        + PUBLIC_KEY_LENGTH  // to_location_id
+        + DISTANCE_LENGTH
+        + AMOUNT_LENGTH      // amount_per_cycle
+        + TIMESTAMP_LENGTH   // cadence
+        + TIMESTAMP_LENGTH   // next_cycle_at
+        + AMOUNT_LENGTH      // in_flight
+    ;
+
+    pub fn is_current(&self) -> bool {
+        self.version == Self::VERSION
+    }
+
+    pub fn due_cycles(&self, current_timestamp: i64) -> Result<i64> {
+        match math::sub(current_timestamp, self.next_cycle_at)? {
+            diff if diff >= 0 => math::add(math::div(diff, self.cadence)?, 1),
+            _ => Ok(0),
+        }
+    }
+}
+
+const AMOUNT_LENGTH: usize = 8;
+const DISCRIMINATOR_LENGTH: usize = 8;
+const DISTANCE_LENGTH: usize = 8;
+const PUBLIC_KEY_LENGTH: usize = 32;
+const TIMESTAMP_LENGTH: usize = 8;
+const VERSION_LENGTH: usize = 1;
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+#[account]
+pub struct TransferAllowList {
+    pub version: u8,
+    pub owner: Pubkey,
+    pub allowed: Vec<Pubkey>,   // Players allowed to transfer to [owner]'s storages.
+    pub bump: u8,
+}
+
+impl TransferAllowList {
+    pub const VERSION: u8 = 1;
+
+    pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + VERSION_LENGTH
+        + PUBLIC_KEY_LENGTH  // owner
+        + ALLOWED_LENGTH
+        + BUMP_LENGTH
+    ;
+
+    pub fn is_current(&self) -> bool {
+        self.version == Self::VERSION
+    }
+
+    pub fn allows(&self, sender: &Pubkey) -> bool {
+        self.allowed.iter().any(|player| player == sender)
+    }
+}
+
+const VEC_BASE_LEN: usize = 4;
+pub const ALLOW_LIST_MAX_SIZE: usize = 10;
+
+const ALLOWED_LENGTH: usize = VEC_BASE_LEN + (PUBLIC_KEY_LENGTH * ALLOW_LIST_MAX_SIZE);
+const BUMP_LENGTH: usize = 1;
+const DISCRIMINATOR_LENGTH: usize = 8;
+const PUBLIC_KEY_LENGTH: usize = 32;
+const VERSION_LENGTH: usize = 1;
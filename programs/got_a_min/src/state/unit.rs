@@ -1,7 +1,9 @@
 use anchor_lang::prelude::*;
+use got_a_min_rules::movement;
 
 #[account]
 pub struct Unit {
+    pub version: u8,
     pub owner: Pubkey,
     pub at_location_id: Pubkey,
     pub name: String,
@@ -14,7 +16,10 @@ pub struct Unit {
 }
 
 impl Unit {
+    pub const VERSION: u8 = 1;
+
     pub const LEN: usize = DISCRIMINATOR_LENGTH
+        + VERSION_LENGTH
         + PUBLIC_KEY_LENGTH  // owner
         + PUBLIC_KEY_LENGTH  // at_location_id
         + NAME_LENGTH
@@ -23,28 +28,25 @@ impl Unit {
         + BUMP_LENGTH
     ;
 
+    pub fn is_current(&self) -> bool {
+        self.version == Self::VERSION
+    }
+
     pub fn location_id(&self, current_time: i64) -> Option<Pubkey> {
-        match self.arrives_at {
-            0 => Some(self.at_location_id),
-            timestamp => {
-                match current_time >= timestamp {
-                    true => Some(self.at_location_id),
-                    false => None,
-                }
-            },
-        }
+        movement::location_at(self.at_location_id, self.arrives_at, current_time)
     }
 
     pub fn is_moving(&self, current_time: i64) -> bool {
-        self.location_id(current_time).is_none()
+        movement::is_moving(self.arrives_at, current_time)
     }
 
     pub fn has_arrived(&self, current_time: i64) -> bool {
-        self.location_id(current_time).is_some() && self.arrives_at > 0
+        movement::has_arrived(self.arrives_at, current_time)
     }
 }
 
 const DISCRIMINATOR_LENGTH: usize = 8;
+const VERSION_LENGTH: usize = 1;
 pub const NAME_LENGTH: usize = 8;
 const PUBLIC_KEY_LENGTH: usize = 32;
 const MOVEMENT_SPEED_LENGTH: usize = 8;
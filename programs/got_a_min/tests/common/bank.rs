@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::sync::Once;
+
+use anchor_lang::prelude::{AccountInfo, Clock, ProgramError, Pubkey, Rent};
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::solana_program::entrypoint::{self, ProgramResult, BPF_ALIGN_OF_U128, MAX_PERMITTED_DATA_INCREASE, SUCCESS};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_stubs::{self, SyscallStubs};
+use anchor_lang::solana_program::program_utils::limited_deserialize;
+use anchor_lang::solana_program::system_instruction::{SystemError, SystemInstruction};
+use anchor_lang::solana_program::system_program;
+
+use got_a_min::ID;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Account {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+// Runs got_a_min::entry natively on accounts kept in memory.
+//
+// The program-test bank of this Solana version can't resize accounts in a native CPI, which every
+// `init` needs. Accounts are handed to the program in the runtime's own input layout instead, so
+// `realloc` and the system program work like they do on chain. Signer flags of an instruction are
+// taken as signed, there are no fees and an instruction either commits or changes nothing.
+pub struct Bank {
+    accounts: HashMap<Pubkey, Account>,
+}
+
+impl Bank {
+    pub fn new() -> Bank {
+        static STUBS: Once = Once::new();
+        STUBS.call_once(|| {
+            program_stubs::set_syscall_stubs(Box::new(Stubs));
+        });
+
+        let mut bank = Bank { accounts: HashMap::new() };
+        bank.set_account(system_program::ID, Account { lamports: 1, executable: true, ..Account::default() });
+        bank
+    }
+
+    pub fn account(&self, address: &Pubkey) -> Option<&Account> {
+        self.accounts.get(address)
+    }
+
+    pub fn set_account(&mut self, address: Pubkey, account: Account) {
+        self.accounts.insert(address, account);
+    }
+
+    pub fn airdrop(&mut self, address: Pubkey, lamports: u64) {
+        self.accounts.entry(address).or_default().lamports += lamports;
+    }
+
+    pub fn process(&mut self, ix: &Instruction) -> ProgramResult {
+        assert_eq!(ix.program_id, ID, "only got_a_min instructions can be processed");
+
+        let mut input = Input::default();
+        let mut positions: Vec<Option<usize>> = vec!();
+        for (i, meta) in ix.accounts.iter().enumerate() {
+            let first = ix.accounts.iter().position(|other| other.pubkey == meta.pubkey).unwrap();
+            if first < i {
+                input.duplicate(first);
+                positions.push(None);
+                continue;
+            }
+            let is_signer = ix.accounts.iter().any(|other| other.pubkey == meta.pubkey && other.is_signer);
+            let is_writable = ix.accounts.iter().any(|other| other.pubkey == meta.pubkey && other.is_writable);
+            let account = self.accounts.get(&meta.pubkey).cloned().unwrap_or_default();
+            input.account(&meta.pubkey, &account, is_signer, is_writable);
+            positions.push(Some(i));
+        }
+        input.instruction(&ix.data, &ix.program_id);
+
+        // The account infos point into [input], which stays in place until they are dropped.
+        let (program_id, infos, data) = unsafe { entrypoint::deserialize(input.as_mut_ptr()) };
+        got_a_min::entry(program_id, &infos, data)?;
+
+        let mut changed = vec!();
+        for info in positions.into_iter().flatten().map(|i| &infos[i]) {
+            let account = Account {
+                lamports: info.lamports(),
+                data: info.data.borrow().to_vec(),
+                owner: *info.owner,
+                executable: info.executable,
+            };
+            let before = self.accounts.get(info.key).cloned().unwrap_or_default();
+            if !info.is_writable {
+                assert_eq!(account, before, "read-only account {} was modified", info.key);
+                continue;
+            }
+            changed.push((*info.key, account));
+        }
+
+        for (address, account) in changed {
+            if account.lamports == 0 {
+                self.accounts.remove(&address);
+            } else {
+                self.accounts.insert(address, account);
+            }
+        }
+        Ok(())
+    }
+}
+
+// The serialized input of a program, see solana_program::entrypoint::deserialize.
+#[derive(Default)]
+struct Input {
+    bytes: Vec<u8>,
+    accounts: u64,
+    aligned: Vec<u64>,
+}
+
+impl Input {
+    fn duplicate(&mut self, first: usize) {
+        self.accounts += 1;
+        self.bytes.push(first as u8);
+        self.bytes.extend_from_slice(&[0; 7]);
+    }
+
+    fn account(&mut self, address: &Pubkey, account: &Account, is_signer: bool, is_writable: bool) {
+        self.accounts += 1;
+        self.bytes.extend_from_slice(&[u8::MAX, is_signer as u8, is_writable as u8, account.executable as u8]);
+        self.bytes.extend_from_slice(&[0; size_of::<u32>()]);
+        self.bytes.extend_from_slice(address.as_ref());
+        self.bytes.extend_from_slice(account.owner.as_ref());
+        self.bytes.extend_from_slice(&account.lamports.to_le_bytes());
+        self.bytes.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+        self.bytes.extend_from_slice(&account.data);
+        // Room to grow, padded so the rent epoch is aligned again. The account count in front is 8 bytes.
+        let end = self.bytes.len() + MAX_PERMITTED_DATA_INCREASE;
+        self.bytes.resize(end.div_ceil(BPF_ALIGN_OF_U128) * BPF_ALIGN_OF_U128, 0);
+        self.bytes.extend_from_slice(&0u64.to_le_bytes());
+    }
+
+    fn instruction(&mut self, data: &[u8], program_id: &Pubkey) {
+        self.bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        self.bytes.extend_from_slice(data);
+        self.bytes.extend_from_slice(program_id.as_ref());
+    }
+
+    // The program reads the lamports and lengths in place, so they have to be aligned like on chain.
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.bytes.splice(0..0, self.accounts.to_le_bytes());
+        self.aligned = vec![0; self.bytes.len().div_ceil(8)];
+        let ptr = self.aligned.as_mut_ptr() as *mut u8;
+        unsafe { std::ptr::copy_nonoverlapping(self.bytes.as_ptr(), ptr, self.bytes.len()) };
+        ptr
+    }
+}
+
+// The syscalls the program makes when it isn't running on chain.
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_invoke_signed(&self, ix: &Instruction, infos: &[AccountInfo], signers_seeds: &[&[&[u8]]]) -> ProgramResult {
+        assert_eq!(ix.program_id, system_program::ID, "only system program CPIs are supported");
+
+        let signers: Vec<Pubkey> = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &ID).unwrap())
+            .collect();
+        let info = |i: usize| -> Result<&AccountInfo, ProgramError> {
+            let address = ix.accounts.get(i).ok_or(ProgramError::NotEnoughAccountKeys)?.pubkey;
+            infos.iter().find(|info| *info.key == address).ok_or(ProgramError::NotEnoughAccountKeys)
+        };
+        let signer = |i: usize| -> Result<&AccountInfo, ProgramError> {
+            let info = info(i)?;
+            if !info.is_signer && !signers.contains(info.key) {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            Ok(info)
+        };
+
+        match limited_deserialize(&ix.data, 1024).map_err(|_| ProgramError::InvalidInstructionData)? {
+            SystemInstruction::CreateAccount { lamports, space, owner } => {
+                let (from, to) = (signer(0)?, signer(1)?);
+                if to.lamports() > 0 {
+                    return Err(ProgramError::Custom(SystemError::AccountAlreadyInUse as u32));
+                }
+                transfer(from, to, lamports)?;
+                allocate(to, space)?;
+                to.assign(&owner);
+            }
+            SystemInstruction::Transfer { lamports } => transfer(signer(0)?, info(1)?, lamports)?,
+            SystemInstruction::Allocate { space } => allocate(signer(0)?, space)?,
+            SystemInstruction::Assign { owner } => signer(0)?.assign(&owner),
+            other => panic!("system instruction {:?} is not supported", other),
+        }
+        Ok(())
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Clock) = Clock::default() };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+}
+
+fn transfer(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    if from.lamports() < lamports {
+        return Err(ProgramError::Custom(SystemError::ResultWithNegativeLamports as u32));
+    }
+    **from.try_borrow_mut_lamports()? -= lamports;
+    **to.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
+
+fn allocate(account: &AccountInfo, space: u64) -> ProgramResult {
+    if !account.data_is_empty() || *account.owner != system_program::ID {
+        return Err(ProgramError::Custom(SystemError::AccountAlreadyInUse as u32));
+    }
+    account.realloc(space as usize, true)
+}
+
+// An upgradeable program account of got_a_min, pointing at its program data.
+pub fn program_account(program_data: &Pubkey) -> Account {
+    let mut data = 2u32.to_le_bytes().to_vec();
+    data.extend_from_slice(program_data.as_ref());
+    Account { lamports: 1, data, owner: bpf_loader_upgradeable::ID, executable: true }
+}
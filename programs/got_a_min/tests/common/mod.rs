@@ -0,0 +1,307 @@
+#![allow(dead_code)]
+
+mod bank;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::{ProgramError, Pubkey, Rent};
+use anchor_lang::solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{AccountDeserialize, AccountSerialize};
+
+use got_a_min::errors::ValidationError;
+use got_a_min::instructions::processor::ProcessorSpec;
+use got_a_min::state::{FuelCostType, Location, LocationType, MobilityType, Processor, ProcessorType, Storage};
+use got_a_min::ID;
+use got_a_min_sdk::instructions::{self as ix, with_game_clock};
+use got_a_min_sdk::pda;
+
+pub use bank::{Account, Bank};
+
+// Game clock time when a test starts.
+pub const START: i64 = 1_000_000;
+// Lamports handed to every player.
+pub const FUNDS: u64 = 10_000_000_000;
+// Location::LEN only has room for two occupants, test locations are grown to fit this many.
+pub const LOCATION_ROOM: usize = 16;
+
+// The program running in an in-process bank, with a game and a game clock owned by [admin], who is
+// also the upgrade authority. Every instruction is sent with the game clock, so time only moves with [advance].
+pub struct TestGame {
+    pub bank: Bank,
+    pub admin: Pubkey,
+    pub now: i64,
+}
+
+impl TestGame {
+    pub fn start() -> TestGame {
+        let mut bank = Bank::new();
+        let admin = Pubkey::new_unique();
+        bank.airdrop(admin, FUNDS);
+        bank.set_account(ID, bank::program_account(&pda::program_data().0));
+
+        let mut game = TestGame { bank, admin, now: START };
+        game.put_program_data(admin);
+        game.ok(ix::init_game(admin, 30));
+        game.ok(ix::init_game_clock(admin, START));
+        game
+    }
+
+    pub fn game(&self) -> Pubkey {
+        pda::game(&self.admin).0
+    }
+
+    pub fn send(&mut self, ix: Instruction) -> ProgramResult {
+        self.bank.process(&with_game_clock(ix))
+    }
+
+    pub fn ok(&mut self, ix: Instruction) {
+        if let Err(error) = self.send(ix) {
+            panic!("instruction failed: {:?}", error);
+        }
+    }
+
+    pub fn advance(&mut self, seconds: i64) {
+        self.ok(ix::advance_game_clock(self.admin, seconds));
+        self.now += seconds;
+    }
+
+    pub fn create_player(&mut self) -> Pubkey {
+        let player = Pubkey::new_unique();
+        self.bank.airdrop(player, FUNDS);
+        player
+    }
+
+    // Accounts ---------------------------------
+
+    pub fn try_account<T: AccountDeserialize>(&self, address: Pubkey) -> Option<T> {
+        let account = self.bank.account(&address)?;
+        Some(T::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    pub fn account<T: AccountDeserialize>(&self, address: Pubkey) -> T {
+        match self.try_account(address) {
+            Some(account) => account,
+            None => panic!("account {} not found", address),
+        }
+    }
+
+    pub fn exists(&self, address: Pubkey) -> bool {
+        self.bank.account(&address).is_some()
+    }
+
+    pub fn lamports(&self, address: Pubkey) -> u64 {
+        self.bank.account(&address).map_or(0, |account| account.lamports)
+    }
+
+    // Writes [account] straight into the bank, for state no instruction can set up.
+    pub fn put<T: AccountSerialize>(&mut self, address: Pubkey, account: &T, space: usize) {
+        let mut data = vec!();
+        account.try_serialize(&mut data).unwrap();
+        data.resize(space, 0);
+        self.put_data(address, data, ID);
+    }
+
+    fn put_data(&mut self, address: Pubkey, data: Vec<u8>, owner: Pubkey) {
+        let lamports = Rent::default().minimum_balance(data.len());
+        self.bank.set_account(address, Account { lamports, data, owner, executable: false });
+    }
+
+    pub fn resize(&mut self, address: Pubkey, space: usize) {
+        let mut account = self.bank.account(&address).unwrap().clone();
+        account.data.resize(space, 0);
+        self.put_data(address, account.data, account.owner);
+    }
+
+    // Rewrites an account the way a program from before the version byte left it.
+    pub fn unversion(&mut self, address: Pubkey) {
+        let mut account = self.bank.account(&address).unwrap().clone();
+        account.data.remove(8);
+        self.put_data(address, account.data, account.owner);
+    }
+
+    pub fn set_storage_amount(&mut self, storage: Pubkey, amount: i64) {
+        self.ok(ix::debug_set_storage_amount(self.admin, storage, amount));
+    }
+
+    pub fn storage_amount(&self, storage: Pubkey) -> i64 {
+        self.account::<Storage>(storage).amount
+    }
+
+    // Program data naming [authority] as the upgrade authority, the debug instructions check it.
+    pub fn put_program_data(&mut self, authority: Pubkey) {
+        // Bincode of UpgradeableLoaderState::ProgramData { slot: 0, upgrade_authority_address: Some(authority) }.
+        let mut data = 3u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(authority.as_ref());
+        data.resize(UpgradeableLoaderState::programdata_data_offset().unwrap(), 0);
+        self.put_data(pda::program_data().0, data, bpf_loader_upgradeable::ID);
+    }
+
+    // Setup ------------------------------------
+
+    pub fn resource(&mut self, owner: Pubkey, name: &str, inputs: &[(Pubkey, i64)]) -> Pubkey {
+        let resource = Pubkey::new_unique();
+        self.ok(ix::init_resource(owner, resource, name, inputs));
+        resource
+    }
+
+    pub fn location(&mut self, owner: Pubkey, x: i64, y: i64, capacity: i64) -> Pubkey {
+        self.typed_location(owner, x, y, capacity, LocationType::Space)
+    }
+
+    pub fn typed_location(&mut self, owner: Pubkey, x: i64, y: i64, capacity: i64, location_type: LocationType) -> Pubkey {
+        self.ok(ix::init_location(owner, x, y, capacity, location_type));
+        let location = pda::map_location(&owner, x, y).0;
+        self.resize(location, location_space(LOCATION_ROOM));
+        location
+    }
+
+    // No instruction creates the locations seeded by single byte coordinates, they are written directly.
+    pub fn xy_location(&mut self, owner: Pubkey, xy: [u8; 2], location_type: LocationType) -> Pubkey {
+        let (location, bump) = pda::map_location_xy(&owner, xy);
+        let account = Location {
+            version: Location::VERSION,
+            owner,
+            occupied_space: 0,
+            capacity: LOCATION_ROOM as i64,
+            pos_x: xy[0] as i64,
+            pos_y: xy[1] as i64,
+            location_type,
+            occupied_by: vec!(),
+            bump,
+        };
+        self.put(location, &account, location_space(LOCATION_ROOM));
+        location
+    }
+
+    pub fn storage(&mut self, owner: Pubkey, resource: Pubkey, capacity: i64, at: (i64, i64)) -> Pubkey {
+        self.movable_storage(owner, resource, capacity, MobilityType::Fixed, 0, at)
+    }
+
+    pub fn movable_storage(&mut self, owner: Pubkey, resource: Pubkey, capacity: i64, mobility_type: MobilityType, movement_speed: i64, at: (i64, i64)) -> Pubkey {
+        let storage = Pubkey::new_unique();
+        self.ok(ix::init_storage(owner, storage, pda::map_location(&owner, at.0, at.1).0, at, resource, capacity, (mobility_type, movement_speed)));
+        storage
+    }
+
+    // A storage of [owner] at [location], which might belong to another player. It's made at the owner's
+    // location with the same coordinates and moved over, which takes no time.
+    pub fn storage_at(&mut self, owner: Pubkey, resource: Pubkey, capacity: i64, location: Pubkey) -> Pubkey {
+        let target: Location = self.account(location);
+        let own_location = pda::map_location(&owner, target.pos_x, target.pos_y).0;
+        if !self.exists(own_location) {
+            self.location(owner, target.pos_x, target.pos_y, LOCATION_ROOM as i64);
+        }
+        let storage = self.movable_storage(owner, resource, capacity, MobilityType::Movable, 1, (target.pos_x, target.pos_y));
+        if own_location != location {
+            self.ok(ix::move_storage(owner, storage, own_location, location));
+        }
+        storage
+    }
+
+    pub fn funded_storage(&mut self, owner: Pubkey, resource: Pubkey, capacity: i64, amount: i64, at: (i64, i64)) -> Pubkey {
+        let storage = self.storage(owner, resource, capacity, at);
+        self.set_storage_amount(storage, amount);
+        storage
+    }
+
+    pub fn processor(&mut self, owner: Pubkey, new_processor: NewProcessor) -> Pubkey {
+        let processor = Pubkey::new_unique();
+        self.ok(new_processor.init(self.game(), processor, owner));
+        processor
+    }
+
+    pub fn link(&mut self, owner: Pubkey, processor: Pubkey, storages: &[Pubkey]) {
+        let output = self.account::<Processor>(processor).output_resource_id;
+        for storage in storages {
+            self.ok(ix::link_storage(owner, processor, output, *storage));
+        }
+    }
+}
+
+pub fn location_space(occupants: usize) -> usize {
+    Location::LEN + occupants * 64
+}
+
+// Arguments of init_processor, see [NewProcessor::producer] and [NewProcessor::sender].
+pub struct NewProcessor {
+    pub processor_type: ProcessorType,
+    pub fuel_resource_id: Pubkey,
+    pub output_resource_id: Pubkey,
+    pub output_rate: i64,
+    pub processing_duration: i64,
+    pub fuel_cost_type: FuelCostType,
+    pub mobility_type: MobilityType,
+    pub movement_speed: i64,
+    pub location: Pubkey,
+}
+
+impl NewProcessor {
+    // Makes [output_rate] units every [processing_duration] without using fuel.
+    pub fn producer(output_resource_id: Pubkey, output_rate: i64, processing_duration: i64, location: Pubkey) -> NewProcessor {
+        NewProcessor {
+            processor_type: ProcessorType::Producer,
+            fuel_resource_id: Pubkey::default(),
+            output_resource_id,
+            output_rate,
+            processing_duration,
+            fuel_cost_type: FuelCostType::Nothing,
+            mobility_type: MobilityType::Fixed,
+            movement_speed: 0,
+            location,
+        }
+    }
+
+    // Ships [resource_id], burning distance squared of [fuel_resource_id] per unit.
+    pub fn sender(resource_id: Pubkey, fuel_resource_id: Pubkey, location: Pubkey) -> NewProcessor {
+        NewProcessor {
+            processor_type: ProcessorType::Sender,
+            fuel_resource_id,
+            output_resource_id: resource_id,
+            output_rate: 1,
+            processing_duration: 1,
+            fuel_cost_type: FuelCostType::Distance,
+            mobility_type: MobilityType::Fixed,
+            movement_speed: 0,
+            location,
+        }
+    }
+
+    pub fn init(&self, game: Pubkey, processor: Pubkey, owner: Pubkey) -> Instruction {
+        ix::init_processor(
+            owner,
+            processor,
+            game,
+            self.location,
+            ProcessorSpec {
+                processor_type: self.processor_type.clone(),
+                fuel_resource_id: self.fuel_resource_id,
+                output_resource_id: self.output_resource_id,
+                output_rate: self.output_rate,
+                processing_duration: self.processing_duration,
+                fuel_cost_type: self.fuel_cost_type.clone(),
+                mobility_type: self.mobility_type.clone(),
+                movement_speed: self.movement_speed,
+            },
+        )
+    }
+}
+
+// Errors -----------------------------------
+
+pub fn error_code(result: &ProgramResult) -> Option<u32> {
+    match result {
+        Err(ProgramError::Custom(code)) => Some(*code),
+        _ => None,
+    }
+}
+
+pub fn assert_error(result: ProgramResult, error: ValidationError) {
+    assert_eq!(error_code(&result), Some(u32::from(error)), "expected {:?}, got {:?}", error, result);
+}
+
+pub fn assert_anchor_error(result: ProgramResult, error: ErrorCode) {
+    assert_eq!(error_code(&result), Some(u32::from(error)), "expected {:?}, got {:?}", error, result);
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::system_program;
+
+use got_a_min::errors::ValidationError;
+use got_a_min::state::{LocationType, Stuff};
+use got_a_min::{accounts, instruction};
+use got_a_min_sdk::instructions::{self as ix, build, debug_authority};
+use got_a_min_sdk::pda;
+
+use common::*;
+
+#[test]
+fn debug_set_storage_amount() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    let ore = game.resource(player, "ore", &[]);
+    game.location(player, 0, 0, 5);
+    let storage = game.storage(player, ore, 100, (0, 0));
+
+    game.ok(ix::debug_set_storage_amount(game.admin, storage, 42));
+    assert_eq!(game.storage_amount(storage), 42);
+
+    // Only the authority of the game can use the debug instructions.
+    let result = game.send(build(
+        accounts::DebugSetStorageAmount { storage, admin: accounts::DebugAuthority { game: game.game(), authority: player } },
+        instruction::DebugSetStorageAmount { amount: 1 },
+    ));
+    assert_error(result, ValidationError::OwnerRequired);
+    assert_eq!(game.storage_amount(storage), 42);
+}
+
+#[test]
+fn debug_init_stuff() {
+    let mut game = TestGame::start();
+    let admin = game.admin;
+    let player = game.create_player();
+
+    game.ok(ix::debug_init_stuff(admin, 3));
+    let stuff: Stuff = game.account(pda::stuff(&admin, 3).0);
+    assert_eq!((stuff.number, stuff.x), (7, 3));
+
+    let result = game.send(build(
+        accounts::InitStuff {
+            stuff: pda::stuff(&player, 3).0,
+            owner: player,
+            admin: accounts::DebugAuthority { game: game.game(), authority: player },
+            system_program: system_program::ID,
+        },
+        instruction::DebugInitStuff { x: 3 },
+    ));
+    assert_error(result, ValidationError::OwnerRequired);
+}
+
+#[test]
+fn simple_test_storage() {
+    let mut game = TestGame::start();
+    let admin = game.admin;
+    let storage = Pubkey::new_unique();
+
+    // The position doubles as the seed of the location.
+    let ordered = game.xy_location(admin, [1, 2], LocationType::Space);
+    let unordered = game.xy_location(admin, [2, 1], LocationType::Space);
+    game.ok(build(
+        accounts::SimpleInitStorage { owner: admin, storage, location: ordered, system_program: system_program::ID },
+        instruction::SimpleInitStorage { xy: [1, 2] },
+    ));
+
+    let simple_test = |location, position| build(
+        accounts::SimpleTestStorage { owner: admin, storage, location, admin: debug_authority(admin), system_program: system_program::ID },
+        instruction::SimpleTestStorage { position },
+    );
+    game.ok(simple_test(ordered, [1, 2]));
+    assert_error(game.send(simple_test(unordered, [2, 1])), ValidationError::ExperimentalError);
+}
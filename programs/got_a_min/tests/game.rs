@@ -0,0 +1,266 @@
+mod common;
+
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_lang::solana_program::system_program;
+
+use got_a_min::errors::ValidationError;
+use got_a_min::instructions::GameTile;
+use got_a_min::state::{Game, GameClock, Location, LocationType, Map, Resource, BPS_DENOMINATOR, MAP_MAX_WIDTH};
+use got_a_min::{accounts, instruction};
+use got_a_min_sdk::instructions::{self as ix, build};
+use got_a_min_sdk::pda;
+
+use common::*;
+
+fn update_game(game: &TestGame) -> accounts::UpdateGame {
+    accounts::UpdateGame { game: game.game(), authority: game.admin }
+}
+
+// Game -------------------------------------
+
+#[test]
+fn init_game() {
+    let game = TestGame::start();
+
+    let account: Game = game.account(game.game());
+    assert_eq!(account.authority, game.admin);
+    assert_eq!(account.swap_fee_bps, 30);
+    assert_eq!(account.level_throughput_bps, vec!(BPS_DENOMINATOR as u32));
+}
+
+#[test]
+fn init_game_rejects_fees_above_everything() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    game.put_program_data(player);
+
+    let result = game.send(ix::init_game(player, 10_001));
+    assert_error(result, ValidationError::InvalidInput);
+}
+
+#[test]
+fn init_game_requires_the_upgrade_authority() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+
+    let result = game.send(ix::init_game(player, 30));
+    assert_error(result, ValidationError::OwnerRequired);
+}
+
+#[test]
+fn update_game_fees() {
+    let mut game = TestGame::start();
+
+    game.ok(build(update_game(&game), instruction::UpdateGameFees { swap_fee_bps: 100 }));
+    assert_eq!(game.account::<Game>(game.game()).swap_fee_bps, 100);
+
+    let result = game.send(build(update_game(&game), instruction::UpdateGameFees { swap_fee_bps: 10_001 }));
+    assert_error(result, ValidationError::InvalidInput);
+}
+
+#[test]
+fn update_game_levels() {
+    let mut game = TestGame::start();
+    let gold = Pubkey::new_unique();
+
+    let levels = instruction::UpdateGameLevels { level_throughput_bps: vec!(10_000, 20_000), upgrade_resource_id: gold, upgrade_cost_base: 5 };
+    game.ok(build(update_game(&game), levels));
+
+    let account: Game = game.account(game.game());
+    assert_eq!(account.level_throughput_bps, vec!(10_000, 20_000));
+    assert_eq!(account.upgrade_resource_id, gold);
+    assert_eq!(account.upgrade_cost_base, 5);
+
+    let no_levels = instruction::UpdateGameLevels { level_throughput_bps: vec!(), upgrade_resource_id: gold, upgrade_cost_base: 5 };
+    assert_error(game.send(build(update_game(&game), no_levels)), ValidationError::InvalidInput);
+
+    let zero_throughput = instruction::UpdateGameLevels { level_throughput_bps: vec!(10_000, 0), upgrade_resource_id: gold, upgrade_cost_base: 5 };
+    assert_error(game.send(build(update_game(&game), zero_throughput)), ValidationError::InvalidInput);
+}
+
+#[test]
+fn update_game_retool_delay() {
+    let mut game = TestGame::start();
+
+    game.ok(build(update_game(&game), instruction::UpdateGameRetoolDelay { retool_delay: 60 }));
+    assert_eq!(game.account::<Game>(game.game()).retool_delay, 60);
+
+    let result = game.send(build(update_game(&game), instruction::UpdateGameRetoolDelay { retool_delay: -1 }));
+    assert_error(result, ValidationError::InvalidInput);
+}
+
+#[test]
+fn update_game_wear() {
+    let mut game = TestGame::start();
+    let metal = Pubkey::new_unique();
+
+    let wear = instruction::UpdateGameWear { max_durability: 100, repair_resource_id: metal, repair_cost_per_point: 2 };
+    game.ok(build(update_game(&game), wear));
+
+    let account: Game = game.account(game.game());
+    assert_eq!(account.max_durability, 100);
+    assert_eq!(account.repair_resource_id, metal);
+    assert_eq!(account.repair_cost_per_point, 2);
+
+    let negative = instruction::UpdateGameWear { max_durability: -1, repair_resource_id: metal, repair_cost_per_point: 2 };
+    assert_error(game.send(build(update_game(&game), negative)), ValidationError::InvalidInput);
+}
+
+// Game clock -------------------------------
+
+#[test]
+fn advance_game_clock() {
+    let mut game = TestGame::start();
+
+    game.advance(500);
+    let clock: GameClock = game.account(pda::game_clock().0);
+    assert_eq!(clock.timestamp, START + 500);
+
+    let player = game.create_player();
+    assert_error(game.send(ix::advance_game_clock(player, 500)), ValidationError::OwnerRequired);
+}
+
+#[test]
+fn only_the_game_clock_tells_the_time() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    let location = game.location(player, 0, 0, 5);
+    let ore = game.resource(player, "ore", &[]);
+    let output = game.storage(player, ore, 1_000, (0, 0));
+    let fuel = game.storage(player, ore, 1_000, (0, 0));
+    let processor = game.processor(player, NewProcessor::producer(ore, 1, 10, location));
+    game.link(player, processor, &[output]);
+    game.advance(10);
+
+    // A clock of the player's own making, far ahead of the game's.
+    let mut own_clock: GameClock = game.account(pda::game_clock().0);
+    own_clock.timestamp = START + 1_000;
+    let own_clock_address = Pubkey::new_unique();
+    game.put(own_clock_address, &own_clock, GameClock::LEN);
+
+    let mut produce = ix::produce_without_input(processor, ore, output, fuel);
+    produce.accounts.push(AccountMeta::new_readonly(own_clock_address, false));
+    game.ok(produce);
+    assert_eq!(game.storage_amount(output), 1);
+}
+
+#[test]
+fn game_clock_only_moves_forward() {
+    let mut game = TestGame::start();
+    let admin = game.admin;
+
+    assert_error(game.send(ix::advance_game_clock(admin, -1)), ValidationError::InvalidInput);
+    assert_error(game.send(ix::advance_game_clock(admin, i64::MAX)), ValidationError::MathOverflow);
+}
+
+// Game tiles -------------------------------
+
+#[test]
+fn create_and_explore_game_tile() {
+    let mut game = TestGame::start();
+    let admin = game.admin;
+    let xy = [2, 3];
+
+    game.ok(ix::create_game_tile(admin, xy));
+    let tile: GameTile = game.account(pda::game_tile(&admin, xy).0);
+    assert_eq!((tile.x, tile.y, tile.tile_type), (2, 3, 0));
+
+    game.xy_location(admin, xy, LocationType::Unexplored);
+
+    game.ok(ix::explore_game_tile(admin, xy));
+    let tile: GameTile = game.account(pda::game_tile(&admin, xy).0);
+    assert!((1..=3).contains(&tile.tile_type));
+
+    // Tiles are only explored once.
+    let result = game.send(ix::explore_game_tile(admin, xy));
+    assert_error(result, ValidationError::ExperimentalError);
+}
+
+// Locations --------------------------------
+
+#[test]
+fn init_location() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+
+    game.ok(ix::init_location(player, -4, 7, 3, LocationType::Planet));
+
+    let location: Location = game.account(pda::map_location(&player, -4, 7).0);
+    assert_eq!(location.owner, player);
+    assert_eq!((location.pos_x, location.pos_y, location.capacity), (-4, 7, 3));
+    assert_eq!(location.location_type, LocationType::Planet);
+    assert!(location.occupied_by.is_empty());
+}
+
+// Resources --------------------------------
+
+#[test]
+fn init_resource() {
+    let mut game = TestGame::start();
+    let admin = game.admin;
+
+    let ore = game.resource(admin, "ore", &[]);
+    let coal = game.resource(admin, "coal", &[]);
+    let steel = game.resource(admin, "steel", &[(ore, 2), (coal, 1)]);
+
+    let account: Resource = game.account(steel);
+    assert_eq!(account.name, "steel");
+    assert_eq!(account.input, vec!(ore, coal));
+    assert_eq!(account.input_amount, vec!(2, 1));
+}
+
+fn init_resource_error(name: &str, inputs: Vec<Pubkey>, input_amounts: Vec<i64>, error: ValidationError) {
+    let mut game = TestGame::start();
+    let admin = game.admin;
+    let resource = Pubkey::new_unique();
+
+    let ix = build(
+        accounts::InitResource { resource, owner: admin, system_program: system_program::ID },
+        instruction::InitResource { name: name.to_string(), inputs, input_amounts },
+    );
+    assert_error(game.send(ix), error);
+}
+
+#[test]
+fn init_resource_rejects_long_names() {
+    init_resource_error(&"x".repeat(65), vec!(), vec!(), ValidationError::NameTooLong);
+}
+
+#[test]
+fn init_resource_rejects_too_many_inputs() {
+    let inputs = vec!(Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    init_resource_error("alloy", inputs, vec!(1, 1, 1), ValidationError::ResourceInputMax);
+}
+
+#[test]
+fn init_resource_requires_an_amount_per_input() {
+    init_resource_error("alloy", vec!(Pubkey::new_unique()), vec!(), ValidationError::MissingResourceInputAmount);
+}
+
+// Map --------------------------------------
+
+#[test]
+fn init_map_and_put() {
+    let mut game = TestGame::start();
+    let admin = game.admin;
+    let map = pda::map(&admin).0;
+
+    game.ok(ix::init_map(admin, 0));
+
+    let account: Map = game.account(map);
+    assert_eq!((account.width, account.height), (6, 5));
+    assert_eq!((account.csr().get(1, 1), account.csr().get(2, 1)), (1, 0));
+
+    game.ok(ix::map_put(map, 3, 1, 7));
+
+    let csr = game.account::<Map>(map).csr();
+    assert_eq!((csr.get(1, 1), csr.get(3, 1)), (1, 7));
+
+    let put = |x, y| ix::map_put(map, x, y, 2);
+    assert_error(game.send(put(MAP_MAX_WIDTH as u8, 0)), ValidationError::MapOutOfBounds);
+    for x in 0..15 {
+        game.ok(put(x, 9));
+    }
+    assert_error(game.send(put(15, 9)), ValidationError::MapFull);
+    assert_eq!(game.account::<Map>(map).csr().get(15, 9), 0);
+}
@@ -0,0 +1,287 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+
+use got_a_min::errors::ValidationError;
+use got_a_min::state::{FuelCostType, Shipment, TradeRoute};
+use got_a_min_sdk::instructions as ix;
+use got_a_min_sdk::pda;
+
+use common::*;
+
+// A sender of ore at (0, 0) burning coal, with a storage to deliver to at (2, 0). The distance of 2
+// costs 4 coal per unit and takes 2000 seconds.
+struct Route {
+    game: TestGame,
+    player: Pubkey,
+    ore: Pubkey,
+    coal: Pubkey,
+    sender: Pubkey,
+    from: Pubkey,
+    to: Pubkey,
+    fuel: Pubkey,
+    to_xy: (i64, i64),
+}
+
+fn route(ore: i64, coal: i64) -> Route {
+    route_to(ore, coal, (2, 0))
+}
+
+fn route_to(ore: i64, coal: i64, to_xy: (i64, i64)) -> Route {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    let location = game.location(player, 0, 0, LOCATION_ROOM as i64);
+    game.location(player, to_xy.0, to_xy.1, LOCATION_ROOM as i64);
+    let ore_id = game.resource(player, "ore", &[]);
+    let coal_id = game.resource(player, "coal", &[]);
+    let from = game.funded_storage(player, ore_id, 1_000, ore, (0, 0));
+    let to = game.storage(player, ore_id, 1_000, to_xy);
+    let fuel = game.funded_storage(player, coal_id, 1_000, coal, (0, 0));
+    let sender = game.processor(player, NewProcessor::sender(ore_id, coal_id, location));
+    game.link(player, sender, &[to, from, fuel]);
+    Route { game, player, ore: ore_id, coal: coal_id, sender, from, to, fuel, to_xy }
+}
+
+impl Route {
+    fn keys(&self, fuel: Pubkey) -> ix::Sender {
+        ix::Sender { processor: self.sender, storage_from: self.from, storage_to: self.to, storage_fuel: fuel }
+    }
+
+    fn locations(&self) -> (Pubkey, Pubkey) {
+        (pda::map_location(&self.player, 0, 0).0, pda::map_location(&self.player, self.to_xy.0, self.to_xy.1).0)
+    }
+
+    fn send(&self, shipment: Pubkey, send_amount: i64) -> Instruction {
+        self.send_with_fuel(shipment, send_amount, self.fuel)
+    }
+
+    fn send_with_fuel(&self, shipment: Pubkey, send_amount: i64, fuel: Pubkey) -> Instruction {
+        ix::send(self.player, &self.keys(fuel), self.ore, shipment, [(0, 0), self.to_xy], send_amount)
+    }
+
+    fn receive(&self, shipment: Pubkey) -> Instruction {
+        ix::receive_shipment(self.player, shipment, self.to, None)
+    }
+
+    fn receive_from(&self, trade_route: Pubkey, shipment: Pubkey) -> Instruction {
+        ix::receive_shipment(self.player, shipment, self.to, Some(trade_route))
+    }
+
+    fn create_trade_route(&self, trade_route: Pubkey, amount_per_cycle: i64, cadence: i64) -> Instruction {
+        ix::create_trade_route(self.player, trade_route, &self.keys(self.fuel), self.locations(), amount_per_cycle, cadence)
+    }
+
+    fn run_trade_route(&self, trade_route: Pubkey, shipment: Pubkey) -> Instruction {
+        ix::run_trade_route(self.player, trade_route, &self.keys(self.fuel), shipment)
+    }
+}
+
+// Shipments --------------------------------
+
+#[test]
+fn send_and_receive_shipment() {
+    let mut route = route(30, 100);
+    let shipment = Pubkey::new_unique();
+
+    route.game.ok(route.send(shipment, 5));
+
+    let account: Shipment = route.game.account(shipment);
+    assert_eq!((account.owner, account.sender_id, account.resource_id), (route.player, route.sender, route.ore));
+    assert_eq!((account.amount, account.storage_to, account.arrives_at), (5, route.to, START + 2_000));
+    assert_eq!(route.game.storage_amount(route.from), 25);
+    assert_eq!(route.game.storage_amount(route.fuel), 80);
+
+    assert_error(route.game.send(route.receive(shipment)), ValidationError::ShipmentNotArrived);
+
+    route.game.advance(2_000);
+    route.game.ok(route.receive(shipment));
+    assert_eq!(route.game.storage_amount(route.to), 5);
+    assert!(!route.game.exists(shipment));
+}
+
+#[test]
+fn send_errors() {
+    let mut route = route(30, 10);
+    let wrong_fuel = route.game.funded_storage(route.player, route.ore, 1_000, 100, (0, 0));
+    route.game.link(route.player, route.sender, &[wrong_fuel]);
+
+    assert_error(route.game.send(route.send(Pubkey::new_unique(), 0)), ValidationError::InvalidInput);
+    assert_error(route.game.send(route.send(Pubkey::new_unique(), 31)), ValidationError::InputStorageAmountTooLow);
+    assert_error(route.game.send(route.send_with_fuel(Pubkey::new_unique(), 5, wrong_fuel)), ValidationError::FuelNotSupplied);
+    assert_error(route.game.send(route.send_with_fuel(Pubkey::new_unique(), 5, route.from)), ValidationError::SameStorage);
+    assert_error(route.game.send(route.send(Pubkey::new_unique(), 5)), ValidationError::FuelNotEnough);
+
+    route.game.ok(route.send(Pubkey::new_unique(), 2));
+    assert_eq!(route.game.storage_amount(route.fuel), 2);
+}
+
+#[test]
+fn send_requires_a_sender() {
+    let mut route = route(30, 100);
+    let location = pda::map_location(&route.player, 0, 0).0;
+    let producer = NewProcessor { fuel_resource_id: route.coal, ..NewProcessor::producer(route.ore, 1, 1, location) };
+    let producer = route.game.processor(route.player, producer);
+    route.game.link(route.player, producer, &[route.to, route.from, route.fuel]);
+    route.sender = producer;
+
+    assert_error(route.game.send(route.send(Pubkey::new_unique(), 5)), ValidationError::InvalidProcessorType);
+}
+
+// Trade routes -----------------------------
+
+#[test]
+fn create_and_run_trade_route() {
+    let mut route = route(30, 100);
+    let trade_route = Pubkey::new_unique();
+
+    route.game.ok(route.create_trade_route(trade_route, 5, 2_000));
+
+    let account: TradeRoute = route.game.account(trade_route);
+    assert_eq!((account.owner, account.sender_id, account.to_location_id), (route.player, route.sender, pda::map_location(&route.player, 2, 0).0));
+    assert_eq!((account.distance, account.amount_per_cycle, account.next_cycle_at), (2, 5, START + 2_000));
+
+    assert_error(route.game.send(route.run_trade_route(trade_route, Pubkey::new_unique())), ValidationError::TradeRouteNotDue);
+
+    // Two cycles are due, both shipped at once and on the way for the travel time.
+    route.game.advance(4_000);
+    let shipment = Pubkey::new_unique();
+    route.game.ok(route.run_trade_route(trade_route, shipment));
+    assert_eq!(route.game.storage_amount(route.from), 20);
+    assert_eq!(route.game.storage_amount(route.fuel), 60);
+    assert_eq!(route.game.account::<TradeRoute>(trade_route).next_cycle_at, START + 6_000);
+    let account: Shipment = route.game.account(shipment);
+    assert_eq!((account.amount, account.storage_to, account.arrives_at), (10, route.to, START + 6_000));
+    assert_eq!(account.trade_route, trade_route);
+    assert_eq!(route.game.account::<TradeRoute>(trade_route).in_flight, 10);
+
+    assert_error(route.game.send(route.receive_from(trade_route, shipment)), ValidationError::ShipmentNotArrived);
+    route.game.advance(2_000);
+    route.game.ok(route.receive_from(trade_route, shipment));
+    assert_eq!(route.game.storage_amount(route.to), 10);
+    assert_eq!(route.game.account::<TradeRoute>(trade_route).in_flight, 0);
+
+    // Fuel for 15 units is left, what can't be paid for is skipped.
+    route.game.advance(20_000);
+    let shipment = Pubkey::new_unique();
+    route.game.ok(route.run_trade_route(trade_route, shipment));
+    assert_eq!(route.game.storage_amount(route.fuel), 0);
+    route.game.advance(2_000);
+    route.game.ok(route.receive_from(trade_route, shipment));
+    assert_eq!(route.game.storage_amount(route.to), 25);
+}
+
+#[test]
+fn create_trade_route_errors() {
+    let mut route = route(30, 100);
+    let other = route.game.create_player();
+
+    assert_error(route.game.send(route.create_trade_route(Pubkey::new_unique(), 0, 2_000)), ValidationError::InvalidInput);
+    assert_error(route.game.send(route.create_trade_route(Pubkey::new_unique(), 5, 1_999)), ValidationError::InvalidInput);
+
+    let not_mine = ix::create_trade_route(other, Pubkey::new_unique(), &route.keys(route.fuel), route.locations(), 5, 2_000);
+    route.game.bank.airdrop(other, FUNDS);
+    assert_error(route.game.send(not_mine), ValidationError::OwnerRequired);
+
+    let location = pda::map_location(&route.player, 0, 0).0;
+    let other_fuel = route.game.storage_at(other, route.coal, 1_000, location);
+    let with_other_fuel = ix::create_trade_route(route.player, Pubkey::new_unique(), &route.keys(other_fuel), route.locations(), 5, 2_000);
+    assert_error(route.game.send(with_other_fuel), ValidationError::OwnerRequired);
+
+    // Fuel taken from the cargo would be written back over the cargo's removal.
+    let from_as_fuel = ix::create_trade_route(route.player, Pubkey::new_unique(), &route.keys(route.from), route.locations(), 5, 2_000);
+    assert_error(route.game.send(from_as_fuel), ValidationError::SameStorage);
+
+    let coal_storage = route.game.storage(route.player, route.coal, 1_000, (2, 0));
+    route.to = coal_storage;
+    assert_error(route.game.send(route.create_trade_route(Pubkey::new_unique(), 5, 2_000)), ValidationError::ResourceNotMatching);
+}
+
+#[test]
+fn trade_routes_are_fuelled_by_distance() {
+    let mut route = route(30, 100);
+    let location = pda::map_location(&route.player, 0, 0).0;
+    let free = NewProcessor { fuel_cost_type: FuelCostType::Nothing, ..NewProcessor::sender(route.ore, route.coal, location) };
+    route.sender = route.game.processor(route.player, free);
+
+    assert_error(route.game.send(route.create_trade_route(Pubkey::new_unique(), 5, 2_000)), ValidationError::InvalidFuelCostType);
+}
+
+#[test]
+fn trade_route_counts_shipments_in_flight() {
+    let mut route = route(30, 100);
+    route.to = route.game.storage(route.player, route.ore, 8, (2, 0));
+    let trade_route = Pubkey::new_unique();
+    route.game.ok(route.create_trade_route(trade_route, 5, 2_000));
+
+    route.game.advance(2_000);
+    let first = Pubkey::new_unique();
+    route.game.ok(route.run_trade_route(trade_route, first));
+
+    // Only the room left next to the first shipment is filled.
+    route.game.advance(2_000);
+    let second = Pubkey::new_unique();
+    route.game.ok(route.run_trade_route(trade_route, second));
+    assert_eq!(route.game.account::<Shipment>(second).amount, 3);
+
+    // A run that can't ship anything fails instead of skipping the cycle.
+    route.game.advance(2_000);
+    assert_error(route.game.send(route.run_trade_route(trade_route, Pubkey::new_unique())), ValidationError::NothingToShip);
+    assert_eq!(route.game.account::<TradeRoute>(trade_route).next_cycle_at, START + 6_000);
+
+    assert_error(route.game.send(route.receive(first)), ValidationError::InvalidInput);
+    route.game.ok(route.receive_from(trade_route, first));
+    assert_eq!(route.game.account::<TradeRoute>(trade_route).in_flight, 3);
+
+    // Received after the route is gone, there's nothing left to release.
+    route.game.ok(ix::close_trade_route(route.player, trade_route));
+    route.game.ok(route.receive_from(trade_route, second));
+    assert_eq!(route.game.storage_amount(route.to), 8);
+}
+
+#[test]
+fn close_trade_route_as_owner() {
+    let mut route = route(30, 100);
+    let trade_route = Pubkey::new_unique();
+    let other = route.game.create_player();
+    route.game.ok(route.create_trade_route(trade_route, 5, 2_000));
+
+    assert_error(route.game.send(ix::close_trade_route(other, trade_route)), ValidationError::OwnerRequired);
+
+    route.game.ok(ix::close_trade_route(route.player, trade_route));
+    assert!(!route.game.exists(trade_route));
+}
+
+// Extreme values -------------------------
+
+#[test]
+fn send_extreme_values() {
+    let mut route = route(30, 100);
+    assert_error(route.game.send(route.send(Pubkey::new_unique(), i64::MAX)), ValidationError::InputStorageAmountTooLow);
+    assert_error(route.game.send(route.send(Pubkey::new_unique(), i64::MIN)), ValidationError::InvalidInput);
+
+    let mut far = route_to(30, 100, (i64::MAX, 0));
+    assert_error(far.game.send(far.send(Pubkey::new_unique(), 1)), ValidationError::MathOverflow);
+    let mut beyond = route_to(30, 100, (i64::MIN, i64::MIN));
+    assert_error(beyond.game.send(beyond.send(Pubkey::new_unique(), 1)), ValidationError::MathOverflow);
+}
+
+#[test]
+fn trade_route_extreme_values() {
+    let mut route = route(30, 100);
+    assert_error(route.game.send(route.create_trade_route(Pubkey::new_unique(), i64::MIN, 2_000)), ValidationError::InvalidInput);
+    assert_error(route.game.send(route.create_trade_route(Pubkey::new_unique(), 5, i64::MIN)), ValidationError::InvalidInput);
+
+    assert_error(route.game.send(route.create_trade_route(Pubkey::new_unique(), 5, i64::MAX)), ValidationError::MathOverflow);
+
+    // Cycles that ask for more than there is ship what the storage and the fuel allow.
+    let trade_route = Pubkey::new_unique();
+    route.game.ok(route.create_trade_route(trade_route, i64::MAX, 2_000));
+    route.game.advance(4_000);
+    let shipment = Pubkey::new_unique();
+    route.game.ok(route.run_trade_route(trade_route, shipment));
+    assert_eq!(route.game.account::<Shipment>(shipment).amount, 25);
+
+    let mut far = route_to(30, 100, (i64::MAX, 0));
+    assert_error(far.game.send(far.create_trade_route(Pubkey::new_unique(), 5, 2_000)), ValidationError::MathOverflow);
+}
@@ -0,0 +1,660 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::system_program;
+
+use got_a_min::errors::ValidationError;
+use got_a_min::state::{Auction, Game, LiquidityPosition, Location, MobilityType, Offer, OrderBook, OrderSide, Pool, Processor};
+use got_a_min::{accounts, instruction};
+use got_a_min_sdk::instructions::{self as ix, build};
+use got_a_min_sdk::pda;
+
+use common::*;
+
+// A seller with a location at (0, 0) and a buyer trading there, ore changes hands for gold.
+struct Market {
+    game: TestGame,
+    seller: Pubkey,
+    buyer: Pubkey,
+    location: Pubkey,
+    ore: Pubkey,
+    gold: Pubkey,
+}
+
+fn market() -> Market {
+    let mut game = TestGame::start();
+    let seller = game.create_player();
+    let buyer = game.create_player();
+    let location = game.location(seller, 0, 0, LOCATION_ROOM as i64);
+    let ore = game.resource(seller, "ore", &[]);
+    let gold = game.resource(seller, "gold", &[]);
+    Market { game, seller, buyer, location, ore, gold }
+}
+
+impl Market {
+    // A storage of [owner] at the market holding [amount].
+    fn storage(&mut self, owner: Pubkey, resource: Pubkey, amount: i64) -> Pubkey {
+        self.storage_with_capacity(owner, resource, amount, 10_000)
+    }
+
+    fn storage_with_capacity(&mut self, owner: Pubkey, resource: Pubkey, amount: i64, capacity: i64) -> Pubkey {
+        let storage = self.game.storage_at(owner, resource, capacity, self.location);
+        self.game.set_storage_amount(storage, amount);
+        storage
+    }
+}
+
+// Offers -----------------------------------
+
+#[test]
+fn create_and_accept_offer() {
+    let mut market = market();
+    let (seller, buyer) = (market.seller, market.buyer);
+    let seller_ore = market.storage(seller, market.ore, 30);
+    let seller_gold = market.storage(seller, market.gold, 0);
+    let buyer_gold = market.storage(buyer, market.gold, 50);
+    let buyer_ore = market.storage(buyer, market.ore, 0);
+    let offer = Pubkey::new_unique();
+
+    market.game.ok(ix::create_offer(seller, offer, seller_ore, 10, market.gold, 20));
+
+    let account: Offer = market.game.account(offer);
+    assert_eq!((account.maker, account.location_id, account.offered_resource_id), (seller, market.location, market.ore));
+    assert_eq!((account.offered_amount, account.requested_resource_id, account.requested_amount), (10, market.gold, 20));
+    assert_eq!(market.game.storage_amount(seller_ore), 20);
+    assert_eq!(market.game.storage_amount(pda::offer_escrow(&offer).0), 10);
+
+    market.game.ok(ix::accept_offer(buyer, offer, seller, seller_gold, (buyer_gold, buyer_ore)));
+
+    assert_eq!(market.game.storage_amount(buyer_gold), 30);
+    assert_eq!(market.game.storage_amount(seller_gold), 20);
+    assert_eq!(market.game.storage_amount(buyer_ore), 10);
+    assert!(!market.game.exists(offer));
+    assert!(!market.game.exists(pda::offer_escrow(&offer).0));
+}
+
+#[test]
+fn offer_errors() {
+    let mut market = market();
+    let (seller, buyer) = (market.seller, market.buyer);
+    let seller_ore = market.storage(seller, market.ore, 30);
+    let seller_gold = market.storage(seller, market.gold, 0);
+    let buyer_gold = market.storage(buyer, market.gold, 10);
+    let buyer_ore = market.storage(buyer, market.ore, 0);
+    let offer = Pubkey::new_unique();
+
+    assert_error(market.game.send(ix::create_offer(seller, Pubkey::new_unique(), seller_ore, 0, market.gold, 20)), ValidationError::InvalidInput);
+    assert_error(market.game.send(ix::create_offer(seller, Pubkey::new_unique(), seller_ore, 10, market.gold, 0)), ValidationError::InvalidInput);
+    assert_error(market.game.send(ix::create_offer(seller, Pubkey::new_unique(), buyer_ore, 10, market.gold, 20)), ValidationError::OwnerRequired);
+    assert_error(market.game.send(ix::create_offer(seller, Pubkey::new_unique(), seller_ore, 31, market.gold, 20)), ValidationError::StorageAmountTooLow);
+    assert_error(market.game.send(ix::create_offer(seller, Pubkey::new_unique(), seller_ore, 10, market.ore, 20)), ValidationError::InvalidInput);
+
+    market.game.ok(ix::create_offer(seller, offer, seller_ore, 10, market.gold, 20));
+
+    assert_error(market.game.send(ix::accept_offer(buyer, offer, seller, seller_ore, (buyer_gold, buyer_ore))), ValidationError::ResourceNotMatching);
+    assert_error(market.game.send(ix::accept_offer(buyer, offer, seller, seller_gold, (buyer_ore, buyer_gold))), ValidationError::ResourceNotMatching);
+    assert_error(market.game.send(ix::accept_offer(buyer, offer, seller, seller_gold, (seller_gold, buyer_ore))), ValidationError::SameStorage);
+    assert_error(market.game.send(ix::accept_offer(buyer, offer, seller, seller_gold, (buyer_gold, buyer_gold))), ValidationError::SameStorage);
+    assert_error(market.game.send(ix::accept_offer(buyer, offer, seller, seller_gold, (buyer_gold, seller_ore))), ValidationError::OwnerRequired);
+    assert_error(market.game.send(ix::accept_offer(buyer, offer, seller, seller_gold, (buyer_gold, buyer_ore))), ValidationError::StorageAmountTooLow);
+
+    market.game.location(buyer, 5, 5, 5);
+    let gold_elsewhere = market.game.funded_storage(buyer, market.gold, 100, 50, (5, 5));
+    assert_error(market.game.send(ix::accept_offer(buyer, offer, seller, seller_gold, (gold_elsewhere, buyer_ore))), ValidationError::DifferentLocations);
+}
+
+#[test]
+fn cancel_offer_returns_escrow() {
+    let mut market = market();
+    let (seller, buyer) = (market.seller, market.buyer);
+    let seller_ore = market.storage(seller, market.ore, 30);
+    let buyer_ore = market.storage(buyer, market.ore, 0);
+    let offer = Pubkey::new_unique();
+    market.game.ok(ix::create_offer(seller, offer, seller_ore, 10, market.gold, 20));
+
+    assert_error(market.game.send(ix::cancel_offer(buyer, offer, buyer_ore)), ValidationError::OwnerRequired);
+
+    market.game.ok(ix::cancel_offer(seller, offer, seller_ore));
+    assert_eq!(market.game.storage_amount(seller_ore), 30);
+    assert!(!market.game.exists(offer));
+}
+
+// Order book -------------------------------
+
+fn init_order_book(market: &mut Market) -> ix::OrderBookKeys {
+    market.game.ok(ix::init_order_book(market.seller, market.location, market.ore, market.gold));
+    ix::OrderBookKeys::new(&market.location, &market.ore, &market.gold)
+}
+
+fn place_order(book: &ix::OrderBookKeys, owner: Pubkey, storages: (Pubkey, Pubkey), order: (OrderSide, i64, i64)) -> Instruction {
+    let (side, price, amount) = order;
+    ix::place_order(owner, book, storages, side, price, amount)
+}
+
+#[test]
+fn init_order_book_for_a_pair() {
+    let mut market = market();
+    let book = init_order_book(&mut market);
+
+    let account: OrderBook = market.game.account(book.address);
+    assert_eq!((account.location_id, account.base_resource_id, account.quote_resource_id), (market.location, market.ore, market.gold));
+    assert_eq!(account.order_count(), 0);
+
+    let same = ix::init_order_book(market.seller, market.location, market.ore, market.ore);
+    assert!(market.game.send(same).is_err());
+}
+
+#[test]
+fn place_and_match_orders() {
+    let mut market = market();
+    let book = init_order_book(&mut market);
+    let (seller, buyer) = (market.seller, market.buyer);
+    let seller_storages = (market.storage(seller, market.ore, 10), market.storage(seller, market.gold, 0));
+    let buyer_storages = (market.storage(buyer, market.ore, 0), market.storage(buyer, market.gold, 100));
+
+    market.game.ok(place_order(&book, seller, seller_storages, (OrderSide::Ask, 3, 10)));
+    assert_eq!(market.game.storage_amount(book.base_vault), 10);
+
+    // Filled at the resting price, not the bid. The seller's gold waits in the vault.
+    market.game.ok(place_order(&book, buyer, buyer_storages, (OrderSide::Bid, 5, 4)));
+    assert_eq!(market.game.storage_amount(buyer_storages.0), 4);
+    assert_eq!(market.game.storage_amount(buyer_storages.1), 88);
+    assert_eq!(market.game.storage_amount(book.quote_vault), 12);
+    assert_eq!(market.game.account::<OrderBook>(book.address).asks[0].amount, 6);
+
+    market.game.ok(ix::claim_order(seller, &book, seller_storages, 0));
+    assert_eq!(market.game.storage_amount(seller_storages.1), 12);
+    assert_eq!(market.game.storage_amount(book.quote_vault), 0);
+
+    // Below the ask, so it rests with its gold locked.
+    market.game.ok(place_order(&book, buyer, buyer_storages, (OrderSide::Bid, 2, 10)));
+    assert_eq!(market.game.storage_amount(book.quote_vault), 20);
+
+    // The seller hits the bid with fresh ore, leaving 5 of it resting.
+    market.game.set_storage_amount(seller_storages.0, 5);
+    market.game.ok(place_order(&book, seller, seller_storages, (OrderSide::Ask, 2, 5)));
+    assert_eq!(market.game.storage_amount(seller_storages.1), 22);
+    assert_eq!(market.game.storage_amount(book.quote_vault), 10);
+    assert_eq!(market.game.storage_amount(book.base_vault), 11);
+
+    // Cancelling hands back the locked gold and the ore that wasn't claimed yet.
+    market.game.ok(ix::cancel_order(buyer, &book, buyer_storages, 1));
+    assert_eq!(market.game.storage_amount(buyer_storages.0), 9);
+    assert_eq!(market.game.storage_amount(buyer_storages.1), 78);
+    assert_eq!(market.game.storage_amount(book.quote_vault), 0);
+    assert_error(market.game.send(ix::cancel_order(buyer, &book, buyer_storages, 1)), ValidationError::OrderNotFound);
+
+    market.game.ok(ix::cancel_order(seller, &book, seller_storages, 0));
+    assert_eq!(market.game.storage_amount(seller_storages.0), 6);
+    assert_eq!(market.game.account::<OrderBook>(book.address).order_count(), 0);
+}
+
+#[test]
+fn makers_claim_fills_from_the_vault() {
+    let mut market = market();
+    let book = init_order_book(&mut market);
+    let (seller, buyer) = (market.seller, market.buyer);
+    let seller_storages = (market.storage(seller, market.ore, 10), market.storage(seller, market.gold, 0));
+    let buyer_storages = (market.storage(buyer, market.ore, 0), market.storage(buyer, market.gold, 100));
+    let elsewhere = market.game.location(seller, 1, 0, LOCATION_ROOM as i64);
+    let size = |market: &Market| market.game.bank.account(&book.address).unwrap().data.len();
+
+    market.game.ok(place_order(&book, seller, seller_storages, (OrderSide::Ask, 3, 10)));
+    assert_eq!(size(&market), OrderBook::space(1));
+
+    // The seller's gold storage leaving doesn't stop the buyer, and a filled order takes no new room.
+    market.game.ok(ix::move_storage(seller, seller_storages.1, market.location, elsewhere));
+    market.game.ok(place_order(&book, buyer, buyer_storages, (OrderSide::Bid, 3, 10)));
+    assert_eq!(market.game.storage_amount(buyer_storages.0), 10);
+    assert_eq!(market.game.account::<OrderBook>(book.address).asks[0].unclaimed, 30);
+    assert_eq!(size(&market), OrderBook::space(1));
+
+    // Claimed into any of the seller's gold storages at the market, which closes the order.
+    let gold = market.storage(seller, market.gold, 0);
+    assert_error(market.game.send(ix::claim_order(buyer, &book, buyer_storages, 0)), ValidationError::OrderNotFound);
+    assert_error(market.game.send(ix::claim_order(seller, &book, (seller_storages.0, seller_storages.0), 0)), ValidationError::ResourceNotMatching);
+    market.game.ok(ix::claim_order(seller, &book, (seller_storages.0, gold), 0));
+    assert_eq!(market.game.storage_amount(gold), 30);
+    assert_eq!(market.game.account::<OrderBook>(book.address).order_count(), 0);
+    assert_eq!(size(&market), OrderBook::space(0));
+    assert_error(market.game.send(ix::claim_order(seller, &book, (seller_storages.0, gold), 0)), ValidationError::OrderNotFound);
+}
+
+#[test]
+fn place_order_errors() {
+    let mut market = market();
+    let book = init_order_book(&mut market);
+    let (seller, buyer) = (market.seller, market.buyer);
+    let seller_storages = (market.storage(seller, market.ore, 10), market.storage(seller, market.gold, 100));
+    let buyer_storages = (market.storage(buyer, market.ore, 0), market.storage(buyer, market.gold, 100));
+
+    assert_error(market.game.send(place_order(&book, seller, seller_storages, (OrderSide::Ask, 0, 1))), ValidationError::InvalidInput);
+    assert_error(market.game.send(place_order(&book, seller, seller_storages, (OrderSide::Ask, 1, 0))), ValidationError::InvalidInput);
+    assert_error(market.game.send(place_order(&book, seller, (seller_storages.1, seller_storages.1), (OrderSide::Ask, 1, 1))), ValidationError::ResourceNotMatching);
+    assert_error(market.game.send(place_order(&book, seller, (buyer_storages.0, seller_storages.1), (OrderSide::Ask, 1, 1))), ValidationError::OwnerRequired);
+    assert_error(market.game.send(place_order(&book, seller, seller_storages, (OrderSide::Ask, 1, 11))), ValidationError::StorageAmountTooLow);
+
+    market.game.ok(place_order(&book, seller, seller_storages, (OrderSide::Ask, 3, 5)));
+    assert_error(market.game.send(place_order(&book, buyer, buyer_storages, (OrderSide::Bid, 1, 1_000))), ValidationError::StorageAmountTooLow);
+}
+
+#[test]
+fn order_book_is_limited() {
+    let mut market = market();
+    let book = init_order_book(&mut market);
+    let seller = market.seller;
+    let storages = (market.storage(seller, market.ore, 100), market.storage(seller, market.gold, 0));
+
+    for price in 1..=64 {
+        market.game.ok(place_order(&book, seller, storages, (OrderSide::Ask, price, 1)));
+    }
+    assert_error(market.game.send(place_order(&book, seller, storages, (OrderSide::Ask, 1, 1))), ValidationError::OrderBookFull);
+}
+
+#[test]
+fn filled_orders_waiting_for_a_claim_leave_room() {
+    let mut market = market();
+    let book = init_order_book(&mut market);
+    let (seller, buyer) = (market.seller, market.buyer);
+    let storages = (market.storage(seller, market.ore, 100), market.storage(seller, market.gold, 0));
+    let buyer_storages = (market.storage(buyer, market.ore, 0), market.storage(buyer, market.gold, 10_000));
+
+    for price in 1..=64 {
+        market.game.ok(place_order(&book, seller, storages, (OrderSide::Ask, price, 1)));
+    }
+    market.game.ok(place_order(&book, buyer, buyer_storages, (OrderSide::Bid, 2, 2)));
+
+    // The two filled asks wait for the seller's claim without keeping others out.
+    market.game.ok(place_order(&book, buyer, buyer_storages, (OrderSide::Bid, 1, 1)));
+    market.game.ok(place_order(&book, seller, storages, (OrderSide::Ask, 100, 1)));
+    assert_error(market.game.send(place_order(&book, seller, storages, (OrderSide::Ask, 100, 1))), ValidationError::OrderBookFull);
+    assert_eq!(market.game.account::<OrderBook>(book.address).order_count(), 66);
+}
+
+#[test]
+fn own_orders_are_passed_over() {
+    let mut market = market();
+    let book = init_order_book(&mut market);
+    let (seller, buyer) = (market.seller, market.buyer);
+    let seller_storages = (market.storage(seller, market.ore, 10), market.storage(seller, market.gold, 100));
+    let buyer_storages = (market.storage(buyer, market.ore, 10), market.storage(buyer, market.gold, 0));
+
+    market.game.ok(place_order(&book, seller, seller_storages, (OrderSide::Ask, 2, 5)));
+    market.game.ok(place_order(&book, buyer, buyer_storages, (OrderSide::Ask, 3, 5)));
+
+    // The seller's bid skips their own cheaper ask and fills against the buyer's.
+    market.game.ok(place_order(&book, seller, seller_storages, (OrderSide::Bid, 3, 2)));
+    assert_eq!(market.game.storage_amount(seller_storages.1), 94);
+    let account: OrderBook = market.game.account(book.address);
+    assert_eq!((account.asks[0].amount, account.asks[1].amount, account.asks[1].unclaimed), (5, 3, 6));
+    assert_eq!(account.bids.len(), 0);
+}
+
+// Pools ------------------------------------
+
+fn pool_keys(market: &Market) -> ix::PoolKeys {
+    ix::PoolKeys::new(&market.game.game(), &market.location, &market.ore, &market.gold)
+}
+
+// Seeded in the given order, unlike ix::create_pool which sorts the resources.
+fn create_pool_in(market: &Market, game: Pubkey, resource_a_id: Pubkey, resource_b_id: Pubkey) -> Instruction {
+    let seeds: &[&[u8]] = &[b"pool", game.as_ref(), market.location.as_ref(), resource_a_id.as_ref(), resource_b_id.as_ref()];
+    let pool = Pubkey::find_program_address(seeds, &got_a_min::ID).0;
+    let accounts = accounts::CreatePool {
+        game,
+        pool,
+        reserve_a: pda::pool_reserve(&pool, &resource_a_id).0,
+        reserve_b: pda::pool_reserve(&pool, &resource_b_id).0,
+        location: market.location,
+        owner: market.seller,
+        system_program: system_program::ID,
+    };
+    build(accounts, instruction::CreatePool { resource_a_id, resource_b_id })
+}
+
+// The storages of a player in the order of the pool, resource a first.
+fn sorted_storages(market: &Market, ore_storage: Pubkey, gold_storage: Pubkey) -> (Pubkey, Pubkey) {
+    match market.ore < market.gold {
+        true => (ore_storage, gold_storage),
+        false => (gold_storage, ore_storage),
+    }
+}
+
+fn add_liquidity(market: &Market, owner: Pubkey, storages: (Pubkey, Pubkey), amounts: (i64, i64), min_shares: i64) -> Instruction {
+    ix::add_liquidity(owner, &pool_keys(market), storages, amounts, min_shares)
+}
+
+fn remove_liquidity(market: &Market, owner: Pubkey, storages: (Pubkey, Pubkey), shares: i64) -> Instruction {
+    ix::remove_liquidity(owner, &pool_keys(market), storages, shares, (0, 0))
+}
+
+fn swap(market: &Market, owner: Pubkey, storage_in: Pubkey, storage_out: Pubkey, amount_in: i64, min_amount_out: i64) -> Instruction {
+    ix::swap(owner, &pool_keys(market), storage_in, storage_out, amount_in, min_amount_out)
+}
+
+fn create_sorted_pool(market: &mut Market) -> ix::PoolKeys {
+    market.game.ok(ix::create_pool(market.seller, market.game.game(), market.location, market.ore, market.gold));
+    pool_keys(market)
+}
+
+#[test]
+fn create_pool_for_a_pair() {
+    let mut market = market();
+    let (resource_a, resource_b) = pda::sorted_pair(&market.ore, &market.gold);
+    let (resource_a, resource_b) = (*resource_a, *resource_b);
+
+    assert_error(market.game.send(create_pool_in(&market, market.game.game(), resource_b, resource_a)), ValidationError::InvalidInput);
+
+    // A game of the seller's own making can't set the fees of a pool.
+    let mut own_rules: Game = market.game.account(market.game.game());
+    (own_rules.authority, own_rules.swap_fee_bps) = (market.seller, 0);
+    let own_game = Pubkey::new_unique();
+    market.game.put(own_game, &own_rules, Game::LEN);
+    assert_anchor_error(market.game.send(create_pool_in(&market, own_game, resource_a, resource_b)), ErrorCode::ConstraintSeeds);
+
+    let pool = create_sorted_pool(&mut market);
+    let account: Pool = market.game.account(pool.address);
+    assert_eq!((account.game, account.location_id), (market.game.game(), market.location));
+    assert_eq!((account.resource_a_id, account.resource_b_id, account.lp_supply), (resource_a, resource_b, 0));
+}
+
+#[test]
+fn provide_liquidity_and_swap() {
+    let mut market = market();
+    let pool = create_sorted_pool(&mut market);
+    let (seller, buyer) = (market.seller, market.buyer);
+    let (seller_ore, seller_gold) = (market.storage(seller, market.ore, 2_000), market.storage(seller, market.gold, 2_000));
+    let (buyer_ore, buyer_gold) = (market.storage(buyer, market.ore, 200), market.storage(buyer, market.gold, 0));
+    let seller_storages = sorted_storages(&market, seller_ore, seller_gold);
+
+    market.game.ok(add_liquidity(&market, seller, seller_storages, (1_000, 1_000), 1_000));
+    assert_eq!(market.game.account::<Pool>(pool.address).lp_supply, 1_000);
+    let position: LiquidityPosition = market.game.account(pda::liquidity_position(&pool.address, &seller).0);
+    assert_eq!((position.owner, position.shares), (seller, 1_000));
+
+    assert_error(market.game.send(add_liquidity(&market, seller, seller_storages, (10, 10), 11)), ValidationError::SlippageExceeded);
+
+    // 30 bps of 100 rounds down to no fee.
+    assert_error(market.game.send(swap(&market, buyer, buyer_ore, buyer_gold, 100, 91)), ValidationError::SlippageExceeded);
+    market.game.ok(swap(&market, buyer, buyer_ore, buyer_gold, 100, 90));
+    assert_eq!(market.game.storage_amount(buyer_ore), 100);
+    assert_eq!(market.game.storage_amount(buyer_gold), 90);
+
+    assert_error(market.game.send(remove_liquidity(&market, seller, seller_storages, 1_001)), ValidationError::InsufficientShares);
+    market.game.ok(remove_liquidity(&market, seller, seller_storages, 1_000));
+    assert_eq!(market.game.storage_amount(seller_ore), 2_100);
+    assert_eq!(market.game.storage_amount(seller_gold), 1_910);
+    assert_eq!(market.game.account::<Pool>(pool.address).lp_supply, 0);
+}
+
+#[test]
+fn pool_errors() {
+    let mut market = market();
+    create_sorted_pool(&mut market);
+    let (seller, buyer) = (market.seller, market.buyer);
+    let (seller_ore, seller_gold) = (market.storage(seller, market.ore, 100), market.storage(seller, market.gold, 100));
+    let buyer_ore = market.storage(buyer, market.ore, 100);
+    let seller_storages = sorted_storages(&market, seller_ore, seller_gold);
+
+    assert_error(market.game.send(swap(&market, seller, seller_ore, seller_gold, 10, 0)), ValidationError::InsufficientLiquidity);
+
+    assert_error(market.game.send(add_liquidity(&market, seller, seller_storages, (0, 10), 0)), ValidationError::InvalidInput);
+    assert_error(market.game.send(add_liquidity(&market, seller, (seller_storages.1, seller_storages.0), (10, 10), 0)), ValidationError::ResourceNotMatching);
+    market.game.ok(add_liquidity(&market, seller, seller_storages, (50, 50), 0));
+
+    assert_error(market.game.send(swap(&market, seller, seller_ore, seller_ore, 10, 0)), ValidationError::ResourceNotMatching);
+    assert_error(market.game.send(swap(&market, seller, buyer_ore, seller_gold, 10, 0)), ValidationError::OwnerRequired);
+    assert_error(market.game.send(swap(&market, seller, seller_ore, seller_gold, 0, 0)), ValidationError::InvalidInput);
+}
+
+// Auctions ---------------------------------
+
+fn place_bid(auction: Pubkey, bidder: Pubkey, bidder_storage: Pubkey, amount: i64, highest_bidder: Pubkey) -> Instruction {
+    ix::place_bid(bidder, auction, bidder_storage, highest_bidder, amount)
+}
+
+fn create_location_auction(market: &Market, min_bid: i64, duration: i64) -> Instruction {
+    ix::create_location_auction(market.seller, market.location, market.gold, min_bid, duration)
+}
+
+fn settle_location_auction(market: &Market, seller_storage: Pubkey, settler: Pubkey) -> Instruction {
+    ix::settle_location_auction(settler, market.seller, market.location, seller_storage)
+}
+
+#[test]
+fn location_auction() {
+    let mut market = market();
+    let (seller, buyer) = (market.seller, market.buyer);
+    let rival = market.game.create_player();
+    let seller_gold = market.storage(seller, market.gold, 0);
+    let buyer_gold = market.storage(buyer, market.gold, 100);
+    let rival_gold = market.storage(rival, market.gold, 100);
+    let auction = pda::auction(&market.location).0;
+
+    assert_error(market.game.send(create_location_auction(&market, 0, 100)), ValidationError::InvalidInput);
+    assert_error(market.game.send(create_location_auction(&market, 10, 0)), ValidationError::InvalidInput);
+    market.game.ok(create_location_auction(&market, 10, 100));
+
+    let account: Auction = market.game.account(auction);
+    assert_eq!((account.seller, account.item, account.end_at), (seller, market.location, START + 100));
+    assert_eq!(market.game.account::<Location>(market.location).owner, auction);
+
+    let none = Pubkey::default();
+    assert_error(market.game.send(place_bid(auction, buyer, buyer_gold, 9, none)), ValidationError::BidTooLow);
+    assert_error(market.game.send(place_bid(auction, seller, seller_gold, 10, none)), ValidationError::SelfTradeNotAllowed);
+    market.game.ok(place_bid(auction, buyer, buyer_gold, 10, none));
+    assert_eq!(market.game.storage_amount(buyer_gold), 90);
+
+    // Raising your own bid only locks the difference.
+    market.game.ok(place_bid(auction, buyer, buyer_gold, 12, buyer));
+    assert_eq!(market.game.storage_amount(buyer_gold), 88);
+
+    // The outbid gold waits in the buyer's refund escrow.
+    assert_error(market.game.send(place_bid(auction, rival, rival_gold, 12, buyer)), ValidationError::BidTooLow);
+    assert_anchor_error(market.game.send(place_bid(auction, rival, rival_gold, 15, none)), ErrorCode::ConstraintSeeds);
+    market.game.ok(place_bid(auction, rival, rival_gold, 15, buyer));
+    assert_eq!(market.game.storage_amount(rival_gold), 85);
+    assert_eq!(market.game.storage_amount(pda::auction_refund(&auction, &buyer).0), 12);
+    assert_eq!(market.game.storage_amount(pda::auction_bid(&auction).0), 15);
+
+    assert_error(market.game.send(settle_location_auction(&market, seller_gold, rival)), ValidationError::AuctionNotEnded);
+
+    market.game.advance(100);
+    assert_error(market.game.send(place_bid(auction, buyer, buyer_gold, 20, rival)), ValidationError::AuctionEnded);
+    assert_error(market.game.send(settle_location_auction(&market, seller_gold, buyer)), ValidationError::OwnerRequired);
+
+    market.game.ok(settle_location_auction(&market, seller_gold, rival));
+    assert_eq!(market.game.account::<Location>(market.location).owner, rival);
+    assert_eq!(market.game.storage_amount(seller_gold), 15);
+    assert!(!market.game.exists(auction));
+
+    // Refunds outlive the auction.
+    assert_anchor_error(market.game.send(ix::claim_bid_refund(rival, auction, rival_gold)), ErrorCode::AccountNotInitialized);
+    market.game.ok(ix::claim_bid_refund(buyer, auction, buyer_gold));
+    assert_eq!(market.game.storage_amount(buyer_gold), 100);
+    assert!(!market.game.exists(pda::auction_refund(&auction, &buyer).0));
+}
+
+#[test]
+fn sold_location_is_used_by_its_new_owner() {
+    let mut market = market();
+    let (seller, buyer) = (market.seller, market.buyer);
+    let seller_gold = market.storage(seller, market.gold, 0);
+    let buyer_gold = market.storage(buyer, market.gold, 100);
+    let auction = pda::auction(&market.location).0;
+
+    market.game.ok(create_location_auction(&market, 10, 100));
+    market.game.ok(place_bid(auction, buyer, buyer_gold, 10, Pubkey::default()));
+    market.game.advance(100);
+    market.game.ok(settle_location_auction(&market, seller_gold, buyer));
+
+    // Still at the address derived from the seller, but only the buyer builds there.
+    let init_storage = |owner, at| ix::init_storage(owner, Pubkey::new_unique(), market.location, at, market.ore, 10, (MobilityType::Fixed, 0));
+    assert_error(market.game.send(init_storage(seller, (0, 0))), ValidationError::OwnerRequired);
+    market.game.ok(init_storage(buyer, (0, 0)));
+
+    assert_error(market.game.send(init_storage(buyer, (1, 0))), ValidationError::DifferentLocations);
+}
+
+#[test]
+fn processor_auction() {
+    let mut market = market();
+    let (seller, buyer) = (market.seller, market.buyer);
+    let seller_gold = market.storage(seller, market.gold, 0);
+    let buyer_gold = market.storage(buyer, market.gold, 100);
+    let processor = market.game.processor(seller, NewProcessor::producer(market.ore, 1, 1, market.location));
+    let seller_ore = market.storage(seller, market.ore, 0);
+    market.game.link(seller, processor, &[seller_ore]);
+    let auction = pda::auction(&processor).0;
+
+    assert_error(market.game.send(ix::create_processor_auction(buyer, processor, market.gold, 10, 100)), ValidationError::OwnerRequired);
+
+    // What the seller's processor made is theirs to claim first.
+    market.game.advance(10);
+    assert_error(market.game.send(ix::create_processor_auction(seller, processor, market.gold, 10, 100)), ValidationError::ProductionNotClaimed);
+    market.game.ok(ix::produce_without_input(processor, market.ore, seller_ore, seller_gold));
+    assert_eq!(market.game.storage_amount(seller_ore), 10);
+
+    market.game.ok(ix::create_processor_auction(seller, processor, market.gold, 10, 100));
+    let account: Processor = market.game.account(processor);
+    assert_eq!((account.owner, account.linked_storages), (auction, vec!()));
+
+    market.game.ok(place_bid(auction, buyer, buyer_gold, 10, Pubkey::default()));
+    market.game.advance(100);
+
+    market.game.ok(ix::settle_processor_auction(seller, seller, processor, market.location, seller_gold));
+
+    // Nothing was made while it was up for auction.
+    let account: Processor = market.game.account(processor);
+    assert_eq!((account.owner, account.paused_at, account.claimed_at), (buyer, None, START + 110));
+    let buyer_ore = market.storage(buyer, market.ore, 0);
+    market.game.link(buyer, processor, &[buyer_ore]);
+    assert_eq!(market.game.account::<Processor>(processor).linked_storages, vec!(buyer_ore));
+    let location: Location = market.game.account(market.location);
+    assert!(location.occupied_by.iter().any(|occupant| occupant.item == processor && occupant.player == buyer));
+    assert_eq!(market.game.storage_amount(seller_gold), 10);
+}
+
+struct GoodsAuction {
+    auction: Pubkey,
+    goods_escrow: Pubkey,
+    seller_ore: Pubkey,
+    seller_gold: Pubkey,
+}
+
+fn goods_auction(market: &mut Market) -> GoodsAuction {
+    let seller = market.seller;
+    let seller_ore = market.storage(seller, market.ore, 30);
+    let seller_gold = market.storage(seller, market.gold, 0);
+    let auction = pda::auction(&seller_ore).0;
+    GoodsAuction { auction, goods_escrow: pda::auction_goods(&auction).0, seller_ore, seller_gold }
+}
+
+#[test]
+fn goods_auction_with_a_bid() {
+    let mut market = market();
+    let goods = goods_auction(&mut market);
+    let (seller, buyer) = (market.seller, market.buyer);
+    let buyer_gold = market.storage(buyer, market.gold, 100);
+    let buyer_ore = market.storage(buyer, market.ore, 0);
+
+    let create = |amount| ix::create_goods_auction(seller, goods.seller_ore, amount, market.gold, 10, 100);
+    assert_error(market.game.send(create(0)), ValidationError::InvalidInput);
+    market.game.ok(create(10));
+    assert_eq!(market.game.storage_amount(goods.seller_ore), 20);
+    assert_eq!(market.game.storage_amount(goods.goods_escrow), 10);
+
+    assert_error(market.game.send(place_bid(goods.auction, buyer, buyer_ore, 10, Pubkey::default())), ValidationError::ResourceNotMatching);
+    market.game.ok(place_bid(goods.auction, buyer, buyer_gold, 25, Pubkey::default()));
+    market.game.advance(100);
+
+    // The goods only go to the winner.
+    let to_seller = ix::settle_goods_auction(buyer, seller, goods.seller_ore, goods.seller_gold, goods.seller_ore);
+    assert_error(market.game.send(to_seller), ValidationError::OwnerRequired);
+
+    market.game.ok(ix::settle_goods_auction(buyer, seller, goods.seller_ore, goods.seller_gold, buyer_ore));
+    assert_eq!(market.game.storage_amount(buyer_ore), 10);
+    assert_eq!(market.game.storage_amount(goods.seller_gold), 25);
+    assert!(!market.game.exists(goods.goods_escrow));
+}
+
+// Extreme values -------------------------
+
+#[test]
+fn offer_extreme_values() {
+    let mut market = market();
+    let (seller, buyer) = (market.seller, market.buyer);
+    let seller_ore = market.storage(seller, market.ore, 30);
+    let seller_gold = market.storage_with_capacity(seller, market.gold, i64::MAX, i64::MAX);
+    let buyer_gold = market.storage_with_capacity(buyer, market.gold, i64::MAX, i64::MAX);
+    let buyer_ore = market.storage(buyer, market.ore, 0);
+
+    assert_error(market.game.send(ix::create_offer(seller, Pubkey::new_unique(), seller_ore, i64::MAX, market.gold, 20)), ValidationError::StorageAmountTooLow);
+    assert_error(market.game.send(ix::create_offer(seller, Pubkey::new_unique(), seller_ore, i64::MIN, market.gold, 20)), ValidationError::InvalidInput);
+    assert_error(market.game.send(ix::create_offer(seller, Pubkey::new_unique(), seller_ore, 10, market.gold, i64::MIN)), ValidationError::InvalidInput);
+
+    // The seller's gold is already as full as it gets.
+    let offer = Pubkey::new_unique();
+    market.game.ok(ix::create_offer(seller, offer, seller_ore, 10, market.gold, i64::MAX));
+    assert_error(market.game.send(ix::accept_offer(buyer, offer, seller, seller_gold, (buyer_gold, buyer_ore))), ValidationError::MathOverflow);
+}
+
+#[test]
+fn order_book_extreme_values() {
+    let mut market = market();
+    let book = init_order_book(&mut market);
+    let (seller, buyer) = (market.seller, market.buyer);
+    let seller_storages = (market.storage(seller, market.ore, 10), market.storage_with_capacity(seller, market.gold, 0, i64::MAX));
+    let buyer_storages = (market.storage(buyer, market.ore, 0), market.storage_with_capacity(buyer, market.gold, i64::MAX, i64::MAX));
+
+    assert_error(market.game.send(place_order(&book, buyer, buyer_storages, (OrderSide::Bid, i64::MAX, 2))), ValidationError::MathOverflow);
+    assert_error(market.game.send(place_order(&book, buyer, buyer_storages, (OrderSide::Bid, i64::MIN, 2))), ValidationError::InvalidInput);
+    assert_error(market.game.send(place_order(&book, seller, seller_storages, (OrderSide::Ask, 1, i64::MAX))), ValidationError::StorageAmountTooLow);
+
+    market.game.ok(place_order(&book, seller, seller_storages, (OrderSide::Ask, i64::MAX, 2)));
+    assert_error(market.game.send(place_order(&book, buyer, buyer_storages, (OrderSide::Bid, i64::MAX, 2))), ValidationError::MathOverflow);
+    market.game.ok(place_order(&book, buyer, buyer_storages, (OrderSide::Bid, i64::MAX, 1)));
+    assert_eq!(market.game.storage_amount(buyer_storages.1), 0);
+}
+
+#[test]
+fn pool_extreme_values() {
+    let mut market = market();
+    create_sorted_pool(&mut market);
+    let (seller, buyer) = (market.seller, market.buyer);
+    let mut full_storages = |owner| {
+        let ore = market.storage_with_capacity(owner, market.ore, i64::MAX, i64::MAX);
+        let gold = market.storage_with_capacity(owner, market.gold, i64::MAX, i64::MAX);
+        (ore, gold)
+    };
+    let (seller_ore, seller_gold) = full_storages(seller);
+    let (buyer_ore, buyer_gold) = full_storages(buyer);
+    let seller_storages = sorted_storages(&market, seller_ore, seller_gold);
+    let buyer_storages = sorted_storages(&market, buyer_ore, buyer_gold);
+
+    assert_error(market.game.send(add_liquidity(&market, seller, seller_storages, (i64::MIN, 10), 0)), ValidationError::InvalidInput);
+    market.game.ok(add_liquidity(&market, seller, seller_storages, (i64::MAX - 10, i64::MAX - 10), 0));
+    assert_error(market.game.send(add_liquidity(&market, buyer, buyer_storages, (i64::MAX, i64::MAX), 0)), ValidationError::MathOverflow);
+    assert_error(market.game.send(swap(&market, buyer, buyer_ore, buyer_gold, i64::MAX, 0)), ValidationError::MathOverflow);
+    assert_error(market.game.send(swap(&market, buyer, buyer_ore, buyer_gold, i64::MIN, 0)), ValidationError::InvalidInput);
+    assert_error(market.game.send(remove_liquidity(&market, seller, seller_storages, i64::MAX)), ValidationError::InsufficientShares);
+}
+
+#[test]
+fn auction_extreme_values() {
+    let mut market = market();
+    let buyer = market.buyer;
+    let buyer_gold = market.storage_with_capacity(buyer, market.gold, i64::MAX, i64::MAX);
+    let auction = pda::auction(&market.location).0;
+
+    assert_error(market.game.send(create_location_auction(&market, 10, i64::MAX)), ValidationError::MathOverflow);
+    assert_error(market.game.send(create_location_auction(&market, i64::MIN, 100)), ValidationError::InvalidInput);
+    assert_error(market.game.send(create_location_auction(&market, 10, i64::MIN)), ValidationError::InvalidInput);
+
+    market.game.ok(create_location_auction(&market, i64::MAX, 100));
+    assert_error(market.game.send(place_bid(auction, buyer, buyer_gold, i64::MIN, Pubkey::default())), ValidationError::BidTooLow);
+    market.game.ok(place_bid(auction, buyer, buyer_gold, i64::MAX, Pubkey::default()));
+    assert_eq!(market.game.storage_amount(pda::auction_bid(&auction).0), i64::MAX);
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::{Pubkey, Rent};
+
+use got_a_min::errors::ValidationError;
+use got_a_min::state::{Game, Location, LocationType, OrderBook, OrderSide, Processor, Storage};
+use got_a_min_sdk::instructions as ix;
+use got_a_min_sdk::pda;
+
+use common::*;
+
+#[test]
+fn migrate_location() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    game.ok(ix::init_location(player, 0, 0, 5, LocationType::Space));
+    let location = pda::map_location(&player, 0, 0).0;
+    let current = game.bank.account(&location).unwrap().clone();
+
+    game.unversion(location);
+    assert_anchor_error(game.send(ix::init_unit(player, "ship", 0, 0, player)), ErrorCode::AccountDidNotDeserialize);
+
+    game.ok(ix::migrate_location(player, location));
+
+    let migrated = game.bank.account(&location).unwrap();
+    assert_eq!(migrated.data, current.data);
+    assert!(migrated.lamports >= Rent::default().minimum_balance(migrated.data.len()));
+    assert_eq!(game.account::<Location>(location).version, Location::VERSION);
+    game.ok(ix::init_unit(player, "ship", 0, 0, player));
+
+    assert_error(game.send(ix::migrate_location(player, location)), ValidationError::AccountUpToDate);
+}
+
+#[test]
+fn migrate_storage() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    game.location(player, 0, 0, LOCATION_ROOM as i64);
+    let ore = game.resource(player, "ore", &[]);
+    let from = game.funded_storage(player, ore, 100, 30, (0, 0));
+    let to = game.storage(player, ore, 100, (0, 0));
+    let current = game.bank.account(&from).unwrap().clone();
+
+    game.unversion(from);
+    assert_anchor_error(game.send(ix::move_between_storage(player, from, to, 10)), ErrorCode::AccountDidNotDeserialize);
+
+    // Anyone can pay for a migration, it only moves the fields.
+    let payer = game.create_player();
+    game.ok(ix::migrate_storage(payer, from));
+
+    assert_eq!(game.bank.account(&from).unwrap().data, current.data);
+    game.ok(ix::move_between_storage(player, from, to, 10));
+    assert_eq!((game.storage_amount(from), game.storage_amount(to)), (20, 10));
+}
+
+#[test]
+fn migrate_processor_and_unit() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    let location = game.location(player, 0, 0, LOCATION_ROOM as i64);
+    game.location(player, 1, 0, LOCATION_ROOM as i64);
+    let ore = game.resource(player, "ore", &[]);
+    let storage = game.storage(player, ore, 1_000, (0, 0));
+    let processor = game.processor(player, NewProcessor::producer(ore, 1, 1, location));
+    game.ok(ix::init_unit(player, "ship", 0, 0, player));
+    let unit = pda::unit(&player, "ship").0;
+    let current = (game.bank.account(&processor).unwrap().clone(), game.bank.account(&unit).unwrap().clone());
+
+    game.unversion(processor);
+    game.unversion(unit);
+    assert!(game.send(ix::link_storage(player, processor, ore, storage)).is_err());
+    assert!(game.send(ix::move_unit_start(player, "ship", (0, 0), (1, 0))).is_err());
+
+    game.ok(ix::migrate_processor(player, processor));
+    game.ok(ix::migrate_unit(player, unit));
+
+    assert_eq!(game.bank.account(&processor).unwrap().data, current.0.data);
+    assert_eq!(game.bank.account(&unit).unwrap().data, current.1.data);
+    game.ok(ix::link_storage(player, processor, ore, storage));
+    game.ok(ix::move_unit_start(player, "ship", (0, 0), (1, 0)));
+}
+
+#[test]
+fn old_layouts_are_told_apart_by_length() {
+    let mut game = TestGame::start();
+    // The owner comes first, so before the migration its first byte sits where the version goes.
+    let player = Pubkey::new_from_array([Processor::VERSION; 32]);
+    game.bank.airdrop(player, FUNDS);
+    let location = game.location(player, 0, 0, LOCATION_ROOM as i64);
+    let ore = game.resource(player, "ore", &[]);
+    let processor = game.processor(player, NewProcessor::producer(ore, 1, 1, location));
+    let current = game.bank.account(&processor).unwrap().clone();
+
+    game.unversion(processor);
+    assert_eq!(game.bank.account(&processor).unwrap().data[8], Processor::VERSION);
+    game.ok(ix::migrate_processor(player, processor));
+
+    assert_eq!(game.bank.account(&processor).unwrap().data, current.data);
+    assert_eq!(game.account::<Processor>(processor).owner, player);
+}
+
+#[test]
+fn migrate_game_and_resource() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    let ore = game.resource(player, "ore", &[]);
+    let current = (game.bank.account(&game.game()).unwrap().clone(), game.bank.account(&ore).unwrap().clone());
+
+    game.unversion(game.game());
+    game.unversion(ore);
+    assert_anchor_error(game.send(ix::update_game_fees(game.admin, 50)), ErrorCode::AccountDidNotDeserialize);
+
+    game.ok(ix::migrate_game(player, game.game()));
+    game.ok(ix::migrate_resource(player, ore));
+
+    assert_eq!(game.bank.account(&game.game()).unwrap().data, current.0.data);
+    assert_eq!(game.bank.account(&ore).unwrap().data, current.1.data);
+    game.ok(ix::update_game_fees(game.admin, 50));
+    assert_eq!(game.account::<Game>(game.game()).swap_fee_bps, 50);
+    assert_error(game.send(ix::migrate_resource(player, ore)), ValidationError::AccountUpToDate);
+}
+
+#[test]
+fn migrate_order_book_with_orders() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    let location = game.location(player, 0, 0, LOCATION_ROOM as i64);
+    let ore = game.resource(player, "ore", &[]);
+    let gold = game.resource(player, "gold", &[]);
+    let storages = (game.funded_storage(player, ore, 100, 10, (0, 0)), game.storage(player, gold, 100, (0, 0)));
+    game.ok(ix::init_order_book(player, location, ore, gold));
+    let book = ix::OrderBookKeys::new(&location, &ore, &gold);
+    game.ok(ix::place_order(player, &book, storages, OrderSide::Ask, 3, 5));
+    let current = game.bank.account(&book.address).unwrap().clone();
+    assert_eq!(current.data.len(), OrderBook::space(1));
+
+    game.unversion(book.address);
+    game.ok(ix::migrate_order_book(player, book.address));
+
+    assert_eq!(game.bank.account(&book.address).unwrap().data, current.data);
+    game.ok(ix::cancel_order(player, &book, storages, 0));
+    assert_eq!(game.storage_amount(storages.0), 10);
+}
+
+#[test]
+fn stale_versions_are_refused() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    let location = game.location(player, 0, 0, LOCATION_ROOM as i64);
+    let ore = game.resource(player, "ore", &[]);
+    let from = game.funded_storage(player, ore, 100, 30, (0, 0));
+    let to = game.storage(player, ore, 100, (0, 0));
+
+    let mut account: Location = game.account(location);
+    account.version = 0;
+    game.put(location, &account, location_space(LOCATION_ROOM));
+    let mut account: Storage = game.account(to);
+    account.version = 0;
+    game.put(to, &account, Storage::LEN);
+    let mut account: Game = game.account(game.game());
+    account.version = 0;
+    game.put(game.game(), &account, Game::LEN);
+
+    assert_error(game.send(ix::init_unit(player, "ship", 0, 0, player)), ValidationError::AccountOutdated);
+    assert_error(game.send(ix::move_between_storage(player, from, to, 10)), ValidationError::AccountOutdated);
+    assert_error(game.send(ix::update_game_fees(game.admin, 50)), ValidationError::AccountOutdated);
+}
+
+#[test]
+fn migrate_checks_the_account() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    game.location(player, 0, 0, LOCATION_ROOM as i64);
+    let ore = game.resource(player, "ore", &[]);
+    let storage = game.storage(player, ore, 100, (0, 0));
+    game.unversion(storage);
+
+    assert_anchor_error(game.send(ix::migrate_location(player, storage)), ErrorCode::AccountDiscriminatorMismatch);
+    assert_anchor_error(game.send(ix::migrate_storage(player, player)), ErrorCode::ConstraintOwner);
+    assert_eq!(game.bank.account(&storage).unwrap().data.len(), Storage::LEN - 1);
+
+    // Neither layout is this long.
+    game.resize(storage, Storage::LEN + 1);
+    assert_anchor_error(game.send(ix::migrate_storage(player, storage)), ErrorCode::AccountDidNotDeserialize);
+}
@@ -0,0 +1,652 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::Pubkey;
+
+use got_a_min::errors::ValidationError;
+use got_a_min::state::{FuelCostType, Game, Location, MobilityType, Processor, ProductionQueue, Storage};
+use got_a_min_sdk::instructions as ix;
+use got_a_min_sdk::pda;
+
+use common::*;
+
+// A player with a producer of ore at (0, 0), linked to an output storage. The fuel storage is
+// only there to fill the account slot, the producer doesn't burn anything.
+struct Factory {
+    game: TestGame,
+    player: Pubkey,
+    location: Pubkey,
+    ore: Pubkey,
+    processor: Pubkey,
+    output: Pubkey,
+    fuel: Pubkey,
+}
+
+fn factory(output_rate: i64, processing_duration: i64) -> Factory {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    let location = game.location(player, 0, 0, LOCATION_ROOM as i64);
+    let ore = game.resource(player, "ore", &[]);
+    let output = game.storage(player, ore, 1_000, (0, 0));
+    let fuel = game.storage(player, ore, 1_000, (0, 0));
+    let processor = game.processor(player, NewProcessor::producer(ore, output_rate, processing_duration, location));
+    game.link(player, processor, &[output]);
+    Factory { game, player, location, ore, processor, output, fuel }
+}
+
+// Init -------------------------------------
+
+#[test]
+fn init_processor() {
+    let Factory { game, player, location, ore, processor, output, .. } = factory(2, 10);
+
+    let account: Processor = game.account(processor);
+    assert_eq!(account.owner, player);
+    assert_eq!(account.location_id, location);
+    assert_eq!(account.output_resource_id, ore);
+    assert_eq!((account.output_rate, account.processing_duration), (2, 10));
+    assert_eq!((account.claimed_at, account.level), (START, 1));
+    assert_eq!(account.linked_storages, vec!(output));
+
+    let location: Location = game.account(location);
+    assert!(location.occupied_by.iter().any(|occupant| occupant.item == processor));
+}
+
+#[test]
+fn init_processor_requires_the_game() {
+    let Factory { mut game, player, location, ore, .. } = factory(2, 10);
+
+    // A game of the player's own making, with rules of their own.
+    let mut own_rules: Game = game.account(game.game());
+    own_rules.authority = player;
+    let own_game = Pubkey::new_unique();
+    game.put(own_game, &own_rules, Game::LEN);
+
+    let producer = NewProcessor::producer(ore, 1, 10, location);
+    assert_anchor_error(game.send(producer.init(own_game, Pubkey::new_unique(), player)), ErrorCode::ConstraintSeeds);
+}
+
+#[test]
+fn init_processor_rejects_no_output() {
+    let Factory { mut game, player, location, ore, .. } = factory(2, 10);
+
+    let no_rate = NewProcessor::producer(ore, 0, 10, location);
+    assert_error(game.send(no_rate.init(game.game(), Pubkey::new_unique(), player)), ValidationError::InvalidInput);
+
+    let no_duration = NewProcessor::producer(ore, 1, 0, location);
+    assert_error(game.send(no_duration.init(game.game(), Pubkey::new_unique(), player)), ValidationError::InvalidInput);
+}
+
+#[test]
+fn init_processor_rejects_distance_fuel_for_producers() {
+    let Factory { mut game, player, location, ore, fuel, .. } = factory(2, 10);
+    let fuel_resource = game.account::<Storage>(fuel).resource_id;
+
+    let mut producer = NewProcessor::producer(ore, 1, 10, location);
+    producer.fuel_resource_id = fuel_resource;
+    producer.fuel_cost_type = FuelCostType::Distance;
+    assert_error(game.send(producer.init(game.game(), Pubkey::new_unique(), player)), ValidationError::InvalidFuelCostType);
+
+    game.processor(player, NewProcessor::sender(ore, fuel_resource, location));
+}
+
+// Linked storages --------------------------
+
+#[test]
+fn link_and_unlink_storage() {
+    let Factory { mut game, player, ore, processor, output, fuel, .. } = factory(2, 10);
+
+    game.ok(ix::link_storage(player, processor, ore, fuel));
+    assert_eq!(game.account::<Processor>(processor).linked_storages, vec!(output, fuel));
+    assert_error(game.send(ix::link_storage(player, processor, ore, fuel)), ValidationError::InvalidInput);
+
+    game.ok(ix::unlink_storage(player, processor, fuel));
+    assert_eq!(game.account::<Processor>(processor).linked_storages, vec!(output));
+    assert_error(game.send(ix::unlink_storage(player, processor, fuel)), ValidationError::StorageNotLinked);
+
+    let other = game.create_player();
+    let location = pda::map_location(&player, 0, 0).0;
+    let not_mine = game.storage_at(other, ore, 10, location);
+    assert_error(game.send(ix::link_storage(player, processor, ore, not_mine)), ValidationError::OwnerRequired);
+
+    let coal = game.resource(player, "coal", &[]);
+    let coal_storage = game.storage(player, coal, 10, (0, 0));
+    assert_error(game.send(ix::link_storage(player, processor, ore, coal_storage)), ValidationError::ResourceNotMatching);
+    assert_error(game.send(ix::link_storage(player, processor, coal, coal_storage)), ValidationError::ResourceNotMatching);
+}
+
+#[test]
+fn linked_storages_are_limited() {
+    let Factory { mut game, player, ore, processor, .. } = factory(2, 10);
+
+    for _ in 0..7 {
+        let storage = game.storage(player, ore, 10, (0, 0));
+        game.ok(ix::link_storage(player, processor, ore, storage));
+    }
+    let storage = game.storage(player, ore, 10, (0, 0));
+    assert_error(game.send(ix::link_storage(player, processor, ore, storage)), ValidationError::LinkedStoragesFull);
+}
+
+// Production -------------------------------
+
+#[test]
+fn produce_without_input() {
+    let Factory { mut game, processor, ore, output, fuel, .. } = factory(2, 10);
+
+    game.advance(100);
+    game.ok(ix::produce_without_input(processor, ore, output, fuel));
+    assert_eq!(game.storage_amount(output), 20);
+
+    // Claimed up to now, so nothing more until time passes.
+    game.ok(ix::produce_without_input(processor, ore, output, fuel));
+    assert_eq!(game.storage_amount(output), 20);
+
+    game.advance(15);
+    game.ok(ix::produce_without_input(processor, ore, output, fuel));
+    assert_eq!(game.storage_amount(output), 23);
+}
+
+#[test]
+fn produce_without_input_errors() {
+    let Factory { mut game, player, processor, ore, output, fuel, .. } = factory(2, 10);
+    let steel = game.resource(player, "steel", &[(ore, 1)]);
+
+    assert_error(game.send(ix::produce_without_input(processor, ore, fuel, fuel)), ValidationError::StorageNotLinked);
+    assert_error(game.send(ix::produce_without_input(processor, steel, output, fuel)), ValidationError::ResourceNotMatching);
+
+    let steel_output = game.storage(player, steel, 1_000, (0, 0));
+    assert_error(game.send(ix::link_storage(player, processor, ore, steel_output)), ValidationError::ResourceNotMatching);
+
+    let location = game.account::<Processor>(processor).location_id;
+    let steel_producer = game.processor(player, NewProcessor::producer(steel, 1, 10, location));
+    game.link(player, steel_producer, &[steel_output]);
+    assert_error(game.send(ix::produce_without_input(steel_producer, steel, steel_output, fuel)), ValidationError::ResourceInputMax);
+}
+
+#[test]
+fn produce_burns_fuel() {
+    let Factory { mut game, player, location, ore, .. } = factory(2, 10);
+    let coal = game.resource(player, "coal", &[]);
+    let output = game.storage(player, ore, 1_000, (0, 0));
+    let fuel = game.funded_storage(player, coal, 1_000, 5, (0, 0));
+    let wrong_fuel = game.funded_storage(player, ore, 1_000, 5, (0, 0));
+
+    let mut new_processor = NewProcessor::producer(ore, 2, 10, location);
+    new_processor.fuel_resource_id = coal;
+    new_processor.fuel_cost_type = FuelCostType::Output;
+    let processor = game.processor(player, new_processor);
+    game.link(player, processor, &[output, fuel, wrong_fuel]);
+
+    assert_error(game.send(ix::produce_without_input(processor, ore, output, wrong_fuel)), ValidationError::FuelNotSupplied);
+
+    // One fuel per unit, so the 20 units made by now stop at 5.
+    game.advance(100);
+    game.ok(ix::produce_without_input(processor, ore, output, fuel));
+    assert_eq!(game.storage_amount(output), 5);
+    assert_eq!(game.storage_amount(fuel), 0);
+}
+
+#[test]
+fn produce_from_one_input() {
+    let Factory { mut game, player, location, ore, .. } = factory(2, 10);
+    let bar = game.resource(player, "bar", &[(ore, 2)]);
+    let output = game.storage(player, bar, 1_000, (0, 0));
+    let input = game.funded_storage(player, ore, 1_000, 10, (0, 0));
+    let fuel = game.storage(player, ore, 1_000, (0, 0));
+    let processor = game.processor(player, NewProcessor::producer(bar, 1, 10, location));
+    game.link(player, processor, &[output, input]);
+
+    // 10 bars are done, the ore only covers 5 of them.
+    game.advance(100);
+    game.ok(ix::produce_with_one_input(processor, bar, output, input, fuel));
+    assert_eq!(game.storage_amount(output), 5);
+    assert_eq!(game.storage_amount(input), 0);
+}
+
+#[test]
+fn produce_with_one_input_errors() {
+    let Factory { mut game, player, location, ore, processor: ore_producer, output: ore_output, fuel, .. } = factory(2, 10);
+    let coal = game.resource(player, "coal", &[]);
+    let bar = game.resource(player, "bar", &[(ore, 2)]);
+    let output = game.storage(player, bar, 1_000, (0, 0));
+    let input = game.funded_storage(player, ore, 1_000, 10, (0, 0));
+    let coal_input = game.funded_storage(player, coal, 1_000, 10, (0, 0));
+    let processor = game.processor(player, NewProcessor { fuel_resource_id: coal, ..NewProcessor::producer(bar, 1, 10, location) });
+    game.link(player, processor, &[output, input, coal_input]);
+    let bar_input = game.storage(player, bar, 1_000, (0, 0));
+    let sender = game.processor(player, NewProcessor::sender(bar, coal, location));
+    game.link(player, sender, &[output, bar_input, coal_input]);
+    game.link(player, ore_producer, &[input]);
+
+    assert_error(game.send(ix::produce_with_one_input(sender, bar, output, bar_input, coal_input)), ValidationError::InvalidProcessorType);
+    assert_error(game.send(ix::produce_with_one_input(ore_producer, bar, ore_output, input, fuel)), ValidationError::InputStorageNotSupplied);
+    assert_error(game.send(ix::produce_with_one_input(processor, bar, output, coal_input, fuel)), ValidationError::InputStorageNotSupplied);
+    assert_error(game.send(ix::produce_with_one_input(processor, bar, output, fuel, fuel)), ValidationError::StorageNotLinked);
+    // Fuel written back over the input would undo its removal.
+    assert_error(game.send(ix::produce_with_one_input(processor, bar, output, input, input)), ValidationError::SameStorage);
+}
+
+// A steel producer at the factory, steel takes 2 ore and 1 coal.
+struct Smelter {
+    steel: Pubkey,
+    processor: Pubkey,
+    output: Pubkey,
+    ore_input: Pubkey,
+    coal_input: Pubkey,
+}
+
+fn smelter(factory: &mut Factory, ore: i64, coal: i64) -> Smelter {
+    let Factory { game, player, location, .. } = factory;
+    let (player, location) = (*player, *location);
+    let coal_id = game.resource(player, "coal", &[]);
+    let steel = game.resource(player, "steel", &[(factory.ore, 2), (coal_id, 1)]);
+    let output = game.storage(player, steel, 1_000, (0, 0));
+    let ore_input = game.funded_storage(player, factory.ore, 1_000, ore, (0, 0));
+    let coal_input = game.funded_storage(player, coal_id, 1_000, coal, (0, 0));
+    let processor = game.processor(player, NewProcessor::producer(steel, 1, 10, location));
+    game.link(player, processor, &[output, ore_input, coal_input]);
+    Smelter { steel, processor, output, ore_input, coal_input }
+}
+
+#[test]
+fn produce_from_two_inputs() {
+    let mut factory = factory(2, 10);
+    let Smelter { steel, processor, output, ore_input, coal_input } = smelter(&mut factory, 10, 3);
+    let Factory { mut game, fuel, .. } = factory;
+
+    // 10 steel are done, the coal only covers 3 of them.
+    game.advance(100);
+    game.ok(ix::produce_with_two_inputs(processor, steel, output, (ore_input, coal_input), fuel));
+    assert_eq!(game.storage_amount(output), 3);
+    assert_eq!(game.storage_amount(ore_input), 4);
+    assert_eq!(game.storage_amount(coal_input), 0);
+}
+
+#[test]
+fn produce_with_two_inputs_errors() {
+    let mut factory = factory(2, 10);
+    let Smelter { steel, processor, output, ore_input, coal_input } = smelter(&mut factory, 1, 3);
+    let Factory { mut game, fuel, .. } = factory;
+
+    // Each storage is written back on exit, so one account can't take two of the roles.
+    game.advance(100);
+    assert_error(game.send(ix::produce_with_two_inputs(processor, steel, output, (output, coal_input), fuel)), ValidationError::SameStorage);
+    assert_error(game.send(ix::produce_with_two_inputs(processor, steel, output, (ore_input, ore_input), fuel)), ValidationError::SameStorage);
+    assert_error(game.send(ix::produce_with_two_inputs(processor, steel, output, (ore_input, coal_input), coal_input)), ValidationError::SameStorage);
+    assert_error(game.send(ix::produce_with_two_inputs(processor, steel, output, (ore_input, coal_input), fuel)), ValidationError::InputStorageAmountTooLow);
+}
+
+// Upgrades ---------------------------------
+
+fn with_levels(factory: &mut Factory) -> Pubkey {
+    let Factory { game, player, .. } = factory;
+    let player = *player;
+    let gold = game.resource(player, "gold", &[]);
+    game.ok(ix::update_game_levels(game.admin, vec!(10_000, 20_000), gold, 5));
+    game.funded_storage(player, gold, 1_000, 10, (0, 0))
+}
+
+#[test]
+fn upgrade_processor() {
+    let mut factory = factory(2, 10);
+    let gold = with_levels(&mut factory);
+    let Factory { mut game, player, processor, output, .. } = factory;
+
+    game.ok(ix::upgrade_processor(player, game.game(), processor, gold));
+
+    // Twice the throughput at level 2, 40_000 / 100_000 reduced.
+    let account: Processor = game.account(processor);
+    assert_eq!(account.level, 2);
+    assert_eq!((account.output_rate, account.processing_duration), (2, 5));
+    assert_eq!(game.storage_amount(gold), 5);
+
+    assert_error(game.send(ix::upgrade_processor(player, game.game(), processor, gold)), ValidationError::MaxLevelReached);
+    assert_error(game.send(ix::upgrade_processor(player, game.game(), processor, output)), ValidationError::ResourceNotMatching);
+}
+
+#[test]
+fn upgrade_extreme_rates() {
+    let mut factory = factory(i64::MAX, 10);
+    let gold = with_levels(&mut factory);
+    let Factory { mut game, player, location, ore, processor, .. } = factory;
+    assert_error(game.send(ix::upgrade_processor(player, game.game(), processor, gold)), ValidationError::MathOverflow);
+
+    let slow = game.processor(player, NewProcessor::producer(ore, 1, i64::MAX, location));
+    assert_error(game.send(ix::upgrade_processor(player, game.game(), slow, gold)), ValidationError::MathOverflow);
+
+    let gold_id = game.account::<Storage>(gold).resource_id;
+    game.ok(ix::update_game_levels(game.admin, vec!(10_000, 20_000), gold_id, i64::MAX));
+    let fast = game.processor(player, NewProcessor::producer(ore, 1, 10, location));
+    assert_error(game.send(ix::upgrade_processor(player, game.game(), fast, gold)), ValidationError::StorageAmountTooLow);
+}
+
+// Pausing ----------------------------------
+
+#[test]
+fn pause_and_resume_processor() {
+    let Factory { mut game, player, processor, ore, output, fuel, .. } = factory(2, 10);
+
+    game.advance(10);
+    game.ok(ix::pause_processor(player, processor));
+    assert_eq!(game.account::<Processor>(processor).paused_at, Some(START + 10));
+    assert_error(game.send(ix::pause_processor(player, processor)), ValidationError::ProcessorPaused);
+
+    game.advance(100);
+    game.ok(ix::produce_without_input(processor, ore, output, fuel));
+    assert_eq!(game.storage_amount(output), 2);
+
+    game.ok(ix::resume_processor(player, processor));
+    assert_eq!(game.account::<Processor>(processor).paused_at, None);
+    assert_error(game.send(ix::resume_processor(player, processor)), ValidationError::ProcessorNotPaused);
+
+    game.advance(10);
+    game.ok(ix::produce_without_input(processor, ore, output, fuel));
+    assert_eq!(game.storage_amount(output), 4);
+}
+
+// Retooling --------------------------------
+
+#[test]
+fn retool_processor() {
+    let Factory { mut game, player, processor, ore, output, fuel, .. } = factory(2, 10);
+    let coal = game.resource(player, "coal", &[]);
+    game.ok(ix::update_game_retool_delay(game.admin, 30));
+
+    game.advance(100);
+    game.ok(ix::retool_processor(player, game.game(), processor, ore, (output, fuel), coal));
+
+    // What was made before switching is delivered, the new output starts after the delay.
+    assert_eq!(game.storage_amount(output), 20);
+    let account: Processor = game.account(processor);
+    assert_eq!(account.output_resource_id, coal);
+    assert_eq!(account.claimed_at, game.now + 30);
+
+    assert_error(game.send(ix::retool_processor(player, game.game(), processor, coal, (output, fuel), ore)), ValidationError::ResourceNotMatching);
+
+    // Production now goes to coal, the ore storage doesn't get any more.
+    let coal_output = game.storage(player, coal, 1_000, (0, 0));
+    game.link(player, processor, &[coal_output]);
+    game.advance(50);
+    assert_error(game.send(ix::produce_without_input(processor, ore, output, fuel)), ValidationError::ResourceNotMatching);
+    assert_error(game.send(ix::produce_without_input(processor, coal, output, fuel)), ValidationError::ResourceNotMatching);
+    game.ok(ix::produce_without_input(processor, coal, coal_output, fuel));
+    assert_eq!(game.storage_amount(coal_output), 4);
+    assert_eq!(game.storage_amount(output), 20);
+}
+
+#[test]
+fn retool_processor_errors() {
+    let Factory { mut game, player, location, processor, ore, output, fuel, .. } = factory(2, 10);
+    let coal = game.resource(player, "coal", &[]);
+    let sender = game.processor(player, NewProcessor::sender(ore, coal, location));
+
+    assert_error(game.send(ix::retool_processor(player, game.game(), processor, ore, (output, fuel), ore)), ValidationError::InvalidInput);
+    assert_error(game.send(ix::retool_processor(player, game.game(), sender, ore, (output, fuel), coal)), ValidationError::InvalidProcessorType);
+
+    let other = game.create_player();
+    let others_fuel = game.storage_at(other, ore, 1_000, location);
+    assert_error(game.send(ix::retool_processor(player, game.game(), processor, ore, (output, others_fuel), coal)), ValidationError::OwnerRequired);
+}
+
+// Repairs ----------------------------------
+
+#[test]
+fn repair_processor() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    let location = game.location(player, 0, 0, 5);
+    let ore = game.resource(player, "ore", &[]);
+    let metal = game.resource(player, "metal", &[]);
+    game.ok(ix::update_game_wear(game.admin, 100, metal, 2));
+    let output = game.storage(player, ore, 1_000, (0, 0));
+    let payment = game.funded_storage(player, metal, 1_000, 100, (0, 0));
+    let processor = game.processor(player, NewProcessor::producer(ore, 1, 1, location));
+    game.link(player, processor, &[output]);
+
+    assert_error(game.send(ix::repair_processor(player, game.game(), processor, payment, 5)), ValidationError::InvalidInput);
+
+    // Every unit made wears the processor down by one.
+    game.advance(10);
+    game.ok(ix::produce_without_input(processor, ore, output, payment));
+    assert_eq!(game.account::<Processor>(processor).durability, 90);
+
+    game.ok(ix::repair_processor(player, game.game(), processor, payment, 4));
+    assert_eq!(game.account::<Processor>(processor).durability, 94);
+    assert_eq!(game.storage_amount(payment), 92);
+
+    // Never beyond the maximum.
+    game.ok(ix::repair_processor(player, game.game(), processor, payment, 50));
+    assert_eq!(game.account::<Processor>(processor).durability, 100);
+    assert_eq!(game.storage_amount(payment), 80);
+
+    assert_error(game.send(ix::repair_processor(player, game.game(), processor, output, 1)), ValidationError::ResourceNotMatching);
+}
+
+// Moving -----------------------------------
+
+#[test]
+fn move_processor_to_another_location() {
+    let Factory { mut game, player, location, ore, output, fuel, .. } = factory(2, 10);
+    let destination = game.location(player, 0, 5, 5);
+    let mut new_processor = NewProcessor::producer(ore, 2, 10, location);
+    new_processor.mobility_type = MobilityType::Movable;
+    new_processor.movement_speed = 1;
+    let processor = game.processor(player, new_processor);
+    game.link(player, processor, &[output]);
+
+    game.ok(ix::move_processor(player, processor, location, destination));
+
+    let account: Processor = game.account(processor);
+    assert_eq!(account.location_id, destination);
+    assert_eq!(account.arrives_at, START + 5_000);
+    assert_eq!(account.claimed_at, START + 5_000);
+
+    assert_error(game.send(ix::produce_without_input(processor, ore, output, fuel)), ValidationError::NotAllowedWhileMoving);
+    assert_error(game.send(ix::move_processor(player, processor, destination, location)), ValidationError::NotAllowedWhileMoving);
+    assert_error(game.send(ix::move_processor(player, processor, location, destination)), ValidationError::DifferentLocations);
+}
+
+#[test]
+fn moving_a_paused_processor_holds_production_once() {
+    let Factory { mut game, player, location, ore, output, .. } = factory(2, 10);
+    let destination = game.location(player, 0, 5, 5);
+    let mut new_processor = NewProcessor::producer(ore, 2, 10, location);
+    new_processor.mobility_type = MobilityType::Movable;
+    new_processor.movement_speed = 1;
+    let processor = game.processor(player, new_processor);
+    game.link(player, processor, &[output]);
+
+    // What was made before the pause is kept, the trip overlaps the pause.
+    game.advance(100);
+    game.ok(ix::pause_processor(player, processor));
+    game.advance(1_000);
+    game.ok(ix::move_processor(player, processor, location, destination));
+    let account: Processor = game.account(processor);
+    assert_eq!(account.progress, 100 * account.unit_work().unwrap() * 2 / 10);
+    assert_eq!(account.claimed_at, START + 6_100);
+
+    game.advance(10_000);
+    game.ok(ix::resume_processor(player, processor));
+    assert_eq!(game.account::<Processor>(processor).claimed_at, START + 11_100);
+}
+
+#[test]
+fn fixed_processor_does_not_move() {
+    let Factory { mut game, player, location, processor, .. } = factory(2, 10);
+    let destination = game.location(player, 0, 5, 5);
+
+    assert_error(game.send(ix::move_processor(player, processor, location, destination)), ValidationError::StorageTypeNotMovable);
+}
+
+// Production queue -------------------------
+
+// A bar producer with a queue, bars take 2 ore each from [input]. Like the factory's, the fuel
+// storage only fills the account slot.
+struct Foundry {
+    bar: Pubkey,
+    processor: Pubkey,
+    output: Pubkey,
+    input: Pubkey,
+    fuel: Pubkey,
+}
+
+fn foundry(factory: &mut Factory, ore: i64) -> Foundry {
+    let Factory { game, player, location, .. } = factory;
+    let (player, location) = (*player, *location);
+    let bar = game.resource(player, "bar", &[(factory.ore, 2)]);
+    let output = game.storage(player, bar, 1_000, (0, 0));
+    let input = game.funded_storage(player, factory.ore, 1_000, ore, (0, 0));
+    let processor = game.processor(player, NewProcessor::producer(bar, 1, 10, location));
+    game.link(player, processor, &[output, input]);
+    game.ok(ix::init_production_queue(player, processor));
+    Foundry { bar, processor, output, input, fuel: factory.fuel }
+}
+
+#[test]
+fn init_production_queue() {
+    let Factory { mut game, player, location, ore, processor, .. } = factory(2, 10);
+
+    game.ok(ix::init_production_queue(player, processor));
+    let queue: ProductionQueue = game.account(pda::production_queue(&processor).0);
+    assert_eq!(queue.processor, processor);
+    assert!(queue.jobs.is_empty());
+
+    let coal = game.resource(player, "coal", &[]);
+    let sender = game.processor(player, NewProcessor::sender(ore, coal, location));
+    assert_error(game.send(ix::init_production_queue(player, sender)), ValidationError::InvalidProcessorType);
+}
+
+#[test]
+fn enqueue_and_claim_jobs() {
+    let mut factory = factory(2, 10);
+    let Foundry { bar, processor, output, input, fuel } = foundry(&mut factory, 10);
+    let Factory { mut game, player, .. } = factory;
+
+    game.ok(ix::enqueue_job(player, processor, bar, &[input], 3, 0));
+    assert_eq!(game.storage_amount(input), 4);
+    assert_eq!(game.account::<Processor>(processor).awaiting_units, 0);
+
+    game.advance(20);
+    game.ok(ix::claim_jobs(processor, output, fuel));
+    assert_eq!(game.storage_amount(output), 2);
+
+    game.advance(100);
+    game.ok(ix::claim_jobs(processor, output, fuel));
+    assert_eq!(game.storage_amount(output), 3);
+    assert!(game.account::<ProductionQueue>(pda::production_queue(&processor).0).jobs.is_empty());
+}
+
+#[test]
+fn regular_claims_wait_for_the_queue() {
+    let Factory { mut game, player, ore, processor, output, fuel, .. } = factory(2, 10);
+    game.ok(ix::init_production_queue(player, processor));
+
+    game.ok(ix::enqueue_job(player, processor, ore, &[], 3, 0));
+    game.advance(20);
+    assert_error(game.send(ix::produce_without_input(processor, ore, output, fuel)), ValidationError::ProductionQueueActive);
+    let coal = game.resource(player, "coal", &[]);
+    assert_error(game.send(ix::retool_processor(player, game.game(), processor, ore, (output, fuel), coal)), ValidationError::ProductionQueueActive);
+
+    game.ok(ix::claim_jobs(processor, output, fuel));
+    assert_eq!(game.storage_amount(output), 3);
+
+    // Only the unit made past the job is left, what the queue delivered isn't paid again.
+    game.ok(ix::produce_without_input(processor, ore, output, fuel));
+    assert_eq!(game.storage_amount(output), 4);
+}
+
+#[test]
+fn queued_jobs_burn_fuel() {
+    let Factory { mut game, player, location, ore, output, .. } = factory(2, 10);
+    let coal = game.resource(player, "coal", &[]);
+    let fuel = game.funded_storage(player, coal, 1_000, 5, (0, 0));
+    let unlinked_fuel = game.funded_storage(player, coal, 1_000, 5, (0, 0));
+
+    let mut new_processor = NewProcessor::producer(ore, 2, 10, location);
+    new_processor.fuel_resource_id = coal;
+    new_processor.fuel_cost_type = FuelCostType::Output;
+    let processor = game.processor(player, new_processor);
+    game.link(player, processor, &[output, fuel]);
+    game.ok(ix::init_production_queue(player, processor));
+
+    game.ok(ix::enqueue_job(player, processor, ore, &[], 10, 0));
+    game.advance(100);
+    assert_error(game.send(ix::claim_jobs(processor, output, unlinked_fuel)), ValidationError::StorageNotLinked);
+
+    // One fuel per unit, the rest of the job waits for more.
+    game.ok(ix::claim_jobs(processor, output, fuel));
+    assert_eq!(game.storage_amount(output), 5);
+    assert_eq!(game.storage_amount(fuel), 0);
+    assert_eq!(game.account::<ProductionQueue>(pda::production_queue(&processor).0).jobs[0].produced, 5);
+}
+
+#[test]
+fn enqueue_job_errors() {
+    let mut factory = factory(2, 10);
+    let Foundry { bar, processor, input, .. } = foundry(&mut factory, 100);
+    let Factory { mut game, player, ore, processor: ore_processor, .. } = factory;
+
+    assert_error(game.send(ix::enqueue_job(player, processor, bar, &[input], 0, 0)), ValidationError::InvalidInput);
+    assert_error(game.send(ix::enqueue_job(player, processor, bar, &[], 1, 0)), ValidationError::InputStorageNotSupplied);
+    assert_error(game.send(ix::enqueue_job(player, processor, bar, &[input], 51, 0)), ValidationError::InputStorageAmountTooLow);
+    assert_error(game.send(ix::enqueue_job(player, processor, ore, &[], 1, 0)), ValidationError::ResourceNotMatching);
+
+    let unlinked_input = game.funded_storage(player, ore, 1_000, 100, (0, 0));
+    assert_error(game.send(ix::enqueue_job(player, processor, bar, &[unlinked_input], 1, 0)), ValidationError::StorageNotLinked);
+
+    // Ore doesn't need anything, so the ore producer's queue fills up without inputs.
+    game.ok(ix::init_production_queue(player, ore_processor));
+    for _ in 0..8 {
+        game.ok(ix::enqueue_job(player, ore_processor, ore, &[], 1, 0));
+    }
+    assert_error(game.send(ix::enqueue_job(player, ore_processor, ore, &[], 1, 0)), ValidationError::ProductionQueueFull);
+}
+
+#[test]
+fn claim_jobs_errors() {
+    let mut factory = factory(2, 10);
+    let Foundry { bar, processor, output, input, fuel } = foundry(&mut factory, 10);
+    let Factory { mut game, player, .. } = factory;
+
+    game.ok(ix::enqueue_job(player, processor, bar, &[input], 1, 0));
+    game.advance(20);
+
+    let unlinked_output = game.storage(player, bar, 1_000, (0, 0));
+    assert_error(game.send(ix::claim_jobs(processor, unlinked_output, fuel)), ValidationError::StorageNotLinked);
+    assert_error(game.send(ix::claim_jobs(processor, output, output)), ValidationError::SameStorage);
+}
+
+#[test]
+fn enqueue_extreme_quantities() {
+    let mut factory = factory(2, 10);
+    let Foundry { bar, processor, input, .. } = foundry(&mut factory, 100);
+    let Factory { mut game, player, ore, processor: ore_processor, output, fuel, .. } = factory;
+
+    assert_error(game.send(ix::enqueue_job(player, processor, bar, &[input], i64::MIN, 0)), ValidationError::InvalidInput);
+    assert_error(game.send(ix::enqueue_job(player, processor, bar, &[input], i64::MAX, 0)), ValidationError::MathOverflow);
+
+    // Nothing to reserve for ore, the job just takes forever.
+    game.ok(ix::init_production_queue(player, ore_processor));
+    game.ok(ix::enqueue_job(player, ore_processor, ore, &[], i64::MAX, u8::MAX));
+    game.advance(100);
+    game.ok(ix::claim_jobs(ore_processor, output, fuel));
+    let queue: ProductionQueue = game.account(pda::production_queue(&ore_processor).0);
+    assert_eq!(queue.jobs[0].remaining().unwrap(), i64::MAX - 20);
+}
+
+#[test]
+fn cancel_queued_job() {
+    let mut factory = factory(2, 10);
+    let Foundry { bar, processor, input, .. } = foundry(&mut factory, 10);
+    let Factory { mut game, player, ore, .. } = factory;
+
+    game.ok(ix::enqueue_job(player, processor, bar, &[input], 3, 0));
+    game.ok(ix::cancel_job(player, processor, bar, &[input], 0));
+    assert_eq!(game.storage_amount(input), 10);
+
+    assert_error(game.send(ix::cancel_job(player, processor, bar, &[input], 0)), ValidationError::JobNotFound);
+
+    game.ok(ix::enqueue_job(player, processor, bar, &[input], 1, 0));
+    assert_error(game.send(ix::cancel_job(player, processor, ore, &[input], 1)), ValidationError::ResourceNotMatching);
+}
+
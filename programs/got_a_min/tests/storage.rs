@@ -0,0 +1,282 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+
+use got_a_min::errors::ValidationError;
+use got_a_min::state::{Location, LocationType, MobilityType, Storage, TransferAllowList};
+use got_a_min_sdk::instructions as ix;
+use got_a_min_sdk::pda;
+
+use common::*;
+
+// Init -------------------------------------
+
+#[test]
+fn init_storage() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    let ore = game.resource(player, "ore", &[]);
+    let location = game.location(player, 1, 2, 5);
+
+    let storage = game.storage(player, ore, 100, (1, 2));
+
+    let account: Storage = game.account(storage);
+    assert_eq!(account.owner, player);
+    assert_eq!(account.resource_id, ore);
+    assert_eq!(account.location_id, location);
+    assert_eq!((account.amount, account.capacity), (0, 100));
+    assert_eq!(account.mobility_type, MobilityType::Fixed);
+
+    let location: Location = game.account(location);
+    assert_eq!(location.occupied_space, 1);
+    assert_eq!(location.occupied_by[0].item, storage);
+}
+
+#[test]
+fn init_storage_rejects_full_location() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    let ore = game.resource(player, "ore", &[]);
+    game.location(player, 0, 0, 1);
+    game.storage(player, ore, 100, (0, 0));
+
+    let result = game.send(ix::init_storage(player, Pubkey::new_unique(), pda::map_location(&player, 0, 0).0, (0, 0), ore, 100, (MobilityType::Fixed, 0)));
+    assert_error(result, ValidationError::LocationFull);
+}
+
+#[test]
+fn simple_init_storage() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    let storage = Pubkey::new_unique();
+    let xy = [1, 1];
+
+    game.xy_location(player, xy, LocationType::Space);
+
+    game.ok(ix::simple_init_storage(player, storage, xy));
+
+    let account: Storage = game.account(storage);
+    assert_eq!((account.owner, account.capacity, account.movement_speed), (player, 10, 1));
+}
+
+// Move between -----------------------------
+
+#[test]
+fn move_between_storage() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    let ore = game.resource(player, "ore", &[]);
+    game.location(player, 0, 0, 5);
+    let from = game.funded_storage(player, ore, 100, 30, (0, 0));
+    let to = game.storage(player, ore, 100, (0, 0));
+
+    game.ok(ix::move_between_storage(player, from, to, 20));
+
+    assert_eq!(game.storage_amount(from), 10);
+    assert_eq!(game.storage_amount(to), 20);
+}
+
+#[test]
+fn move_between_storage_errors() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    let other = game.create_player();
+    let ore = game.resource(player, "ore", &[]);
+    let coal = game.resource(player, "coal", &[]);
+    game.location(player, 0, 0, 10);
+    game.location(player, 5, 5, 10);
+    let from = game.funded_storage(player, ore, 100, 30, (0, 0));
+    let small = game.storage(player, ore, 10, (0, 0));
+    let coal_storage = game.storage(player, coal, 100, (0, 0));
+    let far_away = game.storage(player, ore, 100, (5, 5));
+    let location = pda::map_location(&player, 0, 0).0;
+    let not_mine = game.storage_at(other, ore, 100, location);
+
+    assert_error(game.send(ix::move_between_storage(player, from, small, 31)), ValidationError::StorageAmountTooLow);
+    assert_error(game.send(ix::move_between_storage(player, from, small, 11)), ValidationError::StorageFull);
+    assert_error(game.send(ix::move_between_storage(player, from, small, 0)), ValidationError::InvalidInput);
+    assert_error(game.send(ix::move_between_storage(player, from, small, -5)), ValidationError::InvalidInput);
+    assert_error(game.send(ix::move_between_storage(player, from, coal_storage, 5)), ValidationError::ResourceNotMatching);
+    assert_error(game.send(ix::move_between_storage(player, from, coal_storage, 31)), ValidationError::ResourceNotMatching);
+    assert_error(game.send(ix::move_between_storage(player, from, far_away, 5)), ValidationError::DifferentLocations);
+    assert_error(game.send(ix::move_between_storage(player, from, not_mine, 5)), ValidationError::OwnerRequired);
+    assert_error(game.send(ix::move_between_storage(player, from, from, 5)), ValidationError::SameStorage);
+    assert_eq!(game.storage_amount(from), 30);
+}
+
+// Moving storage ---------------------------
+
+#[test]
+fn move_storage_to_another_location() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    let ore = game.resource(player, "ore", &[]);
+    let from = game.location(player, 0, 0, 5);
+    let to = game.location(player, 3, 4, 5);
+    let storage = game.movable_storage(player, ore, 100, MobilityType::Movable, 1, (0, 0));
+
+    game.ok(ix::move_storage(player, storage, from, to));
+
+    // Manhattan distance 7 at speed 1.
+    let account: Storage = game.account(storage);
+    assert_eq!(account.location_id, to);
+    assert_eq!(account.arrives_at, START + 7_000);
+    assert!(game.account::<Location>(from).occupied_by.is_empty());
+    assert_eq!(game.account::<Location>(to).occupied_by[0].item, storage);
+
+    assert_error(game.send(ix::move_storage(player, storage, to, from)), ValidationError::NotAllowedWhileMoving);
+
+    game.ok(ix::update_storage_move_status(player, storage));
+    assert_eq!(game.account::<Storage>(storage).arrives_at, START + 7_000);
+
+    game.advance(7_000);
+    game.ok(ix::update_storage_move_status(player, storage));
+    assert_eq!(game.account::<Storage>(storage).arrives_at, 0);
+
+    game.ok(ix::move_storage(player, storage, to, from));
+}
+
+#[test]
+fn fixed_storage_does_not_move() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    let ore = game.resource(player, "ore", &[]);
+    let from = game.location(player, 0, 0, 5);
+    let to = game.location(player, 1, 0, 5);
+    let fixed = game.storage(player, ore, 100, (0, 0));
+    let no_speed = game.movable_storage(player, ore, 100, MobilityType::Movable, 0, (0, 0));
+
+    assert_error(game.send(ix::move_storage(player, fixed, from, to)), ValidationError::StorageTypeNotMovable);
+    assert_error(game.send(ix::move_storage(player, no_speed, from, to)), ValidationError::StorageTypeNotMovable);
+    assert_error(game.send(ix::update_storage_move_status(player, fixed)), ValidationError::StorageTypeNotMovable);
+}
+
+// Transfers --------------------------------
+
+#[test]
+fn transfer_to_player() {
+    let mut game = TestGame::start();
+    let sender = game.create_player();
+    let recipient = game.create_player();
+    let ore = game.resource(sender, "ore", &[]);
+    let location = game.location(sender, 0, 0, 5);
+    let from = game.funded_storage(sender, ore, 100, 30, (0, 0));
+    let to = game.storage_at(recipient, ore, 100, location);
+
+    game.ok(ix::transfer_to_player(sender, from, to, recipient, 10));
+
+    assert_eq!(game.storage_amount(from), 20);
+    assert_eq!(game.storage_amount(to), 10);
+
+    assert_error(game.send(ix::transfer_to_player(sender, from, to, recipient, 0)), ValidationError::InvalidInput);
+    assert_error(game.send(ix::transfer_to_player(recipient, from, to, recipient, 5)), ValidationError::OwnerRequired);
+}
+
+#[test]
+fn transfer_extreme_amounts() {
+    let mut game = TestGame::start();
+    let sender = game.create_player();
+    let recipient = game.create_player();
+    let ore = game.resource(sender, "ore", &[]);
+    let location = game.location(sender, 0, 0, 5);
+    let from = game.funded_storage(sender, ore, 100, 30, (0, 0));
+    let full = game.storage_at(recipient, ore, i64::MAX, location);
+    game.set_storage_amount(full, i64::MAX);
+
+    assert_error(game.send(ix::transfer_to_player(sender, from, full, recipient, i64::MIN)), ValidationError::InvalidInput);
+    assert_error(game.send(ix::transfer_to_player(sender, from, full, recipient, i64::MAX)), ValidationError::StorageAmountTooLow);
+    assert_error(game.send(ix::transfer_to_player(sender, from, full, recipient, 1)), ValidationError::MathOverflow);
+}
+
+#[test]
+fn transfer_to_the_same_storage() {
+    let mut game = TestGame::start();
+    let sender = game.create_player();
+    let ore = game.resource(sender, "ore", &[]);
+    game.location(sender, 0, 0, 5);
+    let from = game.funded_storage(sender, ore, 100, 30, (0, 0));
+
+    assert_error(game.send(ix::transfer_to_player(sender, from, from, sender, 10)), ValidationError::SameStorage);
+    assert_eq!(game.storage_amount(from), 30);
+}
+
+#[test]
+fn transfer_to_player_elsewhere() {
+    let mut game = TestGame::start();
+    let sender = game.create_player();
+    let recipient = game.create_player();
+    let ore = game.resource(sender, "ore", &[]);
+    game.location(sender, 0, 0, 5);
+    game.location(recipient, 1, 1, 5);
+    let from = game.funded_storage(sender, ore, 100, 30, (0, 0));
+    let to = game.storage(recipient, ore, 100, (1, 1));
+
+    assert_error(game.send(ix::transfer_to_player(sender, from, to, recipient, 10)), ValidationError::DifferentLocations);
+}
+
+#[test]
+fn transfer_to_player_while_moving() {
+    let mut game = TestGame::start();
+    let sender = game.create_player();
+    let recipient = game.create_player();
+    let ore = game.resource(sender, "ore", &[]);
+    let start = game.location(sender, 0, 0, 5);
+    let destination = game.location(sender, 2, 0, 5);
+    let from = game.movable_storage(sender, ore, 100, MobilityType::Movable, 1, (0, 0));
+    let here = game.storage_at(recipient, ore, 100, start);
+    let there = game.storage_at(recipient, ore, 100, destination);
+    game.set_storage_amount(from, 30);
+
+    game.ok(ix::move_storage(sender, from, start, destination));
+    assert_error(game.send(ix::transfer_to_player(sender, from, here, recipient, 10)), ValidationError::DifferentLocations);
+    assert_error(game.send(ix::transfer_to_player(sender, from, there, recipient, 10)), ValidationError::DifferentLocations);
+
+    game.advance(2_000);
+    game.ok(ix::transfer_to_player(sender, from, there, recipient, 10));
+    assert_eq!((game.storage_amount(from), game.storage_amount(there)), (20, 10));
+}
+
+#[test]
+fn transfer_allow_list() {
+    let mut game = TestGame::start();
+    let sender = game.create_player();
+    let recipient = game.create_player();
+    let ore = game.resource(sender, "ore", &[]);
+    let location = game.location(sender, 0, 0, 5);
+    let from = game.funded_storage(sender, ore, 100, 30, (0, 0));
+    let to = game.storage_at(recipient, ore, 100, location);
+
+    game.ok(ix::init_transfer_allow_list(recipient, vec!()));
+    assert_error(game.send(ix::transfer_to_player(sender, from, to, recipient, 10)), ValidationError::TransferNotAllowed);
+
+    game.ok(ix::update_transfer_allow_list(recipient, vec!(sender)));
+    let allow_list: TransferAllowList = game.account(pda::transfer_allow_list(&recipient).0);
+    assert_eq!(allow_list.allowed, vec!(sender));
+
+    game.ok(ix::transfer_to_player(sender, from, to, recipient, 10));
+    assert_eq!(game.storage_amount(to), 10);
+
+    // Only the recipient's own list counts, and only for the players on it.
+    let stranger = game.create_player();
+    let stranger_storage = game.storage_at(stranger, ore, 100, location);
+    game.set_storage_amount(stranger_storage, 30);
+    game.ok(ix::init_transfer_allow_list(sender, vec!()));
+    assert_error(game.send(ix::transfer_to_player(stranger, stranger_storage, to, recipient, 10)), ValidationError::TransferNotAllowed);
+
+    game.ok(ix::update_transfer_allow_list(recipient, vec!(stranger)));
+    game.ok(ix::transfer_to_player(stranger, stranger_storage, to, recipient, 10));
+    assert_error(game.send(ix::transfer_to_player(sender, from, to, recipient, 10)), ValidationError::TransferNotAllowed);
+    assert_eq!((game.storage_amount(from), game.storage_amount(to)), (20, 20));
+}
+
+#[test]
+fn transfer_allow_list_is_limited() {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    let too_many: Vec<Pubkey> = (0..11).map(|_| Pubkey::new_unique()).collect();
+
+    assert_error(game.send(ix::init_transfer_allow_list(player, too_many.clone())), ValidationError::AllowListFull);
+
+    game.ok(ix::init_transfer_allow_list(player, too_many[..10].to_vec()));
+    assert_error(game.send(ix::update_transfer_allow_list(player, too_many)), ValidationError::AllowListFull);
+}
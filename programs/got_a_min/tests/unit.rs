@@ -0,0 +1,78 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+
+use got_a_min::errors::ValidationError;
+use got_a_min::state::{Location, LocationType, Map, Unit};
+use got_a_min_sdk::instructions as ix;
+use got_a_min_sdk::pda;
+
+use common::*;
+
+const NAME: &str = "ship";
+
+// A player with explored space at (0, 0) and unexplored space around it, plus the map of the game.
+fn explorer() -> (TestGame, Pubkey, Pubkey) {
+    let mut game = TestGame::start();
+    let player = game.create_player();
+    game.location(player, 0, 0, 5);
+    game.typed_location(player, 1, 0, 5, LocationType::Unexplored);
+    game.typed_location(player, 2, 0, 5, LocationType::Unexplored);
+
+    let map = pda::map(&game.admin).0;
+    game.ok(ix::init_map(game.admin, 0));
+
+    (game, player, map)
+}
+
+#[test]
+fn init_unit() {
+    let (mut game, player, _) = explorer();
+
+    game.ok(ix::init_unit(player, NAME, 0, 0, player));
+
+    let account: Unit = game.account(pda::unit(&player, NAME).0);
+    assert_eq!((account.owner, account.at_location_id), (player, pda::map_location(&player, 0, 0).0));
+    assert_eq!((account.name.as_str(), account.movement_speed, account.arrives_at), (NAME, 1, 0));
+}
+
+#[test]
+fn init_unit_errors() {
+    let (mut game, player, _) = explorer();
+
+    assert_error(game.send(ix::init_unit(player, NAME, 1, 0, player)), ValidationError::LocationUnexplored);
+    assert_error(game.send(ix::init_unit(player, "ninecharz", 0, 0, player)), ValidationError::NameTooLong);
+}
+
+#[test]
+fn move_unit_and_explore() {
+    let (mut game, player, map) = explorer();
+    let unit = pda::unit(&player, NAME).0;
+    game.ok(ix::init_unit(player, NAME, 0, 0, player));
+
+    game.ok(ix::move_unit_start(player, NAME, (0, 0), (1, 0)));
+
+    let account: Unit = game.account(unit);
+    assert_eq!((account.at_location_id, account.arrives_at), (pda::map_location(&player, 1, 0).0, START + 1_000));
+    assert_error(game.send(ix::move_unit_start(player, NAME, (1, 0), (2, 0))), ValidationError::NotAllowedWhileMoving);
+
+    // Nothing happens before the unit is there.
+    game.ok(ix::move_unit_complete(player, NAME, (1, 0), map));
+    assert_eq!(game.account::<Unit>(unit).arrives_at, START + 1_000);
+
+    game.advance(1_000);
+    game.ok(ix::move_unit_complete(player, NAME, (1, 0), map));
+    assert_eq!(game.account::<Unit>(unit).arrives_at, 0);
+    assert_ne!(game.account::<Location>(pda::map_location(&player, 1, 0).0).location_type, LocationType::Unexplored);
+    assert_eq!(game.account::<Map>(map).csr().get(1, 0), 1);
+}
+
+#[test]
+fn move_unit_one_step_at_a_time() {
+    let (mut game, player, _) = explorer();
+    game.ok(ix::init_unit(player, NAME, 0, 0, player));
+
+    assert_error(game.send(ix::move_unit_start(player, NAME, (0, 0), (2, 0))), ValidationError::UnitMoveInvalid);
+    assert_error(game.send(ix::move_unit_start(player, NAME, (1, 0), (2, 0))), ValidationError::ExperimentalError);
+}
+
@@ -0,0 +1,19 @@
+// Game rules on plain structs, no Anchor accounts or Solana types, so the program,
+// simulators and bots all run the same code. Only needs `alloc`.
+#![no_std]
+
+extern crate alloc;
+
+pub mod map;
+pub mod math;
+pub mod movement;
+pub mod production;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuleError {
+    MathOverflow,
+    MapOutOfBounds,
+    MapFull,
+}
+
+pub type Result<T> = core::result::Result<T, RuleError>;
@@ -0,0 +1,223 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{Result, RuleError};
+
+pub const MAP_MAX_HEIGHT: usize = 10;
+pub const MAP_MAX_WIDTH: usize = 20;
+
+// Map tiles in compressed sparse row form: [row_ptrs][y] is where row y starts in [columns]
+// and [values], rows past the last one start at the end of the stored tiles. Tiles equal to
+// [compressed_value] aren't stored and unused slots hold [compressed_value], so at most
+// MAP_MAX_WIDTH tiles of the whole map can differ from it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CsrMap {
+    pub row_ptrs: [u8; MAP_MAX_HEIGHT],
+    pub columns: [u8; MAP_MAX_WIDTH],
+    pub values: [u8; MAP_MAX_WIDTH],
+    pub width: u8,
+    pub height: u8,
+    pub compressed_value: u8,
+}
+
+impl CsrMap {
+    pub fn new(compressed_value: u8) -> Self {
+        CsrMap {
+            row_ptrs: [0; MAP_MAX_HEIGHT],
+            columns: [compressed_value; MAP_MAX_WIDTH],
+            values: [compressed_value; MAP_MAX_WIDTH],
+            width: 0,
+            height: 0,
+            compressed_value,
+        }
+    }
+
+    pub fn get(&self, x: u8, y: u8) -> u8 {
+        self.value_index(x, y).map_or(self.compressed_value, |i| self.values[i])
+    }
+
+    // Index of (x, y) in [values] when it's stored.
+    pub fn value_index(&self, x: u8, y: u8) -> Option<usize> {
+        self.row(y).find(|i| self.columns[*i] == x)
+    }
+
+    pub fn clear(&mut self, x: u8, y: u8) -> Result<()> {
+        self.put(x, y, self.compressed_value)
+    }
+
+    // Fails without changing the map when (x, y) is outside the max size or the tile doesn't fit.
+    pub fn put(&mut self, x: u8, y: u8, new_value: u8) -> Result<()> {
+        if x as usize >= MAP_MAX_WIDTH || y as usize >= MAP_MAX_HEIGHT {
+            return Err(RuleError::MapOutOfBounds);
+        }
+
+        let mut tiles = self.tiles();
+        let position = tiles.binary_search_by_key(&(y, x), |(ty, tx, _)| (*ty, *tx));
+        match (position, new_value == self.compressed_value) {
+            (Ok(i), true) => { tiles.remove(i); },
+            (Ok(i), false) => tiles[i].2 = new_value,
+            (Err(_), true) => {},
+            (Err(i), false) => tiles.insert(i, (y, x, new_value)),
+        }
+        if tiles.len() > MAP_MAX_WIDTH {
+            return Err(RuleError::MapFull);
+        }
+
+        self.row_ptrs = [0; MAP_MAX_HEIGHT];
+        self.columns = [self.compressed_value; MAP_MAX_WIDTH];
+        self.values = [self.compressed_value; MAP_MAX_WIDTH];
+        for (i, (ty, tx, value)) in tiles.iter().enumerate() {
+            self.columns[i] = *tx;
+            self.values[i] = *value;
+            for row_ptr in self.row_ptrs.iter_mut().skip(*ty as usize + 1) {
+                *row_ptr = i as u8 + 1;
+            }
+        }
+        self.width = self.width.max(x + 1);
+        self.height = self.height.max(y + 1);
+        Ok(())
+    }
+
+    // Stored tiles as (y, x, value), ordered by row and column.
+    fn tiles(&self) -> Vec<(u8, u8, u8)> {
+        let mut tiles = Vec::new();
+        for y in 0..MAP_MAX_HEIGHT as u8 {
+            for i in self.row(y) {
+                if self.values[i] != self.compressed_value {
+                    tiles.push((y, self.columns[i], self.values[i]));
+                }
+            }
+        }
+        tiles
+    }
+
+    fn stored(&self) -> usize {
+        self.values.iter().rposition(|value| *value != self.compressed_value).map_or(0, |i| i + 1)
+    }
+
+    fn row(&self, y: u8) -> Range<usize> {
+        if y >= self.height {
+            return 0..0;
+        }
+        let y = y as usize;
+        let stored = self.stored();
+        let start = (self.row_ptrs[y] as usize).min(stored);
+        let mut end = self.row_ptrs.get(y + 1).map_or(stored, |next| *next as usize);
+        // Maps written before rows past the last one pointed at the end have them at 0.
+        if end < start {
+            end = stored;
+        }
+        start..end.min(stored)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use proptest::prelude::*;
+
+    // The same map as a plain grid.
+    #[derive(Clone)]
+    struct Dense {
+        tiles: [[u8; MAP_MAX_WIDTH]; MAP_MAX_HEIGHT],
+        width: u8,
+        height: u8,
+    }
+
+    impl Dense {
+        fn new(compressed_value: u8) -> Self {
+            Dense { tiles: [[compressed_value; MAP_MAX_WIDTH]; MAP_MAX_HEIGHT], width: 0, height: 0 }
+        }
+
+        fn put(&mut self, x: u8, y: u8, value: u8) {
+            self.tiles[y as usize][x as usize] = value;
+            self.width = self.width.max(x + 1);
+            self.height = self.height.max(y + 1);
+        }
+
+        fn stored(&self, compressed_value: u8) -> usize {
+            self.tiles.iter().flatten().filter(|value| **value != compressed_value).count()
+        }
+    }
+
+    fn assert_same(map: &CsrMap, dense: &Dense) -> core::result::Result<(), TestCaseError> {
+        for y in 0..MAP_MAX_HEIGHT as u8 {
+            for x in 0..MAP_MAX_WIDTH as u8 {
+                prop_assert_eq!(map.get(x, y), dense.tiles[y as usize][x as usize], "tile ({}, {})", x, y);
+            }
+        }
+        prop_assert_eq!((map.width, map.height), (dense.width, dense.height));
+        Ok(())
+    }
+
+    // Few values and a small area, so puts overwrite, clear and fill up the map.
+    fn puts() -> impl Strategy<Value = Vec<(u8, u8, u8)>> {
+        prop::collection::vec((0..MAP_MAX_WIDTH as u8, 0..MAP_MAX_HEIGHT as u8, 0..4u8), 0..80)
+    }
+
+    #[test]
+    fn legacy_rows_past_the_last_one_are_empty() {
+        let mut map = CsrMap::new(0);
+        map.row_ptrs = [0, 2, 4, 0, 0, 0, 0, 0, 0, 0];
+        map.columns[..4].copy_from_slice(&[0, 1, 0, 1]);
+        map.values[..4].copy_from_slice(&[1, 2, 3, 4]);
+        map.width = 6;
+        map.height = 5;
+
+        assert_eq!((map.get(0, 0), map.get(1, 0), map.get(0, 1), map.get(1, 1)), (1, 2, 3, 4));
+        assert_eq!((map.get(0, 2), map.get(0, 4), map.get(1, 9)), (0, 0, 0));
+
+        map.put(3, 2, 5).unwrap();
+        assert_eq!((map.get(1, 1), map.get(3, 2), map.get(0, 4)), (4, 5, 0));
+        assert_eq!(map.row_ptrs, [0, 2, 4, 5, 5, 5, 5, 5, 5, 5]);
+    }
+
+    #[test]
+    fn outside_of_the_max_size_is_an_error() {
+        let mut map = CsrMap::new(0);
+        assert_eq!(map.put(MAP_MAX_WIDTH as u8, 0, 1), Err(RuleError::MapOutOfBounds));
+        assert_eq!(map.put(0, MAP_MAX_HEIGHT as u8, 1), Err(RuleError::MapOutOfBounds));
+        assert_eq!(map.put(u8::MAX, u8::MAX, 1), Err(RuleError::MapOutOfBounds));
+        assert_eq!(map, CsrMap::new(0));
+    }
+
+    proptest! {
+        #[test]
+        fn reads_back_like_a_grid(compressed_value in 0..4u8, puts in puts()) {
+            let mut map = CsrMap::new(compressed_value);
+            let mut dense = Dense::new(compressed_value);
+
+            for (x, y, value) in puts {
+                let before = map;
+                let mut after = dense.clone();
+                after.put(x, y, value);
+
+                if after.stored(compressed_value) > MAP_MAX_WIDTH {
+                    prop_assert_eq!(map.put(x, y, value), Err(RuleError::MapFull));
+                    prop_assert_eq!(map, before);
+                } else {
+                    prop_assert_eq!(map.put(x, y, value), Ok(()));
+                    dense = after;
+                }
+                assert_same(&map, &dense)?;
+            }
+        }
+
+        #[test]
+        fn clearing_every_tile_empties_the_map(compressed_value in 0..4u8, puts in puts()) {
+            let mut map = CsrMap::new(compressed_value);
+            for (x, y, value) in &puts {
+                let _ = map.put(*x, *y, *value);
+            }
+            for (x, y, _) in &puts {
+                map.clear(*x, *y).unwrap();
+            }
+
+            prop_assert_eq!(map.values, [compressed_value; MAP_MAX_WIDTH]);
+            prop_assert_eq!(map.columns, [compressed_value; MAP_MAX_WIDTH]);
+            prop_assert_eq!(map.row_ptrs, [0; MAP_MAX_HEIGHT]);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{Result, RuleError};
+
+// Checked arithmetic for game math, anything out of range fails with MathOverflow instead of wrapping or panicking.
+
+pub fn add(a: i64, b: i64) -> Result<i64> {
+    a.checked_add(b).ok_or(RuleError::MathOverflow)
+}
+
+pub fn sub(a: i64, b: i64) -> Result<i64> {
+    a.checked_sub(b).ok_or(RuleError::MathOverflow)
+}
+
+pub fn mul(a: i64, b: i64) -> Result<i64> {
+    a.checked_mul(b).ok_or(RuleError::MathOverflow)
+}
+
+pub fn div(a: i64, b: i64) -> Result<i64> {
+    a.checked_div(b).ok_or(RuleError::MathOverflow)
+}
+
+pub fn abs(a: i64) -> Result<i64> {
+    a.checked_abs().ok_or(RuleError::MathOverflow)
+}
+
+// Rounds towards negative infinity, also for negative products.
+pub fn mul_div_floor(value: i64, numerator: i64, denominator: i64) -> Result<i64> {
+    if denominator <= 0 {
+        return Err(RuleError::MathOverflow);
+    }
+    let product = (value as i128).checked_mul(numerator as i128).ok_or(RuleError::MathOverflow)?;
+    to_i64(product.div_euclid(denominator as i128))
+}
+
+// Rounds towards positive infinity, also for negative products.
+pub fn mul_div_ceil(value: i64, numerator: i64, denominator: i64) -> Result<i64> {
+    if denominator <= 0 {
+        return Err(RuleError::MathOverflow);
+    }
+    let product = (value as i128).checked_mul(numerator as i128).ok_or(RuleError::MathOverflow)?;
+    let quotient = product.div_euclid(denominator as i128);
+    match product.rem_euclid(denominator as i128) {
+        0 => to_i64(quotient),
+        _ => to_i64(quotient + 1),
+    }
+}
+
+pub fn to_i64(value: i128) -> Result<i64> {
+    i64::try_from(value).map_err(|_| RuleError::MathOverflow)
+}
+
+pub fn next_id(id: u64) -> Result<u64> {
+    id.checked_add(1).ok_or(RuleError::MathOverflow)
+}
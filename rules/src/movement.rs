@@ -0,0 +1,83 @@
+use crate::math;
+use crate::Result;
+
+// Seconds per unit of distance at speed 1.
+pub const DISTANCE_TIME_FACTOR: i64 = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Position {
+    pub fn new(x: i64, y: i64) -> Self {
+        Position { x, y }
+    }
+
+    // Manhattan distance.
+    pub fn distance(&self, other: &Position) -> Result<i64> {
+        let diff_x = math::abs(math::sub(self.x, other.x)?)?;
+        let diff_y = math::abs(math::sub(self.y, other.y)?)?;
+        math::add(diff_x, diff_y)
+    }
+
+    pub fn distance_time(&self, other: &Position) -> Result<i64> {
+        math::mul(self.distance(other)?, DISTANCE_TIME_FACTOR)
+    }
+}
+
+// Where something heading for [location_id] is at [current_time], None while still on the way.
+// [arrives_at] is 0 for things that never moved.
+pub fn location_at<T>(location_id: T, arrives_at: i64, current_time: i64) -> Option<T> {
+    match arrives_at {
+        0 => Some(location_id),
+        timestamp => {
+            match current_time >= timestamp {
+                true => Some(location_id),
+                false => None,
+            }
+        },
+    }
+}
+
+pub fn is_moving(arrives_at: i64, current_time: i64) -> bool {
+    location_at((), arrives_at, current_time).is_none()
+}
+
+pub fn has_arrived(arrives_at: i64, current_time: i64) -> bool {
+    location_at((), arrives_at, current_time).is_some() && arrives_at > 0
+}
+
+// Both are at a location and it's the same one.
+pub fn same_location<T: PartialEq>(location_1: Option<T>, location_2: Option<T>) -> bool {
+    match (location_1, location_2) {
+        (Some(l1), Some(l2)) => l1 == l2,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moving_until_arrival() {
+        assert_eq!(location_at(7, 0, 0), Some(7));
+        assert_eq!(location_at(7, 100, 99), None);
+        assert_eq!(location_at(7, 100, 100), Some(7));
+        assert!(is_moving(100, 99));
+        assert!(!has_arrived(0, 100));
+        assert!(has_arrived(100, 100));
+        assert!(!same_location(Some(7), location_at(7, 100, 99)));
+    }
+
+    #[test]
+    fn distance_is_manhattan() {
+        let from = Position::new(-2, 3);
+        let to = Position::new(1, -1);
+        assert_eq!(from.distance(&to), Ok(7));
+        assert_eq!(from.distance_time(&to), Ok(7 * DISTANCE_TIME_FACTOR));
+        assert!(Position::new(i64::MIN, 0).distance(&Position::new(0, 0)).is_err());
+    }
+}
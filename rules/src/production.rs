@@ -0,0 +1,254 @@
+use crate::math;
+use crate::Result;
+
+pub const BPS_DENOMINATOR: i64 = 10_000;
+pub const WEAR_PER_UNIT: i64 = 1;
+// Efficiency while durability stays above each share of max_durability, 0 below the last one.
+pub const WEAR_THRESHOLDS_BPS: [(i64, i64); 3] = [(5_000, 10_000), (2_500, 7_500), (0, 5_000)];
+
+// The part of a processor that decides how much it has produced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Production {
+    pub output_rate: i64,   // Produce this many units per [processing_duration].
+    pub processing_duration: i64,
+    pub claimed_at: i64,    // Time up to which [progress] has been counted.
+    pub progress: i64,  // Work towards the next units, see [unit_work].
+    pub paused_at: Option<i64>,     // Production stops accruing from this time until resumed.
+    pub durability: i64,    // Worn down by one per unit produced, production stops at 0.
+    pub max_durability: i64,    // 0 when the processor doesn't wear.
+}
+
+impl Production {
+    // Production accrues up to this time, which stands still while paused.
+    pub fn accrual_timestamp(&self, current_timestamp: i64) -> i64 {
+        match self.paused_at {
+            Some(paused_at) => paused_at.min(current_timestamp),
+            None => current_timestamp,
+        }
+    }
+
+    pub fn pause(&mut self, current_timestamp: i64) {
+        self.paused_at = Some(current_timestamp);
+    }
+
+    // Shifting the claim clock by the pause keeps the progress made before it. A claim clock already
+    // ahead of the pause, held or retooled, only moves up to now. Returns how long it was paused.
+    pub fn resume(&mut self, current_timestamp: i64) -> Result<i64> {
+        let paused_for = match self.paused_at {
+            Some(paused_at) => {
+                let held_for = math::sub(current_timestamp, self.claimed_at.max(paused_at))?.max(0);
+                self.claimed_at = math::add(self.claimed_at, held_for)?;
+                math::sub(current_timestamp, paused_at)?.max(0)
+            },
+            None => 0,
+        };
+
+        self.paused_at = None;
+        Ok(paused_for)
+    }
+
+    // Settles the progress up to now and accrues nothing before [until].
+    pub fn hold(&mut self, current_timestamp: i64, until: i64) -> Result<()> {
+        self.accrue(current_timestamp)?;
+        self.claimed_at = self.claimed_at.max(until);
+        Ok(())
+    }
+
+    // Share of the output still produced at the current durability.
+    pub fn efficiency_bps(&self) -> Result<i64> {
+        self.efficiency_bps_at(self.durability)
+    }
+
+    fn efficiency_bps_at(&self, durability: i64) -> Result<i64> {
+        if self.max_durability == 0 {
+            return Ok(BPS_DENOMINATOR);
+        }
+
+        let durability_bps = math::mul_div_floor(durability.max(0), BPS_DENOMINATOR, self.max_durability)?;
+        match WEAR_THRESHOLDS_BPS.iter().find(|(threshold_bps, _)| durability_bps > *threshold_bps) {
+            Some((_, efficiency_bps)) => Ok(*efficiency_bps),
+            None => Ok(0),
+        }
+    }
+
+    // The highest durability below the efficiency band [durability] is in, None when it doesn't wear.
+    fn next_wear_threshold(&self, durability: i64) -> Result<Option<i64>> {
+        if self.max_durability == 0 {
+            return Ok(None);
+        }
+
+        let durability_bps = math::mul_div_floor(durability.max(0), BPS_DENOMINATOR, self.max_durability)?;
+        match WEAR_THRESHOLDS_BPS.iter().find(|(threshold_bps, _)| durability_bps > *threshold_bps) {
+            // The last durability whose share is at most the threshold.
+            Some((threshold_bps, _)) => Ok(Some(math::div(math::sub(math::mul(threshold_bps + 1, self.max_durability)?, 1)?, BPS_DENOMINATOR)?)),
+            None => Ok(None),
+        }
+    }
+
+    // Progress counts elapsed time * efficiency_bps * output_rate, so nothing is rounded away between claims.
+    pub fn unit_work(&self) -> Result<i64> {
+        math::mul(self.processing_duration, BPS_DENOMINATOR)
+    }
+
+    // Adds the time since the last claim to [progress]. Units already in the progress wear the processor
+    // down, so the time is split wherever they take it past a wear threshold.
+    pub fn accrue(&mut self, current_timestamp: i64) -> Result<()> {
+        let accrued_until = self.accrual_timestamp(current_timestamp);
+        let unit_work = self.unit_work()?;
+
+        // A retooled processor can have claimed_at ahead of the current time.
+        while accrued_until > self.claimed_at {
+            let elapsed = math::sub(accrued_until, self.claimed_at)?;
+            let pending_units = math::div(self.progress, unit_work)?;
+            let durability = math::sub(self.durability, math::mul(pending_units, WEAR_PER_UNIT)?)?;
+            let work_per_second = math::mul(self.efficiency_bps_at(durability)?, self.output_rate)?;
+
+            let span = match self.next_wear_threshold(durability)? {
+                _ if work_per_second == 0 => elapsed,
+                Some(threshold) => {
+                    let units_to_threshold = math::mul_div_ceil(math::sub(durability, threshold)?, 1, WEAR_PER_UNIT)?;
+                    let work_to_threshold = math::sub(math::mul(math::add(pending_units, units_to_threshold)?, unit_work)?, self.progress)?;
+                    elapsed.min(math::mul_div_ceil(work_to_threshold, 1, work_per_second)?)
+                },
+                None => elapsed,
+            };
+
+            self.progress = math::add(self.progress, math::mul(span, work_per_second)?)?;
+            self.claimed_at = math::add(self.claimed_at, span)?;
+        }
+
+        Ok(())
+    }
+
+    // Units the progress is enough for, never more than the durability left.
+    pub fn finished_units(&self) -> Result<i64> {
+        let units = math::div(self.progress, self.unit_work()?)?;
+
+        match self.max_durability {
+            0 => Ok(units),
+            _ => Ok(units.min(self.durability.max(0))),
+        }
+    }
+
+    // Uses up the progress and durability of [units] out of finished_units.
+    pub fn complete(&mut self, units: i64) -> Result<()> {
+        self.progress = math::sub(self.progress, math::mul(units, self.unit_work()?)?)?;
+        self.wear(units)
+    }
+
+    pub fn wear(&mut self, units: i64) -> Result<()> {
+        if self.max_durability > 0 {
+            self.durability = math::sub(self.durability, math::mul(units, WEAR_PER_UNIT)?)?.max(0);
+
+            // Work beyond the breakdown is lost.
+            if self.durability == 0 {
+                self.progress = 0;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Keeps the production pending at the current rate when switching to [output_rate] and [processing_duration].
+    pub fn rebase(&mut self, output_rate: i64, processing_duration: i64, current_timestamp: i64) -> Result<()> {
+        self.accrue(current_timestamp)?;
+
+        // Same share of a unit, measured in the new unit_work.
+        self.progress = math::mul_div_floor(self.progress, processing_duration, self.processing_duration)?;
+        self.output_rate = output_rate;
+        self.processing_duration = processing_duration;
+        Ok(())
+    }
+
+    // Units finished since the last claim, at most [max_output], taken out of the progress.
+    pub fn calc_awaiting(&mut self, current_timestamp: i64, max_output: i64) -> Result<i64> {
+        self.accrue(current_timestamp)?;
+        let units = self.finished_units()?.min(max_output).max(0);
+        self.complete(units)?;
+        Ok(units)
+    }
+}
+
+// How many of [awaiting_units] fit in a storage holding [amount] out of [capacity].
+pub fn deliverable(awaiting_units: i64, capacity: i64, amount: i64) -> Result<i64> {
+    let available_capacity = math::sub(capacity, amount)?.max(0);
+    Ok(available_capacity.min(awaiting_units.max(0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn production(durability: i64, max_durability: i64) -> Production {
+        Production {
+            output_rate: 2,
+            processing_duration: 10,
+            claimed_at: 0,
+            progress: 0,
+            paused_at: None,
+            durability,
+            max_durability,
+        }
+    }
+
+    #[test]
+    fn worn_processors_produce_less() {
+        assert_eq!(production(0, 0).efficiency_bps(), Ok(BPS_DENOMINATOR));
+        assert_eq!(production(60, 100).efficiency_bps(), Ok(10_000));
+        assert_eq!(production(30, 100).efficiency_bps(), Ok(7_500));
+        assert_eq!(production(10, 100).efficiency_bps(), Ok(5_000));
+        assert_eq!(production(0, 100).efficiency_bps(), Ok(0));
+    }
+
+    #[test]
+    fn wear_slows_down_production_as_it_goes() {
+        // 10 units at full speed take 50 seconds and wear it down to half, the next 50 seconds make 7.5.
+        let mut once = production(60, 100);
+        assert_eq!(once.calc_awaiting(100, i64::MAX), Ok(17));
+
+        let mut often = production(60, 100);
+        let produced: i64 = (1..=100).map(|timestamp| often.calc_awaiting(timestamp, i64::MAX).unwrap()).sum();
+        assert_eq!((produced, often.durability, often.progress), (17, once.durability, once.progress));
+    }
+
+    #[test]
+    fn pausing_shifts_the_claim_clock() {
+        let mut production = production(0, 0);
+        production.pause(20);
+        assert_eq!(production.calc_awaiting(50, i64::MAX), Ok(4));
+        assert_eq!(production.resume(50), Ok(30));
+        assert_eq!(production.calc_awaiting(60, i64::MAX), Ok(2));
+        assert_eq!(production.resume(70), Ok(0));
+    }
+
+    #[test]
+    fn holding_overlaps_a_pause() {
+        let mut production = production(0, 0);
+        production.pause(20);
+        production.hold(30, 60).unwrap();
+        assert_eq!((production.claimed_at, production.progress), (60, 20 * 10_000 * 2));
+
+        // Resumed before the hold ends, production waits for it. Resumed after, it picks up from there.
+        let mut early = production;
+        early.resume(40).unwrap();
+        assert_eq!(early.claimed_at, 60);
+        production.resume(80).unwrap();
+        assert_eq!(production.claimed_at, 80);
+    }
+
+    #[test]
+    fn calc_awaiting_keeps_the_rest() {
+        let mut production = production(0, 0);
+        assert_eq!(production.calc_awaiting(25, 3), Ok(3));
+        assert_eq!(production.calc_awaiting(25, i64::MAX), Ok(2));
+        assert_eq!(production.calc_awaiting(30, i64::MAX), Ok(1));
+    }
+
+    #[test]
+    fn deliverable_fits_the_storage() {
+        assert_eq!(deliverable(10, 100, 95), Ok(5));
+        assert_eq!(deliverable(10, 100, 0), Ok(10));
+        assert_eq!(deliverable(10, 100, 120), Ok(0));
+        assert!(deliverable(1, i64::MIN, 1).is_err());
+    }
+}
@@ -0,0 +1,100 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+use got_a_min::instructions::GameTile;
+use got_a_min::state::*;
+
+// Any account owned by the program, told apart by its discriminator.
+pub enum GameAccount {
+    Auction(Auction),
+    Game(Game),
+    GameClock(GameClock),
+    GameTile(GameTile),
+    LiquidityPosition(LiquidityPosition),
+    Location(Location),
+    Map(Map),
+    Offer(Offer),
+    OrderBook(OrderBook),
+    Pool(Pool),
+    Processor(Processor),
+    ProductionQueue(ProductionQueue),
+    Resource(Resource),
+    Shipment(Shipment),
+    Storage(Storage),
+    Stuff(Stuff),
+    TradeRoute(TradeRoute),
+    TransferAllowList(TransferAllowList),
+    Unit(Unit),
+}
+
+// Account data of a known type, as fetched from the cluster.
+pub fn decode_as<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..])
+}
+
+pub fn decode(data: &[u8]) -> Result<GameAccount> {
+    require!(data.len() >= 8, ErrorCode::AccountDiscriminatorNotFound);
+    let discriminator: [u8; 8] = data[..8].try_into().unwrap();
+
+    let account = match discriminator {
+        d if d == Auction::discriminator() => GameAccount::Auction(decode_as(data)?),
+        d if d == Game::discriminator() => GameAccount::Game(decode_as(data)?),
+        d if d == GameClock::discriminator() => GameAccount::GameClock(decode_as(data)?),
+        d if d == GameTile::discriminator() => GameAccount::GameTile(decode_as(data)?),
+        d if d == LiquidityPosition::discriminator() => GameAccount::LiquidityPosition(decode_as(data)?),
+        d if d == Location::discriminator() => GameAccount::Location(decode_as(data)?),
+        d if d == Map::discriminator() => GameAccount::Map(decode_as(data)?),
+        d if d == Offer::discriminator() => GameAccount::Offer(decode_as(data)?),
+        d if d == OrderBook::discriminator() => GameAccount::OrderBook(decode_as(data)?),
+        d if d == Pool::discriminator() => GameAccount::Pool(decode_as(data)?),
+        d if d == Processor::discriminator() => GameAccount::Processor(decode_as(data)?),
+        d if d == ProductionQueue::discriminator() => GameAccount::ProductionQueue(decode_as(data)?),
+        d if d == Resource::discriminator() => GameAccount::Resource(decode_as(data)?),
+        d if d == Shipment::discriminator() => GameAccount::Shipment(decode_as(data)?),
+        d if d == Storage::discriminator() => GameAccount::Storage(decode_as(data)?),
+        d if d == Stuff::discriminator() => GameAccount::Stuff(decode_as(data)?),
+        d if d == TradeRoute::discriminator() => GameAccount::TradeRoute(decode_as(data)?),
+        d if d == TransferAllowList::discriminator() => GameAccount::TransferAllowList(decode_as(data)?),
+        d if d == Unit::discriminator() => GameAccount::Unit(decode_as(data)?),
+        _ => return err!(ErrorCode::AccountDiscriminatorMismatch),
+    };
+
+    Ok(account)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage(amount: i64) -> Storage {
+        Storage {
+            version: Storage::VERSION,
+            owner: Pubkey::new_unique(),
+            resource_id: Pubkey::new_unique(),
+            location_id: Pubkey::new_unique(),
+            amount,
+            capacity: 10,
+            mobility_type: MobilityType::Fixed,
+            movement_speed: 0,
+            arrives_at: 0,
+        }
+    }
+
+    #[test]
+    fn decodes_by_discriminator() {
+        let mut data = vec!();
+        storage(7).try_serialize(&mut data).unwrap();
+
+        match decode(&data).unwrap() {
+            GameAccount::Storage(storage) => assert_eq!(storage.amount, 7),
+            _ => panic!("expected a storage"),
+        }
+        assert!(decode_as::<Processor>(&data).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_data() {
+        assert!(decode(&[0; 4]).is_err());
+        assert!(decode(&[0; 16]).is_err());
+    }
+}
@@ -0,0 +1,848 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::InstructionData;
+
+use got_a_min::state::{LocationType, MobilityType, OrderSide};
+use got_a_min::instructions::game_clock::{AdvanceGameClockArgs, InitGameClockArgs};
+use got_a_min::instructions::processor::ProcessorSpec;
+use got_a_min::{accounts, instruction, ID};
+
+use crate::pda;
+
+// Any instruction from its generated account and argument structs, e.g.
+// build(accounts::Swap { .. }, instruction::Swap { amount_in, min_amount_out }).
+pub fn build<A: ToAccountMetas, D: InstructionData>(accounts: A, args: D) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: args.data(),
+    }
+}
+
+// Like [build], with the storages or counterparties an instruction reads from its remaining accounts.
+pub fn build_with_remaining<A: ToAccountMetas, D: InstructionData>(accounts: A, args: D, remaining: &[AccountMeta]) -> Instruction {
+    let mut ix = build(accounts, args);
+    ix.accounts.extend_from_slice(remaining);
+    ix
+}
+
+// Makes [ix] read the time from the game clock, only honoured by test-clock builds.
+pub fn with_game_clock(mut ix: Instruction) -> Instruction {
+    ix.accounts.push(AccountMeta::new_readonly(pda::game_clock().0, false));
+    ix
+}
+
+pub fn debug_authority(authority: Pubkey) -> accounts::DebugAuthority {
+    accounts::DebugAuthority {
+        game: pda::game(&authority).0,
+        authority,
+    }
+}
+
+fn writable(addresses: &[Pubkey]) -> Vec<AccountMeta> {
+    addresses.iter().map(|address| AccountMeta::new(*address, false)).collect()
+}
+
+// An order book for a pair at a location, with the vaults holding what its orders lock up.
+pub struct OrderBookKeys {
+    pub address: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+}
+
+impl OrderBookKeys {
+    pub fn new(location: &Pubkey, base_resource: &Pubkey, quote_resource: &Pubkey) -> OrderBookKeys {
+        let address = pda::order_book(location, base_resource, quote_resource).0;
+        OrderBookKeys {
+            address,
+            base_vault: pda::order_book_vault(&address, base_resource).0,
+            quote_vault: pda::order_book_vault(&address, quote_resource).0,
+        }
+    }
+}
+
+// A pool of the [game] at a location and its reserves, resource a being the lower key whatever order
+// the resources are given in.
+pub struct PoolKeys {
+    pub game: Pubkey,
+    pub address: Pubkey,
+    pub resource_a: Pubkey,
+    pub resource_b: Pubkey,
+    pub reserve_a: Pubkey,
+    pub reserve_b: Pubkey,
+}
+
+impl PoolKeys {
+    pub fn new(game: &Pubkey, location: &Pubkey, resource_1: &Pubkey, resource_2: &Pubkey) -> PoolKeys {
+        let (resource_a, resource_b) = pda::sorted_pair(resource_1, resource_2);
+        let address = pda::pool(game, location, resource_a, resource_b).0;
+        PoolKeys {
+            game: *game,
+            address,
+            resource_a: *resource_a,
+            resource_b: *resource_b,
+            reserve_a: pda::pool_reserve(&address, resource_a).0,
+            reserve_b: pda::pool_reserve(&address, resource_b).0,
+        }
+    }
+}
+
+// Builders filling in the PDAs ---------------------------------
+
+// Game -------------------------------------
+
+pub fn init_game(authority: Pubkey, swap_fee_bps: u16) -> Instruction {
+    build(
+        accounts::InitGame {
+            game: pda::game(&authority).0,
+            authority,
+            program: ID,
+            program_data: pda::program_data().0,
+            system_program: system_program::ID,
+        },
+        instruction::InitGame { swap_fee_bps },
+    )
+}
+
+fn update_game_accounts(authority: Pubkey) -> accounts::UpdateGame {
+    accounts::UpdateGame { game: pda::game(&authority).0, authority }
+}
+
+pub fn update_game_fees(authority: Pubkey, swap_fee_bps: u16) -> Instruction {
+    build(update_game_accounts(authority), instruction::UpdateGameFees { swap_fee_bps })
+}
+
+pub fn update_game_levels(authority: Pubkey, level_throughput_bps: Vec<u32>, upgrade_resource_id: Pubkey, upgrade_cost_base: i64) -> Instruction {
+    build(update_game_accounts(authority), instruction::UpdateGameLevels { level_throughput_bps, upgrade_resource_id, upgrade_cost_base })
+}
+
+pub fn update_game_retool_delay(authority: Pubkey, retool_delay: i64) -> Instruction {
+    build(update_game_accounts(authority), instruction::UpdateGameRetoolDelay { retool_delay })
+}
+
+pub fn update_game_wear(authority: Pubkey, max_durability: i64, repair_resource_id: Pubkey, repair_cost_per_point: i64) -> Instruction {
+    build(update_game_accounts(authority), instruction::UpdateGameWear { max_durability, repair_resource_id, repair_cost_per_point })
+}
+
+// The clock's instructions only exist in test-clock builds of the program, out of Anchor's sight,
+// so there are no generated account structs to build them from.
+pub fn init_game_clock(authority: Pubkey, timestamp: i64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec!(
+            AccountMeta::new(pda::game_clock().0, false),
+            AccountMeta::new(authority, true),
+            AccountMeta::new_readonly(ID, false),
+            AccountMeta::new_readonly(pda::program_data().0, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ),
+        data: InitGameClockArgs { timestamp }.data(),
+    }
+}
+
+pub fn advance_game_clock(authority: Pubkey, seconds: i64) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: vec!(
+            AccountMeta::new(pda::game_clock().0, false),
+            AccountMeta::new_readonly(authority, true),
+        ),
+        data: AdvanceGameClockArgs { seconds }.data(),
+    }
+}
+
+pub fn init_resource(owner: Pubkey, resource: Pubkey, name: &str, inputs: &[(Pubkey, i64)]) -> Instruction {
+    build(
+        accounts::InitResource { resource, owner, system_program: system_program::ID },
+        instruction::InitResource {
+            name: name.to_string(),
+            inputs: inputs.iter().map(|(input, _)| *input).collect(),
+            input_amounts: inputs.iter().map(|(_, amount)| *amount).collect(),
+        },
+    )
+}
+
+// Maps -------------------------------------
+
+pub fn init_location(owner: Pubkey, x: i64, y: i64, capacity: i64, location_type: LocationType) -> Instruction {
+    build(
+        accounts::InitLocation { location: pda::map_location(&owner, x, y).0, owner, system_program: system_program::ID },
+        instruction::InitLocation { x, y, capacity, location_type },
+    )
+}
+
+pub fn create_game_tile(owner: Pubkey, xy: [u8; 2]) -> Instruction {
+    build(
+        accounts::CreateGameTile { owner, game_tile: pda::game_tile(&owner, xy).0, system_program: system_program::ID },
+        instruction::CreateGameTile { xy },
+    )
+}
+
+pub fn explore_game_tile(owner: Pubkey, xy: [u8; 2]) -> Instruction {
+    build(
+        accounts::ExploreGameTile { owner, game_tile: pda::game_tile(&owner, xy).0, location: pda::map_location_xy(&owner, xy).0 },
+        instruction::ExploreGameTile { xy },
+    )
+}
+
+pub fn init_map(owner: Pubkey, compressed_value: u8) -> Instruction {
+    build(
+        accounts::InitMap { map: pda::map(&owner).0, owner, system_program: system_program::ID },
+        instruction::InitMap { compressed_value },
+    )
+}
+
+pub fn map_put(map: Pubkey, x: u8, y: u8, num: u8) -> Instruction {
+    build(accounts::MapPut { map }, instruction::MapPut { x, y, num })
+}
+
+// Storages ---------------------------------
+
+// [storage] is a new keypair that signs along with [owner]. The [location] at [at] might have been bought
+// from another player, so it isn't derived from the owner. [mobility] is the mobility type and movement speed.
+pub fn init_storage(owner: Pubkey, storage: Pubkey, location: Pubkey, at: (i64, i64), resource_id: Pubkey, capacity: i64, mobility: (MobilityType, i64)) -> Instruction {
+    let (mobility_type, movement_speed) = mobility;
+    build(
+        accounts::InitStorage { storage, location, owner, system_program: system_program::ID },
+        instruction::InitStorage { resource_id, capacity, mobility_type, movement_speed, x: at.0, y: at.1 },
+    )
+}
+
+// [storage] is a new keypair that signs along with [owner], at the single byte location [xy] of the owner.
+pub fn simple_init_storage(owner: Pubkey, storage: Pubkey, xy: [u8; 2]) -> Instruction {
+    build(
+        accounts::SimpleInitStorage { owner, storage, location: pda::map_location_xy(&owner, xy).0, system_program: system_program::ID },
+        instruction::SimpleInitStorage { xy },
+    )
+}
+
+pub fn simple_test_storage(owner: Pubkey, storage: Pubkey, position: [u8; 2], authority: Pubkey) -> Instruction {
+    build(
+        accounts::SimpleTestStorage {
+            owner,
+            storage,
+            location: pda::map_location_xy(&owner, position).0,
+            admin: debug_authority(authority),
+            system_program: system_program::ID,
+        },
+        instruction::SimpleTestStorage { position },
+    )
+}
+
+pub fn move_between_storage(owner: Pubkey, storage_from: Pubkey, storage_to: Pubkey, amount: i64) -> Instruction {
+    build(accounts::MoveBetweenStorage { storage_from, storage_to, owner }, instruction::MoveBetweenStorage { amount })
+}
+
+pub fn move_storage(owner: Pubkey, storage: Pubkey, from_location: Pubkey, to_location: Pubkey) -> Instruction {
+    build(accounts::MoveStorage { storage, from_location, to_location, owner }, instruction::MoveStorage {})
+}
+
+pub fn update_storage_move_status(owner: Pubkey, storage: Pubkey) -> Instruction {
+    build(accounts::UpdateStorageMoveStatus { storage, owner }, instruction::UpdateStorageMoveStatus {})
+}
+
+// [recipient] owns [storage_to], their allow-list is checked if they have one.
+pub fn transfer_to_player(sender: Pubkey, storage_from: Pubkey, storage_to: Pubkey, recipient: Pubkey, amount: i64) -> Instruction {
+    let allow_list = pda::transfer_allow_list(&recipient).0;
+    build(accounts::TransferToPlayer { storage_from, storage_to, allow_list, sender }, instruction::TransferToPlayer { amount })
+}
+
+pub fn init_transfer_allow_list(owner: Pubkey, allowed: Vec<Pubkey>) -> Instruction {
+    build(
+        accounts::InitTransferAllowList { allow_list: pda::transfer_allow_list(&owner).0, owner, system_program: system_program::ID },
+        instruction::InitTransferAllowList { allowed },
+    )
+}
+
+pub fn update_transfer_allow_list(owner: Pubkey, allowed: Vec<Pubkey>) -> Instruction {
+    build(
+        accounts::UpdateTransferAllowList { allow_list: pda::transfer_allow_list(&owner).0, owner },
+        instruction::UpdateTransferAllowList { allowed },
+    )
+}
+
+// Processors -------------------------------
+
+// [processor] is a new keypair that signs along with [owner].
+pub fn init_processor(owner: Pubkey, processor: Pubkey, game: Pubkey, location: Pubkey, spec: ProcessorSpec) -> Instruction {
+    build(accounts::InitProcessor { processor, game, location, owner, system_program: system_program::ID }, instruction::InitProcessor { spec })
+}
+
+// [resource] is the output of the processor, [storage_fuel] can be any storage when it burns nothing.
+pub fn produce_without_input(processor: Pubkey, resource: Pubkey, storage: Pubkey, storage_fuel: Pubkey) -> Instruction {
+    build(produce_accounts(processor, resource, storage, storage_fuel), instruction::ProduceWithoutInput {})
+}
+
+fn produce_accounts(processor: Pubkey, resource: Pubkey, storage: Pubkey, storage_fuel: Pubkey) -> accounts::ProcessesResource {
+    accounts::ProcessesResource { processor, queue: pda::production_queue(&processor).0, resource, storage, storage_fuel }
+}
+
+pub fn produce_with_one_input(processor: Pubkey, resource_to_produce: Pubkey, storage: Pubkey, storage_input: Pubkey, storage_fuel: Pubkey) -> Instruction {
+    build(produce_with_one_input_accounts(processor, resource_to_produce, storage, storage_input, storage_fuel), instruction::ProduceWithOneInput {})
+}
+
+fn produce_with_one_input_accounts(processor: Pubkey, resource_to_produce: Pubkey, storage: Pubkey, storage_input: Pubkey, storage_fuel: Pubkey) -> accounts::ProcessesResourceWith1Input {
+    accounts::ProcessesResourceWith1Input { processor, queue: pda::production_queue(&processor).0, resource_to_produce, storage, storage_input, storage_fuel }
+}
+
+// [inputs] in the order of the inputs of [resource_to_produce].
+pub fn produce_with_two_inputs(processor: Pubkey, resource_to_produce: Pubkey, storage: Pubkey, inputs: (Pubkey, Pubkey), storage_fuel: Pubkey) -> Instruction {
+    build(produce_with_two_inputs_accounts(processor, resource_to_produce, storage, inputs, storage_fuel), instruction::ProduceWithTwoInputs {})
+}
+
+fn produce_with_two_inputs_accounts(processor: Pubkey, resource_to_produce: Pubkey, storage: Pubkey, inputs: (Pubkey, Pubkey), storage_fuel: Pubkey) -> accounts::ProcessesResourceWith2Inputs {
+    accounts::ProcessesResourceWith2Inputs {
+        processor,
+        queue: pda::production_queue(&processor).0,
+        resource_to_produce,
+        storage,
+        storage_input_1: inputs.0,
+        storage_input_2: inputs.1,
+        storage_fuel,
+    }
+}
+
+pub fn upgrade_processor(owner: Pubkey, game: Pubkey, processor: Pubkey, storage_payment: Pubkey) -> Instruction {
+    build(accounts::UpgradeProcessor { game, processor, storage_payment, owner }, instruction::UpgradeProcessor {})
+}
+
+pub fn pause_processor(owner: Pubkey, processor: Pubkey) -> Instruction {
+    build(accounts::PauseProcessor { processor, owner }, instruction::PauseProcessor {})
+}
+
+pub fn resume_processor(owner: Pubkey, processor: Pubkey) -> Instruction {
+    build(accounts::PauseProcessor { processor, owner }, instruction::ResumeProcessor {})
+}
+
+// [resource] is the current output, [storages] are the output and fuel storages claimed into and burnt from first.
+pub fn retool_processor(owner: Pubkey, game: Pubkey, processor: Pubkey, resource: Pubkey, storages: (Pubkey, Pubkey), output_resource_id: Pubkey) -> Instruction {
+    build(retool_accounts(owner, game, processor, resource, storages), instruction::RetoolProcessor { output_resource_id })
+}
+
+fn retool_accounts(owner: Pubkey, game: Pubkey, processor: Pubkey, resource: Pubkey, storages: (Pubkey, Pubkey)) -> accounts::RetoolProcessor {
+    accounts::RetoolProcessor {
+        game,
+        processor,
+        queue: pda::production_queue(&processor).0,
+        resource,
+        storage_out: storages.0,
+        storage_fuel: storages.1,
+        owner,
+    }
+}
+
+pub fn repair_processor(owner: Pubkey, game: Pubkey, processor: Pubkey, storage_payment: Pubkey, points: i64) -> Instruction {
+    build(accounts::RepairProcessor { game, processor, storage_payment, owner }, instruction::RepairProcessor { points })
+}
+
+pub fn move_processor(owner: Pubkey, processor: Pubkey, from_location: Pubkey, to_location: Pubkey) -> Instruction {
+    build(accounts::MoveProcessor { processor, from_location, to_location, owner }, instruction::MoveProcessor {})
+}
+
+// [output] is the current output of the processor.
+pub fn link_storage(owner: Pubkey, processor: Pubkey, output: Pubkey, storage: Pubkey) -> Instruction {
+    build(accounts::LinkStorage { processor, output, storage, owner }, instruction::LinkStorage {})
+}
+
+pub fn unlink_storage(owner: Pubkey, processor: Pubkey, storage: Pubkey) -> Instruction {
+    build(accounts::UnlinkStorage { processor, storage, owner }, instruction::UnlinkStorage {})
+}
+
+// Production queues ------------------------
+
+pub fn init_production_queue(owner: Pubkey, processor: Pubkey) -> Instruction {
+    build(
+        accounts::InitProductionQueue { queue: pda::production_queue(&processor).0, processor, owner, system_program: system_program::ID },
+        instruction::InitProductionQueue {},
+    )
+}
+
+// [inputs] are the storages of the owner holding the inputs of [recipe].
+pub fn enqueue_job(owner: Pubkey, processor: Pubkey, recipe: Pubkey, inputs: &[Pubkey], quantity: i64, priority: u8) -> Instruction {
+    let queue = pda::production_queue(&processor).0;
+    build_with_remaining(accounts::EnqueueJob { queue, processor, recipe, owner }, instruction::EnqueueJob { quantity, priority }, &writable(inputs))
+}
+
+pub fn claim_jobs(processor: Pubkey, storage_out: Pubkey, storage_fuel: Pubkey) -> Instruction {
+    let queue = pda::production_queue(&processor).0;
+    build(accounts::ClaimJobs { queue, processor, storage_out, storage_fuel }, instruction::ClaimJobs {})
+}
+
+// [inputs] are the storages of the owner the unused inputs of the job go back to.
+pub fn cancel_job(owner: Pubkey, processor: Pubkey, recipe: Pubkey, inputs: &[Pubkey], job_id: u64) -> Instruction {
+    let queue = pda::production_queue(&processor).0;
+    build_with_remaining(accounts::CancelJob { queue, processor, recipe, owner }, instruction::CancelJob { job_id }, &writable(inputs))
+}
+
+// Logistics --------------------------------
+
+// A sender processor with the storages it ships between and burns fuel from, [storage_to] at the destination.
+pub struct Sender {
+    pub processor: Pubkey,
+    pub storage_from: Pubkey,
+    pub storage_to: Pubkey,
+    pub storage_fuel: Pubkey,
+}
+
+// [shipment] is a new keypair that signs along with [owner], [route] runs between two locations of the owner.
+pub fn send(owner: Pubkey, sender: &Sender, resource: Pubkey, shipment: Pubkey, route: [(i64, i64); 2], send_amount: i64) -> Instruction {
+    let [from, to] = route;
+    let args = instruction::Send { send_amount, from_x: from.0, from_y: from.1, to_x: to.0, to_y: to.1 };
+    build(send_accounts(owner, sender, resource, shipment, route), args)
+}
+
+fn send_accounts(owner: Pubkey, sender: &Sender, resource: Pubkey, shipment: Pubkey, route: [(i64, i64); 2]) -> accounts::SendResource {
+    let [from, to] = route;
+    accounts::SendResource {
+        processor: sender.processor,
+        resource_to_produce: resource,
+        storage: sender.storage_to,
+        storage_input: sender.storage_from,
+        storage_fuel: sender.storage_fuel,
+        shipment,
+        from_location: pda::map_location(&owner, from.0, from.1).0,
+        to_location: pda::map_location(&owner, to.0, to.1).0,
+        owner,
+        system_program: system_program::ID,
+    }
+}
+
+// [owner] gets the rent of the shipment back. A shipment of a trade route names the route.
+pub fn receive_shipment(owner: Pubkey, shipment: Pubkey, storage_to: Pubkey, trade_route: Option<Pubkey>) -> Instruction {
+    build_with_remaining(accounts::ReceiveShipment { shipment, storage_to, owner }, instruction::ReceiveShipment {}, &writable(trade_route.as_slice()))
+}
+
+// [trade_route] is a new keypair that signs along with [owner]. [locations] are those of the sender and of its storage_to.
+pub fn create_trade_route(owner: Pubkey, trade_route: Pubkey, sender: &Sender, locations: (Pubkey, Pubkey), amount_per_cycle: i64, cadence: i64) -> Instruction {
+    build(create_trade_route_accounts(owner, trade_route, sender, locations), instruction::CreateTradeRoute { amount_per_cycle, cadence })
+}
+
+fn create_trade_route_accounts(owner: Pubkey, trade_route: Pubkey, sender: &Sender, locations: (Pubkey, Pubkey)) -> accounts::CreateTradeRoute {
+    accounts::CreateTradeRoute {
+        trade_route,
+        sender: sender.processor,
+        storage_from: sender.storage_from,
+        storage_to: sender.storage_to,
+        storage_fuel: sender.storage_fuel,
+        from_location: locations.0,
+        to_location: locations.1,
+        owner,
+        system_program: system_program::ID,
+    }
+}
+
+// Anyone can run a due route, [shipment] is a new keypair that signs along with [payer].
+pub fn run_trade_route(payer: Pubkey, trade_route: Pubkey, sender: &Sender, shipment: Pubkey) -> Instruction {
+    build(run_trade_route_accounts(payer, trade_route, sender, shipment), instruction::RunTradeRoute {})
+}
+
+fn run_trade_route_accounts(payer: Pubkey, trade_route: Pubkey, sender: &Sender, shipment: Pubkey) -> accounts::RunTradeRoute {
+    accounts::RunTradeRoute {
+        trade_route,
+        sender: sender.processor,
+        storage_from: sender.storage_from,
+        storage_to: sender.storage_to,
+        storage_fuel: sender.storage_fuel,
+        shipment,
+        payer,
+        system_program: system_program::ID,
+    }
+}
+
+pub fn close_trade_route(owner: Pubkey, trade_route: Pubkey) -> Instruction {
+    build(accounts::CloseTradeRoute { trade_route, owner }, instruction::CloseTradeRoute {})
+}
+
+// Market -----------------------------------
+
+// [offer] is a new keypair that signs along with [maker].
+pub fn create_offer(maker: Pubkey, offer: Pubkey, maker_storage: Pubkey, offered_amount: i64, requested_resource_id: Pubkey, requested_amount: i64) -> Instruction {
+    build(
+        accounts::CreateOffer { offer, escrow: pda::offer_escrow(&offer).0, maker_storage, maker, system_program: system_program::ID },
+        instruction::CreateOffer { offered_amount, requested_resource_id, requested_amount },
+    )
+}
+
+// [taker_storages] pay the requested resource and receive the offered one.
+pub fn accept_offer(taker: Pubkey, offer: Pubkey, maker: Pubkey, maker_storage: Pubkey, taker_storages: (Pubkey, Pubkey)) -> Instruction {
+    build(
+        accounts::AcceptOffer {
+            offer,
+            escrow: pda::offer_escrow(&offer).0,
+            maker_storage,
+            taker_storage_from: taker_storages.0,
+            taker_storage_to: taker_storages.1,
+            maker,
+            taker,
+        },
+        instruction::AcceptOffer {},
+    )
+}
+
+pub fn cancel_offer(maker: Pubkey, offer: Pubkey, maker_storage: Pubkey) -> Instruction {
+    build(accounts::CancelOffer { offer, escrow: pda::offer_escrow(&offer).0, maker_storage, maker }, instruction::CancelOffer {})
+}
+
+pub fn init_order_book(owner: Pubkey, location: Pubkey, base_resource_id: Pubkey, quote_resource_id: Pubkey) -> Instruction {
+    let book = OrderBookKeys::new(&location, &base_resource_id, &quote_resource_id);
+    build(
+        accounts::InitOrderBook {
+            order_book: book.address,
+            base_vault: book.base_vault,
+            quote_vault: book.quote_vault,
+            location,
+            owner,
+            system_program: system_program::ID,
+        },
+        instruction::InitOrderBook { base_resource_id, quote_resource_id },
+    )
+}
+
+// [storages] hold the base and the quote resource of [owner].
+pub fn place_order(owner: Pubkey, book: &OrderBookKeys, storages: (Pubkey, Pubkey), side: OrderSide, price: i64, amount: i64) -> Instruction {
+    build(
+        accounts::PlaceOrder {
+            order_book: book.address,
+            base_vault: book.base_vault,
+            quote_vault: book.quote_vault,
+            base_storage: storages.0,
+            quote_storage: storages.1,
+            owner,
+            system_program: system_program::ID,
+        },
+        instruction::PlaceOrder { side, price, amount },
+    )
+}
+
+pub fn cancel_order(owner: Pubkey, book: &OrderBookKeys, storages: (Pubkey, Pubkey), order_id: u64) -> Instruction {
+    build(
+        accounts::CancelOrder {
+            order_book: book.address,
+            base_vault: book.base_vault,
+            quote_vault: book.quote_vault,
+            base_storage: storages.0,
+            quote_storage: storages.1,
+            owner,
+            system_program: system_program::ID,
+        },
+        instruction::CancelOrder { order_id },
+    )
+}
+
+pub fn claim_order(owner: Pubkey, book: &OrderBookKeys, storages: (Pubkey, Pubkey), order_id: u64) -> Instruction {
+    build(
+        accounts::ClaimOrder {
+            order_book: book.address,
+            base_vault: book.base_vault,
+            quote_vault: book.quote_vault,
+            base_storage: storages.0,
+            quote_storage: storages.1,
+            owner,
+            system_program: system_program::ID,
+        },
+        instruction::ClaimOrder { order_id },
+    )
+}
+
+// The resources may be given in any order, the pool is created with the lower key first.
+pub fn create_pool(owner: Pubkey, game: Pubkey, location: Pubkey, resource_1: Pubkey, resource_2: Pubkey) -> Instruction {
+    let pool = PoolKeys::new(&game, &location, &resource_1, &resource_2);
+    build(
+        accounts::CreatePool {
+            game,
+            pool: pool.address,
+            reserve_a: pool.reserve_a,
+            reserve_b: pool.reserve_b,
+            location,
+            owner,
+            system_program: system_program::ID,
+        },
+        instruction::CreatePool { resource_a_id: pool.resource_a, resource_b_id: pool.resource_b },
+    )
+}
+
+// [storages] and [amounts] in the order of the pool, resource a first.
+pub fn add_liquidity(owner: Pubkey, pool: &PoolKeys, storages: (Pubkey, Pubkey), amounts: (i64, i64), min_shares: i64) -> Instruction {
+    build(
+        accounts::AddLiquidity {
+            pool: pool.address,
+            reserve_a: pool.reserve_a,
+            reserve_b: pool.reserve_b,
+            storage_a: storages.0,
+            storage_b: storages.1,
+            position: pda::liquidity_position(&pool.address, &owner).0,
+            owner,
+            system_program: system_program::ID,
+        },
+        instruction::AddLiquidity { amount_a: amounts.0, amount_b: amounts.1, min_shares },
+    )
+}
+
+pub fn remove_liquidity(owner: Pubkey, pool: &PoolKeys, storages: (Pubkey, Pubkey), shares: i64, min_amounts: (i64, i64)) -> Instruction {
+    build(
+        accounts::RemoveLiquidity {
+            pool: pool.address,
+            reserve_a: pool.reserve_a,
+            reserve_b: pool.reserve_b,
+            storage_a: storages.0,
+            storage_b: storages.1,
+            position: pda::liquidity_position(&pool.address, &owner).0,
+            owner,
+        },
+        instruction::RemoveLiquidity { shares, min_amount_a: min_amounts.0, min_amount_b: min_amounts.1 },
+    )
+}
+
+pub fn swap(owner: Pubkey, pool: &PoolKeys, storage_in: Pubkey, storage_out: Pubkey, amount_in: i64, min_amount_out: i64) -> Instruction {
+    build(
+        accounts::Swap {
+            game: pool.game,
+            pool: pool.address,
+            reserve_a: pool.reserve_a,
+            reserve_b: pool.reserve_b,
+            storage_in,
+            storage_out,
+            owner,
+        },
+        instruction::Swap { amount_in, min_amount_out },
+    )
+}
+
+// Auctions ---------------------------------
+
+pub fn create_location_auction(seller: Pubkey, location: Pubkey, bid_resource_id: Pubkey, min_bid: i64, duration: i64) -> Instruction {
+    build(create_location_auction_accounts(seller, location), instruction::CreateLocationAuction { bid_resource_id, min_bid, duration })
+}
+
+fn create_location_auction_accounts(seller: Pubkey, location: Pubkey) -> accounts::CreateLocationAuction {
+    let auction = pda::auction(&location).0;
+    accounts::CreateLocationAuction { auction, bid_escrow: pda::auction_bid(&auction).0, location, seller, system_program: system_program::ID }
+}
+
+pub fn create_processor_auction(seller: Pubkey, processor: Pubkey, bid_resource_id: Pubkey, min_bid: i64, duration: i64) -> Instruction {
+    build(create_processor_auction_accounts(seller, processor), instruction::CreateProcessorAuction { bid_resource_id, min_bid, duration })
+}
+
+fn create_processor_auction_accounts(seller: Pubkey, processor: Pubkey) -> accounts::CreateProcessorAuction {
+    let auction = pda::auction(&processor).0;
+    accounts::CreateProcessorAuction {
+        auction,
+        bid_escrow: pda::auction_bid(&auction).0,
+        processor,
+        queue: pda::production_queue(&processor).0,
+        seller,
+        system_program: system_program::ID,
+    }
+}
+
+// [amount] of [seller_storage] goes into escrow until the auction is settled.
+pub fn create_goods_auction(seller: Pubkey, seller_storage: Pubkey, amount: i64, bid_resource_id: Pubkey, min_bid: i64, duration: i64) -> Instruction {
+    build(create_goods_auction_accounts(seller, seller_storage), instruction::CreateGoodsAuction { amount, bid_resource_id, min_bid, duration })
+}
+
+fn create_goods_auction_accounts(seller: Pubkey, seller_storage: Pubkey) -> accounts::CreateGoodsAuction {
+    let auction = pda::auction(&seller_storage).0;
+    accounts::CreateGoodsAuction {
+        auction,
+        bid_escrow: pda::auction_bid(&auction).0,
+        goods_escrow: pda::auction_goods(&auction).0,
+        seller_storage,
+        seller,
+        system_program: system_program::ID,
+    }
+}
+
+// [highest_bidder] is the one being outbid, the default key before the first bid.
+pub fn place_bid(bidder: Pubkey, auction: Pubkey, bidder_storage: Pubkey, highest_bidder: Pubkey, amount: i64) -> Instruction {
+    build(place_bid_accounts(bidder, auction, bidder_storage, highest_bidder), instruction::PlaceBid { amount })
+}
+
+fn place_bid_accounts(bidder: Pubkey, auction: Pubkey, bidder_storage: Pubkey, highest_bidder: Pubkey) -> accounts::PlaceBid {
+    accounts::PlaceBid {
+        auction,
+        bid_escrow: pda::auction_bid(&auction).0,
+        refund_escrow: pda::auction_refund(&auction, &highest_bidder).0,
+        bidder_storage,
+        bidder,
+        system_program: system_program::ID,
+    }
+}
+
+pub fn claim_bid_refund(bidder: Pubkey, auction: Pubkey, bidder_storage: Pubkey) -> Instruction {
+    build(
+        accounts::ClaimBidRefund { refund_escrow: pda::auction_refund(&auction, &bidder).0, bidder_storage, bidder },
+        instruction::ClaimBidRefund {},
+    )
+}
+
+// Anyone can settle an auction that has ended, [seller_storage] receives the winning bid.
+pub fn settle_location_auction(settler: Pubkey, seller: Pubkey, location: Pubkey, seller_storage: Pubkey) -> Instruction {
+    build(settle_location_auction_accounts(settler, seller, location, seller_storage), instruction::SettleLocationAuction {})
+}
+
+fn settle_location_auction_accounts(settler: Pubkey, seller: Pubkey, location: Pubkey, seller_storage: Pubkey) -> accounts::SettleLocationAuction {
+    let auction = pda::auction(&location).0;
+    accounts::SettleLocationAuction { auction, bid_escrow: pda::auction_bid(&auction).0, seller_storage, location, seller, settler }
+}
+
+// [location] is where the processor is.
+pub fn settle_processor_auction(settler: Pubkey, seller: Pubkey, processor: Pubkey, location: Pubkey, seller_storage: Pubkey) -> Instruction {
+    build(settle_processor_auction_accounts(settler, seller, processor, location, seller_storage), instruction::SettleProcessorAuction {})
+}
+
+fn settle_processor_auction_accounts(settler: Pubkey, seller: Pubkey, processor: Pubkey, location: Pubkey, seller_storage: Pubkey) -> accounts::SettleProcessorAuction {
+    let auction = pda::auction(&processor).0;
+    accounts::SettleProcessorAuction { auction, bid_escrow: pda::auction_bid(&auction).0, seller_storage, processor, location, seller, settler }
+}
+
+// [goods_storage] is the storage the goods were auctioned from, [goods_to] a storage of the winner, or of
+// the seller when nobody bid.
+pub fn settle_goods_auction(settler: Pubkey, seller: Pubkey, goods_storage: Pubkey, seller_storage: Pubkey, goods_to: Pubkey) -> Instruction {
+    build(settle_goods_auction_accounts(settler, seller, goods_storage, seller_storage, goods_to), instruction::SettleGoodsAuction {})
+}
+
+fn settle_goods_auction_accounts(settler: Pubkey, seller: Pubkey, goods_storage: Pubkey, seller_storage: Pubkey, goods_to: Pubkey) -> accounts::SettleGoodsAuction {
+    let auction = pda::auction(&goods_storage).0;
+    accounts::SettleGoodsAuction {
+        auction,
+        bid_escrow: pda::auction_bid(&auction).0,
+        goods_escrow: pda::auction_goods(&auction).0,
+        seller_storage,
+        goods_to,
+        seller,
+        settler,
+    }
+}
+
+// Units ------------------------------------
+
+// Units start at a location of the [game] owner.
+pub fn init_unit(owner: Pubkey, name: &str, x: i64, y: i64, game: Pubkey) -> Instruction {
+    build(
+        accounts::InitUnit { unit: pda::unit(&owner, name).0, location: pda::map_location(&game, x, y).0, owner, system_program: system_program::ID },
+        instruction::InitUnit { name: name.to_string(), x, y, game },
+    )
+}
+
+pub fn move_unit_start(owner: Pubkey, name: &str, from: (i64, i64), to: (i64, i64)) -> Instruction {
+    build(
+        move_unit_start_accounts(owner, name, from, to),
+        instruction::MoveUnitStart { from_x: from.0, from_y: from.1, to_x: to.0, to_y: to.1, name: name.to_string() },
+    )
+}
+
+fn move_unit_start_accounts(owner: Pubkey, name: &str, from: (i64, i64), to: (i64, i64)) -> accounts::MoveUnitStart {
+    accounts::MoveUnitStart {
+        unit: pda::unit(&owner, name).0,
+        from_location: pda::map_location(&owner, from.0, from.1).0,
+        to_location: pda::map_location(&owner, to.0, to.1).0,
+        owner,
+        system_program: system_program::ID,
+    }
+}
+
+pub fn move_unit_complete(owner: Pubkey, name: &str, to: (i64, i64), map: Pubkey) -> Instruction {
+    build(move_unit_complete_accounts(owner, name, to, map), instruction::MoveUnitComplete { to_x: to.0, to_y: to.1, name: name.to_string() })
+}
+
+fn move_unit_complete_accounts(owner: Pubkey, name: &str, to: (i64, i64), map: Pubkey) -> accounts::MoveUnitComplete {
+    accounts::MoveUnitComplete {
+        unit: pda::unit(&owner, name).0,
+        to_location: pda::map_location(&owner, to.0, to.1).0,
+        map,
+        owner,
+        system_program: system_program::ID,
+    }
+}
+
+// Migrations -------------------------------
+
+// [payer] covers the rent of the extra version byte.
+fn migrate_accounts(payer: Pubkey, account: Pubkey) -> accounts::MigrateAccount {
+    accounts::MigrateAccount { account, payer, system_program: system_program::ID }
+}
+
+pub fn migrate_location(payer: Pubkey, account: Pubkey) -> Instruction {
+    build(migrate_accounts(payer, account), instruction::MigrateLocation {})
+}
+
+pub fn migrate_storage(payer: Pubkey, account: Pubkey) -> Instruction {
+    build(migrate_accounts(payer, account), instruction::MigrateStorage {})
+}
+
+pub fn migrate_processor(payer: Pubkey, account: Pubkey) -> Instruction {
+    build(migrate_accounts(payer, account), instruction::MigrateProcessor {})
+}
+
+pub fn migrate_unit(payer: Pubkey, account: Pubkey) -> Instruction {
+    build(migrate_accounts(payer, account), instruction::MigrateUnit {})
+}
+
+pub fn migrate_game(payer: Pubkey, account: Pubkey) -> Instruction {
+    build(migrate_accounts(payer, account), instruction::MigrateGame {})
+}
+
+pub fn migrate_resource(payer: Pubkey, account: Pubkey) -> Instruction {
+    build(migrate_accounts(payer, account), instruction::MigrateResource {})
+}
+
+pub fn migrate_game_tile(payer: Pubkey, account: Pubkey) -> Instruction {
+    build(migrate_accounts(payer, account), instruction::MigrateGameTile {})
+}
+
+pub fn migrate_map(payer: Pubkey, account: Pubkey) -> Instruction {
+    build(migrate_accounts(payer, account), instruction::MigrateMap {})
+}
+
+pub fn migrate_offer(payer: Pubkey, account: Pubkey) -> Instruction {
+    build(migrate_accounts(payer, account), instruction::MigrateOffer {})
+}
+
+pub fn migrate_order_book(payer: Pubkey, account: Pubkey) -> Instruction {
+    build(migrate_accounts(payer, account), instruction::MigrateOrderBook {})
+}
+
+pub fn migrate_pool(payer: Pubkey, account: Pubkey) -> Instruction {
+    build(migrate_accounts(payer, account), instruction::MigratePool {})
+}
+
+pub fn migrate_liquidity_position(payer: Pubkey, account: Pubkey) -> Instruction {
+    build(migrate_accounts(payer, account), instruction::MigrateLiquidityPosition {})
+}
+
+pub fn migrate_auction(payer: Pubkey, account: Pubkey) -> Instruction {
+    build(migrate_accounts(payer, account), instruction::MigrateAuction {})
+}
+
+pub fn migrate_shipment(payer: Pubkey, account: Pubkey) -> Instruction {
+    build(migrate_accounts(payer, account), instruction::MigrateShipment {})
+}
+
+pub fn migrate_trade_route(payer: Pubkey, account: Pubkey) -> Instruction {
+    build(migrate_accounts(payer, account), instruction::MigrateTradeRoute {})
+}
+
+pub fn migrate_production_queue(payer: Pubkey, account: Pubkey) -> Instruction {
+    build(migrate_accounts(payer, account), instruction::MigrateProductionQueue {})
+}
+
+pub fn migrate_transfer_allow_list(payer: Pubkey, account: Pubkey) -> Instruction {
+    build(migrate_accounts(payer, account), instruction::MigrateTransferAllowList {})
+}
+
+// Debug ------------------------------------
+
+pub fn debug_init_stuff(owner: Pubkey, x: i64) -> Instruction {
+    build(
+        accounts::InitStuff { stuff: pda::stuff(&owner, x).0, owner, admin: debug_authority(owner), system_program: system_program::ID },
+        instruction::DebugInitStuff { x },
+    )
+}
+
+pub fn debug_set_storage_amount(authority: Pubkey, storage: Pubkey, amount: i64) -> Instruction {
+    build(
+        accounts::DebugSetStorageAmount { storage, admin: debug_authority(authority) },
+        instruction::DebugSetStorageAmount { amount },
+    )
+}
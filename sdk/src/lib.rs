@@ -0,0 +1,9 @@
+// Client side of got_a_min: PDAs, instructions and account decoding for bots and tools.
+
+pub mod accounts;
+pub mod instructions;
+pub mod pda;
+
+pub use got_a_min;
+pub use got_a_min_rules as rules;
+pub use got_a_min::{errors, events, state, ID};
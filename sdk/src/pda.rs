@@ -0,0 +1,227 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+
+use got_a_min::ID;
+
+// Every address comes with its bump, in the order find_program_address returns them.
+
+// Locations created by init_location, [x] and [y] as i64 little-endian.
+pub fn map_location(owner: &Pubkey, x: i64, y: i64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"map-location", owner.as_ref(), &x.to_le_bytes(), &y.to_le_bytes()], &ID)
+}
+
+// Locations of the game tile and simple storage instructions, one byte per coordinate.
+pub fn map_location_xy(owner: &Pubkey, xy: [u8; 2]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"map-location", owner.as_ref(), &xy], &ID)
+}
+
+pub fn map(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"map", owner.as_ref()], &ID)
+}
+
+pub fn game_tile(owner: &Pubkey, xy: [u8; 2]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"game-tile", owner.as_ref(), &xy], &ID)
+}
+
+pub fn unit(owner: &Pubkey, name: &str) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"unit", owner.as_ref(), name.as_bytes()], &ID)
+}
+
+pub fn stuff(owner: &Pubkey, x: i64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"stuff", owner.as_ref(), &x.to_le_bytes()], &ID)
+}
+
+pub fn game(authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"game", authority.as_ref()], &ID)
+}
+
+pub fn game_clock() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"game-clock"], &ID)
+}
+
+pub fn production_queue(processor: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"production-queue", processor.as_ref()], &ID)
+}
+
+pub fn transfer_allow_list(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"transfer-allow-list", owner.as_ref()], &ID)
+}
+
+pub fn offer_escrow(offer: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"offer-escrow", offer.as_ref()], &ID)
+}
+
+pub fn order_book(location: &Pubkey, base_resource: &Pubkey, quote_resource: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"order-book", location.as_ref(), base_resource.as_ref(), quote_resource.as_ref()], &ID)
+}
+
+pub fn order_book_vault(order_book: &Pubkey, resource: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"order-book-vault", order_book.as_ref(), resource.as_ref()], &ID)
+}
+
+// The program keeps the lower resource key first, so the order of [resource_1] and [resource_2] doesn't matter.
+pub fn pool(game: &Pubkey, location: &Pubkey, resource_1: &Pubkey, resource_2: &Pubkey) -> (Pubkey, u8) {
+    let (resource_a, resource_b) = sorted_pair(resource_1, resource_2);
+    Pubkey::find_program_address(&[b"pool", game.as_ref(), location.as_ref(), resource_a.as_ref(), resource_b.as_ref()], &ID)
+}
+
+pub fn pool_reserve(pool: &Pubkey, resource: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"pool-reserve", pool.as_ref(), resource.as_ref()], &ID)
+}
+
+pub fn liquidity_position(pool: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"liquidity", pool.as_ref(), owner.as_ref()], &ID)
+}
+
+// [item] is the auctioned location, processor or seller storage.
+pub fn auction(item: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"auction", item.as_ref()], &ID)
+}
+
+pub fn auction_bid(auction: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"auction-bid", auction.as_ref()], &ID)
+}
+
+pub fn auction_goods(auction: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"auction-goods", auction.as_ref()], &ID)
+}
+
+// Where the bids of [bidder] go once they're outbid.
+pub fn auction_refund(auction: &Pubkey, bidder: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"auction-refund", auction.as_ref(), bidder.as_ref()], &ID)
+}
+
+// Holds the upgrade authority checked by the debug instructions.
+pub fn program_data() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ID.as_ref()], &bpf_loader_upgradeable::ID)
+}
+
+pub fn sorted_pair<'a>(resource_1: &'a Pubkey, resource_2: &'a Pubkey) -> (&'a Pubkey, &'a Pubkey) {
+    match resource_1 < resource_2 {
+        true => (resource_1, resource_2),
+        false => (resource_2, resource_1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // [found] must be the address of exactly [seeds] with its bump, spelled out the way the program does.
+    fn assert_seeds(found: (Pubkey, u8), seeds: &[&[u8]]) {
+        let bump = [found.1];
+        let mut with_bump = seeds.to_vec();
+        with_bump.push(&bump);
+        assert_eq!(Pubkey::create_program_address(&with_bump, &ID).unwrap(), found.0);
+        assert_eq!(Pubkey::find_program_address(seeds, &ID), found);
+    }
+
+    #[test]
+    fn map_location_encodes_coordinates_as_i64_le() {
+        let owner = Pubkey::new_unique();
+        let minus_two = [0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+        assert_seeds(map_location(&owner, 1, -2), &[b"map-location", owner.as_ref(), &[1, 0, 0, 0, 0, 0, 0, 0], &minus_two]);
+        assert_ne!(map_location(&owner, 1, -2), map_location(&owner, -2, 1));
+    }
+
+    #[test]
+    fn map_location_xy_encodes_one_byte_per_coordinate() {
+        let owner = Pubkey::new_unique();
+        assert_seeds(map_location_xy(&owner, [3, 4]), &[b"map-location", owner.as_ref(), &[3, 4]]);
+        assert_ne!(map_location_xy(&owner, [3, 4]).0, map_location(&owner, 3, 4).0);
+    }
+
+    #[test]
+    fn map_is_seeded_by_owner() {
+        let owner = Pubkey::new_unique();
+        assert_seeds(map(&owner), &[b"map", owner.as_ref()]);
+    }
+
+    #[test]
+    fn game_tile_encodes_one_byte_per_coordinate() {
+        let owner = Pubkey::new_unique();
+        assert_seeds(game_tile(&owner, [0, 255]), &[b"game-tile", owner.as_ref(), &[0, 255]]);
+    }
+
+    #[test]
+    fn unit_is_seeded_by_name_bytes() {
+        let owner = Pubkey::new_unique();
+        assert_seeds(unit(&owner, "scout"), &[b"unit", owner.as_ref(), b"scout"]);
+    }
+
+    #[test]
+    fn stuff_encodes_x_as_i64_le() {
+        let owner = Pubkey::new_unique();
+        assert_seeds(stuff(&owner, 258), &[b"stuff", owner.as_ref(), &[2, 1, 0, 0, 0, 0, 0, 0]]);
+    }
+
+    #[test]
+    fn game_is_seeded_by_authority() {
+        let authority = Pubkey::new_unique();
+        assert_seeds(game(&authority), &[b"game", authority.as_ref()]);
+    }
+
+    #[test]
+    fn game_clock_is_one_per_program() {
+        assert_seeds(game_clock(), &[b"game-clock"]);
+    }
+
+    #[test]
+    fn production_queue_is_seeded_by_processor() {
+        let processor = Pubkey::new_unique();
+        assert_seeds(production_queue(&processor), &[b"production-queue", processor.as_ref()]);
+    }
+
+    #[test]
+    fn transfer_allow_list_is_seeded_by_owner() {
+        let owner = Pubkey::new_unique();
+        assert_seeds(transfer_allow_list(&owner), &[b"transfer-allow-list", owner.as_ref()]);
+    }
+
+    #[test]
+    fn offer_escrow_is_seeded_by_offer() {
+        let offer = Pubkey::new_unique();
+        assert_seeds(offer_escrow(&offer), &[b"offer-escrow", offer.as_ref()]);
+    }
+
+    #[test]
+    fn order_book_keeps_base_before_quote() {
+        let (location, base, quote) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let book = order_book(&location, &base, &quote);
+        assert_seeds(book, &[b"order-book", location.as_ref(), base.as_ref(), quote.as_ref()]);
+        assert_ne!(book, order_book(&location, &quote, &base));
+        assert_seeds(order_book_vault(&book.0, &base), &[b"order-book-vault", book.0.as_ref(), base.as_ref()]);
+    }
+
+    #[test]
+    fn pool_sorts_its_resources() {
+        let (game, location) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (low, high) = (Pubkey::new_from_array([1; 32]), Pubkey::new_from_array([2; 32]));
+        let pool_address = pool(&game, &location, &high, &low);
+        assert_seeds(pool_address, &[b"pool", game.as_ref(), location.as_ref(), low.as_ref(), high.as_ref()]);
+        assert_eq!(pool_address, pool(&game, &location, &low, &high));
+        assert_seeds(pool_reserve(&pool_address.0, &low), &[b"pool-reserve", pool_address.0.as_ref(), low.as_ref()]);
+    }
+
+    #[test]
+    fn liquidity_position_is_seeded_by_pool_and_owner() {
+        let (pool, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_seeds(liquidity_position(&pool, &owner), &[b"liquidity", pool.as_ref(), owner.as_ref()]);
+    }
+
+    #[test]
+    fn auction_escrows_are_seeded_by_auction() {
+        let (item, bidder) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let auction_address = auction(&item);
+        assert_seeds(auction_address, &[b"auction", item.as_ref()]);
+        assert_seeds(auction_bid(&auction_address.0), &[b"auction-bid", auction_address.0.as_ref()]);
+        assert_seeds(auction_goods(&auction_address.0), &[b"auction-goods", auction_address.0.as_ref()]);
+        assert_seeds(auction_refund(&auction_address.0, &bidder), &[b"auction-refund", auction_address.0.as_ref(), bidder.as_ref()]);
+    }
+
+    #[test]
+    fn program_data_belongs_to_the_upgradeable_loader() {
+        let (address, bump) = program_data();
+        assert_eq!(Pubkey::create_program_address(&[ID.as_ref(), &[bump]], &bpf_loader_upgradeable::ID).unwrap(), address);
+    }
+}
@@ -11,6 +11,7 @@ type KP = anchor.web3.Keypair;
 
 var DEFAULT_FUEL_RES: ResourceState;
 var DEFAULT_LOCATION: LocationState;
+var DEFAULT_FUEL_STORAGE: StorageState;
 type MobilityType = {fixed:{}} | {movable:{}};
 type ProcessorType = {producer:{}} | {sender:{}};
 type FuelCostType = {nothing:{}} | {output:{}} | {distance:{}};
@@ -22,6 +23,19 @@ before("Init", async () => {
 
   DEFAULT_FUEL_RES = await initDefaultFuel(program);
   DEFAULT_LOCATION = await initDefaultLocation(program);
+  DEFAULT_FUEL_STORAGE = await createStorage4(DEFAULT_FUEL_RES, 999, DEFAULT_LOCATION);
+  await initGame(program, 0);
+  await initGameClock(program);
+});
+
+// Every test gets its own stretch of the game clock, starting well past where the previous one left it.
+var TEST_START: number;
+const TEST_CLOCK_GAP = 1_000_000;
+
+beforeEach(async () => {
+  const program = anchor.workspace.GotAMin as Program<GotAMin>;
+  await advanceGameClock(program, TEST_CLOCK_GAP);
+  TEST_START = (await program.account.gameClock.fetch(getGameClockPda(program))).timestamp.toNumber();
 });
 
 function getLocationPda(program, pubKey: PublicKey, pos: [number, number]): PublicKey {
@@ -108,6 +122,7 @@ async function initStuff(program: Program<GotAMin>, x: number): Promise<PublicKe
       .accounts({
         stuff: pda,
         owner: pubKey,
+        admin: debugAuthority(program),
       })
       .rpc();
   }
@@ -309,7 +324,7 @@ describe("/Production", () => {
     let storage = (await createStorage4(resource, 999)).withName("Storage");
     
     for(let num = 0; num < 5; num += 1) {
-      await debug_produce_without_input(producer, storage, resource, num);
+      await produce_without_input_at(producer, storage, resource, num);
       (await producer.refresh()).log(num);
       (await storage.refresh()).log(num);
     }
@@ -325,7 +340,7 @@ describe("/Production", () => {
     let producer = (await createProcessor3(resource, prodRate, duration)).withName("Producer");
     let storage = (await createStorage4(resource, 3)).withName("Storage");
 
-    await debug_produce_without_input(producer, storage, resource, 1);
+    await produce_without_input_at(producer, storage, resource, 1);
 
     await producer.refresh();
     await storage.refresh();
@@ -353,7 +368,7 @@ describe("/Production", () => {
     (await storageB.refresh()).log(time);
 
     time = 3;
-    await debug_produce_with_1_input(producer, storageB, resourceB, storageA, storageFuel, time);
+    await produce_with_1_input_at(producer, storageB, resourceB, storageA, storageFuel, time);
     
     (await producer.refresh()).log(time);
     (await storageA.refresh()).log(time);
@@ -392,7 +407,7 @@ describe("/Production", () => {
     let storageFuel = await createStorage4(DEFAULT_FUEL_RES, 10, location);
     let producer = await createProcessor3(resourceB, 2, 1, location);
 
-    await debug_produce_with_1_input(producer, storageOut, resourceB, storageIn, storageFuel, 1);
+    await produce_with_1_input_at(producer, storageOut, resourceB, storageIn, storageFuel, 1);
       
     expect(storageOut.amount).equal(0);
   });
@@ -418,7 +433,7 @@ describe("/Production", () => {
     (await storageC.refresh()).log(time);
 
     time = 2;
-    await debug_produce_with_2_inputs(producer, storageC, resourceC, storageA, storageB, time);
+    await produce_with_2_inputs_at(producer, storageC, resourceC, storageA, storageB, time);
     
     (await producer.refresh()).log(time);
     (await storageA.refresh()).log(time);
@@ -465,25 +480,299 @@ describe("/Sending", () => {
       .withName("fuel_storage");
 
     await debugStorage(fuelStorage, 2000);
-
     await debugStorage(localStorage, 10);
-    (await sender.refresh()).log();
+
+    console.log("Send");
+
+    let shipment = await send_at(sender, remoteStorage, resource, localStorage, fuelStorage, 0, location1, location2, 4);
     (await localStorage.refresh()).log();
-    (await remoteStorage.refresh()).log();
     (await fuelStorage.refresh()).log();
 
-    console.log("Send");
+    expect(localStorage.amount, "local storage").equal(6);
+    expect(fuelStorage.amount, "fuel, 4 units * 14^2").equal(2000 - 4 * 14 * 14);
+
+    let state = await program.account.shipment.fetch(shipment.publicKey);
+    expect(state.amount.toNumber(), "shipment amount").equal(4);
+    expect(state.arrivesAt.toNumber(), "arrives after distance time").equal(14000);
+
+    try {
+      await receive_shipment_at(shipment.publicKey, remoteStorage, 13999);
+
+      assert(false, "Expected to fail");
+    } catch(e) {
+      assertAnchorError(e, "ShipmentNotArrived");
+    }
+
+    await receive_shipment_at(shipment.publicKey, remoteStorage, 14000);
+
+    expect((await remoteStorage.refresh()).amount, "remote storage").to.equal(4);
+    expect(await program.provider.connection.getAccountInfo(shipment.publicKey), "shipment closed").equal(null);
+  });
+
+  it("Send to a storage outside the destination fails", async () => {
+    let location1 = await createLocation2(program, 'loc1', [2, 7], 9999);
+    let location2 = await createLocation2(program, 'loc2', [13, 7], 9999);
+    let resource = await createResource2(program, 'A', []);
+    let sender = await createProcessor3(resource, 5, 6, location1, { sender: {} });
+    let localStorage = await createStorage4(resource, 100, location1);
+    let otherStorage = await createStorage4(resource, 100, location1);
+    await debugStorage(localStorage, 10);
+
+    try {
+      await send_at(sender, otherStorage, resource, localStorage, DEFAULT_FUEL_STORAGE, 0, location1, location2, 4);
+
+      assert(false, "Expected to fail");
+    } catch(e) {
+      assertAnchorError(e, "DifferentLocations");
+    }
+  });
+});
+
+describe("/Fuel", () => {
+  // Configure the client to use the local cluster.
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.GotAMin as Program<GotAMin>;
+  const programProvider = program.provider as anchor.AnchorProvider;
+
+  it("Producer with fuel cost 'output' is limited by fuel #fuelOutput", async () => {
+    let location = await createLocation2(program, 'fuel1', [92, 0], 20);
+    let resource = await createResource2(program, 'A', []);
+    let producer = await createProcessor3(resource, 1, 5, location, {producer:{}}, {output:{}});
+    let storage = await createStorage4(resource, 999, location);
+    let storageFuel = await createStorage4(DEFAULT_FUEL_RES, 10, location);
+    await debugStorage(storageFuel, 3);
+
+    await produce_without_input_at(producer, storage, resource, 50, storageFuel);
+
+    expect((await storage.refresh()).amount, "limited by fuel").equal(3);
+    expect((await storageFuel.refresh()).amount, "fuel").equal(0);
+
+    await debugStorage(storageFuel, 10);
+    await produce_without_input_at(producer, storage, resource, 50, storageFuel);
+
+    expect((await storage.refresh()).amount, "rest of the time produced").equal(10);
+    expect((await storageFuel.refresh()).amount, "fuel").equal(3);
+  });
+
+  it("Producer with fuel cost 'output' burns fuel per input-limited unit", async () => {
+    let location = await createLocation2(program, 'fuel2', [93, 0], 20);
+    let resourceA = await createResource2(program, 'A', []);
+    let resourceB = await createResource2(program, 'B', [[resourceA, 1]]);
+    let producer = await createProcessor3(resourceB, 1, 1, location, {producer:{}}, {output:{}});
+    let storageA = await createStorage4(resourceA, 10, location);
+    let storageB = await createStorage4(resourceB, 10, location);
+    let storageFuel = await createStorage4(DEFAULT_FUEL_RES, 10, location);
+    await debugStorage(storageA, 2);
+    await debugStorage(storageFuel, 5);
+
+    await produce_with_1_input_at(producer, storageB, resourceB, storageA, storageFuel, 100);
+
+    expect((await storageB.refresh()).amount, "limited by input").equal(2);
+    expect((await storageFuel.refresh()).amount, "fuel").equal(3);
+  });
+
+  it("Producer with fuel cost 'output' and two inputs is limited by fuel", async () => {
+    let location = await createLocation2(program, 'fuel3', [94, 0], 20);
+    let resourceA = await createResource2(program, 'A', []);
+    let resourceB = await createResource2(program, 'B', []);
+    let resourceC = await createResource2(program, 'C', [[resourceA, 1], [resourceB, 1]]);
+    let producer = await createProcessor3(resourceC, 1, 1, location, {producer:{}}, {output:{}});
+    let storageA = await createStorage4(resourceA, 10, location);
+    let storageB = await createStorage4(resourceB, 10, location);
+    let storageC = await createStorage4(resourceC, 10, location);
+    let storageFuel = await createStorage4(DEFAULT_FUEL_RES, 10, location);
+    await debugStorage(storageA, 10);
+    await debugStorage(storageB, 10);
+    await debugStorage(storageFuel, 4);
+
+    await produce_with_2_inputs_at(producer, storageC, resourceC, storageA, storageB, 100, storageFuel);
+
+    expect((await storageC.refresh()).amount, "limited by fuel").equal(4);
+    expect((await storageA.refresh()).amount, "input A").equal(6);
+    expect((await storageFuel.refresh()).amount, "fuel").equal(0);
+  });
+
+  it("Only senders burn fuel by distance", async () => {
+    let location = await createLocation2(program, 'fuel4', [95, 0], 20);
+    let resource = await createResource2(program, 'A', []);
+
+    try {
+      await createProcessor3(resource, 1, 5, location, {producer:{}}, {distance:{}});
+
+      assert(false, "Expected to fail");
+    } catch(e) {
+      assertAnchorError(e, "InvalidFuelCostType");
+    }
+  });
+
+  it("Producer with fuel cost 'nothing' doesn't need a fuel storage at its location", async () => {
+    let location = await createLocation2(program, 'fuel5', [96, 0], 20);
+    let resource = await createResource2(program, 'A', []);
+    let producer = await createProcessor3(resource, 1, 5, location);
+    let storage = await createStorage4(resource, 999, location);
+
+    await produce_without_input_at(producer, storage, resource, 10);
+
+    expect((await storage.refresh()).amount).equal(2);
+  });
+
+  it("Sender with fuel cost 'output' burns fuel per unit sent", async () => {
+    let location1 = await createLocation2(program, 'fuel6', [97, 0], 20);
+    let location2 = await createLocation2(program, 'fuel7', [97, 5], 20);
+    let resource = await createResource2(program, 'A', []);
+    let sender = await createProcessor3(resource, 5, 6, location1, {sender:{}}, {output:{}});
+    let localStorage = await createStorage4(resource, 100, location1);
+    let remoteStorage = await createStorage4(resource, 100, location2);
+    let storageFuel = await createStorage4(DEFAULT_FUEL_RES, 100, location1);
+    await debugStorage(localStorage, 10);
+    await debugStorage(storageFuel, 15);
+
+    await send_at(sender, remoteStorage, resource, localStorage, storageFuel, 0, location1, location2, 10);
+
+    expect((await storageFuel.refresh()).amount, "fuel").equal(5);
+  });
+
+  it("Sender without enough fuel fails", async () => {
+    let location1 = await createLocation2(program, 'fuel8', [98, 0], 20);
+    let location2 = await createLocation2(program, 'fuel9', [98, 5], 20);
+    let resource = await createResource2(program, 'A', []);
+    let sender = await createProcessor3(resource, 5, 6, location1, {sender:{}}, {distance:{}});
+    let localStorage = await createStorage4(resource, 100, location1);
+    let remoteStorage = await createStorage4(resource, 100, location2);
+    let storageFuel = await createStorage4(DEFAULT_FUEL_RES, 100, location1);
+    await debugStorage(localStorage, 10);
+    await debugStorage(storageFuel, 100);
+
+    try {
+      // 10 units * 5^2
+      await send_at(sender, remoteStorage, resource, localStorage, storageFuel, 0, location1, location2, 10);
+
+      assert(false, "Expected to fail");
+    } catch(e) {
+      assertAnchorError(e, "FuelNotEnough");
+    }
+  });
+});
+
+describe("/TradeRoute", () => {
+  // Configure the client to use the local cluster.
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.GotAMin as Program<GotAMin>;
+  const programProvider = program.provider as anchor.AnchorProvider;
+
+  it("Trade route delivers due cycles within destination capacity #tradeRoute", async () => {
+    let location1 = await createLocation2(program, 'route1', [99, 0], 20);
+    let location2 = await createLocation2(program, 'route2', [99, 2], 20);
+    let resource = await createResource2(program, 'A', []);
+    let sender = await createProcessor3(resource, 1, 1, location1, {sender:{}}, {distance:{}});
+    let storageFrom = await createStorage4(resource, 100, location1);
+    let storageTo = await createStorage4(resource, 25, location2);
+    let storageFuel = await createStorage4(DEFAULT_FUEL_RES, 1000, location1);
+    await debugStorage(storageFrom, 40);
+    await debugStorage(storageFuel, 1000);
+
+    let route = await createTradeRoute(sender, storageFrom, storageTo, storageFuel, location1, location2, 10, 2000, 0);
+
+    try {
+      await runTradeRoute(route, 1999);
+
+      assert(false, "Expected to fail");
+    } catch(e) {
+      assertAnchorError(e, "TradeRouteNotDue");
+    }
+
+    await runTradeRoute(route, 4000);
+
+    expect((await storageTo.refresh()).amount, "two cycles").equal(20);
+    expect((await storageFuel.refresh()).amount, "fuel, 20 units * 2^2").equal(920);
+
+    await runTradeRoute(route, 6000);
+
+    expect((await storageTo.refresh()).amount, "clamped to capacity").equal(25);
+    expect((await storageFrom.refresh()).amount, "source").equal(15);
+  });
+
+  it("Trade route faster than the travel time fails", async () => {
+    let location1 = await createLocation2(program, 'route3', [100, 0], 20);
+    let location2 = await createLocation2(program, 'route4', [100, 2], 20);
+    let resource = await createResource2(program, 'A', []);
+    let sender = await createProcessor3(resource, 1, 1, location1, {sender:{}}, {distance:{}});
+    let storageFrom = await createStorage4(resource, 100, location1);
+    let storageTo = await createStorage4(resource, 100, location2);
+    let storageFuel = await createStorage4(DEFAULT_FUEL_RES, 1000, location1);
+
+    try {
+      await createTradeRoute(sender, storageFrom, storageTo, storageFuel, location1, location2, 10, 1999, 0);
 
-    for(let num = 0; num < 11; num += 10) {
-      await debug_send(sender, remoteStorage, resource, localStorage, fuelStorage, num, location1, location2);
-      (await sender.refresh()).log(num);
-      (await localStorage.refresh()).log(num);
-      (await remoteStorage.refresh()).log(num);
-      (await fuelStorage.refresh()).log(num);
+      assert(false, "Expected to fail");
+    } catch(e) {
+      assertAnchorError(e, "InvalidInput");
     }
+  });
+});
+
+describe("/ProductionQueue", () => {
+  // Configure the client to use the local cluster.
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.GotAMin as Program<GotAMin>;
+  const programProvider = program.provider as anchor.AnchorProvider;
+
+  it("Queued job reserves inputs and is claimed across claims #productionQueue", async () => {
+    let location = await createLocation2(program, 'queue1', [101, 0], 20);
+    let resourceA = await createResource2(program, 'A', []);
+    let resourceB = await createResource2(program, 'B', [[resourceA, 2]]);
+    let processor = await createProcessor3(resourceB, 1, 10, location);
+    let storageA = await createStorage4(resourceA, 20, location);
+    let storageB = await createStorage4(resourceB, 20, location);
+    await debugStorage(storageA, 20);
+    let queue = await initProductionQueue(processor);
+
+    await enqueueJob(queue, processor, resourceB, 3, 0, [storageA], 100);
+
+    expect((await storageA.refresh()).amount, "inputs reserved").equal(14);
+
+    await claimJobs(queue, processor, storageB, 125);
 
-    expect(remoteStorage.amount, "remote storage").to.equal(5);
-    expect(localStorage.amount, "local storage").equal(0);
+    expect((await storageB.refresh()).amount, "partially done").equal(2);
+    expect((await processor.refresh()).awaitingUnits, "left of the job").equal(1);
+
+    await claimJobs(queue, processor, storageB, 130);
+
+    expect((await storageB.refresh()).amount, "done").equal(3);
+    expect((await program.account.productionQueue.fetch(queue)).jobs.length, "job claimed").equal(0);
+  });
+
+  it("Higher priority jobs go first, after the job in progress", async () => {
+    let location = await createLocation2(program, 'queue2', [102, 0], 20);
+    let resourceA = await createResource2(program, 'A', []);
+    let resourceB = await createResource2(program, 'B', [[resourceA, 1]]);
+    let processor = await createProcessor3(resourceB, 1, 10, location);
+    let storageA = await createStorage4(resourceA, 20, location);
+    await debugStorage(storageA, 20);
+    let queue = await initProductionQueue(processor);
+
+    await enqueueJob(queue, processor, resourceB, 2, 0, [storageA], 0);
+    await enqueueJob(queue, processor, resourceB, 1, 0, [storageA], 0);
+    await enqueueJob(queue, processor, resourceB, 1, 5, [storageA], 0);
+
+    let state = await program.account.productionQueue.fetch(queue);
+    expect(state.jobs.map(j => j.id.toNumber())).to.deep.equal([0, 2, 1]);
+  });
+
+  it("Cancelled job returns its reserved inputs", async () => {
+    let location = await createLocation2(program, 'queue3', [103, 0], 20);
+    let resourceA = await createResource2(program, 'A', []);
+    let resourceB = await createResource2(program, 'B', [[resourceA, 2]]);
+    let processor = await createProcessor3(resourceB, 1, 10, location);
+    let storageA = await createStorage4(resourceA, 20, location);
+    await debugStorage(storageA, 20);
+    let queue = await initProductionQueue(processor);
+
+    await enqueueJob(queue, processor, resourceB, 3, 0, [storageA], 0);
+    await cancelJob(queue, processor, resourceB, 0, [storageA], 0);
+
+    expect((await storageA.refresh()).amount).equal(20);
+    expect((await program.account.productionQueue.fetch(queue)).jobs.length).equal(0);
   });
 });
 
@@ -499,7 +788,7 @@ describe("/Transportation", () => {
     let storage = await createStorage4(resource, 10, location1, {movable:{}}, 2);
     let location2 = await createLocation2(program, 'loc2', [2, 0], 10);
 
-    await debugMoveStorage(storage, location1, location2, 1);
+    await moveStorageAt(storage, location1, location2, 1);
 
     await storage.refresh();
     expect(storage.locationId.toBase58()).to.equal(location2.getPubKeyStr());
@@ -518,10 +807,10 @@ describe("/Transportation", () => {
     let producer = await createProcessor3(resource, 10, 1, location1);
     let storage = await createStorage4(resource, 10, location1, {movable:{}});
     let location2 = await createLocation2(program, 'loc2', [10, 0], 10);
-    await debugMoveStorage(storage, location1, location2, 1);
+    await moveStorageAt(storage, location1, location2, 1);
   
     try {
-      await debug_produce_without_input(producer, storage, resource, 1);
+      await produce_without_input_at(producer, storage, resource, 1);
 
       assert(false, "Expected to fail");
     } catch(e) {
@@ -600,26 +889,64 @@ describe("/Storage", () => {
       assertAnchorError(e, "ResourceNotMatching");
     }
   });
+
+  it("Setting a storage amount needs the upgrade authority #debugAuthority", async () => {
+    let resourceA = await createResource2(program, 'A', []);
+    let storageA = await createStorage4(resourceA, 10);
+    let player = await createPlayer(program);
+
+    try {
+      await program.methods
+        .debugSetStorageAmount(new anchor.BN(10))
+        .accounts({
+          storage: storageA.getPubKey(),
+          admin: { ...debugAuthority(program), authority: player.publicKey },
+        })
+        .signers([player])
+        .rpc();
+
+      assert(false, "Expected to fail");
+    } catch(e) {
+      assertAnchorError(e, "OwnerRequired");
+    }
+    expect((await storageA.refresh()).amount).equal(0);
+  });
     
 });
 
-describe("/Location", () => {
+describe("/Transfer", () => {
   // Configure the client to use the local cluster.
   anchor.setProvider(anchor.AnchorProvider.env());
   const program = anchor.workspace.GotAMin as Program<GotAMin>;
   const programProvider = program.provider as anchor.AnchorProvider;
-  const pk = programProvider.wallet.publicKey;
 
-  it("Move between Storage in different locations", async () => {
+  it("Transfer to another player at the same location #transferToPlayer", async () => {
     let resource = await createResource2(program, 'A', []);
-    let locationA = await createLocation2(program, 'locA', [0, 0], 10);
-    let storageAFrom = await createStorage4(resource, 10, locationA);
-    let locationB = await createLocation2(program, 'locB', [1, 0], 10);
-    let storageBTo = await createStorage4(resource, 100, locationB);
-    await debugStorage(storageAFrom, 10);
+    let location = await createLocation2(program, 'loc', [70, 0], 10);
+    let storageFrom = (await createStorage4(resource, 10, location)).withName("Sender");
+    let player2 = await createPlayer(program);
+    let storageTo = (await createPlayerStorage(player2, resource, 10, location)).withName("Recipient");
+    await debugStorage(storageFrom, 10);
+
+    await transferToPlayer(storageFrom, storageTo, 4);
+
+    await storageFrom.refresh();
+    await storageTo.refresh();
+    expect(storageFrom.amount, "sender storage").equal(6);
+    expect(storageTo.amount, "recipient storage").equal(4);
+  });
+
+  it("Transfer to another player at a different location fails", async () => {
+    let resource = await createResource2(program, 'A', []);
+    let location1 = await createLocation2(program, 'loc1', [71, 0], 10);
+    let location2 = await createLocation2(program, 'loc2', [72, 0], 10);
+    let storageFrom = await createStorage4(resource, 10, location1);
+    let player2 = await createPlayer(program);
+    let storageTo = await createPlayerStorage(player2, resource, 10, location2);
+    await debugStorage(storageFrom, 10);
 
     try {
-      await move_between_storage(storageAFrom, storageBTo, 1);
+      await transferToPlayer(storageFrom, storageTo, 4);
 
       assert(false, "Expected to fail");
     } catch(e) {
@@ -627,995 +954,2653 @@ describe("/Location", () => {
     }
   });
 
-  it("Producer and Storage in different locations", async () => {
+  it("Transfer from another player's storage fails", async () => {
     let resource = await createResource2(program, 'A', []);
-    let location = await createLocation2(program, 'locA', [0, 0], 10);
-    let producer = await createProcessor3(resource, 10, 1);
-    let storage = await createStorage4(resource, 10, location);
+    let location = await createLocation2(program, 'loc', [73, 0], 10);
+    let player2 = await createPlayer(program);
+    let storageFrom = await createPlayerStorage(player2, resource, 10, location);
+    let storageTo = await createStorage4(resource, 10, location);
+    await debugStorage(storageFrom, 10);
 
     try {
-      await produce_without_input(producer, storage, resource);
+      await transferToPlayer(storageFrom, storageTo, 4);
 
       assert(false, "Expected to fail");
     } catch(e) {
-      assertAnchorError(e, "DifferentLocations");
+      assertAnchorError(e, "OwnerRequired");
     }
-  });  
+  });
 
-  it("Move static Storage fails", async () => {
+  it("Transfer to a player whose allow-list excludes the sender fails", async () => {
     let resource = await createResource2(program, 'A', []);
-    let location1 = await createLocation2(program, 'loc1', [0, 0], 10);
-    let storage = await createStorage4(resource, 10, location1);
-    let location2 = await createLocation2(program, 'loc2', [1, 0], 10);
+    let location = await createLocation2(program, 'loc', [74, 0], 10);
+    let storageFrom = await createStorage4(resource, 10, location);
+    let player2 = await createPlayer(program);
+    let storageTo = await createPlayerStorage(player2, resource, 10, location);
+    await debugStorage(storageFrom, 10);
+    await initTransferAllowList(program, player2, [anchor.web3.Keypair.generate().publicKey]);
 
     try {
-      await debugMoveStorage(storage, location1, location2, 1);
+      await transferToPlayer(storageFrom, storageTo, 4);
 
       assert(false, "Expected to fail");
     } catch(e) {
-      assertAnchorError(e, "StorageTypeNotMovable");
+      assertAnchorError(e, "TransferNotAllowed");
     }
-  });  
 
-  it("Move Storage to full Location fails", async () => {
-    let resource = await createResource2(program, 'A', []);
-    let location1 = await createLocation2(program, 'loc1', [60, 0], 10);
-    let storage = await createStorage4(resource, 10, location1, {movable:{}});
-    let location2 = await createLocation2(program, 'loc2', [61, 0], 0);
+    await updateTransferAllowList(program, player2, [programProvider.wallet.publicKey]);
+    await transferToPlayer(storageFrom, storageTo, 4);
+
+    await storageTo.refresh();
+    expect(storageTo.amount).equal(4);
+  });
+});
+
+describe("/Trading", () => {
+  // Configure the client to use the local cluster.
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.GotAMin as Program<GotAMin>;
+  const programProvider = program.provider as anchor.AnchorProvider;
+
+  it("Accept offer swaps both sides #acceptOffer", async () => {
+    let iron = await createResource2(program, 'iron', []);
+    let fuel = await createResource2(program, 'fuel', []);
+    let location = await createLocation2(program, 'hub', [80, 0], 20);
+    let aliceIron = (await createStorage4(iron, 10, location)).withName("AliceIron");
+    let aliceFuel = (await createStorage4(fuel, 10, location)).withName("AliceFuel");
+    let bob = await createPlayer(program);
+    let bobIron = (await createPlayerStorage(bob, iron, 10, location)).withName("BobIron");
+    let bobFuel = (await createPlayerStorage(bob, fuel, 10, location)).withName("BobFuel");
+    await debugStorage(aliceIron, 10);
+    await debugStorage(bobFuel, 10);
+
+    let offer = await createOffer(aliceIron, 4, fuel, 6);
+
+    await aliceIron.refresh();
+    expect(aliceIron.amount, "iron locked in escrow").equal(6);
+
+    await acceptOffer(offer, bob, aliceFuel, bobFuel, bobIron);
+
+    expect((await aliceIron.refresh()).amount, "alice iron").equal(6);
+    expect((await aliceFuel.refresh()).amount, "alice fuel").equal(6);
+    expect((await bobIron.refresh()).amount, "bob iron").equal(4);
+    expect((await bobFuel.refresh()).amount, "bob fuel").equal(4);
+    expect(await program.provider.connection.getAccountInfo(offer.publicKey), "offer closed").equal(null);
+  });
+
+  it("Accept offer from a different location fails", async () => {
+    let iron = await createResource2(program, 'iron', []);
+    let fuel = await createResource2(program, 'fuel', []);
+    let location1 = await createLocation2(program, 'hub1', [81, 0], 20);
+    let location2 = await createLocation2(program, 'hub2', [82, 0], 20);
+    let aliceIron = await createStorage4(iron, 10, location1);
+    let aliceFuel = await createStorage4(fuel, 10, location1);
+    let bob = await createPlayer(program);
+    let bobIron = await createPlayerStorage(bob, iron, 10, location2);
+    let bobFuel = await createPlayerStorage(bob, fuel, 10, location2);
+    await debugStorage(aliceIron, 10);
+    await debugStorage(bobFuel, 10);
+    let offer = await createOffer(aliceIron, 4, fuel, 6);
 
     try {
-      await debugMoveStorage(storage, location1, location2, 1);
+      await acceptOffer(offer, bob, aliceFuel, bobFuel, bobIron);
 
       assert(false, "Expected to fail");
     } catch(e) {
-      assertAnchorError(e, "LocationFull");
+      assertAnchorError(e, "DifferentLocations");
     }
   });
 
-  it("Move Storage to new Location", async () => {
-    let resource = await createResource2(program, 'A', []);
-    let location1 = await createLocation2(program, 'loc1', [50, 0], 10);
-    let storage = await createStorage4(resource, 10, location1, {movable:{}});
-    let location2 = await createLocation2(program, 'loc2', [51, 0], 10);
+  it("Cancel offer returns the escrowed amount", async () => {
+    let iron = await createResource2(program, 'iron', []);
+    let fuel = await createResource2(program, 'fuel', []);
+    let location = await createLocation2(program, 'hub', [83, 0], 20);
+    let aliceIron = await createStorage4(iron, 10, location);
+    await debugStorage(aliceIron, 10);
+    let offer = await createOffer(aliceIron, 4, fuel, 6);
 
-    await debugMoveStorage(storage, location1, location2, 1);
-    await storage.refresh();
-    await location1.refresh();
-    await location2.refresh();
+    await cancelOffer(offer, aliceIron);
 
-    expect(storage.locationId.toBase58()).equal(location2.getPubKeyStr());
-    expect(location1.occupiedSpace).equal(0);
-    expect(location2.occupiedSpace).equal(1);
-  });  
+    expect((await aliceIron.refresh()).amount).equal(10);
+    expect(await program.provider.connection.getAccountInfo(offer.publicKey), "offer closed").equal(null);
+  });
 
+  it("Order book matches a bid against a resting ask #orderBook", async () => {
+    let iron = await createResource2(program, 'iron', []);
+    let fuel = await createResource2(program, 'fuel', []);
+    let location = await createLocation2(program, 'hub', [84, 0], 20);
+    let aliceIron = await createStorage4(iron, 20, location);
+    let aliceFuel = await createStorage4(fuel, 20, location);
+    let bob = await createPlayer(program);
+    let bobIron = await createPlayerStorage(bob, iron, 20, location);
+    let bobFuel = await createPlayerStorage(bob, fuel, 20, location);
+    await debugStorage(aliceIron, 10);
+    await debugStorage(bobFuel, 20);
+    let orderBook = await initOrderBook(location, iron, fuel);
+
+    await placeOrder(orderBook, {ask:{}}, 3, 5, aliceIron, aliceFuel);
+    await placeOrder(orderBook, {bid:{}}, 4, 3, bobIron, bobFuel, bob, [aliceFuel]);
+
+    expect((await aliceIron.refresh()).amount, "alice iron").equal(5);
+    expect((await aliceFuel.refresh()).amount, "alice fuel, filled at the ask price").equal(9);
+    expect((await bobIron.refresh()).amount, "bob iron").equal(3);
+    expect((await bobFuel.refresh()).amount, "bob fuel").equal(11);
+
+    let state = await program.account.orderBook.fetch(orderBook);
+    expect(state.bids.length).equal(0);
+    expect(state.asks.length).equal(1);
+    expect(state.asks[0].amount.toNumber(), "ask left").equal(2);
+
+    await cancelOrder(orderBook, state.asks[0].id.toNumber(), aliceIron, aliceFuel);
+
+    expect((await aliceIron.refresh()).amount, "alice iron after cancel").equal(7);
+    state = await program.account.orderBook.fetch(orderBook);
+    expect(state.asks.length).equal(0);
+  });
+
+  it("Order book rests a bid that doesn't cross", async () => {
+    let iron = await createResource2(program, 'iron', []);
+    let fuel = await createResource2(program, 'fuel', []);
+    let location = await createLocation2(program, 'hub', [85, 0], 20);
+    let aliceIron = await createStorage4(iron, 20, location);
+    let aliceFuel = await createStorage4(fuel, 20, location);
+    await debugStorage(aliceFuel, 20);
+    let orderBook = await initOrderBook(location, iron, fuel);
+
+    await placeOrder(orderBook, {bid:{}}, 2, 5, aliceIron, aliceFuel);
+    await placeOrder(orderBook, {bid:{}}, 3, 1, aliceIron, aliceFuel);
+
+    expect((await aliceFuel.refresh()).amount, "fuel locked by bids").equal(7);
+    let state = await program.account.orderBook.fetch(orderBook);
+    expect(state.bids.map(o => o.price.toNumber()), "best bid first").to.deep.equal([3, 2]);
+  });
 });
 
-function assertAnchorError(error: any, errorName: String) {
-  if(error instanceof AnchorError) {
-    expect(error, "Expected to be of type AnchorError").to.be.instanceOf(AnchorError);
-    let anchorError: AnchorError = error;
-    expect(anchorError.error.errorCode.code).to.equal(errorName);  
-  } else {
-    throw error;
-  }
-}
+describe("/Pool", () => {
+  // Configure the client to use the local cluster.
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.GotAMin as Program<GotAMin>;
+  const programProvider = program.provider as anchor.AnchorProvider;
 
-async function createResource(program: Program<GotAMin>, name: string, inputs):  Promise<[KP, any]> {
-  const resource: anchor.web3.Keypair = anchor.web3.Keypair.generate();
-  return [resource, await initResource(program, resource, name, inputs)];
-}
+  it("Swap against a pool rounds in the pool's favour #poolSwap", async () => {
+    let game = await initGame(program, 30);
+    let iron = await createResource2(program, 'iron', []);
+    let fuel = await createResource2(program, 'fuel', []);
+    let location = await createLocation2(program, 'hub', [86, 0], 20);
+    let aliceIron = await createStorage4(iron, 2000, location);
+    let aliceFuel = await createStorage4(fuel, 2000, location);
+    await debugStorage(aliceIron, 2000);
+    await debugStorage(aliceFuel, 1000);
+    let pool = await createPool(game, location, iron, fuel);
+
+    await addLiquidity(pool, aliceIron, aliceFuel, 1000, 1000, 1000);
+
+    let state = await program.account.pool.fetch(pool);
+    expect(state.lpSupply.toNumber(), "initial shares").equal(1000);
+
+    // fee = floor(100 * 30 / 10000) = 0, out = floor(1000 * 100 / 1100) = 90
+    await swap(pool, aliceIron, aliceFuel, 100, 90);
+    expect((await aliceFuel.refresh()).amount, "fuel after first swap").equal(90);
+
+    // fee = floor(1000 * 30 / 10000) = 3, out = floor(910 * 997 / (1100 + 997)) = 432
+    await swap(pool, aliceIron, aliceFuel, 1000, 0);
+    expect((await aliceFuel.refresh()).amount, "fuel after second swap").equal(522);
+    expect((await aliceIron.refresh()).amount, "iron after swaps").equal(0);
+  });
 
-async function createResource2(program: Program<GotAMin>, name: string, inputs):  Promise<ResourceState> {
-  const resourceKey: anchor.web3.Keypair = anchor.web3.Keypair.generate();
-  await initResource(program, resourceKey, name, inputs);
-  return new ResourceState(program, resourceKey).refresh();
-}
+  it("Swap below the minimum output fails", async () => {
+    let game = await initGame(program, 30);
+    let iron = await createResource2(program, 'iron', []);
+    let fuel = await createResource2(program, 'fuel', []);
+    let location = await createLocation2(program, 'hub', [87, 0], 20);
+    let aliceIron = await createStorage4(iron, 2000, location);
+    let aliceFuel = await createStorage4(fuel, 2000, location);
+    await debugStorage(aliceIron, 1100);
+    await debugStorage(aliceFuel, 1000);
+    let pool = await createPool(game, location, iron, fuel);
+    await addLiquidity(pool, aliceIron, aliceFuel, 1000, 1000, 1000);
 
-async function initResource(program: Program<GotAMin>, resource, name: string, inputs: [anchor.web3.Keypair, Number][]) {
+    try {
+      await swap(pool, aliceIron, aliceFuel, 100, 91);
+
+      assert(false, "Expected to fail");
+    } catch(e) {
+      assertAnchorError(e, "SlippageExceeded");
+    }
+  });
+
+  it("Remove liquidity returns a share of both reserves", async () => {
+    let game = await initGame(program, 30);
+    let iron = await createResource2(program, 'iron', []);
+    let fuel = await createResource2(program, 'fuel', []);
+    let location = await createLocation2(program, 'hub', [88, 0], 20);
+    let aliceIron = await createStorage4(iron, 2000, location);
+    let aliceFuel = await createStorage4(fuel, 2000, location);
+    await debugStorage(aliceIron, 400);
+    await debugStorage(aliceFuel, 100);
+    let pool = await createPool(game, location, iron, fuel);
+    await addLiquidity(pool, aliceIron, aliceFuel, 400, 100, 200);
+
+    await removeLiquidity(pool, aliceIron, aliceFuel, 50, 100, 25);
+
+    expect((await aliceIron.refresh()).amount, "iron").equal(100);
+    expect((await aliceFuel.refresh()).amount, "fuel").equal(25);
+    let state = await program.account.pool.fetch(pool);
+    expect(state.lpSupply.toNumber(), "shares left").equal(150);
+  });
+});
+
+describe("/Auction", () => {
+  // Configure the client to use the local cluster.
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.GotAMin as Program<GotAMin>;
   const programProvider = program.provider as anchor.AnchorProvider;
 
-  let publicKeyInputs = [];
-  let amountInputs = [];
-  inputs.forEach(tuple => {
-    publicKeyInputs.push(tuple[0].publicKey);
-    amountInputs.push(new anchor.BN(tuple[1].toFixed()));
+  it("Location goes to the highest bidder and outbid players are refunded #auctionLocation", async () => {
+    let fuel = await createResource2(program, 'fuel', []);
+    let location = await createLocation2(program, 'hub', [89, 0], 20);
+    let aliceFuel = await createStorage4(fuel, 20, location);
+    let bob = await createPlayer(program);
+    let bobFuel = await createPlayerStorage(bob, fuel, 20, location);
+    let carol = await createPlayer(program);
+    let carolFuel = await createPlayerStorage(carol, fuel, 20, location);
+    await debugStorage(bobFuel, 10);
+    await debugStorage(carolFuel, 10);
+    let occupiedBefore = (await program.account.location.fetch(location.getPubKey())).occupiedBy.length;
+
+    let auction = await createLocationAuction(location, fuel, 5, 100, 1000);
+    expect((await program.account.location.fetch(location.getPubKey())).owner.toBase58(), "locked by auction").equal(auction.toBase58());
+
+    await placeBid(auction, bob, bobFuel, 6, 1010);
+    await placeBid(auction, carol, carolFuel, 8, 1020);
+
+    expect((await bobFuel.refresh()).amount, "bob refunded").equal(10);
+    expect((await carolFuel.refresh()).amount, "carol bid locked").equal(2);
+
+    await settleLocationAuction(auction, aliceFuel, 1100);
+
+    let state = await program.account.location.fetch(location.getPubKey());
+    expect(state.owner.toBase58(), "new owner").equal(carol.publicKey.toBase58());
+    expect(state.occupiedBy.length, "occupants stay").equal(occupiedBefore);
+    expect((await aliceFuel.refresh()).amount, "seller paid").equal(8);
+    expect(await program.provider.connection.getAccountInfo(auction), "auction closed").equal(null);
   });
 
-  await program.methods
-    .initResource(name, publicKeyInputs, amountInputs)
-    .accounts({
-      resource: resource.publicKey,
-      owner: programProvider.wallet.publicKey,
-      systemProgram: anchor.web3.SystemProgram.programId,
-    })
-    .signers(resource)
-    .rpc();
-    
-  return await program.account.resource.fetch(resource.publicKey);
-}
+  it("Processor and its occupancy go to the winner", async () => {
+    let iron = await createResource2(program, 'iron', []);
+    let fuel = await createResource2(program, 'fuel', []);
+    let location = await createLocation2(program, 'hub', [90, 0], 20);
+    let processor = await createProcessor3(iron, 1, 5, location, {producer:{}}, {nothing:{}}, fuel);
+    let aliceFuel = await createStorage4(fuel, 20, location);
+    let bob = await createPlayer(program);
+    let bobFuel = await createPlayerStorage(bob, fuel, 20, location);
+    await debugStorage(bobFuel, 10);
+
+    let aliceIron = await createStorage4(iron, 1_000_000, location);
+
+    // The seller claims what the processor made before putting it up.
+    await produce_without_input_at(processor, aliceIron, iron, 1000, aliceFuel);
+    let auction = await createProcessorAuction(processor, fuel, 3, 100, 1000);
+    await placeBid(auction, bob, bobFuel, 4, 1050);
+    await settleProcessorAuction(auction, processor, aliceFuel, 1100);
+
+    let state = await program.account.processor.fetch(processor.getPubKey());
+    expect(state.owner.toBase58(), "processor owner").equal(bob.publicKey.toBase58());
+    let occupant = (await program.account.location.fetch(location.getPubKey())).occupiedBy
+      .find(o => o.item.toBase58() == processor.getPubKeyStr());
+    expect(occupant.player.toBase58(), "occupancy owner").equal(bob.publicKey.toBase58());
+    expect((await aliceFuel.refresh()).amount, "seller paid").equal(4);
+  });
 
-async function createProcessor(program: Program<GotAMin>, output_resource, outputRate, processingDuration = 5, location = DEFAULT_LOCATION, type: ProcessorType = {producer:{}}, fuel_resource = DEFAULT_FUEL_RES, fuelCostType: FuelCostType = {nothing:{}}): Promise<[KP, any]> {
-  const processor = anchor.web3.Keypair.generate();
-  return [processor, await initProcessor(processor, fuel_resource, output_resource, outputRate, processingDuration, location, type, fuelCostType)];
-}
+  it("Goods auction can't be bid on after it ends or settled before it ends", async () => {
+    let iron = await createResource2(program, 'iron', []);
+    let fuel = await createResource2(program, 'fuel', []);
+    let location = await createLocation2(program, 'hub', [91, 0], 20);
+    let aliceIron = await createStorage4(iron, 20, location);
+    let aliceFuel = await createStorage4(fuel, 20, location);
+    let bob = await createPlayer(program);
+    let bobIron = await createPlayerStorage(bob, iron, 20, location);
+    let bobFuel = await createPlayerStorage(bob, fuel, 20, location);
+    await debugStorage(aliceIron, 10);
+    await debugStorage(bobFuel, 10);
+
+    let auction = await createGoodsAuction(aliceIron, 6, fuel, 2, 100, 1000);
+    expect((await aliceIron.refresh()).amount, "goods locked").equal(4);
+    await placeBid(auction, bob, bobFuel, 3, 1000);
 
-async function createProcessor3(output_resource: ResourceState, outputRate, processingDuration = 5, location: LocationState = DEFAULT_LOCATION, type: ProcessorType = {producer:{}}, fuelCostType: FuelCostType = {nothing:{}}, fuel_resource = DEFAULT_FUEL_RES): Promise<ProcessorState> {
-  let program = output_resource.program;
-  const keyPair = anchor.web3.Keypair.generate();
-  await initProcessor(keyPair, fuel_resource, output_resource, outputRate, processingDuration, location, type, fuelCostType);
-  return new ProcessorState(program, keyPair).refresh();
-}
+    try {
+      await settleGoodsAuction(auction, aliceFuel, bobIron, 1099);
 
-async function initProcessor(processor, fuelResource: ResourceState, outputResource, outputRate, processingDuration = 5, location = DEFAULT_LOCATION, type: ProcessorType = {producer:{}}, fuelCostType: FuelCostType = {nothing:{}}) {
-  let program = fuelResource.program;
-  assert(outputRate > 0, 'initProcessor requirement: outputRate > 0');
-  assert(processingDuration > 0, 'initProcessor requirement: processingDuration > 0');
-  const programProvider = program.provider as anchor.AnchorProvider;
-  const outputRateBN = new anchor.BN(outputRate);
-  const processingDurationBN = new anchor.BN(processingDuration);
-  const current_timestamp = new anchor.BN(0);
+      assert(false, "Expected to fail");
+    } catch(e) {
+      assertAnchorError(e, "AuctionNotEnded");
+    }
 
-  await program.methods
-    .debugInitProcessor(
-      type,
-      fuelResource.getPubKey(),
-      outputResource.publicKey,
-      outputRateBN,
-      processingDurationBN,
-      fuelCostType,
-      current_timestamp,
-    )
-    .accounts({
-      processor: processor.publicKey,
-      location: location.getPubKey(),
-      owner: programProvider.wallet.publicKey,
-      systemProgram: anchor.web3.SystemProgram.programId,
-    })
-    .signers(processor)
-    .rpc();
-    
-    return await program.account.processor.fetch(processor.publicKey);
-}
+    try {
+      await placeBid(auction, bob, bobFuel, 5, 1100);
 
-async function createStorageNew(
-  program: Program<GotAMin>,
-  owner: KP,
-  resource: KP, 
-  capacity: number, 
-  location: LocationState = DEFAULT_LOCATION, 
-  mobilityType: MobilityType = {fixed:{}}, 
-  speed: number = 1,
-): Promise<[KP, any]> {
-  const storage: KP = anchor.web3.Keypair.generate();
-  return [storage, await initStorageNew(program, owner, storage, resource, capacity, location, mobilityType, speed)];
-}
+      assert(false, "Expected to fail");
+    } catch(e) {
+      assertAnchorError(e, "AuctionEnded");
+    }
 
-async function initStorageNew(
-  program: Program<GotAMin>, 
-  owner: KP,
-  storage, resource: KP, 
-  capacity: number, 
-  location: LocationState = DEFAULT_LOCATION, 
-  mobilityType: MobilityType = {fixed:{}}, 
-  speed: number = 1,
-) {
-  const programProvider = program.provider as anchor.AnchorProvider;
+    await settleGoodsAuction(auction, aliceFuel, bobIron, 1100);
 
-  await program.methods
-    .initStorage(resource.publicKey, new anchor.BN(capacity), mobilityType, new anchor.BN(speed), location.xBN, location.yBN)
-    .accounts({
-      storage: storage.publicKey,
-      location: location.getPubKey(),
-      owner: owner.publicKey,
-      systemProgram: anchor.web3.SystemProgram.programId,
-    })
-    .signers([storage, owner])
-    .rpc();
-    
-  return await program.account.storage.fetch(storage.publicKey);
-}
+    expect((await bobIron.refresh()).amount, "bob iron").equal(6);
+    expect((await bobFuel.refresh()).amount, "bob fuel").equal(7);
+    expect((await aliceFuel.refresh()).amount, "alice fuel").equal(3);
+  });
+});
 
-async function createStorage(
-  program: Program<GotAMin>,
-  resource: KP, 
-  capacity: number, 
-  location: LocationState = DEFAULT_LOCATION, 
-  mobilityType: MobilityType = {fixed:{}}, 
-  speed: number = 1,
-): Promise<[KP, any]> {
-  const storage: KP = anchor.web3.Keypair.generate();
-  return [storage, await initStorage(program, storage, resource, capacity, location, mobilityType, speed)];
-}
+describe("/Upgrade", () => {
+  // Configure the client to use the local cluster.
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.GotAMin as Program<GotAMin>;
 
-async function createStorage4(
-  resource: ResourceState, 
-  capacity: number, 
-  location: LocationState = DEFAULT_LOCATION, 
-  mobilityType: MobilityType = {fixed:{}}, 
-  speed: number = 1,
-  instanceName: string = null,
-): Promise<StorageState> {
-  let [keyPair, b] = await createStorage(resource.program, resource.keyPair, capacity, location, mobilityType, speed);
-  let state = new StorageState(keyPair, resource, instanceName);
-  return await state.refresh();
-}
+  it("Upgrade pays the cost and doubles the throughput #upgrade", async () => {
+    let location = await createLocation2(program, 'upgrade1', [104, 0], 20);
+    let resourceA = await createResource2(program, 'A', []);
+    let resourceGold = await createResource2(program, 'Gold', []);
+    let processor = await createProcessor3(resourceA, 1, 10, location);
+    let storageA = await createStorage4(resourceA, 20, location);
+    let storageGold = await createStorage4(resourceGold, 20, location);
+    await debugStorage(storageGold, 15);
+    await updateGameLevels(program, [10_000, 20_000], resourceGold, 10);
+
+    await upgradeProcessorAt(processor, storageGold, 5);
+
+    await processor.refresh();
+    expect(processor.level).equal(2);
+    expect(processor.outputRate).equal(1);
+    expect(processor.processingDuration).equal(5);
+    expect((await storageGold.refresh()).amount, "paid").equal(5);
+
+    await produce_without_input_at(processor, storageA, resourceA, 8);
+    expect((await storageA.refresh()).amount, "half a unit carried over").equal(1);
+  });
 
-class BaseState<T> {
-  readonly program: Program<GotAMin>;
-  readonly keyPair: KP;
-  readonly publicKey: anchor.web3.PublicKey;
-  instanceName: string;
-  initialized: boolean = false;
+  it("Upgrade fails past the last level of the curve", async () => {
+    let location = await createLocation2(program, 'upgrade2', [105, 0], 20);
+    let resourceA = await createResource2(program, 'A', []);
+    let resourceGold = await createResource2(program, 'Gold', []);
+    let processor = await createProcessor3(resourceA, 1, 10, location);
+    let storageGold = await createStorage4(resourceGold, 20, location);
+    await debugStorage(storageGold, 20);
+    await updateGameLevels(program, [10_000], resourceGold, 10);
 
-  constructor(program: Program<GotAMin>, keyPair: KP, instanceName: string, publicKey: PublicKey = null) {
-    this.program = program;
-    this.keyPair = keyPair;
-    this.publicKey = keyPair?.publicKey ?? publicKey;
-    this.instanceName = instanceName;
-  }
+    try {
+      await upgradeProcessorAt(processor, storageGold, 5);
+      assert(false, "Expected to fail");
+    } catch (e) {
+      assertAnchorError(e, "MaxLevelReached");
+    }
 
-  getPubKey(): PublicKey {
-    return this.publicKey;
-  }
+    expect((await processor.refresh()).level).equal(1);
+  });
+});
 
-  getPubKeyStr(): string {
-    return this.getPubKey().toBase58();
-  }
+describe("/PauseRetool", () => {
+  // Configure the client to use the local cluster.
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.GotAMin as Program<GotAMin>;
 
-  toString(): string {
-    return `${this.instanceName}()`;
-  }
+  it("Paused processor stops accruing until resumed #pause", async () => {
+    let location = await createLocation2(program, 'pause1', [106, 0], 20);
+    let resourceA = await createResource2(program, 'A', []);
+    let processor = await createProcessor3(resourceA, 1, 10, location);
+    let storageA = await createStorage4(resourceA, 20, location);
 
-  log(prefix: number = null): this {
-    let str = (prefix == null ? ">" : prefix + ": ");
-    console.log(str, this.toString());
-    return this;
-  }
+    await pauseProcessorAt(processor, 15);
 
-  withName(instanceName: string): this {
-    this.instanceName = instanceName;
-    return this;
-  }
-}
+    try {
+      await pauseProcessorAt(processor, 20);
+      assert(false, "Expected to fail");
+    } catch (e) {
+      assertAnchorError(e, "ProcessorPaused");
+    }
 
-type LocationType = { unexplored?: Record<string, never>; space?: Record<string, never>; planet?: Record<string, never>; moon?: Record<string, never>; asteroid?: Record<string, never>; };
+    await produce_without_input_at(processor, storageA, resourceA, 50);
+    expect((await storageA.refresh()).amount, "only before the pause").equal(1);
 
-class LocationState extends BaseState<LocationState> {
+    await resumeProcessorAt(processor, 100);
+    await produce_without_input_at(processor, storageA, resourceA, 115);
+    expect((await storageA.refresh()).amount, "progress kept over the pause").equal(3);
+  });
 
-  x: number;
-  xBN: anchor.BN;
-  y: number;
-  yBN: anchor.BN;
-  occupiedSpace: number;
-  type: LocationType;
+  it("Retool settles pending output and waits out the delay #retool", async () => {
+    let location = await createLocation2(program, 'retool1', [107, 0], 20);
+    let resourceA = await createResource2(program, 'A', []);
+    let resourceB = await createResource2(program, 'B', []);
+    let processor = await createProcessor3(resourceA, 1, 10, location);
+    let storageA = await createStorage4(resourceA, 20, location);
+    let storageB = await createStorage4(resourceB, 20, location);
+    await updateGameRetoolDelay(program, 50);
 
-  public static async createPda(program: Program<GotAMin>, publicKey: PublicKey, instanceName: string): Promise<LocationState> {
-    return new LocationState(program, null, instanceName, publicKey)
-      .refresh();
-  }
+    await retoolProcessorAt(processor, resourceA, storageA, resourceB, 35);
+    await updateGameRetoolDelay(program, 0);
 
-  async refresh(): Promise<LocationState> {
-    let state = await this.program.account.location.fetch(this.getPubKey());
-    this.x = state.posX.toNumber();
-    this.xBN = state.posX;
-    this.y = state.posY.toNumber();
-    this.yBN = state.posY;
-    this.occupiedSpace = state.occupiedSpace.toNumber();
-    this.type = state.locationType;
-    return this;
-  }
+    expect((await storageA.refresh()).amount, "settled").equal(3);
 
-  toString(): string {
-    return `${this.instanceName}[${this.x}/${this.y}](type=${this.typeAsJson()})`;
-  }
+    await produce_without_input_at(processor, storageB, resourceB, 80);
+    expect((await storageB.refresh()).amount, "still retooling").equal(0);
 
-  typeAsJson(): string {
-    return JSON.stringify(this.type);
-  }
+    await produce_without_input_at(processor, storageB, resourceB, 105);
+    expect((await storageB.refresh()).amount).equal(2);
+  });
+});
 
-}
+describe("/Wear", () => {
+  // Configure the client to use the local cluster.
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.GotAMin as Program<GotAMin>;
 
-class ProcessorState extends BaseState<ProcessorState> {
+  it("Output slows down with wear, stops when broken and restarts after repair #wear", async () => {
+    let location = await createLocation2(program, 'wear1', [108, 0], 20);
+    let resourceA = await createResource2(program, 'A', []);
+    let resourceGold = await createResource2(program, 'Gold', []);
+    await updateGameWear(program, 10, resourceGold, 2);
+    let processor = await createProcessor3(resourceA, 1, 10, location);
+    await updateGameWear(program, 0, resourceGold, 0);
+    let storageA = await createStorage4(resourceA, 20, location);
+    let storageGold = await createStorage4(resourceGold, 20, location);
+    await debugStorage(storageGold, 20);
 
-  claimedAt: number;
-  claimLocalBase: number = 0;
-  awaitingUnits: number;
+    await produce_without_input_at(processor, storageA, resourceA, 60);
+    expect((await storageA.refresh()).amount, "full efficiency").equal(6);
 
-  constructor(program: Program<GotAMin>, keyPair: KP, instanceName: string = "Processor") {
-    super(program, keyPair, instanceName);
-  }
+    await produce_without_input_at(processor, storageA, resourceA, 100);
+    expect((await storageA.refresh()).amount, "75% efficiency").equal(9);
 
-  async refresh(): Promise<ProcessorState> {
-    let state = await this.program.account.processor.fetch(this.getPubKey());
-    this.claimedAt = state.claimedAt.toNumber();
-    this.awaitingUnits = state.awaitingUnits.toNumber();
-    return this;
-  }
+    await produce_without_input_at(processor, storageA, resourceA, 200);
+    await produce_without_input_at(processor, storageA, resourceA, 300);
+    expect((await storageA.refresh()).amount, "broken").equal(10);
 
-  toString(): string {
-    let relativeClaim = this.claimedAt - this.claimLocalBase;
-    return `${this.instanceName}(claimedAt=${relativeClaim}, await=${this.awaitingUnits})`;
-  }
+    await repairProcessorAt(processor, storageGold, 50, 300);
+    expect((await storageGold.refresh()).amount, "repair capped at max durability").equal(0);
 
-  resetClaimBase(): ProcessorState {
-    this.claimLocalBase = this.claimedAt;
-    return this;
-  }
+    await produce_without_input_at(processor, storageA, resourceA, 350);
+    expect((await storageA.refresh()).amount, "repaired").equal(15);
+  });
+});
 
-}
+describe("/MobileProcessor", () => {
+  // Configure the client to use the local cluster.
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.GotAMin as Program<GotAMin>;
 
-class ResourceState extends BaseState<ResourceState> {
-  name: string;
+  it("Movable processor relocates and doesn't produce in transit #moveProcessor", async () => {
+    let location1 = await createLocation2(program, 'ship1', [109, 0], 20);
+    let location2 = await createLocation2(program, 'ship2', [111, 0], 20);
+    let resourceA = await createResource2(program, 'A', []);
+    let processor = await createProcessor3(resourceA, 1, 10, location1, {producer:{}}, {nothing:{}}, DEFAULT_FUEL_RES, {movable:{}}, 2);
+    let storageA = await createStorage4(resourceA, 20, location2);
 
-  constructor(program: Program<GotAMin>, keyPair: KP, instanceName: string = "Resource") {
-    super(program, keyPair, instanceName);
-  }
+    await moveProcessorAt(processor, location1, location2, 30);
 
-  async refresh(): Promise<ResourceState> {
-    let state = await this.program.account.resource.fetch(this.getPubKey());
-    this.name = state.name;
-    return this;
-  }
+    try {
+      await produce_without_input_at(processor, storageA, resourceA, 500);
+      assert(false, "Expected to fail");
+    } catch (e) {
+      assertAnchorError(e, "NotAllowedWhileMoving");
+    }
 
-  toString(): string {
-    return `${this.instanceName}(${this.name})`;
-  }
-}
+    await produce_without_input_at(processor, storageA, resourceA, 1050);
+    expect((await storageA.refresh()).amount, "travel time excluded").equal(5);
+    expect((await location2.refresh()).occupiedSpace).equal(2);
+  });
 
-class StorageState extends BaseState<StorageState> {
-  amount: number;
-  arrivesAt: number;
-  locationId: PublicKey;
-  readonly resource: ResourceState;
+  it("Fixed processor cannot move", async () => {
+    let location1 = await createLocation2(program, 'ship3', [112, 0], 20);
+    let location2 = await createLocation2(program, 'ship4', [113, 0], 20);
+    let resourceA = await createResource2(program, 'A', []);
+    let processor = await createProcessor3(resourceA, 1, 10, location1);
 
-  constructor(keyPair: KP, resource: ResourceState, instanceName: string = "Storage") {
-    super(resource.program, keyPair, instanceName);
-    this.resource = resource;
-  }
+    try {
+      await moveProcessorAt(processor, location1, location2, 30);
+      assert(false, "Expected to fail");
+    } catch (e) {
+      assertAnchorError(e, "StorageTypeNotMovable");
+    }
+  });
+});
 
-  async refresh(): Promise<StorageState> {
-    let state = await this.program.account.storage.fetch(this.getPubKey());
-    this.amount = state.amount.toNumber();
-    this.arrivesAt = state.arrivesAt.toNumber();
-    this.locationId = state.locationId;
-    return this;
-  }
+describe("/LinkedStorage", () => {
+  // Configure the client to use the local cluster.
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.GotAMin as Program<GotAMin>;
 
-  toString(): string {
-    return `${this.instanceName}(amnt=${this.amount})`;
-  }
+  it("Claim requires the storage to be linked #linkStorage", async () => {
+    let location = await createLocation2(program, 'link1', [114, 0], 20);
+    let resourceA = await createResource2(program, 'A', []);
+    let processor = await createProcessor3(resourceA, 1, 10, location);
+    let storageA = await createStorage4(resourceA, 20, location);
 
-}
+    await produce_without_input_at(processor, storageA, resourceA, 30);
+    expect((await storageA.refresh()).amount).equal(3);
 
-class UnitState extends BaseState<UnitState> {
+    await unlinkStorage(processor.getPubKey(), storageA);
 
-  name: string;
-  atLocation: PublicKey;
-  arrivesAt: number;
+    try {
+      await program.methods
+        .debugProduceWithoutInput(new anchor.BN(60))
+        .accounts({
+          processor: processor.getPubKey(),
+          storage: storageA.getPubKey(),
+          resource: resourceA.getPubKey(),
+          storageFuel: DEFAULT_FUEL_STORAGE.getPubKey(),
+        })
+        .rpc();
+      assert(false, "Expected to fail");
+    } catch (e) {
+      assertAnchorError(e, "StorageNotLinked");
+    }
+  });
+});
 
-  public static async createPda(program: Program<GotAMin>, publicKey: PublicKey, instanceName: string): Promise<UnitState> {
-    return new UnitState(program, null, instanceName, publicKey)
-      .refresh();
-  }
+describe("/Overflow", () => {
+  // Configure the client to use the local cluster.
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.GotAMin as Program<GotAMin>;
 
-  async refresh(): Promise<UnitState> {
-    let state = await this.program.account.unit.fetch(this.getPubKey());
-    this.name = state.name;
-    this.atLocation = state.atLocationId;
-    this.arrivesAt = state.arrivesAt.toNumber();;
-    return this;
-  }
+  it("Claiming from an extreme output rate fails instead of wrapping #overflow", async () => {
+    let location = await createLocation2(program, 'overflow1', [115, 0], 20);
+    let resourceA = await createResource2(program, 'A', []);
+    let processor = await createProcessor3(resourceA, Number.MAX_SAFE_INTEGER, 1, location);
+    let storageA = await createStorage4(resourceA, 20, location);
 
-  toString(): string {
-    return `${this.instanceName}(${this.name}, arrives: ${this.arrivesAt})`;
-  }
-}
+    try {
+      await produce_without_input_at(processor, storageA, resourceA, 1000);
+      assert(false, "Expected to fail");
+    } catch (e) {
+      assertAnchorError(e, "MathOverflow");
+    }
+    expect((await storageA.refresh()).amount).equal(0);
+  });
 
-function anchorBNtoNum(val: anchor.BN){
-  return val.toNumber()
-}
+  it("Sending an extreme amount far away fails on the fuel cost", async () => {
+    let location1 = await createLocation2(program, 'overflow2', [116, 0], 20);
+    let location2 = await createLocation2(program, 'overflow3', [116, 100], 20);
+    let resource = await createResource2(program, 'A', []);
+    let sender = await createProcessor3(resource, 1, 1, location1, {sender:{}}, {distance:{}});
+    let localStorage = await createStorage4(resource, Number.MAX_SAFE_INTEGER, location1);
+    let remoteStorage = await createStorage4(resource, 100, location2);
+    let storageFuel = await createStorage4(DEFAULT_FUEL_RES, 100, location1);
+    await debugStorage(localStorage, Number.MAX_SAFE_INTEGER);
+    await debugStorage(storageFuel, 100);
 
-class MapState extends BaseState<MapState> {
+    try {
+      // MAX_SAFE_INTEGER units * 100^2
+      await send_at(sender, remoteStorage, resource, localStorage, storageFuel, 0, location1, location2, Number.MAX_SAFE_INTEGER);
+      assert(false, "Expected to fail");
+    } catch (e) {
+      assertAnchorError(e, "MathOverflow");
+    }
+    expect((await storageFuel.refresh()).amount).equal(100);
+  });
+});
 
-  csm: CompressedSparseMatrix;
+describe("/GameClock", () => {
+  // Configure the client to use the local cluster.
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.GotAMin as Program<GotAMin>;
 
-  async refresh(): Promise<MapState> {
-    let state = await this.program.account.map.fetch(this.getPubKey());
-    this.initialized = true;
-    this.csm = new CompressedSparseMatrix(state.rowPtrs, state.columns, state.values, state.width, state.height, state.compressedValue);
-    return this;
-  }
+  it("Handlers read the game clock when it is passed along #gameClock", async () => {
+    let location = await createLocation2(program, 'clock1', [117, 0], 20);
+    let resourceA = await createResource2(program, 'A', []);
+    let processor = await createProcessor3(resourceA, 1, 10, location);
+    let storageA = await createStorage4(resourceA, 999, location);
+    let gameClock = await initGameClock(program);
 
-  async put(x: number, y: number, value: number) {
-    await this.program.methods
-    .mapPut(x, y, value)
-    .accounts({map: this.getPubKey()})
-    .rpc();
-  }
+    await produceWithGameClock(processor, storageA, resourceA, gameClock);
+    let before = (await storageA.refresh()).amount;
 
-  get(x: number, y: number): number {
-    return this.csm.get(x, y);
-  }
+    await advanceGameClock(program, 30);
+    await produceWithGameClock(processor, storageA, resourceA, gameClock);
 
-  toString(): string {
-    return `${this.instanceName}(${this.csm.width}x${this.csm.height} matrix=\n${this.csm.asMatrixToString()})`;
-  }
+    expect((await storageA.refresh()).amount - before).equal(3);
+  });
 
-  initMatrix(columns: number, rows: number): Array<Array<number>> {
-    let matrix = new Array<Array<number>>(rows);
+  it("Game clock never goes back", async () => {
+    await initGameClock(program);
 
-    for(let x = 0; x < columns; x++) {
-        matrix[x] = new Array(rows);
-        for(let y = 0; y < rows; y++) {
-            matrix[x][y] = 0;
-        }
+    try {
+      await advanceGameClock(program, -1);
+      assert(false, "Expected to fail");
+    } catch (e) {
+      assertAnchorError(e, "InvalidInput");
     }
-  
-    return matrix;
-}
+  });
+});
 
-  toMatrix(rowPtrs: Array<number>, columns: Array<number>, values: Array<number>): string {
-    let csm = new CompressedSparseMatrix(rowPtrs, columns, values, 6, 5, 0);
-    let matrix = this.initMatrix(20, 12);
-    let costM = (matrix[0].length * 8) * (matrix.length * 8);
-    let costCSM = (rowPtrs.length * 8) + (columns.length * 8) + values.length;
-    //console.log("Matrix cost: " + costM + ", CSM cost: " + costCSM);
+describe("/Events", () => {
+  // Configure the client to use the local cluster.
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.GotAMin as Program<GotAMin>;
 
-    let lines = "\n";
-    lines += "row2: ";
-    rowPtrs.forEach(function(a) { lines += a; });
-    lines += "\n";
-    lines += "col2: ";
-    columns.forEach(function(a) { lines += a; });
-    lines += "\n";
-    lines += "val2: ";
-    values.forEach(function(a) { lines += a; });
-    lines += "\n";
+  it("Claiming production emits ProductionClaimed #events", async () => {
+    let location = await createLocation2(program, 'events1', [118, 0], 20);
+    let resourceA = await createResource2(program, 'A', []);
+    let processor = await createProcessor3(resourceA, 1, 10, location);
+    let storageA = await createStorage4(resourceA, 2, location);
+
+    let [event, _] = await withEvent(program, "ProductionClaimed", async () => {
+      await produce_without_input_at(processor, storageA, resourceA, 30);
+    });
+
+    expect(event.processor.toBase58()).equal(processor.getPubKey().toBase58());
+    expect(event.storage.toBase58()).equal(storageA.getPubKey().toBase58());
+    expect(event.produced.toNumber(), "produced").equal(3);
+    expect(event.delivered.toNumber(), "delivered").equal(2);
+    expect(event.awaitingUnits.toNumber(), "awaiting").equal(1);
+  });
+});
 
-    csm.unpack(matrix, 0, 0);
+describe("/Location", () => {
+  // Configure the client to use the local cluster.
+  anchor.setProvider(anchor.AnchorProvider.env());
+  const program = anchor.workspace.GotAMin as Program<GotAMin>;
+  const programProvider = program.provider as anchor.AnchorProvider;
+  const pk = programProvider.wallet.publicKey;
 
-    let str = "";
+  it("Move between Storage in different locations", async () => {
+    let resource = await createResource2(program, 'A', []);
+    let locationA = await createLocation2(program, 'locA', [0, 0], 10);
+    let storageAFrom = await createStorage4(resource, 10, locationA);
+    let locationB = await createLocation2(program, 'locB', [1, 0], 10);
+    let storageBTo = await createStorage4(resource, 100, locationB);
+    await debugStorage(storageAFrom, 10);
 
-    str += lines;
+    try {
+      await move_between_storage(storageAFrom, storageBTo, 1);
 
-    str += "-- 1 --\n";
-    for(let y = 0; y < matrix[0].length; y++) {
-        for(let x = 0; x < matrix.length; x++) {
-            str += matrix[x][y];
-        }
-        str += "\n";
+      assert(false, "Expected to fail");
+    } catch(e) {
+      assertAnchorError(e, "DifferentLocations");
     }
-    str += "-- 2 --";
+  });
 
-    return str;
-  }
-}
+  it("Producer and Storage in different locations", async () => {
+    let resource = await createResource2(program, 'A', []);
+    let location = await createLocation2(program, 'locA', [0, 0], 10);
+    let producer = await createProcessor3(resource, 10, 1);
+    let storage = await createStorage4(resource, 10, location);
 
-class CompressedSparseMatrix {
-  width: number;
-  height: number;
-  compressedValue: number;
-  rowPtrs: Array<number>;
-  columns: Array<number>;
-  values: Array<number>;
-  
-  constructor(rowPtrs: Array<number>, columns: Array<number>, values: Array<number>, width: number, height: number, compressedValue: number) {
-    let rowPtrsLen = rowPtrs.map(num => num > 0).lastIndexOf(true);
-    let columnsLen = columns.map(num => num > 0).lastIndexOf(true);
-    let valuesLen = values.map(num => num > 0).lastIndexOf(true);
-    let biggestLen = Math.max(columnsLen, valuesLen);
-    console.log("biggestLen", biggestLen);
+    try {
+      await produce_without_input(producer, storage, resource);
 
-    this.rowPtrs = rowPtrs.slice(0, rowPtrsLen + 1);
-    this.columns = columns.slice(0, biggestLen + 1);
-    this.values = values.slice(0, biggestLen + 1);
-    this.width = width;
-    this.height = height;
-    this.compressedValue = compressedValue;
-  }
+      assert(false, "Expected to fail");
+    } catch(e) {
+      assertAnchorError(e, "DifferentLocations");
+    }
+  });  
 
-  static fromMatrix(matrix: Array<Array<number>>, compressValue: number = 0): CompressedSparseMatrix {
-      let ptrs = new Array<number>();
-      let cols = new Array<number>();
-      let vals = new Array<number>();
-  
-      let prevPtr = -1;
-      for(let y = 0; y < matrix[0].length; y++) {
-          let perRowCounter = 0;
-          for(let x = 0; x < matrix.length; x++) {
-              let mvalue = matrix[x][y];
-              if(mvalue != compressValue) {
-                  if(y != prevPtr) {
-                      ptrs.push(cols.length);
-                      prevPtr = y;
-                      perRowCounter++;
-                  }
-                  cols.push(x);
-                  vals.push(mvalue);
-              }
-          }
+  it("Move static Storage fails", async () => {
+    let resource = await createResource2(program, 'A', []);
+    let location1 = await createLocation2(program, 'loc1', [0, 0], 10);
+    let storage = await createStorage4(resource, 10, location1);
+    let location2 = await createLocation2(program, 'loc2', [1, 0], 10);
 
-          if(perRowCounter == 0) {
-              ptrs.push(cols.length);
-              cols.push(0);
-              vals.push(compressValue);
-      }
-      }
+    try {
+      await moveStorageAt(storage, location1, location2, 1);
 
-      return new CompressedSparseMatrix(ptrs, cols, vals, matrix[0].length, matrix.length, compressValue);
-  }
+      assert(false, "Expected to fail");
+    } catch(e) {
+      assertAnchorError(e, "StorageTypeNotMovable");
+    }
+  });  
 
-  static initMatrix(columns: number, rows: number, compressedValue: number): Array<Array<number>> {
-      let matrix = new Array<Array<number>>(rows);
-  
-      for(let x = 0; x < columns; x++) {
-          matrix[x] = new Array(rows);
-          for(let y = 0; y < rows; y++) {
-              matrix[x][y] = compressedValue;
-          }
-      }
-    
-      return matrix;
-  }
-  
-  unpack(targetMatrix: Array<Array<number>>, startX: number = 0, startY: number = 0) {
-      let rp = this.rowPtrs[0];
-      let rpNext = this.rowPtrs[1]
-      let rpIdx = 0;
-      for(let c = 0; c < this.columns.length; c++) {
-          let x = this.columns[c];
-          let v = this.values[c];
-  
-          if(rpIdx < this.rowPtrs.length) {
-              rp = this.rowPtrs[rpIdx];
-          }
-          
-          if(rpIdx+1 < this.rowPtrs.length) {
-              rpNext = this.rowPtrs[rpIdx+1];
-          } else {
-              rpNext = rp;
-          }
-  
-          if(c > 0 && c == rpNext) {
-              rpIdx++;
-          }
-  
-          let targetX = startX + x;
-          let targetY = startY + rpIdx;
+  it("Move Storage to full Location fails", async () => {
+    let resource = await createResource2(program, 'A', []);
+    let location1 = await createLocation2(program, 'loc1', [60, 0], 10);
+    let storage = await createStorage4(resource, 10, location1, {movable:{}});
+    let location2 = await createLocation2(program, 'loc2', [61, 0], 0);
 
-          targetMatrix[targetX][targetY] = v;
-      }
-  }
+    try {
+      await moveStorageAt(storage, location1, location2, 1);
 
-  exists(x: number, y: number): boolean {
-      let [i, _] = this.valuePtr(x, y);
-      if(i >= 0) {
-          return this.get(x, y) != this.compressedValue;
-      } else {
-          return false;
-      }
-  }
+      assert(false, "Expected to fail");
+    } catch(e) {
+      assertAnchorError(e, "LocationFull");
+    }
+  });
 
-  get(x: number, y: number): number {
-      let r = this.compressedValue;
-      let [i, _] = this.valuePtr(x, y);
-      if(i >= 0) {
-          r = this.values[i];
-      }
-      return r;
+  it("Move Storage to new Location", async () => {
+    let resource = await createResource2(program, 'A', []);
+    let location1 = await createLocation2(program, 'loc1', [50, 0], 10);
+    let storage = await createStorage4(resource, 10, location1, {movable:{}});
+    let location2 = await createLocation2(program, 'loc2', [51, 0], 10);
+
+    await moveStorageAt(storage, location1, location2, 1);
+    await storage.refresh();
+    await location1.refresh();
+    await location2.refresh();
+
+    expect(storage.locationId.toBase58()).equal(location2.getPubKeyStr());
+    expect(location1.occupiedSpace).equal(0);
+    expect(location2.occupiedSpace).equal(1);
+  });  
+
+});
+
+function assertAnchorError(error: any, errorName: String) {
+  if(error instanceof AnchorError) {
+    expect(error, "Expected to be of type AnchorError").to.be.instanceOf(AnchorError);
+    let anchorError: AnchorError = error;
+    expect(anchorError.error.errorCode.code).to.equal(errorName);  
+  } else {
+    throw error;
   }
+}
 
-  put(x: number, y: number, newValue: number) {
-      let r = 0;
-      let [i, insertPoint] = this.valuePtr(x, y);
-      if(i >= 0) {
-          this.values[i] = newValue;
-      } else {
-          if(insertPoint >= 0) {
-              this.columns.splice(insertPoint, 0, x);
-              this.values.splice(insertPoint, 0, newValue);
+async function createResource(program: Program<GotAMin>, name: string, inputs):  Promise<[KP, any]> {
+  const resource: anchor.web3.Keypair = anchor.web3.Keypair.generate();
+  return [resource, await initResource(program, resource, name, inputs)];
+}
 
-              if(y < this.rowPtrs.length) {
-                  let rpValNext = this.columns.length;
-                  if(y+1 < this.rowPtrs.length) {
-                      rpValNext = this.rowPtrs[y+1];
-                  }
-                  for(let i = y+1; i < this.rowPtrs.length; i++) {
-                      let newRpVal = this.rowPtrs[i] + 1;
-                      this.rowPtrs.splice(i, 1, newRpVal);
-                  }
-              }
+async function createResource2(program: Program<GotAMin>, name: string, inputs):  Promise<ResourceState> {
+  const resourceKey: anchor.web3.Keypair = anchor.web3.Keypair.generate();
+  await initResource(program, resourceKey, name, inputs);
+  return new ResourceState(program, resourceKey).refresh();
+}
 
-              if(x >= this.width) {
-                  this.width = x + 1;
-              }
-          } else {
-              let xDiff = (x + 1) - this.width;
-              if(xDiff > 0) {
-                  this.width += xDiff;
-              }
+async function initResource(program: Program<GotAMin>, resource, name: string, inputs: [anchor.web3.Keypair, Number][]) {
+  const programProvider = program.provider as anchor.AnchorProvider;
 
-              let yDiff = (y + 1) - this.height;
+  let publicKeyInputs = [];
+  let amountInputs = [];
+  inputs.forEach(tuple => {
+    publicKeyInputs.push(tuple[0].publicKey);
+    amountInputs.push(new anchor.BN(tuple[1].toFixed()));
+  });
 
-              for(let addY = this.height; addY < y+1; addY++) {
-                  this.rowPtrs.push(this.columns.length);
-                  this.columns.push(0);
-                  this.values.push(0);    
-              }
+  await program.methods
+    .initResource(name, publicKeyInputs, amountInputs)
+    .accounts({
+      resource: resource.publicKey,
+      owner: programProvider.wallet.publicKey,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    })
+    .signers(resource)
+    .rpc();
+    
+  return await program.account.resource.fetch(resource.publicKey);
+}
 
-              if(yDiff > 0) {
-                  this.rowPtrs.push(this.columns.length);
-                  this.columns.push(x);
-                  this.values.push(newValue);    
-                  this.height += yDiff;
-              }
-          }
-      }
-      return r;
-  }
+async function createProcessor(program: Program<GotAMin>, output_resource, outputRate, processingDuration = 5, location = DEFAULT_LOCATION, type: ProcessorType = {producer:{}}, fuel_resource = DEFAULT_FUEL_RES, fuelCostType: FuelCostType = {nothing:{}}): Promise<[KP, any]> {
+  const processor = anchor.web3.Keypair.generate();
+  return [processor, await initProcessor(processor, fuel_resource, output_resource, outputRate, processingDuration, location, type, fuelCostType)];
+}
 
-  valuePtr(x: number, y: number): [number, number] {
-      let valueIndex = -1;
-      let insertPoint = -1;
-      if(y < this.rowPtrs.length) {
-          let rpVal = this.rowPtrs[y];
-          let rpValNext = this.columns.length;
-          if(y+1 < this.rowPtrs.length) {
-              rpValNext = this.rowPtrs[y+1];
-          }
-          let checkColSubset = false;
-          let xInColumn = 0;
-          //let colSubset = "";
-          for(let colSubsetPos = rpVal; colSubsetPos < rpValNext; colSubsetPos++) {
-              //colSubset += this.columns[colSubsetPos];
-              if(x == this.columns[colSubsetPos]) {
-                  xInColumn = colSubsetPos;
-                  checkColSubset = true;
-                  break;
-              } else if(x < this.columns[colSubsetPos]) {
-                  insertPoint = colSubsetPos;
-                  break;
-              } else if(colSubsetPos == rpValNext - 1) {
-                  //console.log("colSubsetPos end: ", colSubsetPos);
-                  insertPoint = colSubsetPos + 1;
-              }
-          }
+async function createProcessor3(output_resource: ResourceState, outputRate, processingDuration = 5, location: LocationState = DEFAULT_LOCATION, type: ProcessorType = {producer:{}}, fuelCostType: FuelCostType = {nothing:{}}, fuel_resource = DEFAULT_FUEL_RES, mobilityType: MobilityType = {fixed:{}}, movementSpeed = 0): Promise<ProcessorState> {
+  let program = output_resource.program;
+  const keyPair = anchor.web3.Keypair.generate();
+  await initProcessor(keyPair, fuel_resource, output_resource, outputRate, processingDuration, location, type, fuelCostType, mobilityType, movementSpeed);
+  return new ProcessorState(program, keyPair).refresh();
+}
 
-          let checkMinimum = xInColumn >= 0 && checkColSubset;
-          let checkMaxPerRowOrEnd = (xInColumn <= rpValNext);
-          if(checkMinimum && checkMaxPerRowOrEnd) {
-              let c = xInColumn;
-              if(c < this.values.length) {
-                  valueIndex = c;
-              }
-          }
-          //console.log("valueIndex", valueIndex, "rpVal", rpVal, "x2", x2, "colSubset", colSubset);
-      }
-      return [valueIndex, insertPoint];
-  }
-  
-  asMatrixToString(): string {
-      let matrix = CompressedSparseMatrix.initMatrix(this.width, this.height, this.compressedValue);
-      this.unpack(matrix);
-      let str = "";
-      for(let y = 0; y < matrix[0].length; y++) {
-          for(let x = 0; x < matrix.length; x++) {
-              str += matrix[x][y];
-          }
-          str += "\n";
-      }
-      return str;
-  }
+async function initProcessor(processor, fuelResource: ResourceState, outputResource, outputRate, processingDuration = 5, location = DEFAULT_LOCATION, type: ProcessorType = {producer:{}}, fuelCostType: FuelCostType = {nothing:{}}, mobilityType: MobilityType = {fixed:{}}, movementSpeed = 0) {
+  let program = fuelResource.program;
+  assert(outputRate > 0, 'initProcessor requirement: outputRate > 0');
+  assert(processingDuration > 0, 'initProcessor requirement: processingDuration > 0');
+  const programProvider = program.provider as anchor.AnchorProvider;
+  const outputRateBN = new anchor.BN(outputRate);
+  const processingDurationBN = new anchor.BN(processingDuration);
 
-  debugCompressedAsString(): string {
-      let lines = this.width + " x " + this.height + "\n";
-      lines += "rowPtr: ";
-      this.rowPtrs.forEach(function(a) { lines += a; });
-      lines += "\n";
-      lines += "col: ";
-      this.columns.forEach(function(a) { lines += a; });
-      lines += "\n";
-      lines += "val: ";
-      this.values.forEach(function(a) { lines += a; });
-      return lines;        
-  }
+  await program.methods
+    .initProcessor({
+      processorType: type,
+      fuelResourceId: fuelResource.getPubKey(),
+      outputResourceId: outputResource.publicKey,
+      outputRate: outputRateBN,
+      processingDuration: processingDurationBN,
+      fuelCostType: fuelCostType,
+      mobilityType: mobilityType,
+      movementSpeed: new anchor.BN(movementSpeed),
+    })
+    .accounts({
+      processor: processor.publicKey,
+      game: getGamePda(program, programProvider.wallet.publicKey),
+      location: location.getPubKey(),
+      owner: programProvider.wallet.publicKey,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    })
+    .remainingAccounts([gameClockMeta(program)])
+    .signers(processor)
+    .rpc();
+    
+    return await program.account.processor.fetch(processor.publicKey);
 }
 
-async function initStorage(
-  program: Program<GotAMin>, 
-  storage: KP, 
+async function createStorageNew(
+  program: Program<GotAMin>,
+  owner: KP,
   resource: KP, 
   capacity: number, 
   location: LocationState = DEFAULT_LOCATION, 
   mobilityType: MobilityType = {fixed:{}}, 
   speed: number = 1,
+): Promise<[KP, any]> {
+  const storage: KP = anchor.web3.Keypair.generate();
+  return [storage, await initStorageNew(program, owner, storage, resource, capacity, location, mobilityType, speed)];
+}
+
+async function initStorageNew(
+  program: Program<GotAMin>, 
+  owner: KP,
+  storage, resource: KP, 
+  capacity: number, 
+  location: LocationState = DEFAULT_LOCATION, 
+  mobilityType: MobilityType = {fixed:{}}, 
+  speed: number = 1,
 ) {
-  const provider = program.provider as anchor.AnchorProvider;
+  const programProvider = program.provider as anchor.AnchorProvider;
+
+  await program.methods
+    .initStorage(resource.publicKey, new anchor.BN(capacity), mobilityType, new anchor.BN(speed), location.xBN, location.yBN)
+    .accounts({
+      storage: storage.publicKey,
+      location: location.getPubKey(),
+      owner: owner.publicKey,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    })
+    .signers([storage, owner])
+    .rpc();
+    
+  return await program.account.storage.fetch(storage.publicKey);
+}
+
+async function createStorage(
+  program: Program<GotAMin>,
+  resource: KP, 
+  capacity: number, 
+  location: LocationState = DEFAULT_LOCATION, 
+  mobilityType: MobilityType = {fixed:{}}, 
+  speed: number = 1,
+): Promise<[KP, any]> {
+  const storage: KP = anchor.web3.Keypair.generate();
+  return [storage, await initStorage(program, storage, resource, capacity, location, mobilityType, speed)];
+}
+
+async function createStorage4(
+  resource: ResourceState, 
+  capacity: number, 
+  location: LocationState = DEFAULT_LOCATION, 
+  mobilityType: MobilityType = {fixed:{}}, 
+  speed: number = 1,
+  instanceName: string = null,
+): Promise<StorageState> {
+  let [keyPair, b] = await createStorage(resource.program, resource.keyPair, capacity, location, mobilityType, speed);
+  let state = new StorageState(keyPair, resource, instanceName);
+  return await state.refresh();
+}
+
+class BaseState<T> {
+  readonly program: Program<GotAMin>;
+  readonly keyPair: KP;
+  readonly publicKey: anchor.web3.PublicKey;
+  instanceName: string;
+  initialized: boolean = false;
+
+  constructor(program: Program<GotAMin>, keyPair: KP, instanceName: string, publicKey: PublicKey = null) {
+    this.program = program;
+    this.keyPair = keyPair;
+    this.publicKey = keyPair?.publicKey ?? publicKey;
+    this.instanceName = instanceName;
+  }
+
+  getPubKey(): PublicKey {
+    return this.publicKey;
+  }
+
+  getPubKeyStr(): string {
+    return this.getPubKey().toBase58();
+  }
+
+  toString(): string {
+    return `${this.instanceName}()`;
+  }
+
+  log(prefix: number = null): this {
+    let str = (prefix == null ? ">" : prefix + ": ");
+    console.log(str, this.toString());
+    return this;
+  }
+
+  withName(instanceName: string): this {
+    this.instanceName = instanceName;
+    return this;
+  }
+}
+
+type LocationType = { unexplored?: Record<string, never>; space?: Record<string, never>; planet?: Record<string, never>; moon?: Record<string, never>; asteroid?: Record<string, never>; };
+
+class LocationState extends BaseState<LocationState> {
+
+  x: number;
+  xBN: anchor.BN;
+  y: number;
+  yBN: anchor.BN;
+  occupiedSpace: number;
+  type: LocationType;
+
+  public static async createPda(program: Program<GotAMin>, publicKey: PublicKey, instanceName: string): Promise<LocationState> {
+    return new LocationState(program, null, instanceName, publicKey)
+      .refresh();
+  }
+
+  async refresh(): Promise<LocationState> {
+    let state = await this.program.account.location.fetch(this.getPubKey());
+    this.x = state.posX.toNumber();
+    this.xBN = state.posX;
+    this.y = state.posY.toNumber();
+    this.yBN = state.posY;
+    this.occupiedSpace = state.occupiedSpace.toNumber();
+    this.type = state.locationType;
+    return this;
+  }
+
+  toString(): string {
+    return `${this.instanceName}[${this.x}/${this.y}](type=${this.typeAsJson()})`;
+  }
+
+  typeAsJson(): string {
+    return JSON.stringify(this.type);
+  }
+
+}
+
+class ProcessorState extends BaseState<ProcessorState> {
+
+  claimedAt: number;
+  claimLocalBase: number = 0;
+  awaitingUnits: number;
+  level: number;
+  outputRate: number;
+  processingDuration: number;
+
+  constructor(program: Program<GotAMin>, keyPair: KP, instanceName: string = "Processor") {
+    super(program, keyPair, instanceName);
+  }
+
+  async refresh(): Promise<ProcessorState> {
+    let state = await this.program.account.processor.fetch(this.getPubKey());
+    this.claimedAt = state.claimedAt.toNumber();
+    this.awaitingUnits = state.awaitingUnits.toNumber();
+    this.level = state.level;
+    this.outputRate = state.outputRate.toNumber();
+    this.processingDuration = state.processingDuration.toNumber();
+    return this;
+  }
+
+  toString(): string {
+    let relativeClaim = this.claimedAt - this.claimLocalBase;
+    return `${this.instanceName}(claimedAt=${relativeClaim}, await=${this.awaitingUnits})`;
+  }
+
+  resetClaimBase(): ProcessorState {
+    this.claimLocalBase = this.claimedAt;
+    return this;
+  }
+
+}
+
+class ResourceState extends BaseState<ResourceState> {
+  name: string;
+
+  constructor(program: Program<GotAMin>, keyPair: KP, instanceName: string = "Resource") {
+    super(program, keyPair, instanceName);
+  }
+
+  async refresh(): Promise<ResourceState> {
+    let state = await this.program.account.resource.fetch(this.getPubKey());
+    this.name = state.name;
+    return this;
+  }
+
+  toString(): string {
+    return `${this.instanceName}(${this.name})`;
+  }
+}
+
+class StorageState extends BaseState<StorageState> {
+  amount: number;
+  arrivesAt: number;
+  locationId: PublicKey;
+  readonly resource: ResourceState;
+
+  constructor(keyPair: KP, resource: ResourceState, instanceName: string = "Storage") {
+    super(resource.program, keyPair, instanceName);
+    this.resource = resource;
+  }
+
+  async refresh(): Promise<StorageState> {
+    let state = await this.program.account.storage.fetch(this.getPubKey());
+    this.amount = state.amount.toNumber();
+    this.arrivesAt = state.arrivesAt.toNumber();
+    this.locationId = state.locationId;
+    return this;
+  }
+
+  toString(): string {
+    return `${this.instanceName}(amnt=${this.amount})`;
+  }
+
+}
+
+class UnitState extends BaseState<UnitState> {
+
+  name: string;
+  atLocation: PublicKey;
+  arrivesAt: number;
+
+  public static async createPda(program: Program<GotAMin>, publicKey: PublicKey, instanceName: string): Promise<UnitState> {
+    return new UnitState(program, null, instanceName, publicKey)
+      .refresh();
+  }
+
+  async refresh(): Promise<UnitState> {
+    let state = await this.program.account.unit.fetch(this.getPubKey());
+    this.name = state.name;
+    this.atLocation = state.atLocationId;
+    this.arrivesAt = state.arrivesAt.toNumber();;
+    return this;
+  }
+
+  toString(): string {
+    return `${this.instanceName}(${this.name}, arrives: ${this.arrivesAt})`;
+  }
+}
+
+function anchorBNtoNum(val: anchor.BN){
+  return val.toNumber()
+}
+
+class MapState extends BaseState<MapState> {
+
+  csm: CompressedSparseMatrix;
+
+  async refresh(): Promise<MapState> {
+    let state = await this.program.account.map.fetch(this.getPubKey());
+    this.initialized = true;
+    this.csm = new CompressedSparseMatrix(state.rowPtrs, state.columns, state.values, state.width, state.height, state.compressedValue);
+    return this;
+  }
+
+  async put(x: number, y: number, value: number) {
+    await this.program.methods
+    .mapPut(x, y, value)
+    .accounts({map: this.getPubKey()})
+    .rpc();
+  }
+
+  get(x: number, y: number): number {
+    return this.csm.get(x, y);
+  }
+
+  toString(): string {
+    return `${this.instanceName}(${this.csm.width}x${this.csm.height} matrix=\n${this.csm.asMatrixToString()})`;
+  }
+
+  initMatrix(columns: number, rows: number): Array<Array<number>> {
+    let matrix = new Array<Array<number>>(rows);
+
+    for(let x = 0; x < columns; x++) {
+        matrix[x] = new Array(rows);
+        for(let y = 0; y < rows; y++) {
+            matrix[x][y] = 0;
+        }
+    }
+  
+    return matrix;
+}
+
+  toMatrix(rowPtrs: Array<number>, columns: Array<number>, values: Array<number>): string {
+    let csm = new CompressedSparseMatrix(rowPtrs, columns, values, 6, 5, 0);
+    let matrix = this.initMatrix(20, 12);
+    let costM = (matrix[0].length * 8) * (matrix.length * 8);
+    let costCSM = (rowPtrs.length * 8) + (columns.length * 8) + values.length;
+    //console.log("Matrix cost: " + costM + ", CSM cost: " + costCSM);
+
+    let lines = "\n";
+    lines += "row2: ";
+    rowPtrs.forEach(function(a) { lines += a; });
+    lines += "\n";
+    lines += "col2: ";
+    columns.forEach(function(a) { lines += a; });
+    lines += "\n";
+    lines += "val2: ";
+    values.forEach(function(a) { lines += a; });
+    lines += "\n";
+
+    csm.unpack(matrix, 0, 0);
+
+    let str = "";
+
+    str += lines;
+
+    str += "-- 1 --\n";
+    for(let y = 0; y < matrix[0].length; y++) {
+        for(let x = 0; x < matrix.length; x++) {
+            str += matrix[x][y];
+        }
+        str += "\n";
+    }
+    str += "-- 2 --";
+
+    return str;
+  }
+}
+
+class CompressedSparseMatrix {
+  width: number;
+  height: number;
+  compressedValue: number;
+  rowPtrs: Array<number>;
+  columns: Array<number>;
+  values: Array<number>;
+  
+  constructor(rowPtrs: Array<number>, columns: Array<number>, values: Array<number>, width: number, height: number, compressedValue: number) {
+    let rowPtrsLen = rowPtrs.map(num => num > 0).lastIndexOf(true);
+    let columnsLen = columns.map(num => num > 0).lastIndexOf(true);
+    let valuesLen = values.map(num => num > 0).lastIndexOf(true);
+    let biggestLen = Math.max(columnsLen, valuesLen);
+    console.log("biggestLen", biggestLen);
+
+    this.rowPtrs = rowPtrs.slice(0, rowPtrsLen + 1);
+    this.columns = columns.slice(0, biggestLen + 1);
+    this.values = values.slice(0, biggestLen + 1);
+    this.width = width;
+    this.height = height;
+    this.compressedValue = compressedValue;
+  }
+
+  static fromMatrix(matrix: Array<Array<number>>, compressValue: number = 0): CompressedSparseMatrix {
+      let ptrs = new Array<number>();
+      let cols = new Array<number>();
+      let vals = new Array<number>();
+  
+      let prevPtr = -1;
+      for(let y = 0; y < matrix[0].length; y++) {
+          let perRowCounter = 0;
+          for(let x = 0; x < matrix.length; x++) {
+              let mvalue = matrix[x][y];
+              if(mvalue != compressValue) {
+                  if(y != prevPtr) {
+                      ptrs.push(cols.length);
+                      prevPtr = y;
+                      perRowCounter++;
+                  }
+                  cols.push(x);
+                  vals.push(mvalue);
+              }
+          }
+
+          if(perRowCounter == 0) {
+              ptrs.push(cols.length);
+              cols.push(0);
+              vals.push(compressValue);
+      }
+      }
+
+      return new CompressedSparseMatrix(ptrs, cols, vals, matrix[0].length, matrix.length, compressValue);
+  }
+
+  static initMatrix(columns: number, rows: number, compressedValue: number): Array<Array<number>> {
+      let matrix = new Array<Array<number>>(rows);
+  
+      for(let x = 0; x < columns; x++) {
+          matrix[x] = new Array(rows);
+          for(let y = 0; y < rows; y++) {
+              matrix[x][y] = compressedValue;
+          }
+      }
+    
+      return matrix;
+  }
+  
+  unpack(targetMatrix: Array<Array<number>>, startX: number = 0, startY: number = 0) {
+      let rp = this.rowPtrs[0];
+      let rpNext = this.rowPtrs[1]
+      let rpIdx = 0;
+      for(let c = 0; c < this.columns.length; c++) {
+          let x = this.columns[c];
+          let v = this.values[c];
+  
+          if(rpIdx < this.rowPtrs.length) {
+              rp = this.rowPtrs[rpIdx];
+          }
+          
+          if(rpIdx+1 < this.rowPtrs.length) {
+              rpNext = this.rowPtrs[rpIdx+1];
+          } else {
+              rpNext = rp;
+          }
+  
+          if(c > 0 && c == rpNext) {
+              rpIdx++;
+          }
+  
+          let targetX = startX + x;
+          let targetY = startY + rpIdx;
+
+          targetMatrix[targetX][targetY] = v;
+      }
+  }
+
+  exists(x: number, y: number): boolean {
+      let [i, _] = this.valuePtr(x, y);
+      if(i >= 0) {
+          return this.get(x, y) != this.compressedValue;
+      } else {
+          return false;
+      }
+  }
+
+  get(x: number, y: number): number {
+      let r = this.compressedValue;
+      let [i, _] = this.valuePtr(x, y);
+      if(i >= 0) {
+          r = this.values[i];
+      }
+      return r;
+  }
+
+  put(x: number, y: number, newValue: number) {
+      let r = 0;
+      let [i, insertPoint] = this.valuePtr(x, y);
+      if(i >= 0) {
+          this.values[i] = newValue;
+      } else {
+          if(insertPoint >= 0) {
+              this.columns.splice(insertPoint, 0, x);
+              this.values.splice(insertPoint, 0, newValue);
+
+              if(y < this.rowPtrs.length) {
+                  let rpValNext = this.columns.length;
+                  if(y+1 < this.rowPtrs.length) {
+                      rpValNext = this.rowPtrs[y+1];
+                  }
+                  for(let i = y+1; i < this.rowPtrs.length; i++) {
+                      let newRpVal = this.rowPtrs[i] + 1;
+                      this.rowPtrs.splice(i, 1, newRpVal);
+                  }
+              }
+
+              if(x >= this.width) {
+                  this.width = x + 1;
+              }
+          } else {
+              let xDiff = (x + 1) - this.width;
+              if(xDiff > 0) {
+                  this.width += xDiff;
+              }
+
+              let yDiff = (y + 1) - this.height;
+
+              for(let addY = this.height; addY < y+1; addY++) {
+                  this.rowPtrs.push(this.columns.length);
+                  this.columns.push(0);
+                  this.values.push(0);    
+              }
+
+              if(yDiff > 0) {
+                  this.rowPtrs.push(this.columns.length);
+                  this.columns.push(x);
+                  this.values.push(newValue);    
+                  this.height += yDiff;
+              }
+          }
+      }
+      return r;
+  }
+
+  valuePtr(x: number, y: number): [number, number] {
+      let valueIndex = -1;
+      let insertPoint = -1;
+      if(y < this.rowPtrs.length) {
+          let rpVal = this.rowPtrs[y];
+          let rpValNext = this.columns.length;
+          if(y+1 < this.rowPtrs.length) {
+              rpValNext = this.rowPtrs[y+1];
+          }
+          let checkColSubset = false;
+          let xInColumn = 0;
+          //let colSubset = "";
+          for(let colSubsetPos = rpVal; colSubsetPos < rpValNext; colSubsetPos++) {
+              //colSubset += this.columns[colSubsetPos];
+              if(x == this.columns[colSubsetPos]) {
+                  xInColumn = colSubsetPos;
+                  checkColSubset = true;
+                  break;
+              } else if(x < this.columns[colSubsetPos]) {
+                  insertPoint = colSubsetPos;
+                  break;
+              } else if(colSubsetPos == rpValNext - 1) {
+                  //console.log("colSubsetPos end: ", colSubsetPos);
+                  insertPoint = colSubsetPos + 1;
+              }
+          }
+
+          let checkMinimum = xInColumn >= 0 && checkColSubset;
+          let checkMaxPerRowOrEnd = (xInColumn <= rpValNext);
+          if(checkMinimum && checkMaxPerRowOrEnd) {
+              let c = xInColumn;
+              if(c < this.values.length) {
+                  valueIndex = c;
+              }
+          }
+          //console.log("valueIndex", valueIndex, "rpVal", rpVal, "x2", x2, "colSubset", colSubset);
+      }
+      return [valueIndex, insertPoint];
+  }
+  
+  asMatrixToString(): string {
+      let matrix = CompressedSparseMatrix.initMatrix(this.width, this.height, this.compressedValue);
+      this.unpack(matrix);
+      let str = "";
+      for(let y = 0; y < matrix[0].length; y++) {
+          for(let x = 0; x < matrix.length; x++) {
+              str += matrix[x][y];
+          }
+          str += "\n";
+      }
+      return str;
+  }
+
+  debugCompressedAsString(): string {
+      let lines = this.width + " x " + this.height + "\n";
+      lines += "rowPtr: ";
+      this.rowPtrs.forEach(function(a) { lines += a; });
+      lines += "\n";
+      lines += "col: ";
+      this.columns.forEach(function(a) { lines += a; });
+      lines += "\n";
+      lines += "val: ";
+      this.values.forEach(function(a) { lines += a; });
+      return lines;        
+  }
+}
+
+async function initStorage(
+  program: Program<GotAMin>, 
+  storage: KP, 
+  resource: KP, 
+  capacity: number, 
+  location: LocationState = DEFAULT_LOCATION, 
+  mobilityType: MobilityType = {fixed:{}}, 
+  speed: number = 1,
+) {
+  const provider = program.provider as anchor.AnchorProvider;
+
+  await program.methods
+    .initStorage(resource.publicKey, new anchor.BN(capacity), mobilityType, new anchor.BN(speed), location.xBN, location.yBN)
+    .accounts({
+      storage: storage.publicKey,
+      location: location.getPubKey(),
+      owner: provider.wallet.publicKey,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    })
+    .signers([storage])
+    .rpc();
+    
+  return await program.account.storage.fetch(storage.publicKey);
+}
+
+async function debugStorage(
+  storage: StorageState, 
+  amount: number = 1,
+) {
+  let program = storage.program;
+
+  await program.methods
+    .debugSetStorageAmount(new anchor.BN(amount))
+    .accounts({
+      storage: storage.getPubKey(),
+      admin: debugAuthority(program),
+    })
+    .rpc();    
+}
+
+async function initDefaultFuel(program: Program<GotAMin>): Promise<ResourceState> {
+  return await createResource2(program, "default_fuel", []);
+}
+
+async function createLocation2(program: Program<GotAMin>, name: string, position: [number, number], capacity: number, locationType = null):  Promise<LocationState> {
+  let publicKey = await initLocation2(program, name, position, capacity, locationType);
+  return LocationState.createPda(program, publicKey, `Loc(${position[0]/position[1]})`);
+}
+
+async function initDefaultLocation(program: Program<GotAMin>): Promise<LocationState> {
+  return await createLocation2(program, "L", [9999, 9999], 999);
+}
+
+async function initLocation2(program: Program<GotAMin>, name: string, position: [number, number], capacity: number, locationType = null): Promise<PublicKey> {
+  const provider = program.provider as anchor.AnchorProvider;
+  let pubKey = provider.wallet.publicKey;
+
+  let type = locationType == null ? { unexplored: {} } : locationType;
+  let x = position[0];
+  let y = position[1];
+  let locationPda = getLocationPda(program, pubKey, position);
+  
+  const pdaInfo = await provider.connection.getAccountInfo(locationPda);
+  if(pdaInfo == null) {
+    await program.methods
+      .initLocation(new anchor.BN(x), new anchor.BN(y), new anchor.BN(capacity), type)
+      .accounts({
+        location: locationPda,
+        owner: pubKey,
+      })
+      .rpc();
+  }
+
+  return locationPda;
+}
+
+async function moveUnitStart(unit: UnitState, toLocation: LocationState, current_timestamp: number) {
+  let program = unit.program;
+  const provider = program.provider as anchor.AnchorProvider;
+  let pk = provider.wallet.publicKey;
+
+  let unitPda = getUnitPda(program, pk, unit.name);
+  let currentLocation = await fetchLocationStatePK(program, unit.atLocation);
+  
+  await clockAt(program, current_timestamp);
+  
+  await program.methods
+    .moveUnitStart(
+      currentLocation.xBN, 
+      currentLocation.yBN, 
+      toLocation.xBN, 
+      toLocation.yBN, 
+      unit.name, 
+    )
+    .accounts({
+      unit: unitPda,
+      fromLocation: unit.atLocation,
+      toLocation: toLocation.getPubKey(),
+      owner: pk,
+    })
+    .remainingAccounts([gameClockMeta(program)])
+    .rpc();
+
+}
+
+async function moveUnitComplete(unit: UnitState, toLocation: LocationState, map: MapState, current_timestamp: number) {
+  let program = unit.program;
+  const provider = program.provider as anchor.AnchorProvider;
+  let pk = provider.wallet.publicKey;
+
+  let unitPda = getUnitPda(program, pk, unit.name);
+  
+  await clockAt(program, current_timestamp);
+  
+  await program.methods
+    .moveUnitComplete(
+      toLocation.xBN, 
+      toLocation.yBN, 
+      unit.name, 
+    )
+    .accounts({
+      unit: unitPda,
+      toLocation: toLocation.getPubKey(),
+      map: map.getPubKey(),
+      owner: pk,
+    })
+    .remainingAccounts([gameClockMeta(program)])
+    .rpc();
+
+}
+
+async function initUnit(name: string, location: LocationState): Promise<UnitState> {
+  let program = location.program;
+  const provider = program.provider as anchor.AnchorProvider;
+  let pk = provider.wallet.publicKey;
+
+  let unitPda = getUnitPda(program, pk, name);
+  
+  const pdaInfo = await provider.connection.getAccountInfo(unitPda);
+  if(pdaInfo == null) {
+    await program.methods
+      .initUnit(name, location.xBN, location.yBN)
+      .accounts({
+        unit: unitPda,
+        location: location.getPubKey(),
+        owner: pk,
+      })
+      .rpc();
+  }
+
+  return UnitState.createPda(program, unitPda, name);
+}
+
+async function produce_without_input2(producer: ProcessorState, storage: StorageState, resource: ResourceState, storageFuel: StorageState = DEFAULT_FUEL_STORAGE) {
+  await produce_without_input(producer.keyPair, storage, resource.keyPair, storageFuel);
+}
+
+async function produce_without_input(producer, storage: StorageState, resource, storageFuel: StorageState = DEFAULT_FUEL_STORAGE) {
+  let program = storage.program;
+  const programProvider = program.provider as anchor.AnchorProvider;
+
+  await ensureLinked(program, producer.publicKey, [storage, storageFuel]);
+
+  await program.methods
+    .produceWithoutInput()
+    .accounts({
+      processor: producer.publicKey,
+      storage: storage.getPubKey(),
+      resource: resource.publicKey,
+      storageFuel: storageFuel.getPubKey(),
+    })
+    .rpc();
+}
+
+async function produce_without_input_at(producer: ProcessorState, storage: StorageState, resource: ResourceState, current_timestamp: number, storageFuel: StorageState = DEFAULT_FUEL_STORAGE) {
+  let program = storage.program;
+  const programProvider = program.provider as anchor.AnchorProvider;
+
+  await ensureLinked(program, producer.getPubKey(), [storage, storageFuel]);
+
+  await clockAt(program, current_timestamp);
+  await program.methods
+    .produceWithoutInput()
+    .accounts({
+      processor: producer.getPubKey(),
+      storage: storage.getPubKey(),
+      resource: resource.getPubKey(),
+      storageFuel: storageFuel.getPubKey(),
+    })
+    .remainingAccounts([gameClockMeta(program)])
+    .rpc();
+}
+
+async function produce_with_1_input2(processor: ProcessorState, storage: StorageState, resourceToProduce: ResourceState, storageInput: StorageState, storageFuel: StorageState) {
+  return await produce_with_1_input(processor.keyPair, storage, resourceToProduce.keyPair, storageInput, storageFuel);
+}
+
+async function produce_with_1_input(producer, storage: StorageState, resourceToProduce, storageInput: StorageState, storageFuel: StorageState) {
+  let program = storage.program;
+  const programProvider = program.provider as anchor.AnchorProvider;
+
+  await ensureLinked(program, producer.publicKey, [storage, storageInput, storageFuel]);
+
+  await program.methods
+    .produceWithOneInput()
+    .accounts({
+      processor: producer.publicKey,
+      storage: storage.getPubKey(),
+      resourceToProduce: resourceToProduce.publicKey,
+      storageInput: storageInput.getPubKey(),
+      storageFuel: storageFuel.getPubKey(),
+    })
+    .rpc();
+}
+
+async function produce_with_1_input_at(producer: ProcessorState, storage: StorageState, resourceToProduce: ResourceState, storageInput: StorageState, storageFuel: StorageState, current_timestamp: number) {
+  let program = storage.program;
+  const programProvider = program.provider as anchor.AnchorProvider;
+
+  await ensureLinked(program, producer.getPubKey(), [storage, storageInput, storageFuel]);
+
+  await clockAt(program, current_timestamp);
+  await program.methods
+    .produceWithOneInput()
+    .accounts({
+      processor: producer.getPubKey(),
+      storage: storage.getPubKey(),
+      resourceToProduce: resourceToProduce.getPubKey(),
+      storageInput: storageInput.getPubKey(),
+      storageFuel: storageFuel.getPubKey(),
+    })
+    .remainingAccounts([gameClockMeta(program)])
+    .rpc();
+}
+
+async function produce_with_2_inputs(producer, storage: StorageState, resourceToProduce, storageInput1: StorageState, storageInput2: StorageState, storageFuel: StorageState = DEFAULT_FUEL_STORAGE) {
+  let program = storage.program;
+  const programProvider = program.provider as anchor.AnchorProvider;
+
+  await ensureLinked(program, producer.publicKey, [storage, storageInput1, storageInput2, storageFuel]);
+
+  await program.methods
+    .produceWithTwoInputs()
+    .accounts({
+      processor: producer.publicKey,
+      storage: storage.getPubKey(),
+      resourceToProduce: resourceToProduce.publicKey,
+      storageInput1: storageInput1.getPubKey(),
+      storageInput2: storageInput2.getPubKey(),
+      storageFuel: storageFuel.getPubKey(),
+    })
+    .rpc();
+}
+
+async function produce_with_2_inputs_at(producer: ProcessorState, storageOut: StorageState, resourceToProduce: ResourceState, storageInput1: StorageState, storageInput2: StorageState, current_timestamp: number, storageFuel: StorageState = DEFAULT_FUEL_STORAGE) {
+  let program = storageOut.program;
+  const programProvider = program.provider as anchor.AnchorProvider;
+  
+
+  await ensureLinked(program, producer.getPubKey(), [storageOut, storageInput1, storageInput2, storageFuel]);
+
+  await clockAt(program, current_timestamp);
+  await program.methods
+    .produceWithTwoInputs()
+    .accounts({
+      processor: producer.publicKey,
+      storage: storageOut.getPubKey(),
+      resourceToProduce: resourceToProduce.publicKey,
+      storageInput1: storageInput1.getPubKey(),
+      storageInput2: storageInput2.getPubKey(),
+      storageFuel: storageFuel.getPubKey(),
+    })
+    .remainingAccounts([gameClockMeta(program)])
+    .rpc();
+}
+
+async function send(sender: ProcessorState, toStorage: StorageState, resourceToProduce: ResourceState, fromStorage: StorageState, storageFuel: StorageState, from: LocationState, to: LocationState, sendAmount: number): Promise<KP> {
+  let program = sender.program;
+  const shipment = anchor.web3.Keypair.generate();
+
+  await ensureLinked(program, sender.getPubKey(), [toStorage, fromStorage, storageFuel]);
+
+  await program.methods
+    .send(new anchor.BN(sendAmount), from.xBN, from.yBN, to.xBN, to.yBN)
+    .accounts({
+      processor: sender.getPubKey(),
+      storage: toStorage.getPubKey(),
+      resourceToProduce: resourceToProduce.getPubKey(),
+      storageInput: fromStorage.getPubKey(),
+      storageFuel: storageFuel.getPubKey(),
+      shipment: shipment.publicKey,
+      fromLocation: from.getPubKey(),
+      toLocation: to.getPubKey(),
+    })
+    .signers([shipment])
+    .rpc();
+
+  return shipment;
+}
+
+async function send_at(sender: ProcessorState, toStorage: StorageState, resourceToProduce: ResourceState, fromStorage: StorageState, storageFuel: StorageState, current_timestamp: number, from: LocationState, to: LocationState, sendAmount: number): Promise<KP> {
+  let program = sender.program;
+  const shipment = anchor.web3.Keypair.generate();
+
+  await ensureLinked(program, sender.getPubKey(), [toStorage, fromStorage, storageFuel]);
+
+  await clockAt(program, current_timestamp);
+  await program.methods
+    .send(new anchor.BN(sendAmount), from.xBN, from.yBN, to.xBN, to.yBN)
+    .accounts({
+      processor: sender.getPubKey(),
+      storage: toStorage.getPubKey(),
+      resourceToProduce: resourceToProduce.getPubKey(),
+      storageInput: fromStorage.getPubKey(),
+      storageFuel: storageFuel.getPubKey(),
+      shipment: shipment.publicKey,
+      fromLocation: from.getPubKey(),
+      toLocation: to.getPubKey(),
+    })
+    .remainingAccounts([gameClockMeta(program)])
+    .signers([shipment])
+    .rpc();
+
+  return shipment;
+}
+
+async function receive_shipment(shipment: PublicKey, toStorage: StorageState) {
+  let program = toStorage.program;
+  let state = await program.account.shipment.fetch(shipment);
+
+  await program.methods
+    .receiveShipment()
+    .accounts({
+      shipment: shipment,
+      storageTo: toStorage.getPubKey(),
+      owner: state.owner,
+    })
+    .rpc();
+}
+
+async function receive_shipment_at(shipment: PublicKey, toStorage: StorageState, current_timestamp: number) {
+  let program = toStorage.program;
+  let state = await program.account.shipment.fetch(shipment);
+
+  await clockAt(program, current_timestamp);
+  await program.methods
+    .receiveShipment()
+    .accounts({
+      shipment: shipment,
+      storageTo: toStorage.getPubKey(),
+      owner: state.owner,
+    })
+    .remainingAccounts([gameClockMeta(program)])
+    .rpc();
+}
+
+async function createTradeRoute(sender: ProcessorState, storageFrom: StorageState, storageTo: StorageState, storageFuel: StorageState, from: LocationState, to: LocationState, amountPerCycle: number, cadence: number, current_timestamp: number): Promise<PublicKey> {
+  let program = sender.program;
+  const route = anchor.web3.Keypair.generate();
+
+  await clockAt(program, current_timestamp);
+  await program.methods
+    .createTradeRoute(new anchor.BN(amountPerCycle), new anchor.BN(cadence))
+    .accounts({
+      tradeRoute: route.publicKey,
+      sender: sender.getPubKey(),
+      storageFrom: storageFrom.getPubKey(),
+      storageTo: storageTo.getPubKey(),
+      storageFuel: storageFuel.getPubKey(),
+      fromLocation: from.getPubKey(),
+      toLocation: to.getPubKey(),
+    })
+    .remainingAccounts([gameClockMeta(program)])
+    .signers([route])
+    .rpc();
+
+  return route.publicKey;
+}
+
+async function runTradeRoute(route: PublicKey, current_timestamp: number) {
+  const program = anchor.workspace.GotAMin as Program<GotAMin>;
+  let state = await program.account.tradeRoute.fetch(route);
+
+  await clockAt(program, current_timestamp);
+  await program.methods
+    .runTradeRoute()
+    .accounts({
+      tradeRoute: route,
+      sender: state.senderId,
+      storageFrom: state.storageFrom,
+      storageTo: state.storageTo,
+      storageFuel: state.storageFuel,
+    })
+    .remainingAccounts([gameClockMeta(program)])
+    .rpc();
+}
+
+function getProductionQueuePda(program, processor: PublicKey): PublicKey {
+  const [pda, _] = PublicKey.findProgramAddressSync(
+    [
+      anchor.utils.bytes.utf8.encode("production-queue"),
+      processor.toBuffer(),
+    ],
+    program.programId,
+  );
+  return pda;
+}
+
+async function initProductionQueue(processor: ProcessorState): Promise<PublicKey> {
+  let program = processor.program;
+  let queue = getProductionQueuePda(program, processor.getPubKey());
+
+  await program.methods
+    .initProductionQueue()
+    .accounts({
+      queue: queue,
+      processor: processor.getPubKey(),
+    })
+    .rpc();
+
+  return queue;
+}
+
+async function enqueueJob(queue: PublicKey, processor: ProcessorState, recipe: ResourceState, quantity: number, priority: number, inputStorages: StorageState[], current_timestamp: number) {
+  let program = processor.program;
+
+  await clockAt(program, current_timestamp);
+  await program.methods
+    .enqueueJob(new anchor.BN(quantity), priority)
+    .accounts({
+      queue: queue,
+      processor: processor.getPubKey(),
+      recipe: recipe.getPubKey(),
+    })
+    .remainingAccounts([...inputStorages.map(storage => ({ pubkey: storage.getPubKey(), isWritable: true, isSigner: false })), gameClockMeta(program)])
+    .rpc();
+}
+
+async function claimJobs(queue: PublicKey, processor: ProcessorState, storageOut: StorageState, current_timestamp: number, storageFuel: StorageState = DEFAULT_FUEL_STORAGE) {
+  let program = processor.program;
+
+  await clockAt(program, current_timestamp);
+  await program.methods
+    .claimJobs()
+    .accounts({
+      queue: queue,
+      processor: processor.getPubKey(),
+      storageOut: storageOut.getPubKey(),
+      storageFuel: storageFuel.getPubKey(),
+    })
+    .remainingAccounts([gameClockMeta(program)])
+    .rpc();
+}
+
+async function cancelJob(queue: PublicKey, processor: ProcessorState, recipe: ResourceState, jobId: number, inputStorages: StorageState[], current_timestamp: number) {
+  let program = processor.program;
+
+  await clockAt(program, current_timestamp);
+  await program.methods
+    .cancelJob(new anchor.BN(jobId))
+    .accounts({
+      queue: queue,
+      processor: processor.getPubKey(),
+      recipe: recipe.getPubKey(),
+    })
+    .remainingAccounts([...inputStorages.map(storage => ({ pubkey: storage.getPubKey(), isWritable: true, isSigner: false })), gameClockMeta(program)])
+    .rpc();
+}
+
+async function move_between_storage(storageFrom: StorageState, storageTo, amount: number) {
+  let program = storageFrom.program;
+  const programProvider = program.provider as anchor.AnchorProvider;
+
+  await program.methods
+    .moveBetweenStorage(new anchor.BN(amount))
+    .accounts({
+      storageFrom: storageFrom.getPubKey(),
+      storageTo: storageTo.publicKey,
+    })
+    .rpc();
+}
+
+async function createPlayer(program: Program<GotAMin>): Promise<KP> {
+  const provider = program.provider as anchor.AnchorProvider;
+  const player: KP = anchor.web3.Keypair.generate();
+
+  let signature = await provider.connection.requestAirdrop(player.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+  await provider.connection.confirmTransaction(signature);
+
+  return player;
+}
+
+// Locations are seeded by their owner, so the player creates a Storage at a Location of their own and moves it to [location].
+async function createPlayerStorage(player: KP, resource: ResourceState, capacity: number, location: LocationState): Promise<StorageState> {
+  let program = resource.program;
+  let position: [number, number] = [location.x, location.y];
+  let ownLocationPda = getLocationPda(program, player.publicKey, position);
+
+  if(await program.provider.connection.getAccountInfo(ownLocationPda) == null) {
+    await program.methods
+      .initLocation(location.xBN, location.yBN, new anchor.BN(10), { unexplored: {} })
+      .accounts({
+        location: ownLocationPda,
+        owner: player.publicKey,
+      })
+      .signers([player])
+      .rpc();
+  }
+
+  let ownLocation = await LocationState.createPda(program, ownLocationPda, "PlayerLocation");
+  let [keyPair, _] = await createStorageNew(program, player, resource.keyPair, capacity, ownLocation, {movable:{}});
+  let storage = new StorageState(keyPair, resource);
+
+  await program.methods
+    .moveStorage()
+    .accounts({
+      storage: storage.getPubKey(),
+      fromLocation: ownLocation.getPubKey(),
+      toLocation: location.getPubKey(),
+      owner: player.publicKey,
+    })
+    .remainingAccounts([gameClockMeta(program)])
+    .signers([player])
+    .rpc();
+
+  return await storage.refresh();
+}
+
+function getTransferAllowListPda(program, pk: PublicKey): PublicKey {
+  const [pda, _] = PublicKey.findProgramAddressSync(
+    [
+      anchor.utils.bytes.utf8.encode("transfer-allow-list"),
+      pk.toBuffer(),
+    ],
+    program.programId,
+  );
+  return pda;
+}
+
+async function transferToPlayer(storageFrom: StorageState, storageTo: StorageState, amount: number) {
+  let program = storageFrom.program;
+  let recipient = (await program.account.storage.fetch(storageTo.getPubKey())).owner;
+
+  await program.methods
+    .transferToPlayer(new anchor.BN(amount))
+    .accounts({
+      storageFrom: storageFrom.getPubKey(),
+      storageTo: storageTo.getPubKey(),
+      allowList: getTransferAllowListPda(program, recipient),
+    })
+    .rpc();
+}
+
+async function initTransferAllowList(program: Program<GotAMin>, owner: KP, allowed: PublicKey[]) {
+  await program.methods
+    .initTransferAllowList(allowed)
+    .accounts({
+      allowList: getTransferAllowListPda(program, owner.publicKey),
+      owner: owner.publicKey,
+    })
+    .signers([owner])
+    .rpc();
+}
+
+async function updateTransferAllowList(program: Program<GotAMin>, owner: KP, allowed: PublicKey[]) {
+  await program.methods
+    .updateTransferAllowList(allowed)
+    .accounts({
+      allowList: getTransferAllowListPda(program, owner.publicKey),
+      owner: owner.publicKey,
+    })
+    .signers([owner])
+    .rpc();
+}
+
+function getOfferEscrowPda(program, offer: PublicKey): PublicKey {
+  const [pda, _] = PublicKey.findProgramAddressSync(
+    [
+      anchor.utils.bytes.utf8.encode("offer-escrow"),
+      offer.toBuffer(),
+    ],
+    program.programId,
+  );
+  return pda;
+}
+
+async function createOffer(makerStorage: StorageState, offeredAmount: number, requestedResource: ResourceState, requestedAmount: number): Promise<KP> {
+  let program = makerStorage.program;
+  const offer: KP = anchor.web3.Keypair.generate();
+
+  await program.methods
+    .createOffer(new anchor.BN(offeredAmount), requestedResource.getPubKey(), new anchor.BN(requestedAmount))
+    .accounts({
+      offer: offer.publicKey,
+      escrow: getOfferEscrowPda(program, offer.publicKey),
+      makerStorage: makerStorage.getPubKey(),
+    })
+    .signers([offer])
+    .rpc();
+
+  return offer;
+}
+
+async function acceptOffer(offer: KP, taker: KP, makerStorage: StorageState, takerStorageFrom: StorageState, takerStorageTo: StorageState) {
+  let program = makerStorage.program;
+  let state = await program.account.offer.fetch(offer.publicKey);
+
+  await program.methods
+    .acceptOffer()
+    .accounts({
+      offer: offer.publicKey,
+      escrow: getOfferEscrowPda(program, offer.publicKey),
+      makerStorage: makerStorage.getPubKey(),
+      takerStorageFrom: takerStorageFrom.getPubKey(),
+      takerStorageTo: takerStorageTo.getPubKey(),
+      maker: state.maker,
+      taker: taker.publicKey,
+    })
+    .signers([taker])
+    .rpc();
+}
+
+async function cancelOffer(offer: KP, makerStorage: StorageState) {
+  let program = makerStorage.program;
+
+  await program.methods
+    .cancelOffer()
+    .accounts({
+      offer: offer.publicKey,
+      escrow: getOfferEscrowPda(program, offer.publicKey),
+      makerStorage: makerStorage.getPubKey(),
+    })
+    .rpc();
+}
+
+type OrderSide = {bid:{}} | {ask:{}};
+
+function getOrderBookPda(program, location: PublicKey, base: PublicKey, quote: PublicKey): PublicKey {
+  const [pda, _] = PublicKey.findProgramAddressSync(
+    [
+      anchor.utils.bytes.utf8.encode("order-book"),
+      location.toBuffer(),
+      base.toBuffer(),
+      quote.toBuffer(),
+    ],
+    program.programId,
+  );
+  return pda;
+}
+
+function getOrderBookVaultPda(program, orderBook: PublicKey, resource: PublicKey): PublicKey {
+  const [pda, _] = PublicKey.findProgramAddressSync(
+    [
+      anchor.utils.bytes.utf8.encode("order-book-vault"),
+      orderBook.toBuffer(),
+      resource.toBuffer(),
+    ],
+    program.programId,
+  );
+  return pda;
+}
+
+async function initOrderBook(location: LocationState, base: ResourceState, quote: ResourceState): Promise<PublicKey> {
+  let program = location.program;
+  let orderBook = getOrderBookPda(program, location.getPubKey(), base.getPubKey(), quote.getPubKey());
+
+  await program.methods
+    .initOrderBook(base.getPubKey(), quote.getPubKey())
+    .accounts({
+      orderBook: orderBook,
+      baseVault: getOrderBookVaultPda(program, orderBook, base.getPubKey()),
+      quoteVault: getOrderBookVaultPda(program, orderBook, quote.getPubKey()),
+      location: location.getPubKey(),
+    })
+    .rpc();
+
+  return orderBook;
+}
+
+async function placeOrder(orderBook: PublicKey, side: OrderSide, price: number, amount: number, baseStorage: StorageState, quoteStorage: StorageState, owner: KP = null, counterparties: StorageState[] = []) {
+  let program = baseStorage.program;
+  const provider = program.provider as anchor.AnchorProvider;
+
+  await program.methods
+    .placeOrder(side, new anchor.BN(price), new anchor.BN(amount))
+    .accounts({
+      orderBook: orderBook,
+      baseVault: getOrderBookVaultPda(program, orderBook, baseStorage.resource.getPubKey()),
+      quoteVault: getOrderBookVaultPda(program, orderBook, quoteStorage.resource.getPubKey()),
+      baseStorage: baseStorage.getPubKey(),
+      quoteStorage: quoteStorage.getPubKey(),
+      owner: owner?.publicKey ?? provider.wallet.publicKey,
+    })
+    .remainingAccounts(counterparties.map(storage => ({ pubkey: storage.getPubKey(), isWritable: true, isSigner: false })))
+    .signers(owner == null ? [] : [owner])
+    .rpc();
+}
+
+async function cancelOrder(orderBook: PublicKey, orderId: number, baseStorage: StorageState, quoteStorage: StorageState) {
+  let program = baseStorage.program;
+
+  await program.methods
+    .cancelOrder(new anchor.BN(orderId))
+    .accounts({
+      orderBook: orderBook,
+      baseVault: getOrderBookVaultPda(program, orderBook, baseStorage.resource.getPubKey()),
+      quoteVault: getOrderBookVaultPda(program, orderBook, quoteStorage.resource.getPubKey()),
+      baseStorage: baseStorage.getPubKey(),
+      quoteStorage: quoteStorage.getPubKey(),
+    })
+    .rpc();
+}
+
+function getGamePda(program, authority: PublicKey): PublicKey {
+  const [pda, _] = PublicKey.findProgramAddressSync(
+    [
+      anchor.utils.bytes.utf8.encode("game"),
+      authority.toBuffer(),
+    ],
+    program.programId,
+  );
+  return pda;
+}
+
+async function initGame(program: Program<GotAMin>, swapFeeBps: number): Promise<PublicKey> {
+  const provider = program.provider as anchor.AnchorProvider;
+  let game = getGamePda(program, provider.wallet.publicKey);
+
+  if (await provider.connection.getAccountInfo(game) == null) {
+    await program.methods
+      .initGame(swapFeeBps)
+      .accounts({
+        game: game,
+      })
+      .rpc();
+  } else {
+    await program.methods
+      .updateGameFees(swapFeeBps)
+      .accounts({
+        game: game,
+      })
+      .rpc();
+  }
+
+  return game;
+}
+
+// Pools key their pair by the lower resource id first.
+function sortedPair<T>(a: [PublicKey, T], b: [PublicKey, T]): [T, T] {
+  return Buffer.compare(a[0].toBuffer(), b[0].toBuffer()) < 0 ? [a[1], b[1]] : [b[1], a[1]];
+}
+
+function getPoolPda(program, game: PublicKey, location: PublicKey, resourceA: PublicKey, resourceB: PublicKey): PublicKey {
+  const [pda, _] = PublicKey.findProgramAddressSync(
+    [
+      anchor.utils.bytes.utf8.encode("pool"),
+      game.toBuffer(),
+      location.toBuffer(),
+      resourceA.toBuffer(),
+      resourceB.toBuffer(),
+    ],
+    program.programId,
+  );
+  return pda;
+}
+
+function getPoolReservePda(program, pool: PublicKey, resource: PublicKey): PublicKey {
+  const [pda, _] = PublicKey.findProgramAddressSync(
+    [
+      anchor.utils.bytes.utf8.encode("pool-reserve"),
+      pool.toBuffer(),
+      resource.toBuffer(),
+    ],
+    program.programId,
+  );
+  return pda;
+}
+
+function getLiquidityPositionPda(program, pool: PublicKey, owner: PublicKey): PublicKey {
+  const [pda, _] = PublicKey.findProgramAddressSync(
+    [
+      anchor.utils.bytes.utf8.encode("liquidity"),
+      pool.toBuffer(),
+      owner.toBuffer(),
+    ],
+    program.programId,
+  );
+  return pda;
+}
+
+async function createPool(game: PublicKey, location: LocationState, resource1: ResourceState, resource2: ResourceState): Promise<PublicKey> {
+  let program = location.program;
+  let [resourceA, resourceB] = sortedPair([resource1.getPubKey(), resource1.getPubKey()], [resource2.getPubKey(), resource2.getPubKey()]);
+  let pool = getPoolPda(program, game, location.getPubKey(), resourceA, resourceB);
+
+  await program.methods
+    .createPool(resourceA, resourceB)
+    .accounts({
+      game: game,
+      pool: pool,
+      reserveA: getPoolReservePda(program, pool, resourceA),
+      reserveB: getPoolReservePda(program, pool, resourceB),
+      location: location.getPubKey(),
+    })
+    .rpc();
+
+  return pool;
+}
+
+async function addLiquidity(pool: PublicKey, storage1: StorageState, storage2: StorageState, amount1: number, amount2: number, minShares: number) {
+  let program = storage1.program;
+  const provider = program.provider as anchor.AnchorProvider;
+  let [[storageA, amountA], [storageB, amountB]] = sortedPair(
+    [storage1.resource.getPubKey(), [storage1, amount1]],
+    [storage2.resource.getPubKey(), [storage2, amount2]],
+  );
+
+  await program.methods
+    .addLiquidity(new anchor.BN(amountA), new anchor.BN(amountB), new anchor.BN(minShares))
+    .accounts({
+      pool: pool,
+      reserveA: getPoolReservePda(program, pool, storageA.resource.getPubKey()),
+      reserveB: getPoolReservePda(program, pool, storageB.resource.getPubKey()),
+      storageA: storageA.getPubKey(),
+      storageB: storageB.getPubKey(),
+      position: getLiquidityPositionPda(program, pool, provider.wallet.publicKey),
+    })
+    .rpc();
+}
+
+async function removeLiquidity(pool: PublicKey, storage1: StorageState, storage2: StorageState, shares: number, min1: number, min2: number) {
+  let program = storage1.program;
+  const provider = program.provider as anchor.AnchorProvider;
+  let [[storageA, minA], [storageB, minB]] = sortedPair(
+    [storage1.resource.getPubKey(), [storage1, min1]],
+    [storage2.resource.getPubKey(), [storage2, min2]],
+  );
+
+  await program.methods
+    .removeLiquidity(new anchor.BN(shares), new anchor.BN(minA), new anchor.BN(minB))
+    .accounts({
+      pool: pool,
+      reserveA: getPoolReservePda(program, pool, storageA.resource.getPubKey()),
+      reserveB: getPoolReservePda(program, pool, storageB.resource.getPubKey()),
+      storageA: storageA.getPubKey(),
+      storageB: storageB.getPubKey(),
+      position: getLiquidityPositionPda(program, pool, provider.wallet.publicKey),
+    })
+    .rpc();
+}
+
+async function swap(pool: PublicKey, storageIn: StorageState, storageOut: StorageState, amountIn: number, minAmountOut: number) {
+  let program = storageIn.program;
+  let state = await program.account.pool.fetch(pool);
+
+  await program.methods
+    .swap(new anchor.BN(amountIn), new anchor.BN(minAmountOut))
+    .accounts({
+      game: state.game,
+      pool: pool,
+      reserveA: getPoolReservePda(program, pool, state.resourceAId),
+      reserveB: getPoolReservePda(program, pool, state.resourceBId),
+      storageIn: storageIn.getPubKey(),
+      storageOut: storageOut.getPubKey(),
+    })
+    .rpc();
+}
+
+function getAuctionPda(program, item: PublicKey): PublicKey {
+  const [pda, _] = PublicKey.findProgramAddressSync(
+    [
+      anchor.utils.bytes.utf8.encode("auction"),
+      item.toBuffer(),
+    ],
+    program.programId,
+  );
+  return pda;
+}
+
+function getAuctionEscrowPda(program, seed: string, auction: PublicKey): PublicKey {
+  const [pda, _] = PublicKey.findProgramAddressSync(
+    [
+      anchor.utils.bytes.utf8.encode(seed),
+      auction.toBuffer(),
+    ],
+    program.programId,
+  );
+  return pda;
+}
 
+async function createLocationAuction(location: LocationState, bidResource: ResourceState, minBid: number, duration: number, current_timestamp: number): Promise<PublicKey> {
+  let program = location.program;
+  let auction = getAuctionPda(program, location.getPubKey());
+
+  await clockAt(program, current_timestamp);
   await program.methods
-    .initStorage(resource.publicKey, new anchor.BN(capacity), mobilityType, new anchor.BN(speed), location.xBN, location.yBN)
+    .createLocationAuction(bidResource.getPubKey(), new anchor.BN(minBid), new anchor.BN(duration))
     .accounts({
-      storage: storage.publicKey,
+      auction: auction,
+      bidEscrow: getAuctionEscrowPda(program, "auction-bid", auction),
       location: location.getPubKey(),
-      owner: provider.wallet.publicKey,
-      systemProgram: anchor.web3.SystemProgram.programId,
     })
-    .signers([storage])
+    .remainingAccounts([gameClockMeta(program)])
     .rpc();
-    
-  return await program.account.storage.fetch(storage.publicKey);
+
+  return auction;
 }
 
-async function debugStorage(
-  storage: StorageState, 
-  amount: number = 1,
-) {
-  let program = storage.program;
+async function createProcessorAuction(processor: ProcessorState, bidResource: ResourceState, minBid: number, duration: number, current_timestamp: number): Promise<PublicKey> {
+  let program = processor.program;
+  let auction = getAuctionPda(program, processor.getPubKey());
 
+  await clockAt(program, current_timestamp);
   await program.methods
-    .debugSetStorageAmount(new anchor.BN(amount))
+    .createProcessorAuction(bidResource.getPubKey(), new anchor.BN(minBid), new anchor.BN(duration))
     .accounts({
-      storage: storage.getPubKey(),
+      auction: auction,
+      bidEscrow: getAuctionEscrowPda(program, "auction-bid", auction),
+      processor: processor.getPubKey(),
+      queue: getProductionQueuePda(program, processor.getPubKey()),
     })
-    .rpc();    
-}
+    .remainingAccounts([gameClockMeta(program)])
+    .rpc();
 
-async function initDefaultFuel(program: Program<GotAMin>): Promise<ResourceState> {
-  return await createResource2(program, "default_fuel", []);
+  return auction;
 }
 
-async function createLocation2(program: Program<GotAMin>, name: string, position: [number, number], capacity: number, locationType = null):  Promise<LocationState> {
-  let publicKey = await initLocation2(program, name, position, capacity, locationType);
-  return LocationState.createPda(program, publicKey, `Loc(${position[0]/position[1]})`);
-}
+async function createGoodsAuction(sellerStorage: StorageState, amount: number, bidResource: ResourceState, minBid: number, duration: number, current_timestamp: number): Promise<PublicKey> {
+  let program = sellerStorage.program;
+  let auction = getAuctionPda(program, sellerStorage.getPubKey());
 
-async function initDefaultLocation(program: Program<GotAMin>): Promise<LocationState> {
-  return await createLocation2(program, "L", [9999, 9999], 999);
-}
+  await clockAt(program, current_timestamp);
+  await program.methods
+    .createGoodsAuction(new anchor.BN(amount), bidResource.getPubKey(), new anchor.BN(minBid), new anchor.BN(duration))
+    .accounts({
+      auction: auction,
+      bidEscrow: getAuctionEscrowPda(program, "auction-bid", auction),
+      goodsEscrow: getAuctionEscrowPda(program, "auction-goods", auction),
+      sellerStorage: sellerStorage.getPubKey(),
+    })
+    .remainingAccounts([gameClockMeta(program)])
+    .rpc();
 
-async function initLocation2(program: Program<GotAMin>, name: string, position: [number, number], capacity: number, locationType = null): Promise<PublicKey> {
-  const provider = program.provider as anchor.AnchorProvider;
-  let pubKey = provider.wallet.publicKey;
+  return auction;
+}
 
-  let type = locationType == null ? { unexplored: {} } : locationType;
-  let x = position[0];
-  let y = position[1];
-  let locationPda = getLocationPda(program, pubKey, position);
-  
-  const pdaInfo = await provider.connection.getAccountInfo(locationPda);
-  if(pdaInfo == null) {
-    await program.methods
-      .initLocation(new anchor.BN(x), new anchor.BN(y), new anchor.BN(capacity), type)
-      .accounts({
-        location: locationPda,
-        owner: pubKey,
-      })
-      .rpc();
-  }
+async function placeBid(auction: PublicKey, bidder: KP, bidderStorage: StorageState, amount: number, current_timestamp: number) {
+  let program = bidderStorage.program;
+  let state = await program.account.auction.fetch(auction);
+  let outbid = state.highestBidderStorage.equals(PublicKey.default) || state.highestBidderStorage.equals(bidderStorage.getPubKey())
+    ? []
+    : [{ pubkey: state.highestBidderStorage, isWritable: true, isSigner: false }];
 
-  return locationPda;
+  await clockAt(program, current_timestamp);
+  await program.methods
+    .placeBid(new anchor.BN(amount))
+    .accounts({
+      auction: auction,
+      bidEscrow: getAuctionEscrowPda(program, "auction-bid", auction),
+      bidderStorage: bidderStorage.getPubKey(),
+      bidder: bidder.publicKey,
+    })
+    .remainingAccounts([...outbid, gameClockMeta(program)])
+    .signers([bidder])
+    .rpc();
 }
 
-async function moveUnitStart(unit: UnitState, toLocation: LocationState, current_timestamp: number) {
-  let program = unit.program;
-  const provider = program.provider as anchor.AnchorProvider;
-  let pk = provider.wallet.publicKey;
+async function settleLocationAuction(auction: PublicKey, sellerStorage: StorageState, current_timestamp: number) {
+  let program = sellerStorage.program;
+  let state = await program.account.auction.fetch(auction);
 
-  let unitPda = getUnitPda(program, pk, unit.name);
-  let currentLocation = await fetchLocationStatePK(program, unit.atLocation);
-  
+  await clockAt(program, current_timestamp);
   await program.methods
-    .debugMoveUnitStart(
-      currentLocation.xBN, 
-      currentLocation.yBN, 
-      toLocation.xBN, 
-      toLocation.yBN, 
-      unit.name, 
-      new anchor.BN(current_timestamp),
-    )
+    .settleLocationAuction()
     .accounts({
-      unit: unitPda,
-      fromLocation: unit.atLocation,
-      toLocation: toLocation.getPubKey(),
-      owner: pk,
+      auction: auction,
+      bidEscrow: getAuctionEscrowPda(program, "auction-bid", auction),
+      sellerStorage: sellerStorage.getPubKey(),
+      location: state.item,
+      seller: state.seller,
     })
+    .remainingAccounts([gameClockMeta(program)])
     .rpc();
-
 }
 
-async function moveUnitComplete(unit: UnitState, toLocation: LocationState, map: MapState, current_timestamp: number) {
-  let program = unit.program;
-  const provider = program.provider as anchor.AnchorProvider;
-  let pk = provider.wallet.publicKey;
+async function settleProcessorAuction(auction: PublicKey, processor: ProcessorState, sellerStorage: StorageState, current_timestamp: number) {
+  let program = sellerStorage.program;
+  let state = await program.account.auction.fetch(auction);
 
-  let unitPda = getUnitPda(program, pk, unit.name);
-  
+  await clockAt(program, current_timestamp);
   await program.methods
-    .debugMoveUnitComplete(
-      toLocation.xBN, 
-      toLocation.yBN, 
-      unit.name, 
-      new anchor.BN(current_timestamp),
-    )
+    .settleProcessorAuction()
     .accounts({
-      unit: unitPda,
-      toLocation: toLocation.getPubKey(),
-      map: map.getPubKey(),
-      owner: pk,
+      auction: auction,
+      bidEscrow: getAuctionEscrowPda(program, "auction-bid", auction),
+      sellerStorage: sellerStorage.getPubKey(),
+      processor: processor.getPubKey(),
+      location: state.locationId,
+      seller: state.seller,
     })
+    .remainingAccounts([gameClockMeta(program)])
     .rpc();
-
 }
 
-async function initUnit(name: string, location: LocationState): Promise<UnitState> {
-  let program = location.program;
-  const provider = program.provider as anchor.AnchorProvider;
-  let pk = provider.wallet.publicKey;
-
-  let unitPda = getUnitPda(program, pk, name);
-  
-  const pdaInfo = await provider.connection.getAccountInfo(unitPda);
-  if(pdaInfo == null) {
-    await program.methods
-      .initUnit(name, location.xBN, location.yBN)
-      .accounts({
-        unit: unitPda,
-        location: location.getPubKey(),
-        owner: pk,
-      })
-      .rpc();
-  }
+async function settleGoodsAuction(auction: PublicKey, sellerStorage: StorageState, goodsTo: StorageState, current_timestamp: number) {
+  let program = sellerStorage.program;
+  let state = await program.account.auction.fetch(auction);
 
-  return UnitState.createPda(program, unitPda, name);
+  await clockAt(program, current_timestamp);
+  await program.methods
+    .settleGoodsAuction()
+    .accounts({
+      auction: auction,
+      bidEscrow: getAuctionEscrowPda(program, "auction-bid", auction),
+      goodsEscrow: getAuctionEscrowPda(program, "auction-goods", auction),
+      sellerStorage: sellerStorage.getPubKey(),
+      goodsTo: goodsTo.getPubKey(),
+      seller: state.seller,
+    })
+    .remainingAccounts([gameClockMeta(program)])
+    .rpc();
 }
 
-async function produce_without_input2(producer: ProcessorState, storage: StorageState, resource: ResourceState) {
-  await produce_without_input(producer.keyPair, storage, resource.keyPair);
+async function updateGameLevels(program: Program<GotAMin>, levelThroughputBps: number[], upgradeResource: ResourceState, upgradeCostBase: number) {
+  const provider = program.provider as anchor.AnchorProvider;
+
+  await program.methods
+    .updateGameLevels(levelThroughputBps, upgradeResource.getPubKey(), new anchor.BN(upgradeCostBase))
+    .accounts({
+      game: getGamePda(program, provider.wallet.publicKey),
+    })
+    .rpc();
 }
 
-async function produce_without_input(producer, storage: StorageState, resource) {
-  let program = storage.program;
-  const programProvider = program.provider as anchor.AnchorProvider;
+async function upgradeProcessorAt(processor: ProcessorState, storagePayment: StorageState, current_timestamp: number) {
+  let program = processor.program;
+  const provider = program.provider as anchor.AnchorProvider;
 
+  await clockAt(program, current_timestamp);
   await program.methods
-    .produceWithoutInput()
+    .upgradeProcessor()
     .accounts({
-      processor: producer.publicKey,
-      storage: storage.getPubKey(),
-      resource: resource.publicKey,
+      game: getGamePda(program, provider.wallet.publicKey),
+      processor: processor.getPubKey(),
+      storagePayment: storagePayment.getPubKey(),
     })
+    .remainingAccounts([gameClockMeta(program)])
     .rpc();
 }
 
-async function debug_produce_without_input(producer: ProcessorState, storage: StorageState, resource: ResourceState, current_timestamp: number) {
-  let program = storage.program;
-  const programProvider = program.provider as anchor.AnchorProvider;
+async function updateGameRetoolDelay(program: Program<GotAMin>, retoolDelay: number) {
+  const provider = program.provider as anchor.AnchorProvider;
 
   await program.methods
-    .debugProduceWithoutInput(new anchor.BN(current_timestamp))
+    .updateGameRetoolDelay(new anchor.BN(retoolDelay))
     .accounts({
-      processor: producer.getPubKey(),
-      storage: storage.getPubKey(),
-      resource: resource.getPubKey(),
+      game: getGamePda(program, provider.wallet.publicKey),
     })
     .rpc();
 }
 
-async function produce_with_1_input2(processor: ProcessorState, storage: StorageState, resourceToProduce: ResourceState, storageInput: StorageState, storageFuel: StorageState) {
-  return await produce_with_1_input(processor.keyPair, storage, resourceToProduce.keyPair, storageInput, storageFuel);
+async function pauseProcessorAt(processor: ProcessorState, current_timestamp: number) {
+  await clockAt(processor.program, current_timestamp);
+  await processor.program.methods
+    .pauseProcessor()
+    .accounts({
+      processor: processor.getPubKey(),
+    })
+    .remainingAccounts([gameClockMeta(processor.program)])
+    .rpc();
 }
 
-async function produce_with_1_input(producer, storage: StorageState, resourceToProduce, storageInput: StorageState, storageFuel: StorageState) {
-  let program = storage.program;
-  const programProvider = program.provider as anchor.AnchorProvider;
-
-  await program.methods
-    .produceWithOneInput()
+async function resumeProcessorAt(processor: ProcessorState, current_timestamp: number) {
+  await clockAt(processor.program, current_timestamp);
+  await processor.program.methods
+    .resumeProcessor()
     .accounts({
-      processor: producer.publicKey,
-      storage: storage.getPubKey(),
-      resourceToProduce: resourceToProduce.publicKey,
-      storageInput: storageInput.getPubKey(),
-      storageFuel: storageFuel.getPubKey(),
+      processor: processor.getPubKey(),
     })
+    .remainingAccounts([gameClockMeta(processor.program)])
     .rpc();
 }
 
-async function debug_produce_with_1_input(producer: ProcessorState, storage: StorageState, resourceToProduce: ResourceState, storageInput: StorageState, storageFuel: StorageState, current_timestamp: number) {
-  let program = storage.program;
-  const programProvider = program.provider as anchor.AnchorProvider;
+async function retoolProcessorAt(processor: ProcessorState, resource: ResourceState, storageOut: StorageState, newResource: ResourceState, current_timestamp: number, storageFuel: StorageState = DEFAULT_FUEL_STORAGE) {
+  let program = processor.program;
+  const provider = program.provider as anchor.AnchorProvider;
 
+  await clockAt(program, current_timestamp);
   await program.methods
-    .debugProduceWithOneInput(new anchor.BN(current_timestamp))
+    .retoolProcessor(newResource.getPubKey())
     .accounts({
-      processor: producer.getPubKey(),
-      storage: storage.getPubKey(),
-      resourceToProduce: resourceToProduce.getPubKey(),
-      storageInput: storageInput.getPubKey(),
+      game: getGamePda(program, provider.wallet.publicKey),
+      processor: processor.getPubKey(),
+      resource: resource.getPubKey(),
+      storageOut: storageOut.getPubKey(),
       storageFuel: storageFuel.getPubKey(),
     })
+    .remainingAccounts([gameClockMeta(program)])
     .rpc();
 }
 
-async function produce_with_2_inputs(producer, storage: StorageState, resourceToProduce, storageInput1: StorageState, storageInput2: StorageState) {
-  let program = storage.program;
-  const programProvider = program.provider as anchor.AnchorProvider;
+async function updateGameWear(program: Program<GotAMin>, maxDurability: number, repairResource: ResourceState, repairCostPerPoint: number) {
+  const provider = program.provider as anchor.AnchorProvider;
 
   await program.methods
-    .produceWithTwoInputs()
+    .updateGameWear(new anchor.BN(maxDurability), repairResource.getPubKey(), new anchor.BN(repairCostPerPoint))
     .accounts({
-      processor: producer.publicKey,
-      storage: storage.getPubKey(),
-      resourceToProduce: resourceToProduce.publicKey,
-      storageInput1: storageInput1.getPubKey(),
-      storageInput2: storageInput2.getPubKey(),
+      game: getGamePda(program, provider.wallet.publicKey),
     })
     .rpc();
 }
 
-async function debug_produce_with_2_inputs(producer: ProcessorState, storageOut: StorageState, resourceToProduce: ResourceState, storageInput1: StorageState, storageInput2: StorageState, current_timestamp: number) {
-  let program = storageOut.program;
-  const programProvider = program.provider as anchor.AnchorProvider;
-  
+async function repairProcessorAt(processor: ProcessorState, storagePayment: StorageState, points: number, current_timestamp: number) {
+  let program = processor.program;
+  const provider = program.provider as anchor.AnchorProvider;
 
+  await clockAt(program, current_timestamp);
   await program.methods
-    .debugProduceWithTwoInputs(new anchor.BN(current_timestamp))
+    .repairProcessor(new anchor.BN(points))
     .accounts({
-      processor: producer.publicKey,
-      storage: storageOut.getPubKey(),
-      resourceToProduce: resourceToProduce.publicKey,
-      storageInput1: storageInput1.getPubKey(),
-      storageInput2: storageInput2.getPubKey(),
+      game: getGamePda(program, provider.wallet.publicKey),
+      processor: processor.getPubKey(),
+      storagePayment: storagePayment.getPubKey(),
     })
+    .remainingAccounts([gameClockMeta(program)])
     .rpc();
 }
 
-async function send(sender: ProcessorState, toStorage: StorageState, resourceToProduce: ResourceState, fromStorage: StorageState, storageFuel: StorageState, from: LocationState, to: LocationState) {
-  let program = sender.program;
-  const programProvider = program.provider as anchor.AnchorProvider;
-
-  await program.methods
-    .send(null, from.xBN, from.yBN, to.xBN, to.yBN)
+async function moveProcessorAt(processor: ProcessorState, fromLocation: LocationState, toLocation: LocationState, current_timestamp: number) {
+  await clockAt(processor.program, current_timestamp);
+  await processor.program.methods
+    .moveProcessor()
     .accounts({
-      processor: sender.getPubKey(),
-      storage: toStorage.getPubKey(),
-      resourceToProduce: resourceToProduce.getPubKey(),
-      storageInput: fromStorage.getPubKey(),
-      storageFuel: storageFuel.getPubKey(),
+      processor: processor.getPubKey(),
+      fromLocation: fromLocation.getPubKey(),
+      toLocation: toLocation.getPubKey(),
     })
+    .remainingAccounts([gameClockMeta(processor.program)])
     .rpc();
 }
 
-async function debug_send(sender: ProcessorState, toStorage: StorageState, resourceToProduce: ResourceState, fromStorage: StorageState, storageFuel: StorageState, current_timestamp: number, from: LocationState, to: LocationState) {
-  let program = sender.program;
+// Links whichever of [storages] the processor isn't linked to yet.
+async function ensureLinked(program: Program<GotAMin>, processor: PublicKey, storages: StorageState[]) {
+  let linked = (await program.account.processor.fetch(processor)).linkedStorages.map((s: PublicKey) => s.toBase58());
 
-  await program.methods
-    .debugSend(new anchor.BN(999), new anchor.BN(current_timestamp), from.xBN, from.yBN, to.xBN, to.yBN)
+  for (const storage of storages) {
+    if (!linked.includes(storage.getPubKey().toBase58())) {
+      await linkStorage(processor, storage);
+      linked.push(storage.getPubKey().toBase58());
+    }
+  }
+}
+
+async function linkStorage(processor: PublicKey, storage: StorageState) {
+  await storage.program.methods
+    .linkStorage()
     .accounts({
-      processor: sender.getPubKey(),
-      storage: toStorage.getPubKey(),
-      resourceToProduce: resourceToProduce.getPubKey(),
-      storageInput: fromStorage.getPubKey(),
-      storageFuel: storageFuel.getPubKey(),
-      fromLocation: from.getPubKey(),
-      toLocation: to.getPubKey(),
+      processor: processor,
+      storage: storage.getPubKey(),
     })
     .rpc();
 }
 
-async function move_between_storage(storageFrom: StorageState, storageTo, amount: number) {
-  let program = storageFrom.program;
-  const programProvider = program.provider as anchor.AnchorProvider;
+async function unlinkStorage(processor: PublicKey, storage: StorageState) {
+  await storage.program.methods
+    .unlinkStorage()
+    .accounts({
+      processor: processor,
+      storage: storage.getPubKey(),
+    })
+    .rpc();
+}
+
+function getGameClockPda(program): PublicKey {
+  const [pda, _] = PublicKey.findProgramAddressSync(
+    [anchor.utils.bytes.utf8.encode("game-clock")],
+    program.programId,
+  );
+  return pda;
+}
+
+function gameClockMeta(program) {
+  return { pubkey: getGameClockPda(program), isWritable: false, isSigner: false };
+}
+
+async function initGameClock(program: Program<GotAMin>): Promise<PublicKey> {
+  const provider = program.provider as anchor.AnchorProvider;
+  let gameClock = getGameClockPda(program);
+
+  if (await provider.connection.getAccountInfo(gameClock) == null) {
+    let { programData, authority } = debugAuthority(program);
+    await sendClockInstruction(program, "init_game_clock", 0, [
+      { pubkey: gameClock, isWritable: true, isSigner: false },
+      { pubkey: authority, isWritable: true, isSigner: true },
+      { pubkey: program.programId, isWritable: false, isSigner: false },
+      { pubkey: programData, isWritable: false, isSigner: false },
+      { pubkey: anchor.web3.SystemProgram.programId, isWritable: false, isSigner: false },
+    ]);
+  }
+
+  return gameClock;
+}
+
+async function advanceGameClock(program: Program<GotAMin>, seconds: number) {
+  const provider = program.provider as anchor.AnchorProvider;
+
+  await sendClockInstruction(program, "advance_game_clock", seconds, [
+    { pubkey: getGameClockPda(program), isWritable: true, isSigner: false },
+    { pubkey: provider.wallet.publicKey, isWritable: false, isSigner: true },
+  ]);
+}
+
+// The clock's instructions aren't in the IDL, test-clock builds dispatch them from the program's fallback.
+async function sendClockInstruction(program: Program<GotAMin>, name: string, value: number, keys: anchor.web3.AccountMeta[]) {
+  const provider = program.provider as anchor.AnchorProvider;
+  const discriminator = Buffer.from(anchor.utils.sha256.hash(`global:${name}`), "hex").subarray(0, 8);
+  const data = Buffer.concat([discriminator, new anchor.BN(value).toTwos(64).toArrayLike(Buffer, "le", 8)]);
+  const ix = new anchor.web3.TransactionInstruction({ programId: program.programId, keys, data });
+
+  try {
+    await provider.sendAndConfirm(new anchor.web3.Transaction().add(ix));
+  } catch (e) {
+    throw AnchorError.parse(e.logs) ?? e;
+  }
+}
+
+// Moves the game clock to [seconds] into the current test, see the root beforeEach. Time only goes
+// forward, so a test asking for an earlier time fails.
+async function clockAt(program: Program<GotAMin>, seconds: number) {
+  let clock = await program.account.gameClock.fetch(getGameClockPda(program));
+  await advanceGameClock(program, TEST_START + seconds - clock.timestamp.toNumber());
+}
+
+async function produceWithGameClock(processor: ProcessorState, storage: StorageState, resource: ResourceState, gameClock: PublicKey, storageFuel: StorageState = DEFAULT_FUEL_STORAGE) {
+  let program = storage.program;
+
+  await ensureLinked(program, processor.getPubKey(), [storage, storageFuel]);
 
   await program.methods
-    .moveBetweenStorage(new anchor.BN(amount))
+    .produceWithoutInput()
     .accounts({
-      storageFrom: storageFrom.getPubKey(),
-      storageTo: storageTo.publicKey,
+      processor: processor.getPubKey(),
+      storage: storage.getPubKey(),
+      resource: resource.getPubKey(),
+      storageFuel: storageFuel.getPubKey(),
     })
+    .remainingAccounts([{ pubkey: gameClock, isWritable: false, isSigner: false }])
     .rpc();
 }
 
+// Debug instructions are signed by the upgrade authority, which is the wallet deploying the program in tests.
+function debugAuthority(program: Program<GotAMin>) {
+  const provider = program.provider as anchor.AnchorProvider;
+  const [programData, _] = PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111"),
+  );
+
+  return {
+    program: program.programId,
+    programData: programData,
+    authority: provider.wallet.publicKey,
+  };
+}
+
+// Runs [action] and returns the first [eventName] the program emitted meanwhile.
+async function withEvent(program: Program<GotAMin>, eventName: string, action: () => Promise<void>): Promise<[any, number]> {
+  let listener = null;
+  let received = new Promise<[any, number]>((resolve, _reject) => {
+    listener = program.addEventListener(eventName, (event, slot) => resolve([event, slot]));
+  });
+
+  try {
+    await action();
+    return await received;
+  } finally {
+    await program.removeEventListener(listener);
+  }
+}
+
 async function moveStorage(storage: StorageState, fromLocation, toLocation) {
   let program = storage.program;
   const programProvider = program.provider as anchor.AnchorProvider;
@@ -1630,17 +3615,19 @@ async function moveStorage(storage: StorageState, fromLocation, toLocation) {
     .rpc();
 }
 
-async function debugMoveStorage(storage: StorageState, fromLocation: LocationState, toLocation: LocationState, current_timestamp: number) {
+async function moveStorageAt(storage: StorageState, fromLocation: LocationState, toLocation: LocationState, current_timestamp: number) {
   let program = storage.program;
   const programProvider = program.provider as anchor.AnchorProvider;
 
+  await clockAt(program, current_timestamp);
   await program.methods
-    .debugMoveStorage(new anchor.BN(current_timestamp))
+    .moveStorage()
     .accounts({
       storage: storage.getPubKey(),
       fromLocation: fromLocation.getPubKey(),
       toLocation: toLocation.getPubKey(),
     })
+    .remainingAccounts([gameClockMeta(program)])
     .rpc();
 }
 
@@ -1656,15 +3643,17 @@ async function updateStorageMoveStatus(storage: StorageState) {
     .rpc();
 }
 
-async function debugUpdateStorageMoveStatus(storage: StorageState, current_timestamp: number) {
+async function updateStorageMoveStatusAt(storage: StorageState, current_timestamp: number) {
   let program = storage.program;
   const programProvider = program.provider as anchor.AnchorProvider;
 
+  await clockAt(program, current_timestamp);
   await program.methods
-    .debugUpdateStorageMoveStatus(new anchor.BN(current_timestamp))
+    .updateStorageMoveStatus()
     .accounts({
       storage: storage.getPubKey(),
     })
+    .remainingAccounts([gameClockMeta(program)])
     .rpc();
 }
 